@@ -0,0 +1,221 @@
+//! Chess, with the rules (move generation, checkmate, etc.) provided by `shakmaty`.
+//!
+//! Player one is white. On top of checkmate, stalemate and insufficient material, the game is
+//! drawn by threefold repetition and by the fifty move rule, using the framework's draw rules.
+
+extern crate game;
+extern crate shakmaty;
+
+use std::fmt;
+
+use shakmaty::{CastlingMode, Color, EnPassantMode, KnownOutcome, Outcome, Position, Square};
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct Chess {
+    position: shakmaty::Chess,
+}
+
+impl Default for Chess {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chess {
+    /// The standard starting position.
+    pub fn new() -> Self {
+        Self {
+            position: shakmaty::Chess::default(),
+        }
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, String> {
+        let fen: Fen = fen.parse().map_err(|e| format!("Invalid FEN: {}", e))?;
+        let position = fen.into_position(CastlingMode::Standard).map_err(|e| format!("Invalid position: {}", e))?;
+        Ok(Self { position })
+    }
+
+    pub fn fen(&self) -> String {
+        Fen::from_position(&self.position, EnPassantMode::Legal).to_string()
+    }
+
+    /// The player whose turn it is.
+    pub fn to_move(&self) -> game::PlayerEnum {
+        player_for_color(self.position.turn())
+    }
+
+    /// The underlying `shakmaty` position.
+    pub fn position(&self) -> &shakmaty::Chess {
+        &self.position
+    }
+
+    /// Parse a move in UCI notation (e.g. "e2e4", or "e7e8q"), checking it's legal here.
+    pub fn parse_uci(&self, uci: &str) -> Result<Move, String> {
+        let uci: UciMove = uci.parse().map_err(|e| format!("Invalid UCI move '{}': {}", uci, e))?;
+        uci.to_move(&self.position).map(Move).map_err(|e| format!("Illegal move '{}': {}", uci, e))
+    }
+}
+
+impl fmt::Debug for Chess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}{}", self.position.board(), self.fen())
+    }
+}
+
+/// FEN, which says whose turn it is.
+impl game::Notation for Chess {
+    fn from_notation(text: &str) -> Result<(Self, game::PlayerEnum), String> {
+        let game = Chess::from_fen(text)?;
+        let to_move = game.to_move();
+        Ok((game, to_move))
+    }
+
+    fn to_notation(&self, _to_move: game::PlayerEnum) -> String {
+        self.fen()
+    }
+}
+
+/// UCI notation, e.g. "e2e4".
+impl game::MoveNotation for Chess {
+    fn parse_move(&self, text: &str, player: game::PlayerEnum) -> Result<Move, String> {
+        if player != self.to_move() {
+            return Err(format!("It's not {:?}'s turn", player));
+        }
+        self.parse_uci(text.trim())
+    }
+
+    fn move_notation(game_move: &Move) -> String {
+        game_move.to_uci()
+    }
+}
+
+fn color_for_player(player: game::PlayerEnum) -> Color {
+    match player {
+        game::PlayerEnum::One => Color::White,
+        game::PlayerEnum::Two => Color::Black,
+    }
+}
+
+fn player_for_color(color: Color) -> game::PlayerEnum {
+    match color {
+        Color::White => game::PlayerEnum::One,
+        Color::Black => game::PlayerEnum::Two,
+    }
+}
+
+/// A legal move in some position. Shown in UCI notation.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Move(shakmaty::Move);
+
+impl Move {
+    /// The move in UCI notation, e.g. "e2e4".
+    pub fn to_uci(&self) -> String {
+        self.0.to_uci(CastlingMode::Standard).to_string()
+    }
+
+    /// The underlying `shakmaty` move.
+    pub fn inner(&self) -> shakmaty::Move {
+        self.0
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_uci())
+    }
+}
+
+/// Encodes the board as twelve 8x8 planes: the player to move's pawns, knights, bishops, rooks,
+/// queens and king, then the opponent's, with rank 1 first. The board is flipped for black, so
+/// it's always seen from the side of the player to move.
+///
+/// Moves are indexed by their from and to squares and what they promote to (if anything), also
+/// flipped for black. Castling goes from the king's square to the rook's.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChessFeatures;
+
+/// `square` as seen by `color`.
+fn oriented(square: Square, color: Color) -> usize {
+    match color {
+        Color::White => square.to_usize(),
+        Color::Black => square.flip_vertical().to_usize(),
+    }
+}
+
+impl game::FeatureEncoder<Chess> for ChessFeatures {
+    fn shape(&self) -> Vec<usize> {
+        vec![12, 8, 8]
+    }
+
+    fn encode(&self, state: &Chess, to_move: game::PlayerEnum) -> game::Features {
+        let color = color_for_player(to_move);
+        let mut data = vec![0.0; 12 * 64];
+        for (square, piece) in state.position.board().iter() {
+            let plane = (piece.role as usize - 1) + if piece.color == color { 0 } else { 6 };
+            data[64 * plane + oriented(square, color)] = 1.0;
+        }
+        game::Features { shape: self.shape(), data }
+    }
+
+    fn move_count(&self) -> usize {
+        64 * 64 * 5
+    }
+
+    fn move_index(&self, game_move: &Move, player: game::PlayerEnum) -> usize {
+        let color = color_for_player(player);
+        let to = game_move.0.to();
+        let from = game_move.0.from().unwrap_or(to);
+        let promotion = game_move.0.promotion().map_or(0, |role| role as usize - 1);
+        (64 * oriented(from, color) + oriented(to, color)) * 5 + promotion
+    }
+}
+
+impl game::GameState for Chess {
+    type Move = Move;
+
+    fn update(&mut self, game_move: Self::Move, player: game::PlayerEnum) {
+        assert_eq!(player, self.to_move(), "Not this player's turn");
+        assert!(self.position.is_legal(game_move.0), "Move not legal");
+
+        self.position.play_unchecked(game_move.0);
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: game::PlayerEnum) -> Box<dyn Iterator<Item = Move> + 'a> {
+        if player != self.to_move() {
+            return Box::new(::std::iter::empty());
+        }
+        Box::new(self.position.legal_moves().into_iter().map(Move))
+    }
+
+    fn is_move_legal(&self, game_move: Self::Move, player: game::PlayerEnum) -> Result<(), game::IllegalMove> {
+        if player != self.to_move() {
+            return Err(game::IllegalMove(format!("It's {:?}'s turn", self.to_move())));
+        }
+        if !self.position.is_legal(game_move.0) {
+            return Err(game::IllegalMove(format!("{} isn't legal here", game_move.to_uci())));
+        }
+        Ok(())
+    }
+
+    fn try_conclude(&self, _next_player: game::PlayerEnum) -> Option<game::Conclusion> {
+        match self.position.outcome() {
+            Outcome::Known(KnownOutcome::Decisive { winner }) => Some(game::Conclusion::Win(player_for_color(winner))),
+            Outcome::Known(KnownOutcome::Draw) => Some(game::Conclusion::Draw),
+            Outcome::Unknown => None,
+        }
+    }
+
+    fn draw_rules() -> game::DrawRules {
+        game::DrawRules {
+            repetitions: Some(3),
+            // Fifty moves each
+            moves_without_progress: Some(100),
+        }
+    }
+
+    fn is_progress(&self, game_move: &Self::Move, _player: game::PlayerEnum) -> bool {
+        game_move.0.is_zeroing()
+    }
+}
@@ -0,0 +1,13 @@
+extern crate chess;
+extern crate game;
+
+use chess::Chess;
+use game::testutil::{check_game_state, ConformanceSettings};
+use game::PlayerEnum;
+
+#[test]
+fn conforms() {
+    game::rng::seed_thread_rng(1);
+    let settings = ConformanceSettings { games: 2, max_plies: 40, max_compared: 100 };
+    check_game_state(&Chess::new(), PlayerEnum::One, &settings).unwrap();
+}
@@ -0,0 +1,63 @@
+extern crate chess;
+extern crate game;
+
+use chess::Chess;
+use game::{GameState, PlayerEnum};
+
+fn after(fen: &str, uci: &str) -> String {
+    let mut game = Chess::from_fen(fen).unwrap();
+    let game_move = game.parse_uci(uci).unwrap();
+    assert_eq!(game_move.to_uci(), uci);
+    let to_move = game.to_move();
+    game.update(game_move, to_move);
+    game.fen()
+}
+
+#[test]
+fn castles() {
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    assert_eq!(after(fen, "e1g1"), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+    assert_eq!(after(fen, "e1c1"), "r3k2r/8/8/8/8/8/8/2KR3R b kq - 1 1");
+}
+
+#[test]
+fn promotes() {
+    let fen = "8/4P3/8/8/8/8/k7/4K3 w - - 0 1";
+    assert_eq!(after(fen, "e7e8q"), "4Q3/8/8/8/8/8/k7/4K3 b - - 0 1");
+    assert_eq!(after(fen, "e7e8n"), "4N3/8/8/8/8/8/k7/4K3 b - - 0 1");
+    assert!(Chess::from_fen(fen).unwrap().parse_uci("e7e8").is_err());
+}
+
+#[test]
+fn captures_en_passant() {
+    let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+    assert_eq!(after(fen, "e5f6"), "rnbqkbnr/ppp1p1pp/5P2/3p4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3");
+    // d5 was pushed two squares before f5, so it can't be taken in passing any more.
+    assert!(Chess::from_fen(fen).unwrap().parse_uci("e5d6").is_err());
+}
+
+#[test]
+fn rejects_illegal_and_malformed_moves() {
+    let game = Chess::new();
+    for uci in &["e2e5", "e7e5", "e1g1", "a1a1", "e2", "e2e4x", "z9z9", ""] {
+        assert!(game.parse_uci(uci).is_err(), "{} was accepted", uci);
+    }
+    assert!(game::MoveNotation::parse_move(&game, "e2e4", PlayerEnum::Two).is_err());
+    assert!(game::MoveNotation::parse_move(&game, " e2e4 ", PlayerEnum::One).is_ok());
+}
+
+#[test]
+fn fen_round_trips() {
+    for fen in &[
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R b Qk - 5 20",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ] {
+        assert_eq!(&Chess::from_fen(fen).unwrap().fen(), fen);
+        let (game, to_move) = <Chess as game::Notation>::from_notation(fen).unwrap();
+        assert_eq!(&game::Notation::to_notation(&game, to_move), fen);
+    }
+    assert_eq!(Chess::from_fen(&Chess::new().fen()).unwrap(), Chess::new());
+    assert!(Chess::from_fen("not a fen").is_err());
+    assert!(Chess::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+}
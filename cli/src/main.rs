@@ -0,0 +1,74 @@
+extern crate chess;
+extern crate connect_four;
+extern crate game;
+extern crate player_of_games;
+extern crate tic_tac_toe;
+
+use std::io::{self, Write};
+use std::process;
+
+use game::{signals, CancellationToken, GameRegistry, GameState, PlayerRegistry};
+
+const USAGE: &str = "Usage: pog [--game NAME] [--player-one SPEC] [--player-two SPEC] [--list]";
+
+/// Every player that can play any game.
+fn players<Game: GameState>() -> PlayerRegistry<Game> {
+    let mut players = PlayerRegistry::with_defaults();
+    player_of_games::register_players(&mut players);
+    players
+}
+
+fn games() -> GameRegistry {
+    let mut games = GameRegistry::new();
+    games.register("tictactoe", tic_tac_toe::TicTacToe::new, players());
+    games.register("connect4", connect_four::ConnectFour::new, players());
+    games.register("chess", chess::Chess::new, players());
+    games
+}
+
+fn main() {
+    let mut game = "tictactoe".to_string();
+    let mut player_one = "mcts".to_string();
+    let mut player_two = "random".to_string();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let target = match arg.as_str() {
+            "--game" => &mut game,
+            "--player-one" => &mut player_one,
+            "--player-two" => &mut player_two,
+            "--list" => {
+                println!("Games: {}", games().names().join(", "));
+                println!("Players: {}", players::<tic_tac_toe::TicTacToe>().names().join(", "));
+                return;
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        };
+        match args.next() {
+            Some(value) => *target = value,
+            None => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    // Ctrl-C stops the match after the move being thought about, rather than losing the output
+    let cancellation = CancellationToken::new();
+    signals::cancel_on_shutdown(cancellation.clone());
+    match games().play_match_until_cancelled(&game, &player_one, &player_two, &cancellation) {
+        Ok(Some(conclusion)) => println!("Conclusion: {:?}", conclusion),
+        Ok(None) => {
+            println!("Match stopped before the end");
+            io::stdout().flush().expect("Couldn't write to stdout");
+            process::exit(signals::exit_status().unwrap_or(1));
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -1 +1,425 @@
-// TODO
\ No newline at end of file
+extern crate game;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+
+mod bitboard;
+
+pub use bitboard::BitConnectFour;
+
+use std::fmt;
+use std::str::FromStr;
+
+pub const COLUMNS: usize = 7;
+pub const ROWS: usize = 6;
+
+/// How many discs in a line are needed to win.
+const LINE_LENGTH: usize = 4;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Disc {
+    Red,
+    Yellow,
+}
+
+impl From<game::PlayerEnum> for Disc {
+    fn from(player: game::PlayerEnum) -> Self {
+        match player {
+            game::PlayerEnum::One => Disc::Red,
+            game::PlayerEnum::Two => Disc::Yellow,
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ConnectFour {
+    /// Indexed by column and then row, with row 0 at the bottom.
+    columns: [[Option<Disc>; ROWS]; COLUMNS],
+}
+
+impl Default for ConnectFour {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectFour {
+    pub fn new() -> Self {
+        Self {
+            columns: [[None; ROWS]; COLUMNS],
+        }
+    }
+
+    fn count(&self, disc: Disc) -> usize {
+        self.columns.iter().flat_map(|column| column.iter()).filter(|x| **x == Some(disc)).count()
+    }
+
+    /// The row a disc dropped into this column would land in, if the column isn't full.
+    fn landing_row(&self, column: usize) -> Option<usize> {
+        self.columns[column].iter().position(|x| x.is_none())
+    }
+
+    fn get(&self, column: isize, row: isize) -> Option<Disc> {
+        if column < 0 || row < 0 || column >= COLUMNS as isize || row >= ROWS as isize {
+            return None;
+        }
+        self.columns[column as usize][row as usize]
+    }
+
+    fn does_disc_win(&self, disc: Disc) -> bool {
+        // Look for lines starting from each of this player's discs, going right, up, and along
+        // both diagonals.
+        let directions = [(1, 0), (0, 1), (1, 1), (1, -1)];
+        for column in 0..COLUMNS as isize {
+            for row in 0..ROWS as isize {
+                if self.get(column, row) != Some(disc) {
+                    continue;
+                }
+                for &(dx, dy) in directions.iter() {
+                    if (1..LINE_LENGTH as isize).all(|i| self.get(column + i * dx, row + i * dy) == Some(disc)) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        // Otherwise
+        false
+    }
+
+    /// Whether `disc` could win by dropping into some column.
+    fn threatens(&self, disc: Disc) -> bool {
+        (0..COLUMNS).any(|column| match self.landing_row(column) {
+            Some(row) => {
+                let mut next = self.clone();
+                next.columns[column][row] = Some(disc);
+                next.does_disc_win(disc)
+            },
+            None => false,
+        })
+    }
+
+    fn is_legal(&self, game_move: Move, player: game::PlayerEnum) -> Result<(), String> {
+        if game_move.column >= COLUMNS {
+            return Err("Column out of bounds".to_string());
+        }
+
+        if self.landing_row(game_move.column).is_none() {
+            return Err("Column is full".to_string());
+        }
+
+        let count_red = self.count(Disc::Red);
+        let count_yellow = self.count(Disc::Yellow);
+        match Disc::from(player) {
+            Disc::Red => {
+                // Check that there's the same number of either
+                if count_red != count_yellow {
+                    return Err("Red playing out of turn".to_string());
+                }
+            }
+            Disc::Yellow => {
+                // Check that there's one more red
+                if count_red != count_yellow + 1 {
+                    return Err("Yellow playing out of turn".to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ConnectFour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ConnectFour {{")?;
+        for row in (0..ROWS).rev() {
+            for column in 0..COLUMNS {
+                write!(f, "{}", match self.columns[column][row] {
+                    Some(Disc::Red) => "R",
+                    Some(Disc::Yellow) => "Y",
+                    None => ".",
+                })?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Written as rows from the top down, separated by slashes, with `R` and `Y` for the discs and
+/// `.` for empty squares.
+impl fmt::Display for ConnectFour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in (0..ROWS).rev() {
+            if row + 1 < ROWS {
+                write!(f, "/")?;
+            }
+            for column in 0..COLUMNS {
+                write!(f, "{}", match self.columns[column][row] {
+                    Some(Disc::Red) => "R",
+                    Some(Disc::Yellow) => "Y",
+                    None => ".",
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the `Display` form, checking that the position could come up in a game. Empty squares
+/// can also be `_`, and discs can be lower case.
+impl FromStr for ConnectFour {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let rows: Vec<&str> = s.trim().split('/').collect();
+        if rows.len() != ROWS || rows.iter().any(|row| row.chars().count() != COLUMNS) {
+            return Err(format!("Expected {} rows of {} squares, separated by '/': {}", ROWS, COLUMNS, s));
+        }
+
+        let mut game = ConnectFour::new();
+        for (i, text) in rows.iter().enumerate() {
+            let row = ROWS - 1 - i;
+            for (column, square) in text.chars().enumerate() {
+                game.columns[column][row] = match square {
+                    'R' | 'r' => Some(Disc::Red),
+                    'Y' | 'y' => Some(Disc::Yellow),
+                    '.' | '_' => None,
+                    _ => return Err(format!("Unexpected square '{}' in: {}", square, s)),
+                };
+            }
+        }
+
+        for column in 0..COLUMNS {
+            if (1..ROWS).any(|row| game.columns[column][row].is_some() && game.columns[column][row - 1].is_none()) {
+                return Err(format!("Column {} has a disc floating above an empty square", column));
+            }
+        }
+        let red = game.count(Disc::Red);
+        let yellow = game.count(Disc::Yellow);
+        if red != yellow && red != yellow + 1 {
+            return Err(format!("{} red and {} yellow discs can't come up in a game", red, yellow));
+        }
+        match (game.does_disc_win(Disc::Red), game.does_disc_win(Disc::Yellow)) {
+            (true, true) => Err("Both players have won".to_string()),
+            (true, false) if red == yellow => Err("Yellow moved after red won".to_string()),
+            (false, true) if red != yellow => Err("Red moved after yellow won".to_string()),
+            _ => Ok(game),
+        }
+    }
+}
+
+impl game::Notation for ConnectFour {
+    fn from_notation(text: &str) -> Result<(Self, game::PlayerEnum), String> {
+        let game: ConnectFour = text.parse()?;
+        let to_move = if game.count(Disc::Red) == game.count(Disc::Yellow) {
+            game::PlayerEnum::One
+        } else {
+            game::PlayerEnum::Two
+        };
+        Ok((game, to_move))
+    }
+
+    /// Whose turn it is follows from the position, so `to_move` isn't written down.
+    fn to_notation(&self, _to_move: game::PlayerEnum) -> String {
+        self.to_string()
+    }
+}
+
+/// Columns are numbered 1 to 7 from the left, as people count them.
+impl game::MoveNotation for ConnectFour {
+    fn parse_move(&self, text: &str, player: game::PlayerEnum) -> Result<Move, String> {
+        let column = match text.trim().parse::<usize>() {
+            Ok(column) if (1..=COLUMNS).contains(&column) => column - 1,
+            _ => return Err(format!("Expected a column from 1 to {}, not '{}'", COLUMNS, text)),
+        };
+        let game_move = Move::new(column);
+        game::GameState::is_move_legal(self, game_move, player).map_err(|e| format!("Can't play {}: {}", text, e))?;
+        Ok(game_move)
+    }
+
+    fn move_notation(game_move: &Move) -> String {
+        (game_move.column + 1).to_string()
+    }
+}
+
+/// Drop a disc into a column. Columns are numbered 0 to 6 from the left.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Move {
+    column: usize,
+}
+
+impl Move {
+    pub fn new(column: usize) -> Move {
+        if column >= COLUMNS {
+            panic!("Column was out of bounds.")
+        }
+        Move {
+            column
+        }
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+/// Move weights for `game::SoftmaxRollout`, scoring moves by whether they win, block the
+/// opponent's win, and how close to the centre they are.
+#[derive(Clone, Copy, Debug)]
+pub struct ConnectFourWeights {
+    pub win: f64,
+    pub block: f64,
+    /// The score for the centre column. Other columns get less, down to nothing at the edges.
+    pub centre: f64,
+}
+
+impl Default for ConnectFourWeights {
+    fn default() -> Self {
+        Self {
+            win: 10.0,
+            block: 5.0,
+            centre: 1.0,
+        }
+    }
+}
+
+impl game::MoveWeights<ConnectFour> for ConnectFourWeights {
+    fn score(&self, state: &ConnectFour, game_move: &Move, player: game::PlayerEnum) -> f64 {
+        let column = game_move.column;
+        let would_win = |disc: Disc| {
+            let mut state = state.clone();
+            match state.landing_row(column) {
+                Some(row) => state.columns[column][row] = Some(disc),
+                None => return false,
+            }
+            state.does_disc_win(disc)
+        };
+
+        let mut score = 0.0;
+        if would_win(Disc::from(player)) {
+            score += self.win;
+        }
+        if would_win(Disc::from(player.other())) {
+            score += self.block;
+        }
+        let middle = (COLUMNS / 2) as f64;
+        score + self.centre * (1.0 - (column as f64 - middle).abs() / middle)
+    }
+}
+
+impl game::TunableWeights<ConnectFour> for ConnectFourWeights {
+    fn parameters(&self) -> Vec<f64> {
+        vec![self.win, self.block, self.centre]
+    }
+
+    fn with_parameters(&self, parameters: &[f64]) -> Self {
+        Self {
+            win: parameters[0],
+            block: parameters[1],
+            centre: parameters[2],
+        }
+    }
+}
+
+/// Encodes the board as two `ROWS` by `COLUMNS` planes, of the player to move's discs and then
+/// the opponent's, with row 0 at the bottom. Each column is a move index.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConnectFourFeatures;
+
+impl game::FeatureEncoder<ConnectFour> for ConnectFourFeatures {
+    fn shape(&self) -> Vec<usize> {
+        vec![2, ROWS, COLUMNS]
+    }
+
+    fn encode(&self, state: &ConnectFour, to_move: game::PlayerEnum) -> game::Features {
+        let mut data = vec![0.0; 2 * ROWS * COLUMNS];
+        for (column, discs) in state.columns.iter().enumerate() {
+            for (row, disc) in discs.iter().enumerate() {
+                if let Some(disc) = *disc {
+                    let plane = if disc == Disc::from(to_move) { 0 } else { 1 };
+                    data[ROWS * COLUMNS * plane + COLUMNS * row + column] = 1.0;
+                }
+            }
+        }
+        game::Features { shape: self.shape(), data }
+    }
+
+    fn move_count(&self) -> usize {
+        COLUMNS
+    }
+
+    fn move_index(&self, game_move: &Move, _player: game::PlayerEnum) -> usize {
+        game_move.column
+    }
+}
+
+impl game::GameState for ConnectFour {
+    type Move = Move;
+
+    fn update(&mut self, game_move: Self::Move, player: game::PlayerEnum) {
+        self.is_legal(game_move, player).expect("Move not legal");
+
+        let row = self.landing_row(game_move.column).expect("Column is full");
+        self.columns[game_move.column][row] = Some(Disc::from(player));
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: game::PlayerEnum) -> Box<dyn Iterator<Item = Move> + 'a> {
+        Box::new((0..COLUMNS).map(Move::new).filter(move |game_move| self.is_legal(*game_move, player).is_ok()))
+    }
+
+    fn is_move_legal(&self, game_move: Self::Move, player: game::PlayerEnum) -> Result<(), game::IllegalMove> {
+        self.is_legal(game_move, player).map_err(game::IllegalMove)
+    }
+
+    fn with_move<R, F: FnOnce(&mut Self) -> R>(&mut self, game_move: Self::Move, player: game::PlayerEnum, f: F) -> R {
+        game::UndoableGameState::with_move_in_place(self, game_move, player, f)
+    }
+
+    /// Moves which win, or threaten to win next move.
+    fn is_forcing(&self, game_move: &Self::Move, player: game::PlayerEnum) -> bool {
+        if self.is_legal(*game_move, player).is_err() {
+            return false;
+        }
+        let row = self.landing_row(game_move.column).expect("Column is full");
+        let disc = Disc::from(player);
+        let mut next = self.clone();
+        next.columns[game_move.column][row] = Some(disc);
+        next.does_disc_win(disc) || next.threatens(disc)
+    }
+
+    fn try_conclude(&self, next_player: game::PlayerEnum) -> Option<game::Conclusion> {
+        if self.does_disc_win(Disc::Red) {
+            return Some(game::Conclusion::Win(game::PlayerEnum::One))
+        }
+        if self.does_disc_win(Disc::Yellow) {
+            return Some(game::Conclusion::Win(game::PlayerEnum::Two))
+        }
+
+        // Otherwise, if there are no moves left for the next player, draw
+        if self.all_legal_moves(next_player).next().is_none() {
+            return Some(game::Conclusion::Draw)
+        }
+
+        // Otherwise, the game goes on
+        None
+    }
+}
+
+impl game::UndoableGameState for ConnectFour {
+    /// The column the disc went in.
+    type UndoToken = usize;
+
+    fn apply(&mut self, game_move: Self::Move, player: game::PlayerEnum) -> usize {
+        game::GameState::update(self, game_move, player);
+        game_move.column
+    }
+
+    fn undo(&mut self, column: usize) {
+        let row = self.landing_row(column).unwrap_or(ROWS) - 1;
+        self.columns[column][row] = None;
+    }
+}
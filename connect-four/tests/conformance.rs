@@ -0,0 +1,18 @@
+extern crate connect_four;
+extern crate game;
+
+use connect_four::{BitConnectFour, ConnectFour};
+use game::testutil::check_game_state;
+use game::PlayerEnum;
+
+#[test]
+fn conforms() {
+    game::rng::seed_thread_rng(1);
+    check_game_state(&ConnectFour::new(), PlayerEnum::One, &Default::default()).unwrap();
+}
+
+#[test]
+fn bitboard_conforms() {
+    game::rng::seed_thread_rng(1);
+    check_game_state(&BitConnectFour::new(), PlayerEnum::One, &Default::default()).unwrap();
+}
@@ -0,0 +1,9 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate chess;
+extern crate game;
+
+fuzz_target!(|data: &[u8]| {
+    game::fuzz::play_bytes(chess::Chess::new(), game::PlayerEnum::One, data);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate connect_four;
+extern crate game;
+
+fuzz_target!(|data: &[u8]| {
+    game::fuzz::play_bytes(connect_four::ConnectFour::new(), game::PlayerEnum::One, data);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate tic_tac_toe;
+extern crate game;
+
+fuzz_target!(|data: &[u8]| {
+    game::fuzz::play_bytes(tic_tac_toe::TicTacToe::new(), game::PlayerEnum::One, data);
+});
@@ -0,0 +1,60 @@
+//! Times the tree search on the opening position of connect four, and reports its speed and how
+//! big a tree it built. `--hasher fx` hashes game states with FxHash rather than SipHash, and
+//! `--board bits` plays `BitConnectFour` rather than `ConnectFour`.
+//!
+//!     cargo run --release -p game --example benchmark -- --seed 1 --iterations 10000 --runs 5
+
+extern crate connect_four;
+extern crate fxhash;
+extern crate game;
+extern crate player_of_games;
+
+mod common;
+
+use std::hash::BuildHasher;
+use std::time::Instant;
+
+use connect_four::{BitConnectFour, ConnectFour};
+use game::{GameState, PlayerEnum, Player};
+use player_of_games::{DefaultState, MonteCarloTreeSearchPlayer};
+
+fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+    let board = args.get("board", "array".to_owned());
+    match (board.as_str(), args.get("hasher", "sip".to_owned()).as_str()) {
+        ("array", "sip") => benchmark::<ConnectFour, DefaultState>(&args, ConnectFour::new()),
+        ("array", "fx") => benchmark::<ConnectFour, fxhash::FxBuildHasher>(&args, ConnectFour::new()),
+        ("bits", "sip") => benchmark::<BitConnectFour, DefaultState>(&args, BitConnectFour::new()),
+        ("bits", "fx") => benchmark::<BitConnectFour, fxhash::FxBuildHasher>(&args, BitConnectFour::new()),
+        ("array", hasher) | ("bits", hasher) => panic!("Unknown hasher {}, expected sip or fx", hasher),
+        (board, _) => panic!("Unknown board {}, expected array or bits", board),
+    }
+}
+
+fn benchmark<Game: GameState, S: BuildHasher + Default>(args: &common::Args, initial_state: Game) {
+    let iterations: u32 = args.get("iterations", 10000);
+    let runs: u32 = args.get("runs", 5);
+
+    let mut total_seconds = 0.0;
+    for run in 1..(runs + 1) {
+        let mut mcts = MonteCarloTreeSearchPlayer::<Game, S>::with_hasher(PlayerEnum::One, 2f64.sqrt());
+        mcts.set_budget(args.budget(iterations));
+
+        let start = Instant::now();
+        let chosen_move = mcts.choose_move(initial_state.clone());
+        let seconds = start.elapsed().as_secs_f64();
+        total_seconds += seconds;
+
+        let tree = mcts.tree_size();
+        println!("Run {}: chose {:?} in {:.3}s ({:.0} iterations/s), {} nodes, {} edges",
+                 run, chosen_move, seconds, iterations as f64 / seconds, tree.nodes, tree.edges);
+        // Only counted when built with `--features player-of-games/stats`.
+        let stats = mcts.search_stats();
+        if stats != Default::default() {
+            println!("  {:?}", stats);
+        }
+    }
+
+    println!("Average: {:.0} iterations/s", (iterations * runs) as f64 / total_seconds);
+}
@@ -0,0 +1,76 @@
+//! Bits shared between the examples: argument parsing and an observer which logs the game.
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use game::{self, Conclusion, GameObserver, GameState, PlayerEnum};
+use player_of_games::SearchBudget;
+
+/// Arguments given as `--name value`.
+pub struct Args(Vec<String>);
+
+impl Args {
+    pub fn parse() -> Self {
+        Args(env::args().skip(1).collect())
+    }
+
+    /// The value given for `--name`, or `default` if there isn't one.
+    pub fn get<T: FromStr>(&self, name: &str, default: T) -> T where <T as FromStr>::Err: Debug {
+        self.get_opt(name).unwrap_or(default)
+    }
+
+    /// The value given for `--name`, if any.
+    pub fn get_opt<T: FromStr>(&self, name: &str) -> Option<T> where <T as FromStr>::Err: Debug {
+        let flag = format!("--{}", name);
+        self.0.iter().position(|arg| *arg == flag).map(|i| {
+            let value = self.0.get(i + 1).unwrap_or_else(|| panic!("{} needs a value", flag));
+            value.parse().unwrap_or_else(|e| panic!("Bad value for {}: {:?}", flag, e))
+        })
+    }
+
+    /// Seed the random number generator from `--seed`, if given, so the run can be repeated.
+    pub fn seed_rng(&self) {
+        if let Some(seed) = self.get_opt::<u64>("seed") {
+            println!("Using seed {}", seed);
+            game::rng::seed_thread_rng(seed);
+        }
+    }
+
+    /// The MCTS search budget from `--iterations`.
+    pub fn budget(&self, default: u32) -> SearchBudget {
+        SearchBudget::Iterations(self.get("iterations", default))
+    }
+}
+
+/// Logs each move, and the result, to stdout.
+pub struct MoveLogger {
+    move_number: u32,
+    /// Whether to print the board after each move too.
+    pub show_board: bool,
+}
+
+impl MoveLogger {
+    pub fn new(show_board: bool) -> Self {
+        Self { move_number: 0, show_board }
+    }
+}
+
+impl<Game: GameState> GameObserver<Game> for MoveLogger {
+    fn on_move(&mut self, player: PlayerEnum, game_move: &<Game as GameState>::Move, new_state: &Game, evaluation: Option<f64>) {
+        self.move_number += 1;
+        match evaluation {
+            Some(evaluation) => println!("{}. {:?} plays {:?} (evaluation {:.2})", self.move_number, player, game_move, evaluation),
+            None => println!("{}. {:?} plays {:?}", self.move_number, player, game_move),
+        }
+        if self.show_board {
+            println!("{:?}", new_state);
+        }
+    }
+
+    fn on_conclusion(&mut self, conclusion: Conclusion, _final_score: Option<f64>) {
+        println!("Result: {:?}", conclusion);
+    }
+}
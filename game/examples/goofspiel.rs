@@ -0,0 +1,37 @@
+//! The tree search (as player one) bids against optimal play at Goofspiel, and reports its
+//! average margin, which can't be better than zero on average. The optimal player doesn't look
+//! at the tree search's sealed bids, but the tree search knows the order of the prizes.
+//!
+//!     cargo run --release -p game --example goofspiel -- --size 5 --iterations 2000 --games 100
+
+extern crate game;
+extern crate goofspiel;
+extern crate player_of_games;
+
+mod common;
+
+use game::{Adjudicator, GameState, PlayerEnum};
+use goofspiel::{EquilibriumPlayer, Goofspiel};
+use player_of_games::MonteCarloTreeSearchPlayer;
+
+fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+    let size: u8 = args.get("size", 5);
+    let games: u32 = args.get("games", 100);
+
+    let mut margin = 0.0;
+    for _ in 0..games {
+        let mut player_one = MonteCarloTreeSearchPlayer::new(PlayerEnum::One, 2f64.sqrt());
+        player_one.set_budget(args.budget(2000));
+        let player_two = EquilibriumPlayer::new(PlayerEnum::Two);
+
+        let mut adjudicator = Adjudicator::new(Goofspiel::new(size), player_one, player_two);
+        while adjudicator.conclusion().is_none() {
+            adjudicator.progress_one_turn()
+        }
+        margin += adjudicator.game_state().final_score().expect("Goofspiel has a score");
+    }
+
+    println!("{} games: the tree search's average margin was {:.2} points", games, margin / games as f64);
+}
@@ -0,0 +1,50 @@
+//! Times line detection and population counts on random connect four sized bitboards, with the
+//! SIMD versions (when built with `--features simd` on a processor with AVX2) against the scalar
+//! ones.
+//!
+//!     cargo run --release -p game --features simd --example line_scan -- --seed 1 --boards 100000
+
+extern crate game;
+extern crate player_of_games;
+
+mod common;
+
+use std::time::Instant;
+
+use game::bitboard::{self, BitGrid};
+use game::rng::random_f64;
+
+fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+    let count: usize = args.get("boards", 100_000);
+    let runs: u32 = args.get("runs", 20);
+
+    // Half full boards, a mix with and without lines
+    let grid = BitGrid::new(7, 6, true);
+    let boards: Vec<u64> = (0..count).map(|_| {
+        let mut board = 0;
+        for x in 0..grid.width() {
+            for y in 0..grid.height() {
+                if random_f64() < 0.3 {
+                    board |= grid.bit(x, y);
+                }
+            }
+        }
+        board
+    }).collect();
+
+    let time = |name: &str, f: &dyn Fn() -> u64| {
+        let start = Instant::now();
+        let mut result = 0;
+        for _ in 0..runs {
+            result = f();
+        }
+        let seconds = start.elapsed().as_secs_f64();
+        println!("{}: {} in {:.1}ns per board", name, result, seconds * 1e9 / (count as f64 * runs as f64));
+    };
+    time("Lines (scalar)", &|| boards.iter().filter(|&&board| grid.has_line_scalar(board, 4)).count() as u64);
+    time("Lines", &|| boards.iter().filter(|&&board| grid.has_line(board, 4)).count() as u64);
+    time("Population (scalar)", &|| bitboard::population_scalar(&boards));
+    time("Population", &|| bitboard::population(&boards));
+}
@@ -0,0 +1,34 @@
+//! Play tic-tac-toe against the tree search at the terminal.
+//!
+//!     cargo run -p game --example mcts_vs_human -- --iterations 2000 --human-first true
+
+extern crate tic_tac_toe;
+extern crate game;
+extern crate player_of_games;
+
+mod common;
+
+use game::{Adjudicator, BoxedPlayer, HumanPlayer, PlayerEnum};
+use player_of_games::MonteCarloTreeSearchPlayer;
+
+fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+
+    let human_side = if args.get("human-first", true) { PlayerEnum::One } else { PlayerEnum::Two };
+    let mut mcts = MonteCarloTreeSearchPlayer::new(human_side.other(), 2f64.sqrt());
+    mcts.set_budget(args.budget(2000));
+    let mcts: BoxedPlayer<_> = Box::new(mcts);
+    let human: BoxedPlayer<_> = Box::new(HumanPlayer(human_side));
+    let (player_one, player_two) = match human_side {
+        PlayerEnum::One => (human, mcts),
+        PlayerEnum::Two => (mcts, human),
+    };
+
+    let mut adjudicator = Adjudicator::new_boxed(tic_tac_toe::TicTacToe::new(), player_one, player_two);
+    // The human player already shows the board before each of their moves.
+    adjudicator.add_observer(common::MoveLogger::new(false));
+    while adjudicator.conclusion().is_none() {
+        adjudicator.progress_one_turn()
+    }
+}
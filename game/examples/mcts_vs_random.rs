@@ -0,0 +1,45 @@
+//! The tree search plays a number of games of tic-tac-toe against a random player, taking turns
+//! to go first, and reports how it did.
+//!
+//!     cargo run -p game --example mcts_vs_random -- --seed 1 --iterations 200 --games 20
+
+extern crate tic_tac_toe;
+extern crate game;
+extern crate player_of_games;
+
+mod common;
+
+use game::{Adjudicator, BoxedPlayer, Conclusion, PlayerEnum, RandomPlayer};
+use player_of_games::MonteCarloTreeSearchPlayer;
+
+fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+    let games: u32 = args.get("games", 20);
+
+    let (mut wins, mut draws, mut losses) = (0, 0, 0);
+    for i in 0..games {
+        // Alternate who goes first.
+        let mcts_side = if i % 2 == 0 { PlayerEnum::One } else { PlayerEnum::Two };
+        let mut mcts = MonteCarloTreeSearchPlayer::new(mcts_side, 2f64.sqrt());
+        mcts.set_budget(args.budget(200));
+        let mcts: BoxedPlayer<_> = Box::new(mcts);
+        let random: BoxedPlayer<_> = Box::new(RandomPlayer(mcts_side.other()));
+        let (player_one, player_two) = match mcts_side {
+            PlayerEnum::One => (mcts, random),
+            PlayerEnum::Two => (random, mcts),
+        };
+
+        let mut adjudicator = Adjudicator::new_boxed(tic_tac_toe::TicTacToe::new(), player_one, player_two);
+        while adjudicator.conclusion().is_none() {
+            adjudicator.progress_one_turn()
+        }
+        match adjudicator.conclusion() {
+            Some(Conclusion::Win(winner)) if winner == mcts_side => wins += 1,
+            Some(Conclusion::Win(_)) => losses += 1,
+            _ => draws += 1,
+        }
+    }
+
+    println!("Tree search against random, {} games: {} won, {} drawn, {} lost", games, wins, draws, losses);
+}
@@ -1,16 +1,27 @@
+//! The tree search plays tic-tac-toe against itself. With enough iterations, it's always a draw.
+//!
+//!     cargo run -p game --example monte_carlo_tic_tac_toe -- --seed 1 --iterations 1000
+
 extern crate tic_tac_toe;
 extern crate game;
 extern crate player_of_games;
 
+mod common;
+
+use player_of_games::MonteCarloTreeSearchPlayer;
+
 fn main() {
-    let mut adjudicator = game::Adjudicator::new(
-        tic_tac_toe::TicTacToe::new(),
-        player_of_games::MonteCarloTreeSearchPlayer::new(game::PlayerEnum::One, 2f64.sqrt()),
-        player_of_games::MonteCarloTreeSearchPlayer::new(game::PlayerEnum::Two, 2f64.sqrt()),
-    );
+    let args = common::Args::parse();
+    args.seed_rng();
+
+    let mut player_one = MonteCarloTreeSearchPlayer::new(game::PlayerEnum::One, 2f64.sqrt());
+    player_one.set_budget(args.budget(1000));
+    let mut player_two = MonteCarloTreeSearchPlayer::new(game::PlayerEnum::Two, 2f64.sqrt());
+    player_two.set_budget(args.budget(1000));
+
+    let mut adjudicator = game::Adjudicator::new(tic_tac_toe::TicTacToe::new(), player_one, player_two);
+    adjudicator.add_observer(common::MoveLogger::new(true));
     while adjudicator.conclusion().is_none() {
         adjudicator.progress_one_turn()
     }
-
-    println!("Conclusion: {:?}", adjudicator.conclusion());
-}
\ No newline at end of file
+}
@@ -0,0 +1,31 @@
+//! The tree search plays itself at Quoridor, printing the board after every turn. Each turn has
+//! over a hundred wall placements to choose from, so it needs a good budget to play sensibly.
+//!
+//!     cargo run --release -p game --example quoridor -- --iterations 2000
+
+extern crate game;
+extern crate player_of_games;
+extern crate quoridor;
+
+mod common;
+
+use game::{Adjudicator, PlayerEnum};
+use player_of_games::MonteCarloTreeSearchPlayer;
+use quoridor::Quoridor;
+
+fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+
+    let mut player_one = MonteCarloTreeSearchPlayer::new(PlayerEnum::One, 2f64.sqrt());
+    player_one.set_budget(args.budget(2000));
+    let mut player_two = MonteCarloTreeSearchPlayer::new(PlayerEnum::Two, 2f64.sqrt());
+    player_two.set_budget(args.budget(2000));
+
+    let mut adjudicator = Adjudicator::new(Quoridor::new(), player_one, player_two);
+    while adjudicator.conclusion().is_none() {
+        adjudicator.progress_one_turn();
+        println!("{}\n", adjudicator.game_state());
+    }
+    println!("{:?}", adjudicator.conclusion());
+}
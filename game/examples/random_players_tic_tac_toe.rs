@@ -1,15 +1,24 @@
+//! Two random players play tic-tac-toe.
+//!
+//!     cargo run -p game --example random_players_tic_tac_toe -- --seed 1
+
 extern crate tic_tac_toe;
 extern crate game;
+extern crate player_of_games;
+
+mod common;
 
 fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+
     let mut adjudicator = game::Adjudicator::new(
         tic_tac_toe::TicTacToe::new(),
         game::RandomPlayer(game::PlayerEnum::One),
         game::RandomPlayer(game::PlayerEnum::Two),
     );
+    adjudicator.add_observer(common::MoveLogger::new(true));
     while adjudicator.conclusion().is_none() {
         adjudicator.progress_one_turn()
     }
-
-    println!("Conclusion: {:?}", adjudicator.conclusion());
-}
\ No newline at end of file
+}
@@ -0,0 +1,50 @@
+//! The tree search plays itself at Bridg-It (or Hex, with `--game hex`), and reports how often each
+//! side wins. The first player can always win, and with enough search it usually does.
+//!
+//!     cargo run -p game --example shannon -- --size 3 --iterations 1000 --games 10
+
+extern crate game;
+extern crate player_of_games;
+extern crate shannon;
+
+mod common;
+
+use game::board::BoardTopology;
+use game::{Adjudicator, Conclusion, PlayerEnum};
+use player_of_games::MonteCarloTreeSearchPlayer;
+use shannon::Shannon;
+
+fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+    let size: usize = args.get("size", 3);
+    let games: u32 = args.get("games", 10);
+    match args.get("game", "bridg-it".to_string()).as_str() {
+        "bridg-it" => play(&args, Shannon::bridg_it(size), games),
+        "hex" => play(&args, Shannon::hex(size), games),
+        other => println!("Unknown game '{}', expected bridg-it or hex", other),
+    }
+}
+
+fn play<B: BoardTopology + 'static>(args: &common::Args, start: Shannon<B>, games: u32) {
+    let mut first_player_wins = 0;
+    for _ in 0..games {
+        let mut player_one = MonteCarloTreeSearchPlayer::new(PlayerEnum::One, 2f64.sqrt());
+        player_one.set_budget(args.budget(1000));
+        let mut player_two = MonteCarloTreeSearchPlayer::new(PlayerEnum::Two, 2f64.sqrt());
+        player_two.set_budget(args.budget(1000));
+
+        let mut adjudicator = Adjudicator::new(start.clone(), player_one, player_two);
+        while adjudicator.conclusion().is_none() {
+            adjudicator.progress_one_turn()
+        }
+        if adjudicator.conclusion() == Some(Conclusion::Win(PlayerEnum::One)) {
+            first_player_wins += 1;
+        }
+        if games == 1 {
+            println!("{}", adjudicator.game_state());
+        }
+    }
+
+    println!("{} games: the first player won {}, the second {}", games, first_player_wins, games - first_player_wins);
+}
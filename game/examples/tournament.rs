@@ -0,0 +1,76 @@
+//! A round robin tournament between tree searches with different budgets (and a random player,
+//! for reference) at connect four. Each pair plays each opening twice, once going first each.
+//!
+//!     cargo run --release -p game --features signals --example tournament -- --seed 1 --iterations 100,400,1600 --openings 4
+//!
+//! Ctrl-C stops the tournament, saving the games played so far to the `--checkpoint` file
+//! (`tournament-checkpoint.txt` by default). Running again with the same arguments, including the
+//! seed so that the openings are the same, carries on from there.
+
+extern crate connect_four;
+extern crate game;
+extern crate player_of_games;
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use connect_four::ConnectFour;
+use game::{signals, CancellationToken, Player, RandomPlayer};
+use game::tournament::{Tournament, TournamentResults};
+use player_of_games::{MonteCarloTreeSearchPlayer, SearchBudget};
+
+fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+    let iterations: String = args.get("iterations", "100,400,1600".to_owned());
+    let checkpoint: PathBuf = args.get("checkpoint", PathBuf::from("tournament-checkpoint.txt"));
+
+    // Ctrl-C stops the tournament, still printing the games played so far
+    let cancellation = CancellationToken::new();
+    signals::cancel_on_shutdown(cancellation.clone());
+
+    let mut tournament = Tournament::new(ConnectFour::new());
+    tournament.set_cancellation_token(cancellation.clone());
+    tournament.add_entrant("random", |side| Box::new(RandomPlayer(side)));
+    for iterations in iterations.split(',') {
+        let iterations: u32 = iterations.trim().parse().expect("Bad number of iterations");
+        let cancellation = cancellation.clone();
+        tournament.add_entrant(&format!("mcts({})", iterations), move |side| {
+            let mut mcts = MonteCarloTreeSearchPlayer::new(side, 2f64.sqrt());
+            mcts.set_budget(SearchBudget::Iterations(iterations));
+            mcts.set_cancellation_token(cancellation.clone());
+            Box::new(mcts)
+        });
+    }
+    // Random openings of a few moves, so that games aren't all the same.
+    tournament.set_random_openings(args.get("openings", 4), args.get("plies", 2));
+    tournament.set_skip_duplicates(true);
+
+    let results = match checkpoint.exists() {
+        true => {
+            println!("Carrying on from {}", checkpoint.display());
+            let saved = TournamentResults::load(&checkpoint).expect("Couldn't load the checkpoint");
+            tournament.resume_round_robin(saved).expect("Couldn't carry on from the checkpoint")
+        },
+        false => tournament.play_round_robin(),
+    };
+    for game in results.games.iter() {
+        println!("{} vs {} (opening {}): {:?} ({:?}, {} moves)", results.entrants[game.player_one], results.entrants[game.player_two], game.opening, game.result.conclusion, game.result.termination, game.result.moves);
+    }
+    println!("\nStandings ({} duplicate games skipped):", results.duplicates);
+    for (name, points) in results.standings() {
+        println!("  {:10} {}", name, points);
+    }
+    if results.cancelled {
+        match results.save(&checkpoint) {
+            Ok(()) => println!("Stopped before the end, saved the games so far to {}", checkpoint.display()),
+            Err(e) => println!("Stopped before the end, and couldn't save the games so far to {}: {}", checkpoint.display(), e),
+        }
+        std::process::exit(signals::exit_status().unwrap_or(1));
+    }
+    if checkpoint.exists() {
+        fs::remove_file(&checkpoint).expect("Couldn't remove the finished tournament's checkpoint");
+    }
+}
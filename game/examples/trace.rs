@@ -0,0 +1,41 @@
+//! Traces a search of the opening position of connect four to a file, then reads the trace back
+//! and prints what it adds up to. `--read` just summarises an existing trace.
+//!
+//!     cargo run --release -p game --example trace -- --file search.trace --iterations 10000
+//!     cargo run --release -p game --example trace -- --read search.trace
+
+extern crate connect_four;
+extern crate game;
+extern crate player_of_games;
+
+mod common;
+
+use std::fs::File;
+
+use connect_four::ConnectFour;
+use game::{PlayerEnum, Player};
+use player_of_games::MonteCarloTreeSearchPlayer;
+use player_of_games::trace::TraceAnalysis;
+
+fn main() {
+    let args = common::Args::parse();
+    args.seed_rng();
+
+    let path = match args.get_opt::<String>("read") {
+        Some(path) => path,
+        None => {
+            let path: String = args.get("file", "search.trace".to_owned());
+            let mut mcts = MonteCarloTreeSearchPlayer::<ConnectFour>::new(PlayerEnum::One, 2f64.sqrt());
+            mcts.set_budget(args.budget(10000));
+            mcts.set_trace(File::create(&path).expect("Couldn't create the trace file")).expect("Couldn't start the trace");
+            let chosen_move = mcts.choose_move(ConnectFour::new());
+            mcts.finish_trace().expect("Couldn't write the trace");
+            println!("Chose {:?}, traced to {}", chosen_move, path);
+            path
+        },
+    };
+
+    let file = File::open(&path).expect("Couldn't open the trace file");
+    let analysis = TraceAnalysis::from_reader(::std::io::BufReader::new(file)).expect("Couldn't read the trace");
+    print!("{}", analysis);
+}
@@ -0,0 +1,436 @@
+//! A fixed-depth alpha-beta (negamax) search, judging the positions at the horizon with a
+//! heuristic. Alpha-beta only cuts off much of the tree when it looks at good moves first, so it
+//! orders moves with killer moves (moves which caused a cutoff at the same depth elsewhere) and a
+//! history table (how much each move has caused cutoffs throughout the search).
+//!
+//! Stopping at a fixed depth can stop just before a threat is answered, so past the depth it keeps
+//! searching forcing moves (see `GameState::is_forcing`) until the position is quiet.
+//!
+//! With good ordering the first move tried is usually best, so the rest are searched with a null
+//! window, only to show they're no better (principal variation search), and searched again
+//! properly if they are. Each deepening iteration also starts with a narrow window around the last
+//! iteration's value (an aspiration window), widening it if the value falls outside.
+
+use std::collections::HashMap;
+
+use {value, CancellationToken, GameState, Heuristic, Player, PlayerEnum};
+
+/// Each ply a win takes costs this much of its value, so that quicker wins are preferred.
+const DISTANCE_PENALTY: f64 = 1e-4;
+
+/// The width of a null window, just enough to tell whether a move beats alpha.
+const NULL_WINDOW: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlphaBetaSettings {
+    /// How many moves ahead to look. The search deepens one ply at a time up to this, which costs
+    /// little more and fills in the move ordering tables.
+    pub depth: u32,
+    /// Killer moves remembered for each ply (0 turns them off).
+    pub killer_slots: usize,
+    /// Whether to order moves by the history table.
+    pub history: bool,
+    /// How many forcing moves to search past `depth` (0 turns this off).
+    pub quiescence_depth: u32,
+    /// Whether to search moves after the first with a null window.
+    pub principal_variation: bool,
+    /// How far either side of the last iteration's value each iteration's window starts, if at
+    /// all. This only saves work if the heuristic's values don't swing much from one depth to
+    /// the next, so it's off by default.
+    pub aspiration_window: Option<f64>,
+}
+
+impl Default for AlphaBetaSettings {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            killer_slots: 2,
+            history: true,
+            quiescence_depth: 4,
+            principal_variation: true,
+            aspiration_window: None,
+        }
+    }
+}
+
+/// What the last search did, to see how well the moves were ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AlphaBetaStats {
+    /// Positions searched, up to the depth.
+    pub nodes: u64,
+    /// Positions searched past the depth, following forcing moves.
+    pub quiescence_nodes: u64,
+    /// Positions where a move was good enough to stop looking at the rest.
+    pub cutoffs: u64,
+    /// Cutoffs from the first move tried, which is what good ordering gets.
+    pub first_move_cutoffs: u64,
+    /// Cutoffs from a killer move.
+    pub killer_cutoffs: u64,
+    /// Moves searched again with a full window, after a null window showed they were better.
+    pub researches: u64,
+    /// Iterations searched again with a full window, after the value fell outside the aspiration
+    /// window.
+    pub aspiration_failures: u64,
+    /// Moves in the history table.
+    pub history_entries: usize,
+}
+
+pub struct AlphaBetaPlayer<Game: GameState> {
+    player: PlayerEnum,
+    heuristic: Option<Box<dyn Heuristic<Game>>>,
+    settings: AlphaBetaSettings,
+    /// The killer moves at each ply from the root, most recent first.
+    killers: Vec<Vec<<Game as GameState>::Move>>,
+    history: HashMap<(PlayerEnum, <Game as GameState>::Move), u64>,
+    stats: AlphaBetaStats,
+    last_evaluation: Option<f64>,
+    cancellation: CancellationToken,
+    /// Whether cancelling should cut the current search short, which it only does once there's a
+    /// finished iteration to fall back on.
+    interruptible: bool,
+}
+
+impl<Game: GameState> AlphaBetaPlayer<Game> {
+    /// Without a heuristic, positions at the horizon are all treated as draws.
+    pub fn new(player: PlayerEnum, settings: AlphaBetaSettings) -> Self {
+        Self {
+            player,
+            heuristic: None,
+            settings,
+            killers: Vec::new(),
+            history: HashMap::new(),
+            stats: AlphaBetaStats::default(),
+            last_evaluation: None,
+            cancellation: CancellationToken::new(),
+            interruptible: false,
+        }
+    }
+
+    pub fn with_heuristic<H: Heuristic<Game> + 'static>(player: PlayerEnum, settings: AlphaBetaSettings, heuristic: H) -> Self {
+        let mut alpha_beta = Self::new(player, settings);
+        alpha_beta.heuristic = Some(Box::new(heuristic));
+        alpha_beta
+    }
+
+    pub fn settings(&self) -> AlphaBetaSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: AlphaBetaSettings) {
+        self.settings = settings;
+    }
+
+    /// What the last search did.
+    pub fn stats(&self) -> AlphaBetaStats {
+        self.stats
+    }
+
+    /// The value of `state` for `to_move`, searched to the full depth in one go, e.g. to check a
+    /// move another player is thinking of. The work counts towards `stats`.
+    pub fn evaluate(&mut self, state: &Game, to_move: PlayerEnum) -> f64 {
+        let depth = self.settings.depth;
+        self.interruptible = false;
+        self.search(&mut state.clone(), to_move, 0, depth, 0.0, 1.0)
+    }
+
+    /// `moves`, best first: the killer moves for `ply` first, then by the history table.
+    fn order(&self, moves: &mut [<Game as GameState>::Move], to_move: PlayerEnum, ply: usize) {
+        let killers = self.killers.get(ply);
+        let rank = |game_move: &<Game as GameState>::Move| {
+            let killer = killers.and_then(|killers| killers.iter().position(|killer| killer == game_move));
+            let history = if self.settings.history {
+                self.history.get(&(to_move, *game_move)).cloned().unwrap_or(0)
+            } else {
+                0
+            };
+            // Killers first (in order), then higher history scores
+            (killer.unwrap_or(usize::MAX), u64::MAX - history)
+        };
+        moves.sort_by_key(rank);
+    }
+
+    /// Remember that `game_move` caused a cutoff at `ply`, with `depth` plies left to search.
+    fn record_cutoff(&mut self, game_move: <Game as GameState>::Move, to_move: PlayerEnum, ply: usize, depth: u32) {
+        if self.settings.killer_slots > 0 {
+            if self.killers.len() <= ply {
+                self.killers.resize(ply + 1, Vec::new());
+            }
+            let killers = &mut self.killers[ply];
+            if !killers.contains(&game_move) {
+                killers.insert(0, game_move);
+                killers.truncate(self.settings.killer_slots);
+            }
+        }
+        if self.settings.history {
+            // Cutoffs near the root save more work
+            *self.history.entry((to_move, game_move)).or_insert(0) += u64::from(depth) * u64::from(depth);
+        }
+    }
+
+    /// The value of `state` for `to_move`, looking `depth` plies ahead, as far as it matters
+    /// within the window from `alpha` to `beta`.
+    fn search(&mut self, state: &mut Game, to_move: PlayerEnum, ply: usize, depth: u32, mut alpha: f64, beta: f64) -> f64 {
+        if depth == 0 {
+            let quiescence_depth = self.settings.quiescence_depth;
+            return self.quiescence(state, to_move, ply, quiescence_depth, alpha, beta);
+        }
+        if self.interruptible && self.cancellation.is_cancelled() {
+            // The iteration will be thrown away, so the value doesn't matter
+            return value::DRAW;
+        }
+        self.stats.nodes += 1;
+        if let Some(conclusion) = state.try_conclude(to_move) {
+            return value::by_distance(value::of_conclusion(conclusion, to_move), ply as u32, DISTANCE_PENALTY);
+        }
+
+        let mut moves: Vec<_> = state.all_legal_moves(to_move).collect();
+        self.order(&mut moves, to_move, ply);
+        let mut best = 0.0;
+        for (i, game_move) in moves.into_iter().enumerate() {
+            let move_value = state.with_move(game_move, to_move, |next| match i {
+                0 => self.search_after(next, to_move, ply, depth, alpha, beta),
+                _ => self.search_after_null_window(next, to_move, ply, depth, alpha, beta),
+            });
+            if move_value > best {
+                best = move_value;
+            }
+            if move_value > alpha {
+                alpha = move_value;
+            }
+            if alpha >= beta {
+                self.stats.cutoffs += 1;
+                if i == 0 {
+                    self.stats.first_move_cutoffs += 1;
+                }
+                if self.killers.get(ply).is_some_and(|killers| killers.contains(&game_move)) {
+                    self.stats.killer_cutoffs += 1;
+                }
+                self.record_cutoff(game_move, to_move, ply, depth);
+                break;
+            }
+        }
+        best
+    }
+
+    /// The value for `player` of `next`, the position after they moved, as far as it matters
+    /// within the window from `alpha` to `beta`.
+    fn search_after(&mut self, next: &mut Game, player: PlayerEnum, ply: usize, depth: u32, alpha: f64, beta: f64) -> f64 {
+        value::flip(self.search(next, player.other(), ply + 1, depth - 1, value::flip(beta), value::flip(alpha)))
+    }
+
+    /// The same as `search_after`, for moves after the first: try a null window first, and only
+    /// search properly if that shows the move beats `alpha`.
+    fn search_after_null_window(&mut self, next: &mut Game, player: PlayerEnum, ply: usize, depth: u32, alpha: f64, beta: f64) -> f64 {
+        if !self.settings.principal_variation {
+            return self.search_after(next, player, ply, depth, alpha, beta);
+        }
+        let move_value = self.search_after(next, player, ply, depth, alpha, alpha + NULL_WINDOW);
+        if move_value > alpha && move_value < beta {
+            self.stats.researches += 1;
+            // The null window search showed the move is worth at least this
+            return self.search_after(next, player, ply, depth, move_value, beta);
+        }
+        move_value
+    }
+
+    /// The best of `moves` for this player, and its value, searching `depth` plies within the
+    /// window from `alpha` to `beta`.
+    fn search_root(&mut self, game: &mut Game, moves: &[<Game as GameState>::Move], depth: u32, mut alpha: f64, beta: f64) -> (<Game as GameState>::Move, f64) {
+        let player = self.player;
+        let mut best = None;
+        for (i, &game_move) in moves.iter().enumerate() {
+            let move_value = game.with_move(game_move, player, |next| match i {
+                0 => self.search_after(next, player, 0, depth, alpha, beta),
+                _ => self.search_after_null_window(next, player, 0, depth, alpha, beta),
+            });
+            if best.map_or(true, |(_, best_value)| move_value > best_value) {
+                best = Some((game_move, move_value));
+            }
+            if move_value > alpha {
+                alpha = move_value;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best.expect("There were no legal moves")
+    }
+
+    /// The value of `state` for `to_move` past the depth: the heuristic's, unless one of up to
+    /// `depth` more forcing moves does better. Only `to_move` can choose to stop, so this doesn't
+    /// see forcing moves which would have to be answered with quiet ones.
+    fn quiescence(&mut self, state: &mut Game, to_move: PlayerEnum, ply: usize, depth: u32, mut alpha: f64, beta: f64) -> f64 {
+        self.stats.quiescence_nodes += 1;
+        if let Some(conclusion) = state.try_conclude(to_move) {
+            return value::by_distance(value::of_conclusion(conclusion, to_move), ply as u32, DISTANCE_PENALTY);
+        }
+        let mut best = match self.heuristic {
+            Some(ref heuristic) => heuristic.evaluate(state, to_move),
+            None => value::DRAW,
+        };
+        if depth == 0 || best >= beta {
+            return best;
+        }
+        if best > alpha {
+            alpha = best;
+        }
+
+        let forcing: Vec<_> = state.all_legal_moves(to_move).filter(|game_move| state.is_forcing(game_move, to_move)).collect();
+        for game_move in forcing {
+            let child_value = state.with_move(game_move, to_move, |next| {
+                self.quiescence(next, to_move.other(), ply + 1, depth - 1, value::flip(beta), value::flip(alpha))
+            });
+            let move_value = value::flip(child_value);
+            if move_value > best {
+                best = move_value;
+            }
+            if move_value > alpha {
+                alpha = move_value;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+impl<Game: GameState> Player<Game> for AlphaBetaPlayer<Game> {
+    fn choose_move(&mut self, mut game: Game) -> <Game as GameState>::Move {
+        let player = self.player;
+        self.stats = AlphaBetaStats::default();
+        // Killers are by ply from the root, which has moved on
+        self.killers.clear();
+
+        let mut moves: Vec<_> = game.all_legal_moves(player).collect();
+        let mut best = None;
+        for depth in 1..=self.settings.depth.max(1) {
+            self.interruptible = best.is_some();
+            if self.interruptible && self.cancellation.is_cancelled() {
+                break;
+            }
+            self.order(&mut moves, player, 0);
+            // Try last iteration's best move first
+            if let Some((best_move, _)) = best {
+                if let Some(i) = moves.iter().position(|game_move| *game_move == best_move) {
+                    let best_move = moves.remove(i);
+                    moves.insert(0, best_move);
+                }
+            }
+
+            let (alpha, beta) = match (best, self.settings.aspiration_window) {
+                (Some((_, previous)), Some(window)) => (value::clamp(previous - window), value::clamp(previous + window)),
+                _ => (0.0, 1.0),
+            };
+            let mut iteration_best = self.search_root(&mut game, &moves, depth, alpha, beta);
+            // Outside the window, the value is only a bound, so open the window on that side
+            if iteration_best.1 <= alpha && alpha > 0.0 {
+                self.stats.aspiration_failures += 1;
+                iteration_best = self.search_root(&mut game, &moves, depth, 0.0, beta);
+            } else if iteration_best.1 >= beta && beta < 1.0 {
+                self.stats.aspiration_failures += 1;
+                iteration_best = self.search_root(&mut game, &moves, depth, alpha, 1.0);
+            }
+            if self.interruptible && self.cancellation.is_cancelled() {
+                break;
+            }
+            best = Some(iteration_best);
+        }
+
+        // Older history counts for less as the game goes on
+        for count in self.history.values_mut() {
+            *count /= 2;
+        }
+        self.history.retain(|_, count| *count > 0);
+        self.stats.history_entries = self.history.len();
+
+        let (game_move, evaluation) = best.expect("Searched at least one iteration");
+        self.last_evaluation = Some(evaluation);
+        game_move
+    }
+
+    fn inform_of_move_played(&mut self, _new_state: Game, _game_move: &<Game as GameState>::Move) {
+        // noop
+    }
+
+    fn last_evaluation(&self) -> Option<f64> {
+        self.last_evaluation
+    }
+
+    /// Stop deepening when `token` is cancelled, playing the best move from the last finished
+    /// iteration. The first iteration is always finished, so that there's a move to play.
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_games::Nim;
+
+    /// Plain negamax, looking at every move, with the same scoring as the alpha-beta search.
+    fn minimax(state: &Nim, to_move: PlayerEnum, ply: u32, depth: u32) -> f64 {
+        if let Some(conclusion) = state.try_conclude(to_move) {
+            return value::by_distance(value::of_conclusion(conclusion, to_move), ply, DISTANCE_PENALTY);
+        }
+        if depth == 0 {
+            return value::DRAW;
+        }
+        state.all_legal_moves(to_move).map(|game_move| {
+            let mut next = state.clone();
+            next.update(game_move, to_move);
+            value::flip(minimax(&next, to_move.other(), ply + 1, depth - 1))
+        }).fold(0.0, f64::max)
+    }
+
+    fn all_settings(depth: u32) -> Vec<AlphaBetaSettings> {
+        let plain = AlphaBetaSettings {
+            depth,
+            killer_slots: 0,
+            history: false,
+            quiescence_depth: 0,
+            principal_variation: false,
+            aspiration_window: None,
+        };
+        vec![
+            plain,
+            AlphaBetaSettings { killer_slots: 2, history: true, ..plain },
+            AlphaBetaSettings { principal_variation: true, ..plain },
+            AlphaBetaSettings { depth, aspiration_window: Some(0.1), ..Default::default() },
+        ]
+    }
+
+    #[test]
+    fn agrees_with_minimax() {
+        for depth in 1..=7 {
+            for settings in all_settings(depth) {
+                let mut alpha_beta = AlphaBetaPlayer::new(PlayerEnum::One, settings);
+                for stones in 1..=10 {
+                    let state = Nim::new(stones);
+                    let expected = minimax(&state, PlayerEnum::One, 0, depth);
+                    let actual = alpha_beta.evaluate(&state, PlayerEnum::One);
+                    assert!((actual - expected).abs() < 1e-12, "{:?} from {} stones: {} isn't {}", settings, stones, actual, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chooses_a_move_minimax_rates_best() {
+        for depth in 1..=7 {
+            for settings in all_settings(depth) {
+                let mut alpha_beta = AlphaBetaPlayer::new(PlayerEnum::One, settings);
+                for stones in 1..=10 {
+                    let state = Nim::new(stones);
+                    let chosen = alpha_beta.choose_move(state.clone());
+                    let mut next = state.clone();
+                    next.update(chosen, PlayerEnum::One);
+                    let chosen_value = value::flip(minimax(&next, PlayerEnum::Two, 1, depth - 1));
+                    let best_value = minimax(&state, PlayerEnum::One, 0, depth);
+                    assert!((chosen_value - best_value).abs() < 1e-12, "{:?} took {} from {} stones", settings, chosen, stones);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Players (and an adjudicator to go with them) which choose their moves asynchronously.
+//!
+//! These are for players backed by IO - a remote engine, a database, a human on the other end of a
+//! network connection - which shouldn't block a thread while they think. No particular runtime is
+//! assumed: the futures here can be driven by any executor.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use {end_turn, Conclusion, DrawTracker, GameObserver, GameState, Player, PlayerEnum};
+
+/// The future a player hands back when asked for a move.
+///
+/// This doesn't borrow the player, so players which need their own state while thinking should
+/// keep it behind something they can clone into the future (e.g. an `Arc`). It must be `Send`, so
+/// that multi-threaded executors can move it between their threads.
+pub type MoveFuture<Game> = Pin<Box<dyn Future<Output = <Game as GameState>::Move> + Send>>;
+
+pub trait AsyncPlayer<Game: GameState> {
+    fn choose_move(&mut self, game: Game) -> MoveFuture<Game>;
+    fn inform_of_move_played(&mut self, new_state: Game, game_move: &<Game as GameState>::Move);
+}
+
+/// Adapts a normal (synchronous) player into an asynchronous one.
+///
+/// The move is chosen as soon as it's asked for, blocking the caller, and the returned future is
+/// immediately ready.
+pub struct BlockingPlayer<P>(pub P);
+
+impl<Game: GameState, P: Player<Game>> AsyncPlayer<Game> for BlockingPlayer<P> where <Game as GameState>::Move: Send {
+    fn choose_move(&mut self, game: Game) -> MoveFuture<Game> {
+        Box::pin(::std::future::ready(self.0.choose_move(game)))
+    }
+    fn inform_of_move_played(&mut self, new_state: Game, game_move: &<Game as GameState>::Move) {
+        self.0.inform_of_move_played(new_state, game_move)
+    }
+}
+
+/// The asynchronous counterpart of `Adjudicator`.
+pub struct AsyncAdjudicator<Game: GameState, PlayerOne: AsyncPlayer<Game>, PlayerTwo: AsyncPlayer<Game>> {
+    current_turn: PlayerEnum,
+    game_state: Game,
+    player_one: PlayerOne,
+    player_two: PlayerTwo,
+    conclusion: Option<Conclusion>,
+    observers: Vec<Box<dyn GameObserver<Game> + Send>>,
+    draw_tracker: DrawTracker<Game>,
+}
+
+impl<Game: GameState, PlayerOne: AsyncPlayer<Game>, PlayerTwo: AsyncPlayer<Game>> AsyncAdjudicator<Game, PlayerOne, PlayerTwo> {
+    pub fn new(game_state: Game, player_one: PlayerOne, player_two: PlayerTwo) -> Self {
+        Self::from_position(game_state, PlayerEnum::One, player_one, player_two)
+    }
+
+    /// Start the game from a position where `to_move` is to move. See
+    /// `Adjudicator::from_position`.
+    pub fn from_position(game_state: Game, to_move: PlayerEnum, player_one: PlayerOne, player_two: PlayerTwo) -> Self {
+        let draw_tracker = DrawTracker::new(&game_state, to_move);
+        let conclusion = draw_tracker.try_conclude(&game_state, to_move);
+        Self {
+            current_turn: if conclusion.is_some() { to_move.other() } else { to_move },
+            draw_tracker,
+            game_state,
+            player_one,
+            player_two,
+            conclusion,
+            observers: Vec::new(),
+        }
+    }
+
+    /// Have `observer` told about every move played from now on. Observers must be `Send`, so that
+    /// a turn in progress can be moved between threads along with the players.
+    pub fn add_observer<O: GameObserver<Game> + Send + 'static>(&mut self, observer: O) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Ask the current player for their move and play it. The turn is over once the returned
+    /// future completes. Once the game is over, the future completes straight away.
+    ///
+    /// As with `Adjudicator`, a player who chooses an illegal move forfeits the game.
+    pub fn progress_one_turn(&mut self) -> ProgressOneTurn<'_, Game, PlayerOne, PlayerTwo> {
+        ProgressOneTurn {
+            adjudicator: self,
+            pending_move: None,
+        }
+    }
+
+    pub fn conclusion(&self) -> Option<Conclusion> {
+        self.conclusion
+    }
+
+    /// The final score, once the game is over, if the game has one.
+    pub fn final_score(&self) -> Option<f64> {
+        self.conclusion.and_then(|_| self.game_state.final_score())
+    }
+
+    fn play_move(&mut self, chosen_move: <Game as GameState>::Move) {
+        if self.game_state.is_move_legal(chosen_move, self.current_turn).is_err() {
+            let conclusion = Conclusion::Win(self.current_turn.other());
+            self.conclusion = Some(conclusion);
+            for observer in self.observers.iter_mut() {
+                observer.on_conclusion(conclusion, None);
+            }
+            return;
+        }
+
+        self.draw_tracker.play(&mut self.game_state, chosen_move, self.current_turn);
+
+        self.player_one.inform_of_move_played(self.game_state.clone(), &chosen_move);
+        self.player_two.inform_of_move_played(self.game_state.clone(), &chosen_move);
+
+        for observer in self.observers.iter_mut() {
+            observer.on_move(self.current_turn, &chosen_move, &self.game_state, None);
+        }
+
+        end_turn(&self.game_state, &self.draw_tracker, &mut self.current_turn, &mut self.conclusion, &mut self.observers);
+    }
+}
+
+/// Future for a single turn of an `AsyncAdjudicator`.
+pub struct ProgressOneTurn<'a, Game: GameState, PlayerOne: AsyncPlayer<Game> + 'a, PlayerTwo: AsyncPlayer<Game> + 'a> {
+    adjudicator: &'a mut AsyncAdjudicator<Game, PlayerOne, PlayerTwo>,
+    pending_move: Option<MoveFuture<Game>>,
+}
+
+impl<'a, Game: GameState, PlayerOne: AsyncPlayer<Game>, PlayerTwo: AsyncPlayer<Game>> Future for ProgressOneTurn<'a, Game, PlayerOne, PlayerTwo> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = self.get_mut();
+        if this.adjudicator.conclusion.is_some() {
+            return Poll::Ready(());
+        }
+
+        if this.pending_move.is_none() {
+            let adjudicator = &mut *this.adjudicator;
+            let game = adjudicator.game_state.clone();
+            this.pending_move = Some(match adjudicator.current_turn {
+                PlayerEnum::One => adjudicator.player_one.choose_move(game),
+                PlayerEnum::Two => adjudicator.player_two.choose_move(game),
+            });
+        }
+
+        let chosen_move = match this.pending_move.as_mut().expect("No pending move").as_mut().poll(cx) {
+            Poll::Ready(chosen_move) => chosen_move,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        this.pending_move = None;
+        this.adjudicator.play_move(chosen_move);
+        Poll::Ready(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+
+    use super::*;
+    use test_games::Nim;
+
+    /// Always takes the same number of stones, whether it can or not.
+    struct Take(u32);
+
+    impl Player<Nim> for Take {
+        fn choose_move(&mut self, _game: Nim) -> u32 {
+            self.0
+        }
+
+        fn inform_of_move_played(&mut self, _new_state: Nim, _game_move: &u32) {}
+    }
+
+    struct NoWake;
+
+    impl Wake for NoWake {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Play a turn whose move is ready straight away.
+    fn play_turn<A: AsyncPlayer<Nim>, B: AsyncPlayer<Nim>>(adjudicator: &mut AsyncAdjudicator<Nim, A, B>) {
+        let waker = Waker::from(Arc::new(NoWake));
+        let mut turn = adjudicator.progress_one_turn();
+        assert_eq!(Pin::new(&mut turn).poll(&mut Context::from_waker(&waker)), Poll::Ready(()));
+    }
+
+    #[test]
+    fn plays_legal_moves() {
+        let mut adjudicator = AsyncAdjudicator::new(Nim::new(4), BlockingPlayer(Take(3)), BlockingPlayer(Take(1)));
+        play_turn(&mut adjudicator);
+        assert_eq!(adjudicator.conclusion(), None);
+        play_turn(&mut adjudicator);
+        assert_eq!(adjudicator.conclusion(), Some(Conclusion::Win(PlayerEnum::Two)));
+    }
+
+    #[test]
+    fn illegal_moves_forfeit() {
+        let mut adjudicator = AsyncAdjudicator::new(Nim::new(5), BlockingPlayer(Take(1)), BlockingPlayer(Take(4)));
+        play_turn(&mut adjudicator);
+        play_turn(&mut adjudicator);
+        assert_eq!(adjudicator.conclusion(), Some(Conclusion::Win(PlayerEnum::One)));
+        assert_eq!(adjudicator.game_state.stones, 4, "The illegal move was played");
+    }
+}
@@ -0,0 +1,91 @@
+//! Cheap players which are a bit better than random, for calibrating stronger players against.
+
+use {random_sample, value, GameState, Heuristic, Player, PlayerEnum};
+
+/// Looks one move ahead: plays a move which wins on the spot if there is one, and otherwise the
+/// move leading to the position the heuristic likes best (or a random move, without a heuristic).
+pub struct GreedyPlayer<Game: GameState> {
+    player: PlayerEnum,
+    heuristic: Option<Box<dyn Heuristic<Game>>>,
+}
+
+impl<Game: GameState> GreedyPlayer<Game> {
+    pub fn new(player: PlayerEnum) -> Self {
+        Self { player, heuristic: None }
+    }
+
+    pub fn with_heuristic<H: Heuristic<Game> + 'static>(player: PlayerEnum, heuristic: H) -> Self {
+        Self { player, heuristic: Some(Box::new(heuristic)) }
+    }
+}
+
+impl<Game: GameState> Player<Game> for GreedyPlayer<Game> {
+    fn choose_move(&mut self, mut game: Game) -> <Game as GameState>::Move {
+        let player = self.player;
+        let heuristic = &self.heuristic;
+        best_moves(&mut game, player, |next| {
+            match (next.try_conclude(player.other()), heuristic) {
+                (Some(conclusion), _) => value::of_conclusion(conclusion, player),
+                (None, Some(heuristic)) => heuristic.evaluate(next, player),
+                (None, None) => value::DRAW,
+            }
+        })
+    }
+    fn inform_of_move_played(&mut self, _new_state: Game, _game_move: &<Game as GameState>::Move) {
+        // noop
+    }
+}
+
+/// Looks two moves ahead: plays the move whose worst reply leaves the best position. So it wins
+/// when it can, and otherwise doesn't let the opponent win straight away if it can help it.
+/// Positions after the reply are judged by the heuristic, if there is one.
+pub struct TwoPlyPlayer<Game: GameState> {
+    player: PlayerEnum,
+    heuristic: Option<Box<dyn Heuristic<Game>>>,
+}
+
+impl<Game: GameState> TwoPlyPlayer<Game> {
+    pub fn new(player: PlayerEnum) -> Self {
+        Self { player, heuristic: None }
+    }
+
+    pub fn with_heuristic<H: Heuristic<Game> + 'static>(player: PlayerEnum, heuristic: H) -> Self {
+        Self { player, heuristic: Some(Box::new(heuristic)) }
+    }
+}
+
+impl<Game: GameState> Player<Game> for TwoPlyPlayer<Game> {
+    fn choose_move(&mut self, mut game: Game) -> <Game as GameState>::Move {
+        let player = self.player;
+        let heuristic = &self.heuristic;
+        best_moves(&mut game, player, |next| {
+            if let Some(conclusion) = next.try_conclude(player.other()) {
+                return value::of_conclusion(conclusion, player);
+            }
+            let replies: Vec<_> = next.all_legal_moves(player.other()).collect();
+            replies.into_iter().map(|reply| {
+                next.with_move(reply, player.other(), |after_reply| {
+                    match (after_reply.try_conclude(player), heuristic) {
+                        (Some(conclusion), _) => value::of_conclusion(conclusion, player),
+                        (None, Some(heuristic)) => heuristic.evaluate(after_reply, player),
+                        (None, None) => value::DRAW,
+                    }
+                })
+            }).fold(1.0, f64::min)
+        })
+    }
+    fn inform_of_move_played(&mut self, _new_state: Game, _game_move: &<Game as GameState>::Move) {
+        // noop
+    }
+}
+
+/// Pick (at random) one of the moves which `value` rates highest. `value` is given the state
+/// after the move, and must leave it as it was.
+fn best_moves<Game: GameState, F: Fn(&mut Game) -> f64>(game: &mut Game, player: PlayerEnum, value: F) -> <Game as GameState>::Move {
+    let moves: Vec<_> = game.all_legal_moves(player).collect();
+    let values: Vec<_> = moves.into_iter().map(|game_move| {
+        (game_move, game.with_move(game_move, player, &value))
+    }).collect();
+    let best = values.iter().map(|x| x.1).fold(f64::NEG_INFINITY, f64::max);
+    random_sample(values.into_iter().filter(|x| x.1 >= best).map(|x| x.0)).expect("There were no legal moves")
+}
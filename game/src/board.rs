@@ -0,0 +1,250 @@
+//! Boards which aren't (necessarily) square grids: a `BoardTopology` says what cells there are,
+//! which are next to each other, and which run in lines, so that win detection, rendering and
+//! heat maps can be written once for square grids, hex boards and arbitrary graphs alike.
+//!
+//! A position on a board is a slice with an entry per cell, indexed by `Cell`.
+
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+/// Identifies a cell of a board, from 0 up to the board's `cell_count`.
+pub type Cell = usize;
+
+/// The shape of a board.
+pub trait BoardTopology: Debug {
+    fn cell_count(&self) -> usize;
+
+    /// The cells next to `cell`.
+    fn neighbours(&self, cell: Cell) -> Vec<Cell>;
+
+    /// Every run of `length` cells in a line, e.g. along rows, columns and diagonals, in order
+    /// along the line.
+    fn lines(&self, length: usize) -> Vec<Vec<Cell>>;
+
+    /// Where to draw `cell` in text, as a row and column of characters.
+    fn position(&self, cell: Cell) -> (usize, usize);
+}
+
+/// A `width` by `height` grid, with cells numbered along the rows from `(0, 0)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SquareGrid {
+    pub width: usize,
+    pub height: usize,
+    /// Whether diagonal cells are neighbours, and diagonals are lines.
+    pub diagonals: bool,
+}
+
+impl SquareGrid {
+    pub fn new(width: usize, height: usize, diagonals: bool) -> Self {
+        Self { width, height, diagonals }
+    }
+
+    /// The cell at `(x, y)`, if it's on the board.
+    pub fn cell(&self, x: isize, y: isize) -> Option<Cell> {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            Some(y as usize * self.width + x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn coordinates(&self, cell: Cell) -> (usize, usize) {
+        (cell % self.width, cell / self.width)
+    }
+
+    fn directions(&self) -> &'static [(isize, isize)] {
+        if self.diagonals {
+            &[(1, 0), (0, 1), (1, 1), (1, -1)]
+        } else {
+            &[(1, 0), (0, 1)]
+        }
+    }
+}
+
+impl BoardTopology for SquareGrid {
+    fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn neighbours(&self, cell: Cell) -> Vec<Cell> {
+        let (x, y) = self.coordinates(cell);
+        self.directions().iter()
+            .flat_map(|&(dx, dy)| vec![(dx, dy), (-dx, -dy)])
+            .filter_map(|(dx, dy)| self.cell(x as isize + dx, y as isize + dy))
+            .collect()
+    }
+
+    fn lines(&self, length: usize) -> Vec<Vec<Cell>> {
+        straight_lines(self.cell_count(), length, self.directions(), |cell| {
+            let (x, y) = self.coordinates(cell);
+            (x as isize, y as isize)
+        }, |x, y| self.cell(x, y))
+    }
+
+    fn position(&self, cell: Cell) -> (usize, usize) {
+        let (x, y) = self.coordinates(cell);
+        (y, x)
+    }
+}
+
+/// A rhombus of hexagons, `size` on a side, as in the game of Hex. Cells are numbered along the
+/// rows, and each row is drawn shifted right of the one above.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexGrid {
+    pub size: usize,
+}
+
+impl HexGrid {
+    pub fn new(size: usize) -> Self {
+        Self { size }
+    }
+
+    /// The cell in column `q` of row `r`, if it's on the board.
+    pub fn cell(&self, q: isize, r: isize) -> Option<Cell> {
+        if q >= 0 && r >= 0 && (q as usize) < self.size && (r as usize) < self.size {
+            Some(r as usize * self.size + q as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The column and row of `cell`.
+    pub fn coordinates(&self, cell: Cell) -> (usize, usize) {
+        (cell % self.size, cell / self.size)
+    }
+
+    /// The cells of row `r`, e.g. the top and bottom edges are rows 0 and `size - 1`.
+    pub fn row(&self, r: usize) -> Vec<Cell> {
+        (0..self.size).map(|q| r * self.size + q).collect()
+    }
+
+    /// The cells of column `q`, e.g. the left and right edges are columns 0 and `size - 1`.
+    pub fn column(&self, q: usize) -> Vec<Cell> {
+        (0..self.size).map(|r| r * self.size + q).collect()
+    }
+}
+
+/// The three axes of a hex grid, in column and row steps.
+const HEX_AXES: [(isize, isize); 3] = [(1, 0), (0, 1), (1, -1)];
+
+impl BoardTopology for HexGrid {
+    fn cell_count(&self) -> usize {
+        self.size * self.size
+    }
+
+    fn neighbours(&self, cell: Cell) -> Vec<Cell> {
+        let (q, r) = self.coordinates(cell);
+        HEX_AXES.iter()
+            .flat_map(|&(dq, dr)| vec![(dq, dr), (-dq, -dr)])
+            .filter_map(|(dq, dr)| self.cell(q as isize + dq, r as isize + dr))
+            .collect()
+    }
+
+    fn lines(&self, length: usize) -> Vec<Vec<Cell>> {
+        straight_lines(self.cell_count(), length, &HEX_AXES, |cell| {
+            let (q, r) = self.coordinates(cell);
+            (q as isize, r as isize)
+        }, |q, r| self.cell(q, r))
+    }
+
+    fn position(&self, cell: Cell) -> (usize, usize) {
+        let (q, r) = self.coordinates(cell);
+        (r, 2 * q + r)
+    }
+}
+
+/// Any board, given as which cells are next to which, and the lines through them (runs of any
+/// length along each are lines), e.g. for games on graphs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphBoard {
+    /// Each cell's neighbours.
+    pub adjacency: Vec<Vec<Cell>>,
+    pub lines: Vec<Vec<Cell>>,
+    /// Where to draw each cell. Cells without a position are drawn one per row.
+    pub positions: Vec<(usize, usize)>,
+}
+
+impl BoardTopology for GraphBoard {
+    fn cell_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    fn neighbours(&self, cell: Cell) -> Vec<Cell> {
+        self.adjacency[cell].clone()
+    }
+
+    fn lines(&self, length: usize) -> Vec<Vec<Cell>> {
+        if length == 0 {
+            return Vec::new();
+        }
+        self.lines.iter().flat_map(|line| line.windows(length).map(|run| run.to_vec())).collect()
+    }
+
+    fn position(&self, cell: Cell) -> (usize, usize) {
+        self.positions.get(cell).cloned().unwrap_or((cell, 0))
+    }
+}
+
+/// The runs of `length` cells from each cell in each direction which stay on the board.
+fn straight_lines<C, F>(cell_count: usize, length: usize, directions: &[(isize, isize)], coordinates: C, cell: F) -> Vec<Vec<Cell>>
+    where C: Fn(Cell) -> (isize, isize),
+          F: Fn(isize, isize) -> Option<Cell>
+{
+    match length {
+        0 => return Vec::new(),
+        // A single cell runs in every direction, but is only one line.
+        1 => return (0..cell_count).map(|cell| vec![cell]).collect(),
+        _ => (),
+    }
+    let mut lines = Vec::new();
+    for start in 0..cell_count {
+        let (x, y) = coordinates(start);
+        for &(dx, dy) in directions {
+            let line: Option<Vec<Cell>> = (0..length as isize).map(|i| cell(x + i * dx, y + i * dy)).collect();
+            if let Some(line) = line {
+                lines.push(line);
+            }
+        }
+    }
+    lines
+}
+
+/// A line of `length` cells all holding `piece`, if there is one.
+pub fn winning_line<B: BoardTopology + ?Sized, T: PartialEq>(board: &B, cells: &[T], length: usize, piece: &T) -> Option<Vec<Cell>> {
+    board.lines(length).into_iter().find(|line| line.iter().all(|&cell| cells[cell] == *piece))
+}
+
+/// Whether a chain of neighbouring cells holding `piece` joins one of `from` to one of `to`,
+/// e.g. for connection games like Hex.
+pub fn connects<B: BoardTopology + ?Sized, T: PartialEq>(board: &B, cells: &[T], piece: &T, from: &[Cell], to: &[Cell]) -> bool {
+    let mut seen = vec![false; board.cell_count()];
+    let mut queue: VecDeque<Cell> = from.iter().cloned().filter(|&cell| cells[cell] == *piece).collect();
+    for &cell in queue.iter() {
+        seen[cell] = true;
+    }
+    while let Some(cell) = queue.pop_front() {
+        if to.contains(&cell) {
+            return true;
+        }
+        for neighbour in board.neighbours(cell) {
+            if !seen[neighbour] && cells[neighbour] == *piece {
+                seen[neighbour] = true;
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    false
+}
+
+/// Draw the board as text, with `symbol` for what's in each cell, e.g. a piece or a heat map's
+/// shading.
+pub fn render<B: BoardTopology + ?Sized, T, F: Fn(&T) -> char>(board: &B, cells: &[T], symbol: F) -> String {
+    let positions: Vec<(usize, usize)> = (0..board.cell_count()).map(|cell| board.position(cell)).collect();
+    let rows = positions.iter().map(|p| p.0 + 1).max().unwrap_or(0);
+    let columns = positions.iter().map(|p| p.1 + 1).max().unwrap_or(0);
+    let mut grid = vec![vec![' '; columns]; rows];
+    for (cell, &(row, column)) in positions.iter().enumerate() {
+        grid[row][column] = symbol(&cells[cell]);
+    }
+    grid.iter().map(|row| row.iter().collect::<String>().trim_end().to_string()).collect::<Vec<_>>().join("\n")
+}
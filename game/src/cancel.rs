@@ -0,0 +1,73 @@
+//! Cancelling long-running work (searches, tournaments, self-play) from outside, e.g. from a
+//! server's request handler or a GUI's stop button.
+//!
+//! Cancellation is cooperative: the work checks its token every so often and winds down when it's
+//! cancelled, so it stops promptly without being killed halfway through.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Cancels whatever it's been handed to. Clones share the same cancellation, so one can be kept
+/// to cancel with while the others are handed out. Once cancelled, a token stays cancelled:
+/// hand out a new one for the next piece of work.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    /// Tasks waiting on `cancelled`.
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the work, waking anything waiting on `cancelled`.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+        let wakers = ::std::mem::take(&mut *self.inner.wakers.lock().expect("Waker list poisoned"));
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// A future which completes once this is cancelled, e.g. to race against a `MoveFuture`.
+    pub fn cancelled(&self) -> Cancelled {
+        Cancelled(self.clone())
+    }
+}
+
+/// The future returned by `CancellationToken::cancelled`.
+#[derive(Debug)]
+pub struct Cancelled(CancellationToken);
+
+impl Future for Cancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.0.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.0.inner.wakers.lock().expect("Waker list poisoned");
+        // Check again now the list is locked, in case it was cancelled in between
+        if self.0.is_cancelled() {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
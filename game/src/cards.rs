@@ -0,0 +1,318 @@
+//! Playing cards, for games with hidden information: a standard deck, shuffled with this thread's
+//! random number generator (so seeding it with `rng::seed_thread_rng` makes deals repeatable), and
+//! hands which hash the same however their cards were dealt.
+//!
+//! ```ignore
+//! game::rng::seed_thread_rng(7);
+//! let mut deck = Deck::standard();
+//! deck.shuffle();
+//! let hands: Vec<Hand> = (0..4).map(|_| deck.deal(13)).collect();
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use rng::random_f64;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+    /// `c`, `d`, `h` or `s`.
+    pub fn symbol(self) -> char {
+        match self {
+            Suit::Clubs => 'c',
+            Suit::Diamonds => 'd',
+            Suit::Hearts => 'h',
+            Suit::Spades => 's',
+        }
+    }
+}
+
+/// Ranks in order, with aces high.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Rank {
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six, Rank::Seven, Rank::Eight,
+        Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King, Rank::Ace,
+    ];
+
+    /// From 2 for a two up to 14 for an ace.
+    pub fn value(self) -> u8 {
+        self as u8 + 2
+    }
+
+    /// `2` to `9`, then `T`, `J`, `Q`, `K` and `A`.
+    pub fn symbol(self) -> char {
+        b"23456789TJQKA"[self as usize] as char
+    }
+}
+
+/// Cards are ordered by suit, then rank.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Card {
+    pub suit: Suit,
+    pub rank: Rank,
+}
+
+impl Card {
+    pub fn new(rank: Rank, suit: Suit) -> Self {
+        Self { rank, suit }
+    }
+}
+
+/// Rank then suit, e.g. `Ts` for the ten of spades.
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.rank.symbol(), self.suit.symbol())
+    }
+}
+
+impl FromStr for Card {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut chars = text.trim().chars();
+        let (rank, suit) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(rank), Some(suit), None) => (rank.to_ascii_uppercase(), suit.to_ascii_lowercase()),
+            _ => return Err(format!("Expected a card like Ts, not '{}'", text)),
+        };
+        let rank = Rank::ALL.iter().find(|r| r.symbol() == rank).ok_or_else(|| format!("Unknown rank in '{}'", text))?;
+        let suit = Suit::ALL.iter().find(|s| s.symbol() == suit).ok_or_else(|| format!("Unknown suit in '{}'", text))?;
+        Ok(Card::new(*rank, *suit))
+    }
+}
+
+/// Shuffle anything with this thread's random number generator (Fisher-Yates), e.g. cards which
+/// aren't from a standard deck.
+pub fn shuffle<T>(items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let j = ((random_f64() * (i + 1) as f64) as usize).min(i);
+        items.swap(i, j);
+    }
+}
+
+/// Cards to be dealt, from the top (the end of `cards`).
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Deck {
+    cards: Vec<Card>,
+}
+
+impl Deck {
+    /// The last of `cards` is dealt first. Hands are sets, so a deck made of several packs deals
+    /// hands which hold one of each duplicated card: use `draw` to keep every copy.
+    pub fn new(cards: Vec<Card>) -> Self {
+        Self { cards }
+    }
+
+    /// All 52 cards, in order.
+    pub fn standard() -> Self {
+        Self::new(Suit::ALL.iter().flat_map(|&suit| Rank::ALL.iter().map(move |&rank| Card::new(rank, suit))).collect())
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// Shuffle with this thread's random number generator.
+    pub fn shuffle(&mut self) {
+        shuffle(&mut self.cards);
+    }
+
+    /// The top card, if there are any left.
+    pub fn draw(&mut self) -> Option<Card> {
+        self.cards.pop()
+    }
+
+    /// A hand of the top `count` cards, or all that are left if there aren't enough. Duplicates
+    /// among them are dealt once.
+    pub fn deal(&mut self, count: usize) -> Hand {
+        let at = self.cards.len().saturating_sub(count);
+        self.cards.split_off(at).into_iter().collect()
+    }
+
+    /// Put `cards` back on the bottom, e.g. before reshuffling.
+    pub fn put_back<I: IntoIterator<Item = Card>>(&mut self, cards: I) {
+        let mut cards: Vec<Card> = cards.into_iter().collect();
+        cards.append(&mut self.cards);
+        self.cards = cards;
+    }
+}
+
+/// A set of cards, kept sorted so that equal hands compare and hash the same whatever order the
+/// cards arrived in, as states must for the tree search to recognise them.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Hand {
+    cards: Vec<Card>,
+}
+
+impl Hand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cards, in order of suit and then rank.
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.cards.binary_search(&card).is_ok()
+    }
+
+    /// Add `card`, returning false if it was already in the hand.
+    pub fn insert(&mut self, card: Card) -> bool {
+        match self.cards.binary_search(&card) {
+            Ok(_) => false,
+            Err(at) => {
+                self.cards.insert(at, card);
+                true
+            },
+        }
+    }
+
+    /// Take `card` out, returning false if it wasn't in the hand.
+    pub fn remove(&mut self, card: Card) -> bool {
+        match self.cards.binary_search(&card) {
+            Ok(at) => {
+                self.cards.remove(at);
+                true
+            },
+            Err(_) => false,
+        }
+    }
+
+    /// The cards of `suit`, lowest first, e.g. to follow suit.
+    pub fn of_suit(&self, suit: Suit) -> impl Iterator<Item = Card> + '_ {
+        self.cards.iter().cloned().filter(move |card| card.suit == suit)
+    }
+}
+
+impl ::std::iter::FromIterator<Card> for Hand {
+    fn from_iter<I: IntoIterator<Item = Card>>(cards: I) -> Self {
+        let mut hand = Hand::new();
+        for card in cards {
+            hand.insert(card);
+        }
+        hand
+    }
+}
+
+/// The cards separated by spaces, e.g. `2c Tc Ah`.
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cards: Vec<String> = self.cards.iter().map(|card| card.to_string()).collect();
+        write!(f, "{}", cards.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn card(text: &str) -> Card {
+        text.parse().unwrap()
+    }
+
+    fn hash_of(hand: &Hand) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hand.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn deals_the_top_cards() {
+        let mut deck = Deck::new(vec![card("2c"), card("3d"), card("4h"), card("5s")]);
+        let hand = deck.deal(2);
+        assert_eq!(hand.cards(), &[card("4h"), card("5s")]);
+        assert_eq!(deck.cards(), &[card("2c"), card("3d")]);
+    }
+
+    #[test]
+    fn deals_what_is_left_of_a_short_deck() {
+        let mut deck = Deck::new(vec![card("2c"), card("3d")]);
+        assert_eq!(deck.deal(5).len(), 2);
+        assert!(deck.is_empty());
+        assert!(deck.deal(1).is_empty());
+    }
+
+    #[test]
+    fn deals_duplicates_once() {
+        let mut deck = Deck::new(vec![card("As"), card("As"), card("Kd")]);
+        assert_eq!(deck.deal(3).cards(), &[card("Kd"), card("As")]);
+    }
+
+    #[test]
+    fn puts_cards_back_on_the_bottom_in_order() {
+        let mut deck = Deck::new(vec![card("2c"), card("3d")]);
+        deck.put_back(vec![card("Ah"), card("Ks")]);
+        assert_eq!(deck.cards(), &[card("Ah"), card("Ks"), card("2c"), card("3d")]);
+        assert_eq!(deck.draw(), Some(card("3d")));
+    }
+
+    #[test]
+    fn cards_round_trip_through_text() {
+        for card in Deck::standard().cards() {
+            assert_eq!(card.to_string().parse::<Card>(), Ok(*card));
+        }
+        assert_eq!("tS".parse::<Card>(), Ok(Card::new(Rank::Ten, Suit::Spades)));
+        assert!("1s".parse::<Card>().is_err());
+        assert!("Tx".parse::<Card>().is_err());
+        assert!("Tsx".parse::<Card>().is_err());
+    }
+
+    #[test]
+    fn hands_are_equal_whatever_the_order() {
+        let one: Hand = vec![card("Ah"), card("2c"), card("Ts")].into_iter().collect();
+        let other: Hand = vec![card("Ts"), card("Ah"), card("2c")].into_iter().collect();
+        assert_eq!(one, other);
+        assert_eq!(hash_of(&one), hash_of(&other));
+        assert_eq!(one.to_string(), "2c Ah Ts");
+    }
+}
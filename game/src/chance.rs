@@ -0,0 +1,10 @@
+use {GameState, PlayerEnum};
+
+/// Games with chance, e.g. dice rolled after each move. `update` settles chance at random (with
+/// `rng`'s generator), so the game plays as usual, and searching players can look at every way it
+/// could have gone instead.
+pub trait Stochastic: GameState {
+    /// Each state `player` playing `game_move` from here could lead to, with its probability. The
+    /// probabilities should add up to 1, and `update` should pick between these.
+    fn outcomes(&self, game_move: Self::Move, player: PlayerEnum) -> Vec<(Self, f64)>;
+}
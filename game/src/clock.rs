@@ -0,0 +1,74 @@
+//! Where players and adjudicators get the time from, so that it can be faked, e.g. to test
+//! time-based behaviour without waiting, or on platforms without a system clock.
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub trait Clock: Debug {
+    /// The time since some fixed point, which is up to the clock. Only the differences between
+    /// readings mean anything.
+    fn now(&self) -> Duration;
+}
+
+/// The system's monotonic clock.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A clock which only moves when told to. Clones share the same time, so keep one to move the
+/// time on for whatever the others were given to.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl MockClock {
+    /// A clock stopped at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().expect("Clock poisoned") += duration;
+    }
+
+    pub fn set(&self, now: Duration) {
+        *self.now.lock().expect("Clock poisoned") = now;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().expect("Clock poisoned")
+    }
+}
+
+/// How much time each player gets for the whole game: `initial` to begin with, plus `increment`
+/// after each of their moves. A player who runs out loses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeControl {
+    pub initial: Duration,
+    pub increment: Duration,
+}
@@ -0,0 +1,109 @@
+//! Copy-on-write game states. Cloning a `CowState` only copies a pointer, and the game is only
+//! copied when a clone is changed while shared. This is worth it for games with big boards in a
+//! `Vec` or an array, where searches clone states they only look at, e.g. to remember positions.
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use {Conclusion, DrawRules, GameState, IllegalMove, MoveNotation, Notation, PlayerEnum};
+
+/// A game, shared until it's changed. It plays just like the game it holds, so it can be given to
+/// any player in its place, e.g. `MonteCarloTreeSearchPlayer<CowState<Go>>`.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct CowState<Game: GameState>(Arc<Game>);
+
+impl<Game: GameState> CowState<Game> {
+    pub fn new(game: Game) -> Self {
+        CowState(Arc::new(game))
+    }
+
+    /// The game, copied if it's still shared.
+    pub fn into_inner(self) -> Game {
+        Arc::try_unwrap(self.0).unwrap_or_else(|shared| (*shared).clone())
+    }
+}
+
+impl<Game: GameState> From<Game> for CowState<Game> {
+    fn from(game: Game) -> Self {
+        Self::new(game)
+    }
+}
+
+impl<Game: GameState> Deref for CowState<Game> {
+    type Target = Game;
+
+    fn deref(&self) -> &Game {
+        &self.0
+    }
+}
+
+/// The same as the game's.
+impl<Game: GameState> fmt::Debug for CowState<Game> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The same as the game's.
+impl<Game: GameState + fmt::Display> fmt::Display for CowState<Game> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<Game: GameState> GameState for CowState<Game> {
+    type Move = <Game as GameState>::Move;
+
+    fn update(&mut self, game_move: Self::Move, player: PlayerEnum) {
+        Arc::make_mut(&mut self.0).update(game_move, player);
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: PlayerEnum) -> Box<dyn Iterator<Item = Self::Move> + 'a> {
+        self.0.all_legal_moves(player)
+    }
+
+    fn try_conclude(&self, next_player: PlayerEnum) -> Option<Conclusion> {
+        self.0.try_conclude(next_player)
+    }
+
+    fn is_move_legal(&self, game_move: Self::Move, player: PlayerEnum) -> Result<(), IllegalMove> {
+        self.0.is_move_legal(game_move, player)
+    }
+
+    fn final_score(&self) -> Option<f64> {
+        self.0.final_score()
+    }
+
+    fn draw_rules() -> DrawRules {
+        Game::draw_rules()
+    }
+
+    fn is_progress(&self, game_move: &Self::Move, player: PlayerEnum) -> bool {
+        self.0.is_progress(game_move, player)
+    }
+
+    fn is_forcing(&self, game_move: &Self::Move, player: PlayerEnum) -> bool {
+        self.0.is_forcing(game_move, player)
+    }
+}
+
+impl<Game: Notation> Notation for CowState<Game> {
+    fn from_notation(text: &str) -> Result<(Self, PlayerEnum), String> {
+        Game::from_notation(text).map(|(game, to_move)| (Self::new(game), to_move))
+    }
+
+    fn to_notation(&self, to_move: PlayerEnum) -> String {
+        self.0.to_notation(to_move)
+    }
+}
+
+impl<Game: MoveNotation> MoveNotation for CowState<Game> {
+    fn parse_move(&self, text: &str, player: PlayerEnum) -> Result<Self::Move, String> {
+        self.0.parse_move(text, player)
+    }
+
+    fn move_notation(game_move: &Self::Move) -> String {
+        Game::move_notation(game_move)
+    }
+}
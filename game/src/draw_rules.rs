@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use {Conclusion, GameState, PlayerEnum};
+
+/// Draw rules which the framework can enforce on a game's behalf, so that games don't each need
+/// to track their own history. Games opt in with `GameState::draw_rules`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawRules {
+    /// Draw once the same position (with the same player to move) has come up this many times.
+    pub repetitions: Option<u32>,
+    /// Draw after this many moves in a row without progress, as judged by
+    /// `GameState::is_progress`.
+    pub moves_without_progress: Option<u32>,
+}
+
+/// Keeps track of a game's history, to apply its draw rules.
+///
+/// Whatever plays the game (the adjudicators, or a search's random simulations) should play
+/// every move through `play`, and conclude the game with `try_conclude`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(bound(
+    serialize = "Game: ::serde::Serialize",
+    deserialize = "Game: ::serde::Deserialize<'de>",
+)))]
+pub struct DrawTracker<Game: GameState> {
+    #[cfg_attr(feature = "serialize", serde(skip, default = "Game::draw_rules"))]
+    rules: DrawRules,
+    #[cfg_attr(feature = "serialize", serde(with = "positions_as_list"))]
+    positions: HashMap<(Game, PlayerEnum), u32>,
+    repetition_draw: bool,
+    moves_without_progress: u32,
+}
+
+impl<Game: GameState> DrawTracker<Game> {
+    /// Start tracking a game from `state`, with `to_move` to play.
+    pub fn new(state: &Game, to_move: PlayerEnum) -> Self {
+        let mut tracker = Self {
+            rules: Game::draw_rules(),
+            positions: HashMap::new(),
+            repetition_draw: false,
+            moves_without_progress: 0,
+        };
+        tracker.record_position(state, to_move);
+        tracker
+    }
+
+    /// Play `game_move` for `player`, updating the state and the history.
+    pub fn play(&mut self, state: &mut Game, game_move: <Game as GameState>::Move, player: PlayerEnum) {
+        if self.rules.moves_without_progress.is_some() {
+            if state.is_progress(&game_move, player) {
+                self.moves_without_progress = 0;
+            } else {
+                self.moves_without_progress += 1;
+            }
+        }
+        state.update(game_move, player);
+        self.record_position(state, player.other());
+    }
+
+    /// Whether the draw rules say the game is drawn.
+    pub fn is_draw(&self) -> bool {
+        self.repetition_draw || self.rules.moves_without_progress.is_some_and(|max| self.moves_without_progress >= max)
+    }
+
+    /// Like `GameState::try_conclude`, but also applying the draw rules. A win on the board takes
+    /// precedence over a draw by the rules.
+    pub fn try_conclude(&self, state: &Game, next_player: PlayerEnum) -> Option<Conclusion> {
+        match state.try_conclude(next_player) {
+            Some(conclusion) => Some(conclusion),
+            None if self.is_draw() => Some(Conclusion::Draw),
+            None => None,
+        }
+    }
+
+    fn record_position(&mut self, state: &Game, to_move: PlayerEnum) {
+        let repetitions = match self.rules.repetitions {
+            Some(x) => x,
+            None => return,
+        };
+        let count = self.positions.entry((state.clone(), to_move)).or_insert(0);
+        *count += 1;
+        if *count >= repetitions {
+            self.repetition_draw = true;
+        }
+    }
+}
+
+/// Positions and how often they've come up, as a list, as their keys aren't strings.
+#[cfg(feature = "serialize")]
+mod positions_as_list {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use {GameState, PlayerEnum};
+
+    pub fn serialize<Game: GameState + Serialize, S: Serializer>(positions: &HashMap<(Game, PlayerEnum), u32>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(positions.iter())
+    }
+
+    pub fn deserialize<'de, Game: GameState + Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<(Game, PlayerEnum), u32>, D::Error> {
+        Vec::<((Game, PlayerEnum), u32)>::deserialize(deserializer).map(|positions| positions.into_iter().collect())
+    }
+}
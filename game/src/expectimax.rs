@@ -0,0 +1,103 @@
+//! A fixed-depth search for games with chance (see `Stochastic`): the players each play their best
+//! move, and chance is averaged over, weighting each outcome by its probability. Positions at the
+//! horizon are judged with a heuristic.
+//!
+//! Chance rules out alpha-beta's cutoffs, so the search is exhaustive, and only practical to a few
+//! moves deep.
+
+use {value, GameState, Heuristic, Player, PlayerEnum, Stochastic};
+
+/// Each ply a win takes costs this much of its value, so that quicker wins are preferred.
+const DISTANCE_PENALTY: f64 = 1e-4;
+
+pub struct ExpectimaxPlayer<Game: Stochastic> {
+    player: PlayerEnum,
+    depth: u32,
+    heuristic: Option<Box<dyn Heuristic<Game>>>,
+    nodes: u64,
+    last_evaluation: Option<f64>,
+}
+
+impl<Game: Stochastic> ExpectimaxPlayer<Game> {
+    /// Search `depth` moves ahead. Without a heuristic, positions at the horizon are all treated
+    /// as draws.
+    pub fn new(player: PlayerEnum, depth: u32) -> Self {
+        Self {
+            player,
+            depth,
+            heuristic: None,
+            nodes: 0,
+            last_evaluation: None,
+        }
+    }
+
+    pub fn with_heuristic<H: Heuristic<Game> + 'static>(player: PlayerEnum, depth: u32, heuristic: H) -> Self {
+        let mut expectimax = Self::new(player, depth);
+        expectimax.heuristic = Some(Box::new(heuristic));
+        expectimax
+    }
+
+    pub fn set_depth(&mut self, depth: u32) {
+        self.depth = depth;
+    }
+
+    /// How many positions the last search looked at.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// The expected value for `to_move` of `state`, looking `depth` moves ahead.
+    fn search(&mut self, state: &Game, to_move: PlayerEnum, ply: u32, depth: u32) -> f64 {
+        self.nodes += 1;
+        if let Some(conclusion) = state.try_conclude(to_move) {
+            return value::by_distance(value::of_conclusion(conclusion, to_move), ply, DISTANCE_PENALTY);
+        }
+        if depth == 0 {
+            return match self.heuristic {
+                Some(ref heuristic) => heuristic.evaluate(state, to_move),
+                None => value::DRAW,
+            };
+        }
+
+        let moves: Vec<_> = state.all_legal_moves(to_move).collect();
+        moves.into_iter()
+            .map(|game_move| self.expected_value(state, game_move, to_move, ply, depth))
+            .fold(0.0, f64::max)
+    }
+
+    /// The expected value for `player` of playing `game_move`, over chance's outcomes.
+    fn expected_value(&mut self, state: &Game, game_move: <Game as GameState>::Move, player: PlayerEnum, ply: u32, depth: u32) -> f64 {
+        state.outcomes(game_move, player).into_iter()
+            .map(|(outcome, p)| p * value::flip(self.search(&outcome, player.other(), ply + 1, depth - 1)))
+            .sum()
+    }
+}
+
+impl<Game: Stochastic> Player<Game> for ExpectimaxPlayer<Game> {
+    fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
+        let player = self.player;
+        let depth = self.depth.max(1);
+        self.nodes = 0;
+
+        let moves: Vec<_> = game.all_legal_moves(player).collect();
+        let mut best = None;
+        for game_move in moves {
+            let move_value = self.expected_value(&game, game_move, player, 0, depth);
+            if best.map_or(true, |(_, best_value)| move_value > best_value) {
+                best = Some((game_move, move_value));
+            }
+        }
+
+        let (game_move, evaluation) = best.expect("There were no legal moves");
+        self.last_evaluation = Some(evaluation);
+        game_move
+    }
+
+    fn inform_of_move_played(&mut self, _new_state: Game, _game_move: &<Game as GameState>::Move) {
+        // noop
+    }
+
+    fn last_evaluation(&self) -> Option<f64> {
+        self.last_evaluation
+    }
+}
@@ -0,0 +1,33 @@
+//! Turning positions and moves into numbers, for training neural networks (or anything else) on
+//! games played by the framework.
+
+use {GameState, PlayerEnum};
+
+/// A position as a flat list of numbers, along with the shape of the tensor they make up (e.g.
+/// planes, rows, columns), in row-major order.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Features {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+/// Encodes positions of `Game` as `Features`, and numbers its moves from 0 up to `move_count`.
+pub trait FeatureEncoder<Game: GameState> {
+    /// The shape of every encoded position.
+    fn shape(&self) -> Vec<usize>;
+
+    /// `state`, as seen by `to_move`.
+    fn encode(&self, state: &Game, to_move: PlayerEnum) -> Features;
+
+    /// How many move indices there are, e.g. the size of a policy network's output.
+    fn move_count(&self) -> usize;
+
+    /// The index of `game_move` when played by `player`, below `move_count`.
+    fn move_index(&self, game_move: &<Game as GameState>::Move, player: PlayerEnum) -> usize;
+
+    /// `to_move`'s legal move with index `index` in `state`, if there is one.
+    fn index_move(&self, state: &Game, to_move: PlayerEnum, index: usize) -> Option<<Game as GameState>::Move> {
+        state.all_legal_moves(to_move).find(|game_move| self.move_index(game_move, to_move) == index)
+    }
+}
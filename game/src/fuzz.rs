@@ -0,0 +1,70 @@
+//! Driving games with fuzzer input, to catch rule bugs. See the `fuzz` directory for the targets.
+
+use testutil;
+use {GameState, PlayerEnum};
+
+/// How many of the moves seen so far to keep, to ask `is_move_legal` about in later positions.
+const REMEMBERED_MOVES: usize = 64;
+
+/// Play a game from `start`, with each byte of `data` picking one of the legal moves, and panic if
+/// the game breaks any of the rules the framework relies on:
+///
+/// - the game is over exactly when the player to move has no legal moves, or sooner;
+/// - the legal moves are the same each time they're asked for, with no repeats, and can be
+///   played;
+/// - `is_move_legal` agrees with the legal moves, for moves from earlier in the game and the
+///   other player's moves;
+/// - asking about the player not to move (their legal moves, or whether a move is legal for them)
+///   doesn't panic;
+/// - the conclusion is stable, and playing on a clone leaves the original alone.
+pub fn play_bytes<Game: GameState>(start: Game, first_player: PlayerEnum, data: &[u8]) {
+    let mut state = start;
+    let mut to_move = first_player;
+    let mut seen: Vec<<Game as GameState>::Move> = Vec::new();
+    for &byte in data {
+        testutil::check_conclusion_stable(&state, to_move).unwrap();
+        let legal: Vec<_> = state.all_legal_moves(to_move).collect();
+        let waiting: Vec<_> = state.all_legal_moves(to_move.other()).collect();
+        for game_move in &waiting {
+            let _ = state.is_move_legal(*game_move, to_move.other());
+        }
+        if state.try_conclude(to_move).is_some() {
+            return;
+        }
+        assert!(!legal.is_empty(), "No legal moves for {:?}, but the game isn't over:\n{:?}", to_move, state);
+
+        let again: Vec<_> = state.all_legal_moves(to_move).collect();
+        assert_eq!(legal, again, "Legal moves changed between calls in:\n{:?}", state);
+        for (i, game_move) in legal.iter().enumerate() {
+            assert!(!legal[i + 1..].contains(game_move), "Legal move {:?} listed twice in:\n{:?}", game_move, state);
+        }
+        for game_move in legal.iter().chain(&waiting).chain(&seen) {
+            let listed = legal.contains(game_move);
+            match state.is_move_legal(*game_move, to_move) {
+                Ok(()) => assert!(listed, "Move {:?} for {:?} passed is_move_legal but isn't listed in:\n{:?}", game_move, to_move, state),
+                Err(e) => assert!(!listed, "Legal move {:?} for {:?} failed is_move_legal ({}) in:\n{:?}", game_move, to_move, e, state),
+            }
+        }
+        testutil::check_clone_independence(&state, to_move).unwrap();
+
+        for game_move in legal.iter().chain(&waiting) {
+            if seen.len() < REMEMBERED_MOVES && !seen.contains(game_move) {
+                seen.push(*game_move);
+            }
+        }
+        let game_move = legal[byte as usize % legal.len()];
+        state.update(game_move, to_move);
+        to_move = to_move.other();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_games::Nim;
+
+    #[test]
+    fn plays_any_bytes() {
+        play_bytes(Nim::new(20), PlayerEnum::One, &[0, 1, 2, 3, 255, 7, 9, 100, 4, 4, 4, 4, 4, 4, 4]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Evening up matches between players of different strengths.
+
+use std::hash::{Hash, Hasher};
+
+use {Conclusion, DrawRules, GameState, IllegalMove, PlayerEnum};
+
+/// A handicap for one match: moves played before the game starts (e.g. handicap stones, or extra
+/// material placed by a setup move), and komi taken off player one's final score. For example,
+/// with the weaker player as player one:
+///
+/// ```ignore
+/// let start = Handicap::new()
+///     .with_setup_move(PlayerEnum::One, stone(3, 3))
+///     .with_setup_move(PlayerEnum::One, stone(15, 15))
+///     .with_komi(0.5)
+///     .apply(&Go::new())?;
+/// let adjudicator = Adjudicator::from_position(start, PlayerEnum::Two, player_one, player_two);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Handicap<Game: GameState> {
+    moves: Vec<(PlayerEnum, <Game as GameState>::Move)>,
+    komi: f64,
+}
+
+impl<Game: GameState> Default for Handicap<Game> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Game: GameState> Handicap<Game> {
+    /// No handicap.
+    pub fn new() -> Self {
+        Self {
+            moves: Vec::new(),
+            komi: 0.0,
+        }
+    }
+
+    /// Have `player` play `game_move` before the game starts. Setup moves are played in the order
+    /// they're added, and needn't alternate between the players.
+    pub fn with_setup_move(mut self, player: PlayerEnum, game_move: <Game as GameState>::Move) -> Self {
+        self.moves.push((player, game_move));
+        self
+    }
+
+    /// Take `komi` off player one's final score (so a negative komi helps player one). This only
+    /// affects games with a final score.
+    pub fn with_komi(mut self, komi: f64) -> Self {
+        self.komi = komi;
+        self
+    }
+
+    pub fn moves(&self) -> &[(PlayerEnum, <Game as GameState>::Move)] {
+        &self.moves
+    }
+
+    pub fn komi(&self) -> f64 {
+        self.komi
+    }
+
+    /// The position to start the match from: `start` after the setup moves, with the komi built
+    /// in. Fails if a setup move isn't legal.
+    pub fn apply(&self, start: &Game) -> Result<WithKomi<Game>, IllegalMove> {
+        let mut state = start.clone();
+        for &(player, game_move) in &self.moves {
+            state.is_move_legal(game_move, player)?;
+            state.update(game_move, player);
+        }
+        Ok(WithKomi::new(state, self.komi))
+    }
+}
+
+/// Decorates a game with komi, so that everything judging the game by its final score or its
+/// conclusion (adjudicators, and players' searches) takes the komi into account.
+///
+/// Games which end with a score are decided by the score after komi: player one wins if it's
+/// positive, player two if it's negative, and it's a draw at zero. Games without a score are
+/// unaffected.
+#[derive(Debug, Clone)]
+pub struct WithKomi<Game: GameState> {
+    state: Game,
+    komi: f64,
+}
+
+impl<Game: GameState> WithKomi<Game> {
+    pub fn new(state: Game, komi: f64) -> Self {
+        Self { state, komi }
+    }
+
+    pub fn state(&self) -> &Game {
+        &self.state
+    }
+
+    pub fn komi(&self) -> f64 {
+        self.komi
+    }
+
+    pub fn into_inner(self) -> Game {
+        self.state
+    }
+}
+
+impl<Game: GameState> PartialEq for WithKomi<Game> {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state && self.komi.to_bits() == other.komi.to_bits()
+    }
+}
+
+impl<Game: GameState> Eq for WithKomi<Game> {}
+
+impl<Game: GameState> Hash for WithKomi<Game> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.state.hash(state);
+        self.komi.to_bits().hash(state);
+    }
+}
+
+impl<Game: GameState> GameState for WithKomi<Game> {
+    type Move = <Game as GameState>::Move;
+
+    fn update(&mut self, game_move: Self::Move, player: PlayerEnum) {
+        self.state.update(game_move, player);
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: PlayerEnum) -> Box<dyn Iterator<Item = Self::Move> + 'a> {
+        self.state.all_legal_moves(player)
+    }
+
+    fn is_move_legal(&self, game_move: Self::Move, player: PlayerEnum) -> Result<(), IllegalMove> {
+        self.state.is_move_legal(game_move, player)
+    }
+
+    fn try_conclude(&self, next_player: PlayerEnum) -> Option<Conclusion> {
+        let conclusion = self.state.try_conclude(next_player)?;
+        Some(match self.final_score() {
+            Some(score) if score > 0.0 => Conclusion::Win(PlayerEnum::One),
+            Some(score) if score < 0.0 => Conclusion::Win(PlayerEnum::Two),
+            Some(_) => Conclusion::Draw,
+            None => conclusion,
+        })
+    }
+
+    fn final_score(&self) -> Option<f64> {
+        self.state.final_score().map(|score| score - self.komi)
+    }
+
+    fn draw_rules() -> DrawRules {
+        Game::draw_rules()
+    }
+
+    fn is_progress(&self, game_move: &Self::Move, player: PlayerEnum) -> bool {
+        self.state.is_progress(game_move, player)
+    }
+}
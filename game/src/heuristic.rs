@@ -0,0 +1,9 @@
+use std::fmt::Debug;
+
+use {GameState, PlayerEnum};
+
+/// A rough evaluation of positions, for when there's no time to play the game out.
+pub trait Heuristic<Game: GameState>: Debug {
+    /// How good `state` looks for `player`, from 0 (a certain loss) to 1 (a certain win).
+    fn evaluate(&self, state: &Game, player: PlayerEnum) -> f64;
+}
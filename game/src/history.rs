@@ -0,0 +1,89 @@
+use std::collections::VecDeque;
+
+use {Conclusion, DrawRules, GameState, PlayerEnum};
+
+/// The most recent moves of a game, oldest first, with who played them.
+pub type History<Move> = VecDeque<(PlayerEnum, Move)>;
+
+/// A game whose rules depend on how the position was reached (e.g. en passant, or a ko rule), and
+/// not just on the position itself. Play it through a `GameHistory` to have its history tracked.
+///
+/// Both methods default to ignoring the history.
+pub trait HistoryAware: GameState {
+    fn legal_moves_given_history<'a>(&'a self, _history: &'a History<Self::Move>, player: PlayerEnum) -> Box<dyn Iterator<Item = Self::Move> + 'a> {
+        self.all_legal_moves(player)
+    }
+    fn try_conclude_given_history(&self, _history: &History<Self::Move>, next_player: PlayerEnum) -> Option<Conclusion> {
+        self.try_conclude(next_player)
+    }
+}
+
+/// Decorates a game with (up to `capacity` of) its most recent moves, for the game's rules to
+/// look at.
+///
+/// The history is part of the state, so it's included in equality and hashing: two positions
+/// reached differently are different states (e.g. to the MCTS cache). Keep `capacity` as small as
+/// the rules allow, so that equivalent positions aren't needlessly told apart.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameHistory<Game: HistoryAware> {
+    state: Game,
+    history: History<<Game as GameState>::Move>,
+    capacity: usize,
+}
+
+impl<Game: HistoryAware> GameHistory<Game> {
+    pub fn new(state: Game, capacity: usize) -> Self {
+        Self {
+            state,
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn state(&self) -> &Game {
+        &self.state
+    }
+
+    pub fn history(&self) -> &History<<Game as GameState>::Move> {
+        &self.history
+    }
+
+    pub fn into_inner(self) -> Game {
+        self.state
+    }
+}
+
+impl<Game: HistoryAware> GameState for GameHistory<Game> {
+    type Move = <Game as GameState>::Move;
+
+    fn update(&mut self, game_move: Self::Move, player: PlayerEnum) {
+        self.state.update(game_move, player);
+        if self.capacity == 0 {
+            return;
+        }
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back((player, game_move));
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: PlayerEnum) -> Box<dyn Iterator<Item = Self::Move> + 'a> {
+        self.state.legal_moves_given_history(&self.history, player)
+    }
+
+    fn try_conclude(&self, next_player: PlayerEnum) -> Option<Conclusion> {
+        self.state.try_conclude_given_history(&self.history, next_player)
+    }
+
+    fn final_score(&self) -> Option<f64> {
+        self.state.final_score()
+    }
+
+    fn draw_rules() -> DrawRules {
+        Game::draw_rules()
+    }
+
+    fn is_progress(&self, game_move: &Self::Move, player: PlayerEnum) -> bool {
+        self.state.is_progress(game_move, player)
+    }
+}
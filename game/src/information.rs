@@ -0,0 +1,16 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use {GameState, PlayerEnum};
+
+/// Games where the players can't see everything, e.g. the other player's cards or a sealed bid.
+/// States which look the same to a player are in the same information set, and a player has to
+/// play the same way in all of them, not knowing which it's in.
+pub trait ImperfectInformation: GameState {
+    /// What a player can see of a state: everything which is the same across the states they
+    /// can't tell apart.
+    type InformationSet: Debug + Clone + Eq + Hash + 'static;
+
+    /// What `player` can see of this state.
+    fn information_set(&self, player: PlayerEnum) -> Self::InformationSet;
+}
@@ -1,11 +1,127 @@
 extern crate rand;
+#[cfg(feature = "serialize")]
+extern crate serde;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "serialize")]
+extern crate serde_json;
+#[cfg(feature = "signals")]
+extern crate signal_hook;
+#[cfg(feature = "websocket")]
+extern crate tungstenite;
+
+mod alpha_beta;
+mod asynchronous;
+mod baseline;
+mod cancel;
+pub mod bitboard;
+pub mod board;
+pub mod cards;
+mod chance;
+pub mod clock;
+mod cow;
+mod draw_rules;
+mod expectimax;
+mod features;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
+mod handicap;
+mod heuristic;
+mod history;
+mod information;
+mod notation;
+mod observer;
+pub mod probe;
+pub mod registry;
+mod result;
+pub mod rng;
+mod rollout;
+mod scripted;
+#[cfg(feature = "signals")]
+pub mod signals;
+#[cfg(feature = "serialize")]
+pub mod suite;
+mod table;
+#[cfg(test)]
+mod test_games;
+pub mod testutil;
+pub mod tournament;
+mod undo;
+pub mod value;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+pub use alpha_beta::{AlphaBetaPlayer, AlphaBetaSettings, AlphaBetaStats};
+pub use asynchronous::{AsyncAdjudicator, AsyncPlayer, BlockingPlayer, MoveFuture, ProgressOneTurn};
+pub use baseline::{GreedyPlayer, TwoPlyPlayer};
+pub use cancel::{CancellationToken, Cancelled};
+pub use chance::Stochastic;
+pub use clock::{Clock, MockClock, SystemClock, TimeControl};
+pub use cow::CowState;
+pub use draw_rules::{DrawRules, DrawTracker};
+pub use expectimax::ExpectimaxPlayer;
+pub use features::{FeatureEncoder, Features};
+pub use handicap::{Handicap, WithKomi};
+pub use heuristic::Heuristic;
+pub use history::{GameHistory, History, HistoryAware};
+pub use information::ImperfectInformation;
+pub use notation::{MoveNotation, Notation};
+pub use observer::{GameObserver, PrintObserver};
+pub use registry::{GameRegistry, PlayerRegistry};
+pub use result::{MatchResult, Termination};
+pub use rollout::{MoveWeights, RolloutPolicy, SoftmaxRollout, TunableWeights, UniformRollout};
+pub use scripted::ScriptedPlayer;
+pub use table::{MoveFrequencies, PolicyTable, TablePlayer};
+pub use undo::UndoableGameState;
 
 use std::hash::Hash;
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
 
 pub trait Player<Game: GameState> {
     fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move;
     /// Default implementation is do nothing
     fn inform_of_move_played(&mut self, new_state: Game, game_move: &<Game as GameState>::Move);
+    /// The player's estimate of its chances (from 0 for a certain loss to 1 for a certain win)
+    /// after the move it last chose, if it has one.
+    fn last_evaluation(&self) -> Option<f64> {
+        None
+    }
+    /// Asked at the start of each of the player's turns (before `choose_move`) under an
+    /// `Adjudicator`, which ends the game if this is true.
+    ///
+    /// Default implementation is never resign.
+    fn resigns(&mut self, _game: &Game) -> bool {
+        false
+    }
+    /// Think for less when `token` is cancelled, e.g. because the program is shutting down. The
+    /// player should still choose a move, but promptly.
+    ///
+    /// Default implementation is ignore it, for players which don't think for long.
+    fn set_cancellation_token(&mut self, _token: CancellationToken) {}
+}
+
+/// A player whose type is only known at runtime, e.g. one picked from a config file.
+pub type BoxedPlayer<Game> = Box<dyn Player<Game>>;
+
+impl<Game: GameState, P: Player<Game> + ?Sized> Player<Game> for Box<P> {
+    fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
+        (**self).choose_move(game)
+    }
+    fn inform_of_move_played(&mut self, new_state: Game, game_move: &<Game as GameState>::Move) {
+        (**self).inform_of_move_played(new_state, game_move)
+    }
+    fn last_evaluation(&self) -> Option<f64> {
+        (**self).last_evaluation()
+    }
+    fn resigns(&mut self, game: &Game) -> bool {
+        (**self).resigns(game)
+    }
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        (**self).set_cancellation_token(token)
+    }
 }
 
 pub struct RandomPlayer(pub PlayerEnum);
@@ -14,11 +130,86 @@ impl<Game: GameState> Player<Game> for RandomPlayer {
     fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
         random_sample(game.all_legal_moves(self.0)).expect("There were no legal moves")
     }
-    fn inform_of_move_played(&mut self, new_state: Game, game_move: &<Game as GameState>::Move) {
+    fn inform_of_move_played(&mut self, _new_state: Game, _game_move: &<Game as GameState>::Move) {
         // noop
     }
 }
 
+/// A player who types their moves in at the terminal.
+///
+/// Each turn, the game state and the legal moves are printed, and the player picks a move by
+/// entering its number.
+pub struct HumanPlayer(pub PlayerEnum);
+
+impl<Game: GameState> Player<Game> for HumanPlayer {
+    fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
+        let moves: Vec<_> = game.all_legal_moves(self.0).collect();
+        println!("{:?}\n\nPlayer {:?} to move:", game, self.0);
+        for (i, game_move) in moves.iter().enumerate() {
+            println!("  {}: {:?}", i, game_move);
+        }
+
+        let stdin = io::stdin();
+        loop {
+            print!("> ");
+            io::stdout().flush().expect("Failed to write to stdout");
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).expect("Failed to read from stdin") == 0 {
+                panic!("Reached end of input while waiting for a move");
+            }
+            match line.trim().parse::<usize>().ok().and_then(|i| moves.get(i)) {
+                Some(game_move) => return *game_move,
+                None => println!("Please enter a number from 0 to {}", moves.len().saturating_sub(1)),
+            }
+        }
+    }
+    fn inform_of_move_played(&mut self, _new_state: Game, _game_move: &<Game as GameState>::Move) {
+        // noop
+    }
+}
+
+/// A player which can be shared between threads.
+///
+/// Clones all refer to the same underlying player, so a player can be handed to a worker thread
+/// (or an async task, or a web server handler) while still being reachable from elsewhere. This is
+/// `Send + Sync` whenever the wrapped player is `Send`.
+pub struct SharedPlayer<P>(Arc<Mutex<P>>);
+
+impl<P> SharedPlayer<P> {
+    pub fn new(player: P) -> Self {
+        SharedPlayer(Arc::new(Mutex::new(player)))
+    }
+
+    /// Get exclusive access to the underlying player, blocking until it's free.
+    pub fn lock(&self) -> MutexGuard<'_, P> {
+        self.0.lock().expect("Shared player was poisoned")
+    }
+}
+
+impl<P> Clone for SharedPlayer<P> {
+    fn clone(&self) -> Self {
+        SharedPlayer(self.0.clone())
+    }
+}
+
+impl<Game: GameState, P: Player<Game>> Player<Game> for SharedPlayer<P> {
+    fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
+        self.lock().choose_move(game)
+    }
+    fn inform_of_move_played(&mut self, new_state: Game, game_move: &<Game as GameState>::Move) {
+        self.lock().inform_of_move_played(new_state, game_move)
+    }
+    fn last_evaluation(&self) -> Option<f64> {
+        self.lock().last_evaluation()
+    }
+    fn resigns(&mut self, game: &Game) -> bool {
+        self.lock().resigns(game)
+    }
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.lock().set_cancellation_token(token)
+    }
+}
+
 /// Returns None only if the iterator is empty.
 ///
 /// Suppose there are N elements in the iterator.
@@ -37,7 +228,7 @@ fn random_sample<T, I: Iterator<Item = T>>(iter: I) -> Option<T> {
     let mut elem = None;
     let mut i = 1f64;
     for new_item in iter {
-        if rand::random::<f64>() < (1f64/i) {
+        if rng::random_f64() < (1f64/i) {
             elem = Some(new_item);
         }
         i += 1.0;
@@ -45,7 +236,8 @@ fn random_sample<T, I: Iterator<Item = T>>(iter: I) -> Option<T> {
     elem
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum PlayerEnum {
     One,
     Two
@@ -63,21 +255,83 @@ impl PlayerEnum {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Conclusion {
     Win(PlayerEnum),
     Draw
 }
 
+/// Why a move can't be played, from `GameState::is_move_legal`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IllegalMove(pub String);
+
+impl std::fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Illegal move: {}", self.0)
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
 pub trait GameState: std::fmt::Debug + Clone + PartialEq + Eq + Hash + 'static {
-    type Move: std::fmt::Debug + Copy + Hash + PartialEq + Eq;
+    type Move: std::fmt::Debug + Copy + Hash + PartialEq + Eq + 'static;
     fn update(&mut self, game_move: Self::Move, player: PlayerEnum);
     fn update_with_closure<F: FnMut(&Self) -> Self::Move>(&mut self, mut f: F, player: PlayerEnum) {
         let game_move = f(self);
         self.update(game_move, player);
     }
-    fn all_legal_moves<'a>(&'a self, player: PlayerEnum) -> Box<Iterator<Item = Self::Move> + 'a>;
+    fn all_legal_moves<'a>(&'a self, player: PlayerEnum) -> Box<dyn Iterator<Item = Self::Move> + 'a>;
+    /// Run `f` on the state after `player` plays `game_move`, leaving this state as it was. `f`
+    /// must leave the state it's given as it found it too.
+    ///
+    /// Default implementation plays the move on a clone. Games implementing `UndoableGameState`
+    /// should override this with `UndoableGameState::with_move_in_place`.
+    fn with_move<R, F: FnOnce(&mut Self) -> R>(&mut self, game_move: Self::Move, player: PlayerEnum, f: F) -> R {
+        let mut next = self.clone();
+        next.update(game_move, player);
+        f(&mut next)
+    }
     fn try_conclude(&self, next_player: PlayerEnum) -> Option<Conclusion>;
+    /// Whether `player` can play `game_move` here (so that `update` won't panic), and if not, why
+    /// not. This doesn't check whether the game is over.
+    ///
+    /// Default implementation looks for the move in `all_legal_moves`.
+    fn is_move_legal(&self, game_move: Self::Move, player: PlayerEnum) -> Result<(), IllegalMove> {
+        if self.all_legal_moves(player).any(|m| m == game_move) {
+            Ok(())
+        } else {
+            Err(IllegalMove(format!("{:?} isn't one of {:?}'s legal moves", game_move, player)))
+        }
+    }
+    /// For games which end with a score (rather than just a winner), the final margin in player
+    /// one's favour. Negative margins favour player two. Only asked of concluded games.
+    ///
+    /// Default implementation is no score.
+    fn final_score(&self) -> Option<f64> {
+        None
+    }
+    /// Draw rules for the framework to apply on top of `try_conclude`.
+    ///
+    /// Default implementation is no draw rules.
+    fn draw_rules() -> DrawRules {
+        DrawRules::default()
+    }
+    /// Whether playing `game_move` from this state counts as progress (e.g. a capture), for the
+    /// `moves_without_progress` draw rule.
+    ///
+    /// Default implementation is every move is progress.
+    fn is_progress(&self, _game_move: &Self::Move, _player: PlayerEnum) -> bool {
+        true
+    }
+    /// Whether `game_move` is forcing (e.g. a capture, a check or a threat to win next move), so
+    /// that searches shouldn't stop just before it's answered. Alpha-beta keeps searching forcing
+    /// moves past its depth, and the tree search can expand through them.
+    ///
+    /// Default implementation is no move is forcing.
+    fn is_forcing(&self, _game_move: &Self::Move, _player: PlayerEnum) -> bool {
+        false
+    }
 }
 
 pub struct Adjudicator<Game: GameState, PlayerOne: Player<Game>, PlayerTwo: Player<Game>> {
@@ -86,53 +340,233 @@ pub struct Adjudicator<Game: GameState, PlayerOne: Player<Game>, PlayerTwo: Play
     player_one: PlayerOne,
     player_two: PlayerTwo,
     conclusion: Option<Conclusion>,
+    observers: Vec<Box<dyn GameObserver<Game>>>,
+    draw_tracker: DrawTracker<Game>,
+    clock: Box<dyn Clock>,
+    time_control: Option<TimeControl>,
+    /// Each player's time left, under a time control.
+    time_remaining: (Duration, Duration),
+    termination: Termination,
+    moves_played: u32,
+    time_used: (Duration, Duration),
+    seed: Option<u64>,
 }
 
 impl<Game: GameState, PlayerOne: Player<Game>, PlayerTwo: Player<Game>> Adjudicator<Game, PlayerOne, PlayerTwo> {
     pub fn new(game_state: Game, player_one: PlayerOne, player_two: PlayerTwo) -> Self {
+        Self::from_position(game_state, PlayerEnum::One, player_one, player_two)
+    }
+
+    /// Start the game from a position where `to_move` is to move, e.g. one partway through a game.
+    /// The players are only told about moves played from here on.
+    ///
+    /// If the game is already over in that position, it's concluded straight away (with
+    /// `current_turn` the player who'd have made the last move), and no turns can be played.
+    pub fn from_position(game_state: Game, to_move: PlayerEnum, player_one: PlayerOne, player_two: PlayerTwo) -> Self {
+        let draw_tracker = DrawTracker::new(&game_state, to_move);
+        let conclusion = draw_tracker.try_conclude(&game_state, to_move);
         Self {
-            current_turn: PlayerEnum::One,
+            current_turn: if conclusion.is_some() { to_move.other() } else { to_move },
+            draw_tracker,
             game_state,
             player_one,
             player_two,
-            conclusion: None,
+            conclusion,
+            observers: Vec::new(),
+            clock: Box::new(SystemClock::new()),
+            time_control: None,
+            time_remaining: (Duration::from_secs(0), Duration::from_secs(0)),
+            termination: Termination::Normal,
+            moves_played: 0,
+            time_used: (Duration::from_secs(0), Duration::from_secs(0)),
+            seed: None,
         }
     }
 
+    /// Have `observer` told about every move played from now on.
+    pub fn add_observer<O: GameObserver<Game> + 'static>(&mut self, observer: O) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Time the players with `clock` rather than the system clock.
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.clock = Box::new(clock);
+    }
+
+    /// Give each player `time_control.initial` for the rest of the game. By default, players can
+    /// take as long as they like.
+    pub fn set_time_control(&mut self, time_control: TimeControl) {
+        self.time_control = Some(time_control);
+        self.time_remaining = (time_control.initial, time_control.initial);
+    }
+
+    /// Seed this thread's random number generator (see `rng::seed_thread_rng`), and note the seed
+    /// in the result, so that the game can be played again.
+    pub fn set_seed(&mut self, seed: u64) {
+        rng::seed_thread_rng(seed);
+        self.seed = Some(seed);
+    }
+
+    /// How long `player` has left, under a time control.
+    pub fn time_remaining(&self, player: PlayerEnum) -> Option<Duration> {
+        self.time_control.map(|_| match player {
+            PlayerEnum::One => self.time_remaining.0,
+            PlayerEnum::Two => self.time_remaining.1,
+        })
+    }
+
+    /// The position the game has reached.
+    pub fn game_state(&self) -> &Game {
+        &self.game_state
+    }
+
+    /// Whose turn it is. Once the game is over, this is the player who made the last move.
+    pub fn current_turn(&self) -> PlayerEnum {
+        self.current_turn
+    }
+
+    pub fn player_one(&self) -> &PlayerOne {
+        &self.player_one
+    }
+
+    pub fn player_two(&self) -> &PlayerTwo {
+        &self.player_two
+    }
+
+    /// Play the game out, returning each move along with the position it led to, e.g. to check
+    /// every step of a game in a test. Nothing is printed (unless an observer does so).
+    pub fn play_to_end(&mut self) -> Vec<(PlayerEnum, <Game as GameState>::Move, Game)> {
+        let mut turns = Vec::new();
+        while self.conclusion.is_none() {
+            let player = self.current_turn;
+            if let Some(game_move) = self.play_turn() {
+                turns.push((player, game_move, self.game_state.clone()));
+            }
+        }
+        turns
+    }
+
+    /// Play one turn, unless the game is over.
     pub fn progress_one_turn(&mut self) {
-        let chosen_move = match self.current_turn {
-            PlayerEnum::One => {
-                let player_one = &mut self.player_one;
-                let chosen_move = player_one.choose_move(self.game_state.clone());
-                self.game_state.update(chosen_move, PlayerEnum::One);
-                chosen_move
-            },
-            PlayerEnum::Two => {
-                let player_two = &mut self.player_two;
-                let chosen_move = player_two.choose_move(self.game_state.clone());
-                self.game_state.update(chosen_move, PlayerEnum::Two);
-                chosen_move
-            },
+        if self.conclusion.is_none() {
+            self.play_turn();
+        }
+    }
+
+    /// Play one turn, returning the move played (if the player didn't lose before making one).
+    fn play_turn(&mut self) -> Option<<Game as GameState>::Move> {
+        let start = self.clock.now();
+        let game_state = &self.game_state;
+        let chosen = match self.current_turn {
+            PlayerEnum::One => choose_unless_resigned(&mut self.player_one, game_state),
+            PlayerEnum::Two => choose_unless_resigned(&mut self.player_two, game_state),
+        };
+        let taken = self.clock.now().checked_sub(start).unwrap_or_default();
+        match self.current_turn {
+            PlayerEnum::One => self.time_used.0 += taken,
+            PlayerEnum::Two => self.time_used.1 += taken,
+        }
+        let (chosen_move, evaluation) = match chosen {
+            Some(chosen) => chosen,
+            None => return self.lose_turn(Termination::Resignation),
         };
 
+        if let Some(time_control) = self.time_control {
+            let remaining = match self.current_turn {
+                PlayerEnum::One => &mut self.time_remaining.0,
+                PlayerEnum::Two => &mut self.time_remaining.1,
+            };
+            match remaining.checked_sub(taken) {
+                Some(left) => *remaining = left + time_control.increment,
+                None => {
+                    // Out of time: the move doesn't count.
+                    *remaining = Duration::from_secs(0);
+                    return self.lose_turn(Termination::Timeout);
+                }
+            }
+        }
+
+        if self.game_state.is_move_legal(chosen_move, self.current_turn).is_err() {
+            return self.lose_turn(Termination::Forfeit);
+        }
+
+        self.moves_played += 1;
+        self.draw_tracker.play(&mut self.game_state, chosen_move, self.current_turn);
+
         self.player_one.inform_of_move_played(self.game_state.clone(), &chosen_move);
         self.player_two.inform_of_move_played(self.game_state.clone(), &chosen_move);
 
-        // Log out the new game state:
-        println!("New game state: \n{:?}", self.game_state);
+        for observer in self.observers.iter_mut() {
+            observer.on_move(self.current_turn, &chosen_move, &self.game_state, evaluation);
+        }
 
-        let next_player = self.current_turn.other();
+        end_turn(&self.game_state, &self.draw_tracker, &mut self.current_turn, &mut self.conclusion, &mut self.observers);
+        Some(chosen_move)
+    }
 
-        match self.game_state.try_conclude(next_player) {
-            Some(conclusion) => {
-                self.conclusion = Some(conclusion);
-                println!("Got conclusion: {:?}", conclusion)
-            },
-            None => self.current_turn = next_player,
+    /// End the game without a move, with the player whose turn it is losing.
+    fn lose_turn(&mut self, termination: Termination) -> Option<<Game as GameState>::Move> {
+        let conclusion = Conclusion::Win(self.current_turn.other());
+        self.conclusion = Some(conclusion);
+        self.termination = termination;
+        for observer in self.observers.iter_mut() {
+            observer.on_conclusion(conclusion, None);
         }
+        None
     }
 
     pub fn conclusion(&self) -> Option<Conclusion> {
         self.conclusion
     }
+
+    /// How the game went, once it's over.
+    pub fn result(&self) -> Option<MatchResult> {
+        self.conclusion.map(|conclusion| MatchResult {
+            conclusion,
+            termination: self.termination,
+            final_score: self.final_score(),
+            moves: self.moves_played,
+            time_used: self.time_used,
+            seed: self.seed,
+        })
+    }
+
+    /// The final score, once the game is over, if the game has one and it ended normally.
+    pub fn final_score(&self) -> Option<f64> {
+        match self.termination {
+            Termination::Normal => self.conclusion.and_then(|_| self.game_state.final_score()),
+            _ => None,
+        }
+    }
+}
+
+impl<Game: GameState> Adjudicator<Game, BoxedPlayer<Game>, BoxedPlayer<Game>> {
+    /// Create an adjudicator for players chosen at runtime.
+    pub fn new_boxed(game_state: Game, player_one: BoxedPlayer<Game>, player_two: BoxedPlayer<Game>) -> Self {
+        Self::new(game_state, player_one, player_two)
+    }
+}
+
+/// The player's move and their evaluation of it, or `None` if they resign instead.
+fn choose_unless_resigned<Game: GameState, P: Player<Game>>(player: &mut P, game_state: &Game) -> Option<(<Game as GameState>::Move, Option<f64>)> {
+    if player.resigns(game_state) {
+        return None;
+    }
+    Some((player.choose_move(game_state.clone()), player.last_evaluation()))
+}
+
+/// Once a move has been played, check whether the game is over. If not, pass the turn on.
+fn end_turn<Game: GameState, O: GameObserver<Game> + ?Sized>(game_state: &Game, draw_tracker: &DrawTracker<Game>, current_turn: &mut PlayerEnum, conclusion: &mut Option<Conclusion>, observers: &mut [Box<O>]) {
+    let next_player = current_turn.other();
+
+    match draw_tracker.try_conclude(game_state, next_player) {
+        Some(new_conclusion) => {
+            *conclusion = Some(new_conclusion);
+            let final_score = game_state.final_score();
+            for observer in observers.iter_mut() {
+                observer.on_conclusion(new_conclusion, final_score);
+            }
+        },
+        None => *current_turn = next_player,
+    }
 }
@@ -0,0 +1,19 @@
+use {GameState, PlayerEnum};
+
+/// Games whose positions can be written down as text, e.g. to start a test or an analysis from
+/// partway through a game.
+pub trait Notation: GameState {
+    /// Parse a position, returning it along with whose turn it is. Positions which couldn't come
+    /// up in a game are rejected.
+    fn from_notation(text: &str) -> Result<(Self, PlayerEnum), String>;
+    /// Write the position down, such that `from_notation` gives it back.
+    fn to_notation(&self, to_move: PlayerEnum) -> String;
+}
+
+/// Games whose moves can be written down as text, e.g. for playing by typing moves in.
+pub trait MoveNotation: GameState {
+    /// Parse one of `player`'s legal moves here.
+    fn parse_move(&self, text: &str, player: PlayerEnum) -> Result<Self::Move, String>;
+    /// Write the move down, such that `parse_move` gives it back.
+    fn move_notation(game_move: &Self::Move) -> String;
+}
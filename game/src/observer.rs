@@ -0,0 +1,24 @@
+use {Conclusion, GameState, PlayerEnum};
+
+/// Something which watches a game as the adjudicator runs it, e.g. to log it or to show it to
+/// spectators.
+pub trait GameObserver<Game: GameState> {
+    /// Called after each move, with the state it led to and (if the player gave one) the moving
+    /// player's estimate of its chances.
+    fn on_move(&mut self, player: PlayerEnum, game_move: &<Game as GameState>::Move, new_state: &Game, evaluation: Option<f64>);
+    /// Called once, when the game ends, with the final score if the game has one. Default
+    /// implementation is do nothing.
+    fn on_conclusion(&mut self, _conclusion: Conclusion, _final_score: Option<f64>) {}
+}
+
+/// Prints each new game state, and the conclusion, to stdout.
+pub struct PrintObserver;
+
+impl<Game: GameState> GameObserver<Game> for PrintObserver {
+    fn on_move(&mut self, _player: PlayerEnum, _game_move: &<Game as GameState>::Move, new_state: &Game, _evaluation: Option<f64>) {
+        println!("New game state: \n{:?}", new_state);
+    }
+    fn on_conclusion(&mut self, conclusion: Conclusion, _final_score: Option<f64>) {
+        println!("Got conclusion: {:?}", conclusion);
+    }
+}
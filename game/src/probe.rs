@@ -0,0 +1,86 @@
+//! A quick check of how strong a player is, by playing it against simple baselines.
+
+use {value, Adjudicator, BoxedPlayer, GameState, GreedyPlayer, Player, PlayerEnum, RandomPlayer};
+
+/// Results against one opponent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProbeResult {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Sum of the squares of each game's score, for working out the spread.
+    sum_of_squares: f64,
+}
+
+impl ProbeResult {
+    fn new() -> Self {
+        Self { wins: 0, draws: 0, losses: 0, sum_of_squares: 0.0 }
+    }
+
+    fn record(&mut self, score: f64) {
+        match score {
+            x if x > value::DRAW => self.wins += 1,
+            x if x < value::DRAW => self.losses += 1,
+            _ => self.draws += 1,
+        }
+        self.sum_of_squares += score * score;
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Average score, counting a win as 1 and a draw as a half.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games().max(1) as f64
+    }
+
+    /// A 95% confidence interval for the score (using the normal approximation, so it's rough for
+    /// small numbers of games).
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        let games = self.games().max(1) as f64;
+        let mean = self.score();
+        let variance = (self.sum_of_squares / games - mean * mean).max(0.0);
+        let margin = 1.96 * (variance / games).sqrt();
+        ((mean - margin).max(0.0), (mean + margin).min(1.0))
+    }
+}
+
+/// Results of `strength_probe`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrengthReport {
+    pub vs_random: ProbeResult,
+    pub vs_greedy: ProbeResult,
+}
+
+/// Play `games` games against a random player, and as many again against a greedy player, from
+/// `start`, taking turns to go first. `engine` creates the player being probed, for each game.
+pub fn strength_probe<Game, P, F>(mut engine: F, start: &Game, games: u32) -> StrengthReport
+    where Game: GameState,
+          P: Player<Game> + 'static,
+          F: FnMut(PlayerEnum) -> P
+{
+    let mut vs_random = ProbeResult::new();
+    let mut vs_greedy = ProbeResult::new();
+    for i in 0..games {
+        let side = if i % 2 == 0 { PlayerEnum::One } else { PlayerEnum::Two };
+        vs_random.record(play(start, side, Box::new(engine(side)), Box::new(RandomPlayer(side.other()))));
+        vs_greedy.record(play(start, side, Box::new(engine(side)), Box::new(GreedyPlayer::new(side.other()))));
+    }
+    StrengthReport { vs_random, vs_greedy }
+}
+
+/// Play one game, returning the score of `engine`, which plays as `side`.
+fn play<Game: GameState>(start: &Game, side: PlayerEnum, engine: BoxedPlayer<Game>, opponent: BoxedPlayer<Game>) -> f64 {
+    let (player_one, player_two) = match side {
+        PlayerEnum::One => (engine, opponent),
+        PlayerEnum::Two => (opponent, engine),
+    };
+    let mut adjudicator = Adjudicator::new_boxed(start.clone(), player_one, player_two);
+    loop {
+        match adjudicator.conclusion() {
+            Some(conclusion) => return value::of_conclusion(conclusion, side),
+            None => adjudicator.progress_one_turn(),
+        }
+    }
+}
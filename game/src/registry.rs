@@ -0,0 +1,221 @@
+//! Lookup of players and games by name, so that which game is played (and by whom) can be
+//! decided at runtime, e.g. from the command line or a config file.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use {Adjudicator, AlphaBetaPlayer, AlphaBetaSettings, BoxedPlayer, CancellationToken, Conclusion, GameState, GreedyPlayer, HumanPlayer, Player, PlayerEnum, PrintObserver, RandomPlayer, TwoPlyPlayer};
+
+/// Parameters for constructing a player, e.g. `{"iterations": "500"}`.
+pub type PlayerParams = HashMap<String, String>;
+
+/// Constructs a player to play as the given side.
+pub type PlayerFactory<Game> = Box<dyn Fn(PlayerEnum, &PlayerParams) -> Result<BoxedPlayer<Game>, String>>;
+
+pub struct PlayerRegistry<Game: GameState> {
+    factories: HashMap<String, PlayerFactory<Game>>,
+}
+
+impl<Game: GameState> PlayerRegistry<Game> {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// A registry containing the players defined in this crate: "random", "greedy", "two_ply",
+    /// "alpha_beta" (with parameters "depth", "killers", "history", "quiescence", "pvs" and
+    /// "aspiration") and "human".
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register("random", |player, _| Ok(Box::new(RandomPlayer(player))));
+        registry.register("greedy", |player, _| Ok(Box::new(GreedyPlayer::new(player))));
+        registry.register("two_ply", |player, _| Ok(Box::new(TwoPlyPlayer::new(player))));
+        registry.register("alpha_beta", |player, params| {
+            let defaults = AlphaBetaSettings::default();
+            let settings = AlphaBetaSettings {
+                depth: param_or(params, "depth", defaults.depth)?,
+                killer_slots: param_or(params, "killers", defaults.killer_slots)?,
+                history: param_or(params, "history", defaults.history)?,
+                quiescence_depth: param_or(params, "quiescence", defaults.quiescence_depth)?,
+                principal_variation: param_or(params, "pvs", defaults.principal_variation)?,
+                aspiration_window: match params.contains_key("aspiration") {
+                    true => Some(param_or(params, "aspiration", 0.0)?).filter(|window| *window > 0.0),
+                    false => defaults.aspiration_window,
+                },
+            };
+            Ok(Box::new(AlphaBetaPlayer::new(player, settings)))
+        });
+        registry.register("human", |player, _| Ok(Box::new(HumanPlayer(player))));
+        registry
+    }
+
+    /// Add a player under `name`, replacing any player already registered with that name.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+        where F: Fn(PlayerEnum, &PlayerParams) -> Result<BoxedPlayer<Game>, String> + 'static
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// The names of all registered players, in alphabetical order.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.factories.keys().map(|x| x.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    pub fn create(&self, name: &str, player: PlayerEnum, params: &PlayerParams) -> Result<BoxedPlayer<Game>, String> {
+        match self.factories.get(name) {
+            Some(factory) => factory(player, params),
+            None => Err(format!("Unknown player '{}', expected one of: {}", name, self.names().join(", "))),
+        }
+    }
+
+    /// Create a player from a spec of the form `name` or `name:key=value,key=value`.
+    pub fn create_from_spec(&self, spec: &str, player: PlayerEnum) -> Result<BoxedPlayer<Game>, String> {
+        let (name, params) = parse_spec(spec)?;
+        self.create(&name, player, &params)
+    }
+}
+
+impl<Game: GameState> Default for PlayerRegistry<Game> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Plays one match between the players described by two specs (see
+/// `PlayerRegistry::create_from_spec`), returning the conclusion, or `None` if the token was
+/// cancelled first.
+pub type MatchRunner = Box<dyn Fn(&str, &str, &CancellationToken) -> Result<Option<Conclusion>, String>>;
+
+/// Games which can be chosen at runtime, each along with the players that can play it.
+pub struct GameRegistry {
+    games: HashMap<String, MatchRunner>,
+}
+
+impl GameRegistry {
+    pub fn new() -> Self {
+        Self {
+            games: HashMap::new(),
+        }
+    }
+
+    /// Add a game under `name`. `new_game` creates the starting position for each match.
+    pub fn register<Game, F>(&mut self, name: &str, new_game: F, players: PlayerRegistry<Game>)
+        where Game: GameState,
+              F: Fn() -> Game + 'static
+    {
+        self.games.insert(name.to_string(), Box::new(move |player_one, player_two, cancellation| {
+            let mut player_one = players.create_from_spec(player_one, PlayerEnum::One)?;
+            let mut player_two = players.create_from_spec(player_two, PlayerEnum::Two)?;
+            player_one.set_cancellation_token(cancellation.clone());
+            player_two.set_cancellation_token(cancellation.clone());
+            let mut adjudicator = Adjudicator::new_boxed(new_game(), player_one, player_two);
+            adjudicator.add_observer(PrintObserver);
+            loop {
+                if let Some(conclusion) = adjudicator.conclusion() {
+                    return Ok(Some(conclusion));
+                }
+                if cancellation.is_cancelled() {
+                    return Ok(None);
+                }
+                adjudicator.progress_one_turn();
+            }
+        }));
+    }
+
+    /// The names of all registered games, in alphabetical order.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.games.keys().map(|x| x.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    pub fn play_match(&self, game: &str, player_one: &str, player_two: &str) -> Result<Conclusion, String> {
+        let conclusion = self.play_match_until_cancelled(game, player_one, player_two, &CancellationToken::new())?;
+        Ok(conclusion.expect("Match was cancelled"))
+    }
+
+    /// Play a match, stopping it (and the players' thinking) when `cancellation` is cancelled.
+    /// The conclusion is `None` if it was cancelled before the end.
+    pub fn play_match_until_cancelled(&self, game: &str, player_one: &str, player_two: &str, cancellation: &CancellationToken) -> Result<Option<Conclusion>, String> {
+        match self.games.get(game) {
+            Some(runner) => runner(player_one, player_two, cancellation),
+            None => Err(format!("Unknown game '{}', expected one of: {}", game, self.names().join(", "))),
+        }
+    }
+}
+
+impl Default for GameRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split a spec of the form `name:key=value,key=value` into the name and its parameters.
+pub fn parse_spec(spec: &str) -> Result<(String, PlayerParams), String> {
+    let mut parts = spec.splitn(2, ':');
+    let name = parts.next().unwrap_or("").trim().to_string();
+    let mut params = PlayerParams::new();
+    if let Some(param_list) = parts.next() {
+        for param in param_list.split(',').filter(|x| !x.trim().is_empty()) {
+            let mut key_value = param.splitn(2, '=');
+            match (key_value.next(), key_value.next()) {
+                (Some(key), Some(value)) => {
+                    params.insert(key.trim().to_string(), value.trim().to_string());
+                },
+                _ => return Err(format!("Bad parameter '{}', expected key=value", param)),
+            }
+        }
+    }
+    Ok((name, params))
+}
+
+/// Read a parameter, falling back to `default` if it wasn't given.
+pub fn param_or<T: FromStr>(params: &PlayerParams, key: &str, default: T) -> Result<T, String> {
+    match params.get(key) {
+        Some(value) => value.parse().map_err(|_| format!("Bad value for parameter '{}': {}", key, value)),
+        None => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_games::Nim;
+    use {CancellationToken, Conclusion, PlayerEnum};
+
+    #[test]
+    fn registers_and_creates_players() {
+        let mut registry = PlayerRegistry::<Nim>::default();
+        registry.register("random", |player, _| Ok(Box::new(RandomPlayer(player))));
+        assert_eq!(registry.names(), vec!["random"]);
+        assert!(registry.create_from_spec("random", PlayerEnum::One).is_ok());
+        assert!(registry.create_from_spec("missing", PlayerEnum::One).is_err());
+    }
+
+    #[test]
+    fn defaults_read_their_parameters() {
+        let registry = PlayerRegistry::<Nim>::with_defaults();
+        assert!(registry.names().contains(&"alpha_beta"));
+        assert!(registry.create_from_spec("alpha_beta:depth=4", PlayerEnum::Two).is_ok());
+        assert!(registry.create_from_spec("alpha_beta:depth=deep", PlayerEnum::Two).is_err());
+        assert!(registry.create_from_spec("alpha_beta:depth", PlayerEnum::Two).is_err());
+    }
+
+    #[test]
+    fn plays_registered_games() {
+        let mut games = GameRegistry::default();
+        games.register("nim", || Nim::new(8), PlayerRegistry::with_defaults());
+        assert_eq!(games.names(), vec!["nim"]);
+        // With eight stones, the second player can always leave a multiple of four
+        assert_eq!(games.play_match("nim", "random", "alpha_beta:depth=8"), Ok(Conclusion::Win(PlayerEnum::Two)));
+        assert!(games.play_match("chess", "random", "random").is_err());
+
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        assert_eq!(games.play_match_until_cancelled("nim", "random", "random", &cancellation), Ok(None));
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use {Conclusion, PlayerEnum};
+
+/// How a game came to an end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Termination {
+    /// By the rules of the game (including its draw rules).
+    Normal,
+    /// The loser tried to play an illegal move.
+    Forfeit,
+    /// The loser ran out of time.
+    Timeout,
+    /// The loser resigned.
+    Resignation,
+}
+
+/// Everything there is to know about how a finished game went, from `Adjudicator::result`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MatchResult {
+    pub conclusion: Conclusion,
+    pub termination: Termination,
+    /// The final score, if the game has one and ended normally.
+    pub final_score: Option<f64>,
+    /// Moves played by the adjudicator (not counting any before the position it started from).
+    pub moves: u32,
+    /// How long each player spent choosing their moves, player one's first.
+    pub time_used: (Duration, Duration),
+    /// The seed of the random number generator the game was played with, if it was given one.
+    pub seed: Option<u64>,
+}
+
+impl MatchResult {
+    /// How long `player` spent choosing their moves.
+    pub fn time_used_by(&self, player: PlayerEnum) -> Duration {
+        match player {
+            PlayerEnum::One => self.time_used.0,
+            PlayerEnum::Two => self.time_used.1,
+        }
+    }
+}
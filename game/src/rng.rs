@@ -0,0 +1,56 @@
+//! The source of randomness for random players and simulations, which can be seeded to make games
+//! reproducible.
+
+use std::cell::RefCell;
+
+use rand::{self, Rng};
+
+/// Xorshift, as in `rand::XorShiftRng`, but with its state visible so that it can be saved and
+/// restored.
+struct SeededRng {
+    state: [u32; 4],
+}
+
+impl Rng for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        let [x, y, z, w] = self.state;
+        let t = x ^ (x << 11);
+        self.state = [y, z, w, w ^ (w >> 19) ^ (t ^ (t >> 8))];
+        self.state[3]
+    }
+}
+
+thread_local! {
+    static SEEDED_RNG: RefCell<Option<SeededRng>> = const { RefCell::new(None) };
+}
+
+/// Make this thread's random choices repeatable: after seeding with the same `seed`, the same
+/// sequence of random numbers comes out. Each thread has its own generator, and threads which
+/// haven't been seeded use the system's.
+pub fn seed_thread_rng(seed: u64) {
+    let (low, high) = (seed as u32, (seed >> 32) as u32);
+    // The all-zero state never changes, so mix in some constants.
+    set_thread_rng_state(Some([low, high, low ^ 0x9e37_79b9, high ^ 0x7f4a_7c15 | 1]));
+}
+
+/// Where this thread's seeded generator has got to, or `None` if it isn't seeded.
+pub fn thread_rng_state() -> Option<[u32; 4]> {
+    SEEDED_RNG.with(|x| x.borrow().as_ref().map(|rng| rng.state))
+}
+
+/// Carry on from a state returned by `thread_rng_state`. `None` goes back to the system's
+/// generator.
+pub fn set_thread_rng_state(state: Option<[u32; 4]>) {
+    if let Some(state) = state {
+        assert!(state != [0; 4], "The random number generator can't have an all-zero state");
+    }
+    SEEDED_RNG.with(|x| *x.borrow_mut() = state.map(|state| SeededRng { state }));
+}
+
+/// A random number in [0, 1).
+pub fn random_f64() -> f64 {
+    SEEDED_RNG.with(|x| match *x.borrow_mut() {
+        Some(ref mut rng) => rng.gen(),
+        None => rand::random(),
+    })
+}
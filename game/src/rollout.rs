@@ -0,0 +1,74 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use {random_sample, GameState, PlayerEnum};
+
+/// How to choose moves when playing out random simulations.
+pub trait RolloutPolicy<Game: GameState>: Debug {
+    /// Pick one of `player`'s legal moves, or `None` if there aren't any.
+    fn choose_move(&self, state: &Game, player: PlayerEnum) -> Option<<Game as GameState>::Move>;
+}
+
+/// Shared policies, e.g. one policy used by several players.
+impl<Game: GameState, P: RolloutPolicy<Game> + ?Sized> RolloutPolicy<Game> for Arc<P> {
+    fn choose_move(&self, state: &Game, player: PlayerEnum) -> Option<<Game as GameState>::Move> {
+        (**self).choose_move(state, player)
+    }
+}
+
+/// Every legal move is equally likely. This is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniformRollout;
+
+impl<Game: GameState> RolloutPolicy<Game> for UniformRollout {
+    fn choose_move(&self, state: &Game, player: PlayerEnum) -> Option<<Game as GameState>::Move> {
+        random_sample(state.all_legal_moves(player))
+    }
+}
+
+/// Game-specific knowledge of which moves look good, e.g. moves which win on the spot, block the
+/// opponent's win, or take a strong square.
+pub trait MoveWeights<Game: GameState>: Debug {
+    /// How good `game_move` looks for `player`. Only the differences between the scores of
+    /// different moves matter.
+    fn score(&self, state: &Game, game_move: &<Game as GameState>::Move, player: PlayerEnum) -> f64;
+}
+
+/// Move weights made of numbers which can be tuned automatically, e.g. by evolving them with
+/// `player_of_games::evolution`.
+pub trait TunableWeights<Game: GameState>: MoveWeights<Game> + Clone {
+    /// The numbers, always in the same order.
+    fn parameters(&self) -> Vec<f64>;
+    /// These weights with their numbers replaced by `parameters`, in the order of `parameters`.
+    fn with_parameters(&self, parameters: &[f64]) -> Self;
+}
+
+/// Choose moves with probability proportional to `exp(score / temperature)`, so that simulations
+/// look more like real games. Lower temperatures stick closer to the best looking move.
+#[derive(Clone, Copy, Debug)]
+pub struct SoftmaxRollout<W> {
+    pub weights: W,
+    pub temperature: f64,
+}
+
+impl<Game: GameState, W: MoveWeights<Game>> RolloutPolicy<Game> for SoftmaxRollout<W> {
+    fn choose_move(&self, state: &Game, player: PlayerEnum) -> Option<<Game as GameState>::Move> {
+        let scored: Vec<_> = state.all_legal_moves(player).map(|game_move| {
+            (game_move, self.weights.score(state, &game_move, player))
+        }).collect();
+
+        // Subtract the best score before exponentiating, so nothing overflows.
+        let best = scored.iter().map(|x| x.1).fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = scored.iter().map(|x| ((x.1 - best) / self.temperature).exp()).collect();
+
+        let mut target = ::rng::random_f64() * weights.iter().sum::<f64>();
+        for (&(game_move, _), weight) in scored.iter().zip(weights) {
+            if target < weight {
+                return Some(game_move);
+            }
+            target -= weight;
+        }
+        // Rounding errors can leave a little over at the end.
+        scored.last().map(|x| x.0)
+    }
+}
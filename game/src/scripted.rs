@@ -0,0 +1,50 @@
+//! A player which plays moves it's given in advance, for testing games' rules and how other
+//! players react to particular lines of play.
+
+use std::collections::VecDeque;
+
+use {GameState, Player, PlayerEnum};
+
+/// Plays a fixed sequence of moves, in order, and notes down every move played in the game.
+///
+/// Panics if asked for a move once it's run out, or if its next move isn't legal, so that a test
+/// with a broken script fails loudly.
+#[derive(Debug, Clone)]
+pub struct ScriptedPlayer<Game: GameState> {
+    player: PlayerEnum,
+    script: VecDeque<<Game as GameState>::Move>,
+    moves_played: Vec<<Game as GameState>::Move>,
+}
+
+impl<Game: GameState> ScriptedPlayer<Game> {
+    pub fn new(player: PlayerEnum, script: Vec<<Game as GameState>::Move>) -> Self {
+        Self {
+            player,
+            script: script.into_iter().collect(),
+            moves_played: Vec::new(),
+        }
+    }
+
+    /// The moves it hasn't played yet.
+    pub fn remaining(&self) -> impl Iterator<Item = &<Game as GameState>::Move> {
+        self.script.iter()
+    }
+
+    /// Every move played in the game so far, by either player.
+    pub fn moves_played(&self) -> &[<Game as GameState>::Move] {
+        &self.moves_played
+    }
+}
+
+impl<Game: GameState> Player<Game> for ScriptedPlayer<Game> {
+    fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
+        let game_move = self.script.pop_front().unwrap_or_else(|| panic!("Script for {:?} ran out of moves in:\n{:?}", self.player, game));
+        if let Err(e) = game.is_move_legal(game_move, self.player) {
+            panic!("Scripted move {:?} for {:?} isn't legal ({}) in:\n{:?}", game_move, self.player, e, game);
+        }
+        game_move
+    }
+    fn inform_of_move_played(&mut self, _new_state: Game, game_move: &<Game as GameState>::Move) {
+        self.moves_played.push(*game_move);
+    }
+}
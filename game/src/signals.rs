@@ -0,0 +1,116 @@
+//! Handling signals from outside the program: SIGINT (Ctrl-C) and SIGTERM to shut down
+//! gracefully, and SIGHUP to reload settings. This is behind the `signals` feature, for programs
+//! (rather than libraries) to opt in to.
+//!
+//! The first shutdown signal cancels a token, so that work in flight can wind down and save what
+//! it has, and the program can then exit with a status saying which signal stopped it. A second
+//! signal exits straight away, in case winding down is stuck (e.g. waiting for a human to type in
+//! a move).
+
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Once, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::flag;
+
+use CancellationToken;
+
+/// How often to check for a shutdown signal. Signal handlers can't safely do much more than set a
+/// flag, so the token is cancelled from a thread which watches the flag.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Set by the shutdown signals, once their handlers are installed.
+struct ShutdownFlags {
+    /// Whether there's been a shutdown signal.
+    asked: Arc<AtomicBool>,
+    /// The first shutdown signal received, or 0 if there hasn't been one.
+    signal: Arc<AtomicUsize>,
+    /// The tokens to cancel, until the watcher thread cancels them.
+    tokens: Mutex<Vec<CancellationToken>>,
+}
+
+static SHUTDOWN: OnceLock<Option<ShutdownFlags>> = OnceLock::new();
+
+/// Cancel `token` on SIGINT or SIGTERM (straight away, if there's already been one). If the
+/// handlers can't be installed, this does nothing, and the signals stop the program as usual.
+pub fn cancel_on_shutdown(token: CancellationToken) {
+    let flags = match shutdown_flags() {
+        Some(flags) => flags,
+        None => return,
+    };
+    let mut tokens = flags.tokens.lock().expect("Shutdown tokens poisoned");
+    if flags.asked.load(Ordering::SeqCst) {
+        token.cancel();
+    } else {
+        tokens.retain(|token| !token.is_cancelled());
+        tokens.push(token);
+    }
+}
+
+/// The shutdown signal received, if there's been one.
+pub fn received() -> Option<i32> {
+    let flags = SHUTDOWN.get()?.as_ref()?;
+    match flags.signal.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal as i32),
+    }
+}
+
+/// What to exit with after shutting down because of a signal: 128 plus the signal's number, as
+/// shells report it. `None` if there hasn't been a signal.
+pub fn exit_status() -> Option<i32> {
+    received().map(|signal| 128 + signal)
+}
+
+/// Set `reload` on SIGHUP, for the program to reload its settings when it next checks. On
+/// platforms without SIGHUP, this does nothing.
+#[cfg(unix)]
+pub fn flag_on_hangup(reload: Arc<AtomicBool>) -> io::Result<()> {
+    flag::register(signal_hook::consts::SIGHUP, reload).map(|_| ())
+}
+
+#[cfg(not(unix))]
+pub fn flag_on_hangup(_reload: Arc<AtomicBool>) -> io::Result<()> {
+    Ok(())
+}
+
+/// The shutdown flags, installing their handlers (and starting the thread which watches them) the
+/// first time.
+fn shutdown_flags() -> Option<&'static ShutdownFlags> {
+    let flags = SHUTDOWN.get_or_init(|| {
+        let flags = ShutdownFlags {
+            asked: Arc::new(AtomicBool::new(false)),
+            signal: Arc::new(AtomicUsize::new(0)),
+            tokens: Mutex::new(Vec::new()),
+        };
+        for &signal in &[SIGINT, SIGTERM] {
+            // Handlers run in the order they're registered, so this only exits if asked before
+            flag::register_conditional_shutdown(signal, 128 + signal, flags.asked.clone()).ok()?;
+            flag::register_usize(signal, flags.signal.clone(), signal as usize).ok()?;
+            flag::register(signal, flags.asked.clone()).ok()?;
+        }
+        Some(flags)
+    }).as_ref();
+    static WATCHER: Once = Once::new();
+    if let Some(flags) = flags {
+        WATCHER.call_once(|| {
+            thread::spawn(move || watch(flags));
+        });
+    }
+    flags
+}
+
+/// Wait for a shutdown signal, then cancel every token handed to `cancel_on_shutdown` so far.
+/// Tokens handed over later are cancelled as they come.
+fn watch(flags: &ShutdownFlags) {
+    while !flags.asked.load(Ordering::SeqCst) {
+        thread::sleep(POLL_INTERVAL);
+    }
+    let tokens = ::std::mem::take(&mut *flags.tokens.lock().expect("Shutdown tokens poisoned"));
+    for token in tokens {
+        token.cancel();
+    }
+}
@@ -0,0 +1,128 @@
+//! Suites of positions saved to a file, e.g. openings for a tournament, or troublesome positions
+//! to check engines on.
+//!
+//! A suite file has one JSON position per line, in the game's serialization format along with who
+//! is to move, e.g. `{"state": ..., "to_move": "One", "name": "Corner trap"}`. Blank lines and
+//! lines starting with `#` are skipped.
+//!
+//! Test suites are suites whose positions also list the moves which solve them (`best_moves`)
+//! and/or the moves which fail them (`avoid_moves`), like EPD's `bm` and `am`. `run_test_suite`
+//! checks how many a player solves.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use {GameState, Player, PlayerEnum};
+
+/// A position in a suite.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuitePosition<Game> {
+    pub state: Game,
+    pub to_move: PlayerEnum,
+    /// An optional description, to tell positions apart.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl<Game> SuitePosition<Game> {
+    /// The position and who's to move, as taken by `Tournament::set_openings`.
+    pub fn into_opening(self) -> (Game, PlayerEnum) {
+        (self.state, self.to_move)
+    }
+}
+
+/// A position in a test suite.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "Game: Serialize, <Game as GameState>::Move: Serialize",
+    deserialize = "Game: DeserializeOwned, <Game as GameState>::Move: DeserializeOwned",
+))]
+pub struct TestPosition<Game: GameState> {
+    pub state: Game,
+    pub to_move: PlayerEnum,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The position is solved by playing one of these (if there are any)...
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub best_moves: Vec<<Game as GameState>::Move>,
+    /// ...and not one of these.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub avoid_moves: Vec<<Game as GameState>::Move>,
+}
+
+impl<Game: GameState> TestPosition<Game> {
+    pub fn is_solved_by(&self, game_move: &<Game as GameState>::Move) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(game_move)) && !self.avoid_moves.contains(game_move)
+    }
+}
+
+/// How a player did on one position of a test suite.
+#[derive(Debug, Clone)]
+pub struct TestResult<Move> {
+    pub name: Option<String>,
+    pub chosen_move: Move,
+    pub solved: bool,
+}
+
+/// How a player did on a test suite.
+#[derive(Debug, Clone)]
+pub struct TestSuiteReport<Move> {
+    pub results: Vec<TestResult<Move>>,
+}
+
+impl<Move> TestSuiteReport<Move> {
+    pub fn solved(&self) -> usize {
+        self.results.iter().filter(|x| x.solved).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.results.len()
+    }
+}
+
+/// Ask a player for its move in each position of a test suite. `player` creates a fresh player
+/// (with whatever search budget it should have) for the side to move.
+pub fn run_test_suite<Game, P, F>(positions: &[TestPosition<Game>], mut player: F) -> TestSuiteReport<<Game as GameState>::Move>
+    where Game: GameState,
+          P: Player<Game>,
+          F: FnMut(PlayerEnum) -> P
+{
+    let results = positions.iter().map(|position| {
+        let chosen_move = player(position.to_move).choose_move(position.state.clone());
+        TestResult {
+            name: position.name.clone(),
+            chosen_move,
+            solved: position.is_solved_by(&chosen_move),
+        }
+    }).collect();
+    TestSuiteReport { results }
+}
+
+/// Read the suite in the file at `path`. This works for both `SuitePosition`s and
+/// `TestPosition`s.
+pub fn load_suite<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    parse_suite(&text)
+}
+
+/// Read a suite from the contents of a suite file.
+pub fn parse_suite<T: DeserializeOwned>(text: &str) -> Result<Vec<T>, String> {
+    text.lines().enumerate()
+        .filter(|&(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("Bad position on line {}: {}", i + 1, e)))
+        .collect()
+}
+
+/// Write `positions` to a suite file at `path`, replacing anything already there.
+pub fn save_suite<T: Serialize>(path: &Path, positions: &[T]) -> Result<(), String> {
+    let mut text = String::new();
+    for position in positions {
+        text += &serde_json::to_string(position).map_err(|e| format!("Couldn't serialize position: {}", e))?;
+        text.push('\n');
+    }
+    fs::write(path, text).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+}
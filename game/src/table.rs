@@ -0,0 +1,196 @@
+//! Players written out as tables: every position a player could face in a small game, with the
+//! moves it plays there. A table of a strong search player makes a bot which plays just as well
+//! with no search at all, e.g. to ship a perfect tic-tac-toe player.
+//!
+//! ```ignore
+//! let mut search = MonteCarloTreeSearchPlayer::new(PlayerEnum::One, 2f64.sqrt());
+//! let table = PolicyTable::export(&TicTacToe::new(), &mut search, PlayerEnum::One, 1, Some(10_000))?;
+//! let bot = TablePlayer::new(table);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use rng::random_f64;
+use {random_sample, GameState, Player, PlayerEnum};
+
+/// The moves played in a position, and how often.
+pub type MoveFrequencies<Game> = Vec<(<Game as GameState>::Move, f64)>;
+
+/// What a player does in each position it can face, as each move it plays and how often.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(bound(
+    serialize = "Game: ::serde::Serialize, <Game as GameState>::Move: ::serde::Serialize",
+    deserialize = "Game: ::serde::Deserialize<'de>, <Game as GameState>::Move: ::serde::Deserialize<'de>",
+)))]
+pub struct PolicyTable<Game: GameState> {
+    player: PlayerEnum,
+    entries: Vec<(Game, MoveFrequencies<Game>)>,
+}
+
+impl<Game: GameState> PolicyTable<Game> {
+    /// Write out how `player` plays as `side`, in every position reachable from `initial_state`
+    /// (with player one to move) where it's `side`'s turn. The player is asked `samples` times in
+    /// each, to see how often it plays each move.
+    ///
+    /// The player is asked about positions out of order, so it shouldn't rely on what it's been
+    /// told about the game so far. Fails if there are more than `max_states` positions to ask
+    /// about.
+    pub fn export<P: Player<Game>>(initial_state: &Game, player: &mut P, side: PlayerEnum, samples: u32, max_states: Option<usize>) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        let mut seen = HashSet::new();
+        let mut to_visit = vec![(initial_state.clone(), PlayerEnum::One)];
+        while let Some((state, to_move)) = to_visit.pop() {
+            if !seen.insert((state.clone(), to_move)) || state.try_conclude(to_move).is_some() {
+                continue;
+            }
+
+            let moves: Vec<_> = state.all_legal_moves(to_move).collect();
+            if to_move == side {
+                if max_states.is_some_and(|max| entries.len() >= max) {
+                    return Err(format!("There are more than {} positions for {:?}", entries.len(), side));
+                }
+                let mut counts: MoveFrequencies<Game> = Vec::new();
+                for _ in 0..samples {
+                    let game_move = player.choose_move(state.clone());
+                    match counts.iter_mut().find(|x| x.0 == game_move) {
+                        Some(count) => count.1 += 1.0,
+                        None => counts.push((game_move, 1.0)),
+                    }
+                }
+                for count in counts.iter_mut() {
+                    count.1 /= samples as f64;
+                }
+                entries.push((state.clone(), counts));
+            }
+
+            for game_move in moves {
+                let mut next = state.clone();
+                next.update(game_move, to_move);
+                to_visit.push((next, to_move.other()));
+            }
+        }
+        Ok(Self { player: side, entries })
+    }
+
+    /// Which side the table plays.
+    pub fn player(&self) -> PlayerEnum {
+        self.player
+    }
+
+    /// How many positions the table covers.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Each position, with the moves played there and how often.
+    pub fn entries(&self) -> &[(Game, MoveFrequencies<Game>)] {
+        &self.entries
+    }
+}
+
+/// Plays from a `PolicyTable`, picking moves at random as often as the table says. In positions
+/// the table doesn't cover, it plays a random move.
+pub struct TablePlayer<Game: GameState> {
+    player: PlayerEnum,
+    moves: HashMap<Game, MoveFrequencies<Game>>,
+}
+
+impl<Game: GameState> TablePlayer<Game> {
+    pub fn new(table: PolicyTable<Game>) -> Self {
+        Self {
+            player: table.player,
+            moves: table.entries.into_iter().collect(),
+        }
+    }
+}
+
+impl<Game: GameState> Player<Game> for TablePlayer<Game> {
+    fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
+        if let Some(moves) = self.moves.get(&game) {
+            let mut left = random_f64();
+            for &(game_move, p) in moves.iter() {
+                if left < p {
+                    return game_move;
+                }
+                left -= p;
+            }
+            // Rounding can leave a little over
+            if let Some(&(game_move, _)) = moves.last() {
+                return game_move;
+            }
+        }
+        random_sample(game.all_legal_moves(self.player)).expect("There were no legal moves")
+    }
+
+    fn inform_of_move_played(&mut self, _new_state: Game, _game_move: &<Game as GameState>::Move) {
+        // noop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_games::Nim;
+
+    /// Leaves a multiple of four stones when it can, so it wins Nim whenever it can.
+    struct Perfect;
+
+    impl Player<Nim> for Perfect {
+        fn choose_move(&mut self, game: Nim) -> u32 {
+            match game.stones % 4 {
+                0 => 1,
+                stones => stones,
+            }
+        }
+
+        fn inform_of_move_played(&mut self, _new_state: Nim, _game_move: &u32) {}
+    }
+
+    #[test]
+    fn covers_each_position_for_its_side() {
+        let table = PolicyTable::export(&Nim::new(7), &mut Perfect, PlayerEnum::Two, 3, None).unwrap();
+        assert_eq!(table.player(), PlayerEnum::Two);
+        // Player two can be left with 4, 5 or 6 stones after the first move, then 1 to 3 after
+        // either of its own moves and player one's reply
+        let mut stones: Vec<u32> = table.entries().iter().map(|entry| entry.0.stones).collect();
+        stones.sort();
+        stones.dedup();
+        assert_eq!(stones, vec![1, 2, 3, 4, 5, 6]);
+        for (state, moves) in table.entries() {
+            assert_eq!(*moves, vec![(Perfect.choose_move(state.clone()), 1.0)]);
+        }
+
+        assert!(PolicyTable::export(&Nim::new(7), &mut Perfect, PlayerEnum::Two, 1, Some(2)).is_err());
+    }
+
+    #[test]
+    fn plays_from_the_table() {
+        let table = PolicyTable::export(&Nim::new(7), &mut Perfect, PlayerEnum::One, 1, None).unwrap();
+        let entries = table.entries().to_vec();
+        let mut player = TablePlayer::new(table);
+        for (state, moves) in entries {
+            assert_eq!(player.choose_move(state), moves[0].0);
+        }
+
+        // Off the table, it still plays a legal move
+        let game_move = player.choose_move(Nim::new(20));
+        assert!(Nim::new(20).is_move_legal(game_move, PlayerEnum::One).is_ok());
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trips_through_json() {
+        use serde_json;
+
+        let table = PolicyTable::export(&Nim::new(7), &mut Perfect, PlayerEnum::One, 1, None).unwrap();
+        let json = serde_json::to_string(&table).unwrap();
+        let read: PolicyTable<Nim> = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.player(), table.player());
+        assert_eq!(read.entries(), table.entries());
+    }
+}
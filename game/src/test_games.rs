@@ -0,0 +1,42 @@
+//! Small games for the tests in this crate, which can't use the games in the other crates.
+
+use {Conclusion, GameState, PlayerEnum};
+
+/// Take one to three stones from a pile. Whoever takes the last stone wins, so whoever is left
+/// with a multiple of four stones loses.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Nim {
+    pub stones: u32,
+    pub last_player: Option<PlayerEnum>,
+}
+
+impl Nim {
+    pub fn new(stones: u32) -> Self {
+        Self {
+            stones,
+            last_player: None,
+        }
+    }
+}
+
+impl GameState for Nim {
+    type Move = u32;
+
+    fn update(&mut self, game_move: u32, player: PlayerEnum) {
+        assert!(self.is_move_legal(game_move, player).is_ok(), "Can't take {} from {}", game_move, self.stones);
+        self.stones -= game_move;
+        self.last_player = Some(player);
+    }
+
+    fn all_legal_moves<'a>(&'a self, _player: PlayerEnum) -> Box<dyn Iterator<Item = u32> + 'a> {
+        Box::new(1..=self.stones.min(3))
+    }
+
+    fn try_conclude(&self, _next_player: PlayerEnum) -> Option<Conclusion> {
+        match self.stones {
+            0 => self.last_player.map(Conclusion::Win),
+            _ => None,
+        }
+    }
+}
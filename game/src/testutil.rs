@@ -0,0 +1,242 @@
+//! Checks that a `GameState` implementation keeps the promises the rest of the framework relies
+//! on, so that a new game can get a conformance test for free:
+//!
+//! ```ignore
+//! #[test]
+//! fn conforms() {
+//!     game::testutil::check_game_state(&MyGame::new(), PlayerEnum::One, &Default::default()).unwrap();
+//! }
+//! ```
+//!
+//! The positions checked are found by playing random games from the starting position, with
+//! random numbers from `rng`, so seed that to make the checks repeatable. Illegal moves are
+//! played to check that they panic, so outside of the test harness, expect panic messages.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::panic::{self, AssertUnwindSafe};
+
+use {random_sample, GameState, PlayerEnum};
+
+/// How many positions to check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConformanceSettings {
+    /// Random games to play through.
+    pub games: u32,
+    /// Moves after which to give up on a game which hasn't finished.
+    pub max_plies: u32,
+    /// How many of the positions reached to compare with each other, for the hash/eq check.
+    pub max_compared: usize,
+}
+
+impl Default for ConformanceSettings {
+    fn default() -> Self {
+        Self {
+            games: 20,
+            max_plies: 200,
+            max_compared: 500,
+        }
+    }
+}
+
+/// Run every check on positions reached from `start`, with `first_player` to move. Stops at the
+/// first failure, describing it and the position it happened in. A game which panics where it
+/// shouldn't (e.g. listing the moves of the player not to move) fails the check too.
+pub fn check_game_state<Game: GameState>(start: &Game, first_player: PlayerEnum, settings: &ConformanceSettings) -> Result<(), String> {
+    let positions = without_panics(start, || Ok(random_positions(start, first_player, settings)))?;
+    let mut known_moves = Vec::new();
+    for &(ref state, to_move) in &positions {
+        for player in &[to_move, to_move.other()] {
+            let moves = without_panics(state, || Ok(state.all_legal_moves(*player).collect::<Vec<_>>()))?;
+            for game_move in moves {
+                if !known_moves.contains(&game_move) {
+                    known_moves.push(game_move);
+                }
+            }
+        }
+    }
+
+    for &(ref state, to_move) in &positions {
+        without_panics(state, || {
+            check_conclusion_stable(state, to_move)?;
+            if state.try_conclude(to_move).is_some() {
+                return Ok(());
+            }
+            check_legal_moves_accepted(state, to_move)?;
+            check_illegal_moves_rejected(state, to_move, &known_moves)?;
+            check_clone_independence(state, to_move)?;
+            check_with_move(state, to_move)
+        })?;
+    }
+    let states: Vec<&Game> = positions.iter().map(|x| &x.0).take(settings.max_compared).collect();
+    without_panics(start, || check_hash_eq(&states))
+}
+
+/// Every legal move passes `is_move_legal`, and can be played without panicking.
+pub fn check_legal_moves_accepted<Game: GameState>(state: &Game, player: PlayerEnum) -> Result<(), String> {
+    for game_move in state.all_legal_moves(player) {
+        if let Err(e) = state.is_move_legal(game_move, player) {
+            return Err(format!("Legal move {:?} for {:?} failed is_move_legal ({}) in:\n{:?}", game_move, player, e, state));
+        }
+        let mut next = state.clone();
+        if panic::catch_unwind(AssertUnwindSafe(|| next.update(game_move, player))).is_err() {
+            return Err(format!("Legal move {:?} for {:?} was rejected in:\n{:?}", game_move, player, state));
+        }
+    }
+    Ok(())
+}
+
+/// Any of `candidates` which isn't legal for `player` fails `is_move_legal`, and panics if played.
+pub fn check_illegal_moves_rejected<Game: GameState>(state: &Game, player: PlayerEnum, candidates: &[<Game as GameState>::Move]) -> Result<(), String> {
+    let legal: Vec<_> = state.all_legal_moves(player).collect();
+    for game_move in candidates.iter().filter(|m| !legal.contains(m)) {
+        if state.is_move_legal(*game_move, player).is_ok() {
+            return Err(format!("Illegal move {:?} for {:?} passed is_move_legal in:\n{:?}", game_move, player, state));
+        }
+        let mut next = state.clone();
+        if panic::catch_unwind(AssertUnwindSafe(|| next.update(*game_move, player))).is_ok() {
+            return Err(format!("Illegal move {:?} for {:?} was accepted in:\n{:?}", game_move, player, state));
+        }
+    }
+    Ok(())
+}
+
+/// Asking whether the game is over gives the same answer every time, and for copies of the state.
+pub fn check_conclusion_stable<Game: GameState>(state: &Game, next_player: PlayerEnum) -> Result<(), String> {
+    let conclusion = state.try_conclude(next_player);
+    let again = state.try_conclude(next_player);
+    let for_clone = state.clone().try_conclude(next_player);
+    if conclusion != again || conclusion != for_clone {
+        return Err(format!("Conclusion changed between {:?}, {:?} and (for a clone) {:?} in:\n{:?}", conclusion, again, for_clone, state));
+    }
+    Ok(())
+}
+
+/// States which are equal hash the same, and every state equals its clone.
+pub fn check_hash_eq<Game: GameState>(states: &[&Game]) -> Result<(), String> {
+    for state in states {
+        let clone = (*state).clone();
+        if **state != clone || hash(*state) != hash(&clone) {
+            return Err(format!("State doesn't equal (or hash the same as) its clone:\n{:?}", state));
+        }
+    }
+    for (i, a) in states.iter().enumerate() {
+        for b in &states[i + 1..] {
+            if a == b && hash(*a) != hash(*b) {
+                return Err(format!("Equal states hash differently:\n{:?}\n{:?}", a, b));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Playing a move on a clone of a state leaves the original alone.
+pub fn check_clone_independence<Game: GameState>(state: &Game, player: PlayerEnum) -> Result<(), String> {
+    let before = format!("{:?}", state);
+    let hash_before = hash(state);
+    if let Some(game_move) = state.all_legal_moves(player).next() {
+        let mut clone = state.clone();
+        clone.update(game_move, player);
+        if format!("{:?}", state) != before || hash(state) != hash_before {
+            return Err(format!("Playing {:?} on a clone changed the original, from:\n{}\nto:\n{:?}", game_move, before, state));
+        }
+    }
+    Ok(())
+}
+
+/// `with_move` shows the same state as `update`, and puts the state back afterwards.
+pub fn check_with_move<Game: GameState>(state: &Game, player: PlayerEnum) -> Result<(), String> {
+    let mut scratch = state.clone();
+    for game_move in state.all_legal_moves(player) {
+        let mut expected = state.clone();
+        expected.update(game_move, player);
+        let seen = scratch.with_move(game_move, player, |next| next.clone());
+        if seen != expected {
+            return Err(format!("with_move {:?} for {:?} gave:\n{:?}\nrather than:\n{:?}", game_move, player, seen, expected));
+        }
+        if scratch != *state {
+            return Err(format!("with_move {:?} for {:?} left:\n{:?}\nrather than:\n{:?}", game_move, player, scratch, state));
+        }
+    }
+    Ok(())
+}
+
+/// Every position (and who's to move) reached in some random games.
+fn random_positions<Game: GameState>(start: &Game, first_player: PlayerEnum, settings: &ConformanceSettings) -> Vec<(Game, PlayerEnum)> {
+    let mut positions = vec![(start.clone(), first_player)];
+    for _ in 0..settings.games {
+        let mut state = start.clone();
+        let mut to_move = first_player;
+        for _ in 0..settings.max_plies {
+            if state.try_conclude(to_move).is_some() {
+                break;
+            }
+            let game_move = match random_sample(state.all_legal_moves(to_move)) {
+                Some(game_move) => game_move,
+                None => break,
+            };
+            state.update(game_move, to_move);
+            to_move = to_move.other();
+            positions.push((state.clone(), to_move));
+        }
+    }
+    positions
+}
+
+fn hash<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Run `f`, turning a panic into a failure in `state`.
+fn without_panics<Game: GameState, T, F: FnOnce() -> Result<T, String>>(state: &Game, f: F) -> Result<T, String> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload.downcast_ref::<&str>().map(|x| x.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(format!("Panicked ({}) in or after:\n{:?}", message, state))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_games::Nim;
+    use Conclusion;
+
+    /// Nim, except that asking for player two's moves on player one's turn panics.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct OutOfTurnPanics(Nim);
+
+    impl GameState for OutOfTurnPanics {
+        type Move = u32;
+
+        fn update(&mut self, game_move: u32, player: PlayerEnum) {
+            self.0.update(game_move, player)
+        }
+
+        fn all_legal_moves<'a>(&'a self, player: PlayerEnum) -> Box<dyn Iterator<Item = u32> + 'a> {
+            assert!(player == PlayerEnum::One || self.0.last_player == Some(PlayerEnum::One), "Not player two's turn");
+            self.0.all_legal_moves(player)
+        }
+
+        fn try_conclude(&self, next_player: PlayerEnum) -> Option<Conclusion> {
+            self.0.try_conclude(next_player)
+        }
+    }
+
+    #[test]
+    fn passes_a_conforming_game() {
+        assert_eq!(check_game_state(&Nim::new(10), PlayerEnum::One, &Default::default()), Ok(()));
+    }
+
+    #[test]
+    fn reports_panics_as_failures() {
+        let result = check_game_state(&OutOfTurnPanics(Nim::new(10)), PlayerEnum::One, &Default::default());
+        assert!(result.unwrap_err().contains("Not player two's turn"));
+    }
+}
@@ -0,0 +1,429 @@
+//! Round robin tournaments between players.
+//!
+//! Players which search deterministically would play the same game every time they met, so each
+//! pairing can be started from a set of (e.g. random) opening positions, played once with each
+//! player going first, and repeated games can be left out of the results.
+//!
+//! A cancelled tournament's results can be saved as a checkpoint, to carry on with later:
+//!
+//! ```ignore
+//! let results = tournament.play_round_robin();
+//! if results.cancelled {
+//!     results.save(&path)?;
+//! }
+//! // ...and after a restart
+//! let results = tournament.resume_round_robin(TournamentResults::load(&path)?)?;
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
+
+use {random_sample, Adjudicator, BoxedPlayer, CancellationToken, Conclusion, GameObserver, GameState, MatchResult, PlayerEnum, Termination};
+
+/// Creates an entrant's player, to play as the given side.
+pub type EntrantFactory<Game> = Box<dyn Fn(PlayerEnum) -> BoxedPlayer<Game>>;
+
+pub struct Tournament<Game: GameState> {
+    start: Game,
+    entrants: Vec<(String, EntrantFactory<Game>)>,
+    openings: Vec<(Game, PlayerEnum)>,
+    skip_duplicates: bool,
+    cancellation: CancellationToken,
+}
+
+impl<Game: GameState> Tournament<Game> {
+    /// A tournament with no entrants, where every game starts from `start`.
+    pub fn new(start: Game) -> Self {
+        Self {
+            start,
+            entrants: Vec::new(),
+            openings: Vec::new(),
+            skip_duplicates: false,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    pub fn add_entrant<F>(&mut self, name: &str, factory: F) where F: Fn(PlayerEnum) -> BoxedPlayer<Game> + 'static {
+        self.entrants.push((name.to_string(), Box::new(factory)));
+    }
+
+    /// Start each pairing's games from each of these positions (with the given player to move),
+    /// rather than from the starting position. See also `suite::load_suite`.
+    pub fn set_openings(&mut self, openings: Vec<(Game, PlayerEnum)>) {
+        self.openings = openings;
+    }
+
+    /// Use `count` different openings made by playing `plies` random moves from the starting
+    /// position. Fewer openings are used if there aren't `count` different ones that don't end
+    /// the game.
+    pub fn set_random_openings(&mut self, count: usize, plies: u32) {
+        let mut openings = Vec::new();
+        let mut seen = HashSet::new();
+        // Give up after a while, in case there just aren't enough different openings.
+        for _ in 0..(count * 10) {
+            if openings.len() >= count {
+                break;
+            }
+            if let Some(opening) = random_opening(&self.start, plies) {
+                if seen.insert(opening.clone()) {
+                    openings.push(opening);
+                }
+            }
+        }
+        self.openings = openings;
+    }
+
+    /// Leave games which exactly repeat an earlier game between the same players (with the same
+    /// sides) out of the results.
+    pub fn set_skip_duplicates(&mut self, skip_duplicates: bool) {
+        self.skip_duplicates = skip_duplicates;
+    }
+
+    /// Stop the tournament when `token` is cancelled, between one move and the next. To stop the
+    /// move being thought about promptly too, hand the same token to the entrants' players.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    /// Have every pair of entrants play each opening twice, once going first each. If the
+    /// tournament is cancelled, the results only have the games which finished.
+    pub fn play_round_robin(&self) -> TournamentResults {
+        self.play_from(TournamentResults::new(self.entrant_names()))
+    }
+
+    /// Carry on a cancelled round robin from `checkpoint`, its results so far, playing the games it
+    /// hadn't got to. The tournament must be set up as it was before, with the same entrants and
+    /// openings. Games repeating one from before the checkpoint aren't spotted as duplicates.
+    pub fn resume_round_robin(&self, checkpoint: TournamentResults) -> Result<TournamentResults, String> {
+        let names = self.entrant_names();
+        if checkpoint.entrants != names {
+            return Err(format!("The checkpoint's entrants ({}) aren't this tournament's ({})", checkpoint.entrants.join(", "), names.join(", ")));
+        }
+        Ok(self.play_from(checkpoint))
+    }
+
+    fn entrant_names(&self) -> Vec<String> {
+        self.entrants.iter().map(|x| x.0.clone()).collect()
+    }
+
+    /// Play the round robin's games, skipping as many as `results` already has.
+    fn play_from(&self, mut results: TournamentResults) -> TournamentResults {
+        let openings = match self.openings.is_empty() {
+            true => vec![(self.start.clone(), PlayerEnum::One)],
+            false => self.openings.clone(),
+        };
+        let done = results.games.len() + results.duplicates as usize;
+        let mut scheduled = 0;
+        results.cancelled = false;
+        let mut played = HashSet::new();
+
+        for first in 0..self.entrants.len() {
+            for second in (first + 1)..self.entrants.len() {
+                for (opening_index, opening) in openings.iter().enumerate() {
+                    for &(player_one, player_two) in [(first, second), (second, first)].iter() {
+                        scheduled += 1;
+                        if scheduled <= done {
+                            continue;
+                        }
+                        let (result, moves) = match self.play(opening, player_one, player_two) {
+                            Some(game) => game,
+                            None => {
+                                results.cancelled = true;
+                                return results;
+                            },
+                        };
+                        if self.skip_duplicates && !played.insert((player_one, player_two, opening_index, moves)) {
+                            results.duplicates += 1;
+                            continue;
+                        }
+                        results.record(GameResult { player_one, player_two, opening: opening_index, result });
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    /// Play one game, returning how it went and the moves played, or `None` if the tournament was
+    /// cancelled first.
+    fn play(&self, opening: &(Game, PlayerEnum), player_one: usize, player_two: usize) -> Option<(MatchResult, Vec<<Game as GameState>::Move>)> {
+        let mut adjudicator = Adjudicator::from_position(
+            opening.0.clone(),
+            opening.1,
+            (self.entrants[player_one].1)(PlayerEnum::One),
+            (self.entrants[player_two].1)(PlayerEnum::Two),
+        );
+        let moves = Rc::new(RefCell::new(Vec::new()));
+        adjudicator.add_observer(MoveRecorder(moves.clone()));
+        loop {
+            if let Some(result) = adjudicator.result() {
+                let moves = moves.borrow().clone();
+                return Some((result, moves));
+            }
+            if self.cancellation.is_cancelled() {
+                return None;
+            }
+            adjudicator.progress_one_turn();
+        }
+    }
+}
+
+/// One game of a tournament. Entrants are given by their index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameResult {
+    pub player_one: usize,
+    pub player_two: usize,
+    /// Which opening the game started from.
+    pub opening: usize,
+    pub result: MatchResult,
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentResults {
+    /// The entrants' names, in the order they were added.
+    pub entrants: Vec<String>,
+    /// Each entrant's points: 1 for a win and a half for a draw.
+    pub points: Vec<f64>,
+    /// Every game counted in the results.
+    pub games: Vec<GameResult>,
+    /// How many games were left out for repeating an earlier game.
+    pub duplicates: u32,
+    /// Whether the tournament was cancelled before every game was played.
+    pub cancelled: bool,
+}
+
+impl TournamentResults {
+    fn new(entrants: Vec<String>) -> Self {
+        Self {
+            points: vec![0.0; entrants.len()],
+            entrants,
+            games: Vec::new(),
+            duplicates: 0,
+            cancelled: false,
+        }
+    }
+
+    /// Save the results to `path`, e.g. as a checkpoint of a cancelled tournament, one line for
+    /// each entrant and each game.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        for name in self.entrants.iter() {
+            writeln!(file, "entrant\t{}", name)?;
+        }
+        writeln!(file, "duplicates\t{}", self.duplicates)?;
+        for game in self.games.iter() {
+            let result = &game.result;
+            let conclusion = match result.conclusion {
+                Conclusion::Win(PlayerEnum::One) => "one",
+                Conclusion::Win(PlayerEnum::Two) => "two",
+                Conclusion::Draw => "draw",
+            };
+            let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+            writeln!(file, "game\t{}\t{}\t{}\t{}\t{:?}\t{}\t{}\t{}\t{}\t{}", game.player_one, game.player_two, game.opening,
+                conclusion, result.termination, result.moves, optional(result.final_score.map(|x| x.to_string())),
+                result.time_used.0.as_nanos(), result.time_used.1.as_nanos(), optional(result.seed.map(|x| x.to_string())))?;
+        }
+        file.sync_all()
+    }
+
+    /// Load results saved with `save`. They count as cancelled, as they're only there to be
+    /// resumed.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+        let mut results = Self::new(Vec::new());
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let parsed = match (fields[0], &fields[1..]) {
+                ("entrant", &[name]) => {
+                    results.entrants.push(name.to_string());
+                    results.points.push(0.0);
+                    Some(())
+                },
+                ("duplicates", &[duplicates]) => duplicates.parse().ok().map(|duplicates| results.duplicates = duplicates),
+                ("game", fields) => parse_game(fields).filter(|game| game.player_one.max(game.player_two) < results.entrants.len())
+                    .map(|game| results.record(game)),
+                _ => None,
+            };
+            parsed.ok_or_else(|| format!("Bad checkpoint line {:?}", line))?;
+        }
+        results.cancelled = true;
+        Ok(results)
+    }
+
+    fn record(&mut self, result: GameResult) {
+        match result.result.conclusion {
+            Conclusion::Win(PlayerEnum::One) => self.points[result.player_one] += 1.0,
+            Conclusion::Win(PlayerEnum::Two) => self.points[result.player_two] += 1.0,
+            Conclusion::Draw => {
+                self.points[result.player_one] += 0.5;
+                self.points[result.player_two] += 0.5;
+            }
+        }
+        self.games.push(result);
+    }
+
+    /// Entrants' names and points, from most points to fewest.
+    pub fn standings(&self) -> Vec<(&str, f64)> {
+        let mut standings: Vec<_> = self.entrants.iter().map(|x| x.as_str()).zip(self.points.iter().cloned()).collect();
+        standings.sort_by(|a, b| b.1.partial_cmp(&a.1).expect("Points are never NaN"));
+        standings
+    }
+}
+
+/// A game from a line written by `TournamentResults::save`, after the `game` field.
+fn parse_game(fields: &[&str]) -> Option<GameResult> {
+    if fields.len() != 10 {
+        return None;
+    }
+    fn optional<T: FromStr>(field: &str) -> Option<Option<T>> {
+        match field {
+            "-" => Some(None),
+            value => value.parse().ok().map(Some),
+        }
+    }
+    let nanos = |field: &str| field.parse().ok().map(Duration::from_nanos);
+    Some(GameResult {
+        player_one: fields[0].parse().ok()?,
+        player_two: fields[1].parse().ok()?,
+        opening: fields[2].parse().ok()?,
+        result: MatchResult {
+            conclusion: match fields[3] {
+                "one" => Conclusion::Win(PlayerEnum::One),
+                "two" => Conclusion::Win(PlayerEnum::Two),
+                "draw" => Conclusion::Draw,
+                _ => return None,
+            },
+            termination: match fields[4] {
+                "Normal" => Termination::Normal,
+                "Forfeit" => Termination::Forfeit,
+                "Timeout" => Termination::Timeout,
+                "Resignation" => Termination::Resignation,
+                _ => return None,
+            },
+            moves: fields[5].parse().ok()?,
+            final_score: optional(fields[6])?,
+            time_used: (nanos(fields[7])?, nanos(fields[8])?),
+            seed: optional(fields[9])?,
+        },
+    })
+}
+
+/// Play `plies` random moves from `start`, or give up if the game ends first. Returns the
+/// position and who's to move.
+fn random_opening<Game: GameState>(start: &Game, plies: u32) -> Option<(Game, PlayerEnum)> {
+    let mut state = start.clone();
+    let mut player = PlayerEnum::One;
+    for _ in 0..plies {
+        let game_move = random_sample(state.all_legal_moves(player))?;
+        state.update(game_move, player);
+        player = player.other();
+        if state.try_conclude(player).is_some() {
+            return None;
+        }
+    }
+    Some((state, player))
+}
+
+/// Notes down the moves of a game as they're played.
+struct MoveRecorder<Move>(Rc<RefCell<Vec<Move>>>);
+
+impl<Game: GameState> GameObserver<Game> for MoveRecorder<<Game as GameState>::Move> {
+    fn on_move(&mut self, _player: PlayerEnum, game_move: &<Game as GameState>::Move, _new_state: &Game, _evaluation: Option<f64>) {
+        self.0.borrow_mut().push(*game_move);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::env;
+    use std::path::PathBuf;
+    use std::process;
+    use test_games::Nim;
+    use Player;
+
+    /// Takes as many stones as it's allowed to, up to its limit.
+    struct Takes(u32);
+
+    impl Player<Nim> for Takes {
+        fn choose_move(&mut self, game: Nim) -> u32 {
+            self.0.min(game.stones)
+        }
+
+        fn inform_of_move_played(&mut self, _new_state: Nim, _game_move: &u32) {}
+    }
+
+    /// Three entrants playing Nim, cancelled when `cancel_after` players have been made.
+    fn tournament(cancel_after: Option<usize>) -> Tournament<Nim> {
+        let mut tournament = Tournament::new(Nim::new(10));
+        let token = CancellationToken::new();
+        let made = Rc::new(Cell::new(0));
+        for limit in 1..=3 {
+            let (token, made) = (token.clone(), made.clone());
+            tournament.add_entrant(&format!("takes {}", limit), move |_| {
+                made.set(made.get() + 1);
+                if Some(made.get()) == cancel_after {
+                    token.cancel();
+                }
+                Box::new(Takes(limit))
+            });
+        }
+        tournament.set_cancellation_token(token);
+        tournament
+    }
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("tournament-{}-{}.txt", name, process::id()))
+    }
+
+    #[test]
+    fn saved_results_load_the_same() {
+        let results = tournament(None).play_round_robin();
+        assert_eq!(results.games.len(), 6);
+        let path = checkpoint_path("round-trip");
+        results.save(&path).unwrap();
+        let loaded = TournamentResults::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.entrants, results.entrants);
+        assert_eq!(loaded.points, results.points);
+        assert_eq!(loaded.games, results.games);
+        assert!(loaded.cancelled);
+    }
+
+    #[test]
+    fn resuming_plays_the_rest_of_the_games() {
+        let whole = tournament(None).play_round_robin();
+        // Each game makes two players, so this stops before the third game
+        let stopped = tournament(Some(5)).play_round_robin();
+        assert!(stopped.cancelled);
+        assert_eq!(stopped.games.len(), 2);
+
+        let path = checkpoint_path("resume");
+        stopped.save(&path).unwrap();
+        let checkpoint = TournamentResults::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let resumed = tournament(None).resume_round_robin(checkpoint).unwrap();
+
+        assert!(!resumed.cancelled);
+        assert_eq!(resumed.points, whole.points);
+        let outcomes = |results: &TournamentResults| -> Vec<_> {
+            results.games.iter().map(|game| (game.player_one, game.player_two, game.result.conclusion, game.result.moves)).collect()
+        };
+        assert_eq!(outcomes(&resumed), outcomes(&whole));
+    }
+
+    #[test]
+    fn only_resumes_with_the_same_entrants() {
+        let mut checkpoint = tournament(Some(5)).play_round_robin();
+        checkpoint.entrants.pop();
+        assert!(tournament(None).resume_round_robin(checkpoint).is_err());
+    }
+}
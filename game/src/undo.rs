@@ -0,0 +1,25 @@
+use {GameState, PlayerEnum};
+
+/// Games which can take a move back, so that look-ahead can play moves on one state rather than
+/// cloning it at every ply. This pays off for games whose moves are cheap to apply and undo
+/// compared to copying the whole state.
+///
+/// Implementing this isn't enough for look-ahead to use it: the game should also override
+/// `GameState::with_move`, with `with_move_in_place`.
+pub trait UndoableGameState: GameState {
+    /// Whatever `undo` needs to take a move back.
+    type UndoToken;
+
+    /// Play a move, as `update` would, returning what's needed to undo it.
+    fn apply(&mut self, game_move: Self::Move, player: PlayerEnum) -> Self::UndoToken;
+    /// Take back the last move applied (and not yet undone).
+    fn undo(&mut self, token: Self::UndoToken);
+
+    /// Run `f` on this state with a move applied, then undo the move.
+    fn with_move_in_place<R, F: FnOnce(&mut Self) -> R>(&mut self, game_move: Self::Move, player: PlayerEnum, f: F) -> R {
+        let token = self.apply(game_move, player);
+        let result = f(self);
+        self.undo(token);
+        result
+    }
+}
@@ -0,0 +1,48 @@
+//! The value convention shared by the players and searches: a value is how good a position (or
+//! result) is for one side, from 0 (a certain loss) through a half (a draw) to 1 (a certain win).
+//! Values are always for a particular side, and as the game is zero-sum, the other side's value is
+//! `flip`ped, which is the negamax negation in this convention.
+//!
+//! Use these rather than writing out `1.0 - value` and `match conclusion` at each call site, where
+//! it's easy to get the perspective backwards.
+
+use {Conclusion, PlayerEnum};
+
+/// The value of a draw, and of knowing nothing.
+pub const DRAW: f64 = 0.5;
+
+/// The value of a finished game for `player`.
+pub fn of_conclusion(conclusion: Conclusion, player: PlayerEnum) -> f64 {
+    match conclusion {
+        Conclusion::Win(winner) if winner == player => 1.0,
+        Conclusion::Win(_) => 0.0,
+        Conclusion::Draw => DRAW,
+    }
+}
+
+/// The same value, for the other side.
+pub fn flip(value: f64) -> f64 {
+    1.0 - value
+}
+
+/// Keep a value (e.g. from a model or a heuristic) within bounds.
+pub fn clamp(value: f64) -> f64 {
+    value.clamp(0.0, 1.0)
+}
+
+/// Scale how decisive a value is by `factor`, e.g. to discount rewards from far away. A factor of
+/// 0 makes anything a draw, and 1 leaves it as it is.
+pub fn towards_draw(value: f64, factor: f64) -> f64 {
+    DRAW + factor * (value - DRAW)
+}
+
+/// Mate-distance style scoring: take `penalty` off a win (or add it to a loss) for each of the
+/// `plies` it takes, but never past a draw, so that quick wins and slow losses are preferred.
+pub fn by_distance(value: f64, plies: u32, penalty: f64) -> f64 {
+    let adjustment = penalty * plies as f64;
+    if value > DRAW {
+        (value - adjustment).max(DRAW)
+    } else {
+        (value + adjustment).min(DRAW)
+    }
+}
@@ -0,0 +1,129 @@
+//! Live streaming of games to spectators over WebSockets.
+
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde::Serialize;
+use serde_json;
+use tungstenite::{self, Message};
+
+use {Conclusion, GameObserver, GameState, PlayerEnum};
+
+/// What gets sent to spectators, as JSON tagged with an `event` field.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+enum Event<'a, Game: 'a, Move: 'a> {
+    Move {
+        player: PlayerEnum,
+        #[serde(rename = "move")]
+        game_move: &'a Move,
+        state: &'a Game,
+        evaluation: Option<f64>,
+    },
+    Conclusion {
+        conclusion: Conclusion,
+        final_score: Option<f64>,
+    },
+}
+
+/// An observer which broadcasts each move, the resulting game state and the moving player's
+/// evaluation to every connected WebSocket client.
+///
+/// Spectators can connect at any point; they'll see the game from the next move onwards. Each
+/// spectator has a thread of its own, which does the handshake and then sends it events from a
+/// queue, so a slow spectator holds up neither the others nor the game. Spectators which
+/// disconnect are dropped the next time something is sent.
+pub struct WebSocketBroadcaster {
+    address: SocketAddr,
+    clients: Arc<Mutex<Vec<Sender<String>>>>,
+    errors: Arc<Mutex<Vec<String>>>,
+}
+
+impl WebSocketBroadcaster {
+    /// Start accepting spectators on the given address, in a background thread.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+
+        let accepted_clients = clients.clone();
+        let accept_errors = errors.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let (sender, receiver) = mpsc::channel();
+                        accepted_clients.lock().expect("Client list was poisoned").push(sender);
+                        let errors = accept_errors.clone();
+                        thread::spawn(move || {
+                            if let Err(e) = serve(stream, receiver) {
+                                errors.lock().expect("Error list was poisoned").push(e);
+                            }
+                        });
+                    },
+                    Err(e) => accept_errors.lock().expect("Error list was poisoned").push(format!("Failed to accept spectator: {}", e)),
+                }
+            }
+        });
+
+        Ok(Self {
+            address,
+            clients,
+            errors,
+        })
+    }
+
+    /// The address spectators should connect to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The errors accepting spectators since this was last called, e.g. failed handshakes.
+    pub fn take_errors(&self) -> Vec<String> {
+        ::std::mem::take(&mut *self.errors.lock().expect("Error list was poisoned"))
+    }
+
+    fn broadcast<T: Serialize>(&self, event: &T) {
+        let text = serde_json::to_string(event).expect("Failed to encode event");
+        let mut clients = self.clients.lock().expect("Client list was poisoned");
+        clients.retain(|client| client.send(text.clone()).is_ok());
+    }
+}
+
+/// Do the handshake with a spectator, then send it everything from `events` until either end
+/// hangs up.
+fn serve(stream: TcpStream, events: Receiver<String>) -> Result<(), String> {
+    let mut client = tungstenite::accept(stream).map_err(|e| format!("WebSocket handshake failed: {}", e))?;
+    for text in events {
+        if client.write_message(Message::Text(text)).is_err() {
+            // The spectator has gone
+            break;
+        }
+    }
+    Ok(())
+}
+
+impl<Game> GameObserver<Game> for WebSocketBroadcaster
+    where Game: GameState + Serialize,
+          <Game as GameState>::Move: Serialize
+{
+    fn on_move(&mut self, player: PlayerEnum, game_move: &<Game as GameState>::Move, new_state: &Game, evaluation: Option<f64>) {
+        self.broadcast(&Event::Move {
+            player,
+            game_move,
+            state: new_state,
+            evaluation,
+        });
+    }
+
+    fn on_conclusion(&mut self, conclusion: Conclusion, final_score: Option<f64>) {
+        self.broadcast(&Event::Conclusion::<Game, <Game as GameState>::Move> {
+            conclusion,
+            final_score,
+        });
+    }
+}
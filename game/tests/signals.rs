@@ -0,0 +1,74 @@
+#![cfg(all(unix, feature = "signals"))]
+
+extern crate game;
+extern crate signal_hook;
+
+use std::env;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use game::{signals, CancellationToken};
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::low_level::raise;
+
+/// Set in the copy of this test binary which `second_signal_exits_straight_away` runs.
+const RAISE_TWICE: &str = "SIGNALS_TEST_RAISE_TWICE";
+
+fn wait_for(condition: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !condition() {
+        if Instant::now() > deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    true
+}
+
+// This is the only test in this process to send a shutdown signal, as a second one would exit.
+#[test]
+fn first_signal_cancels() {
+    let (token, other) = (CancellationToken::new(), CancellationToken::new());
+    signals::cancel_on_shutdown(token.clone());
+    signals::cancel_on_shutdown(other.clone());
+    assert_eq!(signals::received(), None);
+
+    raise(SIGTERM).unwrap();
+    assert!(wait_for(|| token.is_cancelled() && other.is_cancelled()), "The tokens weren't cancelled");
+    assert_eq!(signals::received(), Some(SIGTERM));
+    assert_eq!(signals::exit_status(), Some(128 + SIGTERM));
+
+    // Tokens handed over afterwards are cancelled straight away
+    let late = CancellationToken::new();
+    signals::cancel_on_shutdown(late.clone());
+    assert!(wait_for(|| late.is_cancelled()), "The late token wasn't cancelled");
+}
+
+#[test]
+fn second_signal_exits_straight_away() {
+    if env::var_os(RAISE_TWICE).is_some() {
+        signals::cancel_on_shutdown(CancellationToken::new());
+        raise(SIGINT).unwrap();
+        raise(SIGINT).unwrap();
+        panic!("The second signal didn't exit");
+    }
+    let status = Command::new(env::current_exe().unwrap())
+        .args(["second_signal_exits_straight_away", "--exact", "--nocapture"])
+        .env(RAISE_TWICE, "1")
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(128 + SIGINT));
+}
+
+#[test]
+fn hangups_set_the_flag() {
+    let reload = Arc::new(AtomicBool::new(false));
+    signals::flag_on_hangup(reload.clone()).unwrap();
+    for _ in 0..2 {
+        raise(SIGHUP).unwrap();
+        assert!(wait_for(|| reload.swap(false, Ordering::SeqCst)), "The flag wasn't set");
+    }
+}
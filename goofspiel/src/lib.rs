@@ -0,0 +1,236 @@
+//! Goofspiel, the game of sealed bids: each player has the cards 1 to `size`, and so does a deck
+//! of prizes. Each round the top prize is turned over, both players bid a card for it at the same
+//! time, and the higher bid wins the prize's value in points (on a tie, nobody does). Bids are
+//! spent, and whoever has more points when the cards run out wins.
+//!
+//! The framework has players take turns, so player one's bid is sealed in the state until player
+//! two bids, and then both are revealed. The state still holds the sealed bid, and a player two
+//! which searches the state (like the tree search) can see it. Until there's proper support for
+//! simultaneous moves, play player two with something which doesn't look: the `EquilibriumPlayer`,
+//! or a player which goes by the `ImperfectInformation` implementation, which hides the bid.
+//!
+//! The prize order is shuffled when the game is made, and is in the state, so a searching player
+//! knows it in advance. `solver` finds optimal play for that order, exactly, on small decks.
+
+extern crate game;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+
+pub mod solver;
+
+pub use solver::{EquilibriumPlayer, Solver};
+
+use std::fmt;
+
+/// The most cards each player can have, so that a hand fits in a `u32`.
+pub const MAX_SIZE: u8 = 31;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Goofspiel {
+    /// The prizes still to be won, the current one first.
+    prizes: Vec<u8>,
+    /// Each player's unplayed cards, as a bit per card.
+    hands: [u32; 2],
+    scores: [u32; 2],
+    /// Player one's bid for the current prize, until player two bids.
+    sealed_bid: Option<u8>,
+}
+
+/// The cards of `hand`, lowest first.
+fn cards(hand: u32) -> impl Iterator<Item = u8> {
+    (1..=MAX_SIZE).filter(move |card| hand & (1 << card) != 0)
+}
+
+fn index(player: game::PlayerEnum) -> usize {
+    match player {
+        game::PlayerEnum::One => 0,
+        game::PlayerEnum::Two => 1,
+    }
+}
+
+impl Goofspiel {
+    /// A game with the cards 1 to `size`, and the prizes in a random order (from this thread's
+    /// random number generator).
+    pub fn new(size: u8) -> Self {
+        let mut prizes: Vec<u8> = (1..=size).collect();
+        game::cards::shuffle(&mut prizes);
+        Self::with_prizes(prizes)
+    }
+
+    /// A game with the cards 1 to `prizes.len()`, and the prizes in the given order, e.g. highest
+    /// first for a fixed benchmark.
+    pub fn with_prizes(prizes: Vec<u8>) -> Self {
+        assert!(prizes.len() <= MAX_SIZE as usize, "Goofspiel is limited to {} cards", MAX_SIZE);
+        let hand = (1..=prizes.len() as u8).fold(0, |hand, card| hand | (1 << card));
+        Self {
+            prizes,
+            hands: [hand; 2],
+            scores: [0; 2],
+            sealed_bid: None,
+        }
+    }
+
+    /// The prizes still to be won, the current one first.
+    pub fn prizes(&self) -> &[u8] {
+        &self.prizes
+    }
+
+    /// The cards `player` hasn't bid yet, lowest first.
+    pub fn hand(&self, player: game::PlayerEnum) -> Vec<u8> {
+        cards(self.hands[index(player)]).collect()
+    }
+
+    pub fn score(&self, player: game::PlayerEnum) -> u32 {
+        self.scores[index(player)]
+    }
+
+    /// Whose bid it is: player two once player one's bid is sealed.
+    pub fn to_move(&self) -> game::PlayerEnum {
+        match self.sealed_bid {
+            None => game::PlayerEnum::One,
+            Some(_) => game::PlayerEnum::Two,
+        }
+    }
+
+    /// The hands as they were before player one bid.
+    fn hands_before_bidding(&self) -> [u32; 2] {
+        match self.sealed_bid {
+            Some(card) => [self.hands[0] | (1 << card), self.hands[1]],
+            None => self.hands,
+        }
+    }
+
+    fn is_legal(&self, game_move: Move, player: game::PlayerEnum) -> Result<(), String> {
+        if player != self.to_move() {
+            return Err(format!("{:?} bidding out of turn", player));
+        }
+        if self.prizes.is_empty() {
+            return Err("There are no prizes left".to_string());
+        }
+        if game_move.card > MAX_SIZE || self.hands[index(player)] & (1 << game_move.card) == 0 {
+            return Err(format!("{} isn't in {:?}'s hand", game_move.card, player));
+        }
+        Ok(())
+    }
+}
+
+/// Shows the cards left, but not a sealed bid.
+impl fmt::Display for Goofspiel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |cards: &mut dyn Iterator<Item = u8>| cards.map(|card| card.to_string()).collect::<Vec<_>>().join(" ");
+        let hands = self.hands_before_bidding();
+        writeln!(f, "Prizes: {}", list(&mut self.prizes.iter().cloned()))?;
+        writeln!(f, "One: {} points, holding {}", self.scores[0], list(&mut cards(hands[0])))?;
+        write!(f, "Two: {} points, holding {}", self.scores[1], list(&mut cards(hands[1])))?;
+        if self.sealed_bid.is_some() {
+            write!(f, "\nOne has bid")?;
+        }
+        Ok(())
+    }
+}
+
+/// Bid a card.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Move {
+    card: u8,
+}
+
+impl Move {
+    pub fn new(card: u8) -> Move {
+        Move { card }
+    }
+
+    pub fn card(&self) -> u8 {
+        self.card
+    }
+}
+
+impl game::GameState for Goofspiel {
+    type Move = Move;
+
+    fn update(&mut self, game_move: Self::Move, player: game::PlayerEnum) {
+        self.is_legal(game_move, player).expect("Move not legal");
+        self.hands[index(player)] &= !(1 << game_move.card);
+
+        match self.sealed_bid.take() {
+            None => self.sealed_bid = Some(game_move.card),
+            Some(first) => {
+                let prize = self.prizes.remove(0) as u32;
+                if first > game_move.card {
+                    self.scores[0] += prize;
+                } else if game_move.card > first {
+                    self.scores[1] += prize;
+                }
+            },
+        }
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: game::PlayerEnum) -> Box<dyn Iterator<Item = Move> + 'a> {
+        Box::new(cards(self.hands[index(player)]).map(Move::new).filter(move |game_move| self.is_legal(*game_move, player).is_ok()))
+    }
+
+    fn is_move_legal(&self, game_move: Self::Move, player: game::PlayerEnum) -> Result<(), game::IllegalMove> {
+        self.is_legal(game_move, player).map_err(game::IllegalMove)
+    }
+
+    fn try_conclude(&self, _next_player: game::PlayerEnum) -> Option<game::Conclusion> {
+        if !self.prizes.is_empty() {
+            return None;
+        }
+        Some(if self.scores[0] > self.scores[1] {
+            game::Conclusion::Win(game::PlayerEnum::One)
+        } else if self.scores[1] > self.scores[0] {
+            game::Conclusion::Win(game::PlayerEnum::Two)
+        } else {
+            game::Conclusion::Draw
+        })
+    }
+
+    fn final_score(&self) -> Option<f64> {
+        Some(self.scores[0] as f64 - self.scores[1] as f64)
+    }
+}
+
+/// What a player can see: everything but a sealed bid.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Observation {
+    prizes: Vec<u8>,
+    /// The hands as they were before player one bid.
+    hands: [u32; 2],
+    scores: [u32; 2],
+    /// Whether player one has bid.
+    bid_sealed: bool,
+}
+
+impl game::ImperfectInformation for Goofspiel {
+    type InformationSet = Observation;
+
+    /// Player one only ever sees the state before they bid, so they see it all. Player two sees
+    /// that player one has bid, but not what.
+    fn information_set(&self, _player: game::PlayerEnum) -> Observation {
+        Observation {
+            prizes: self.prizes.clone(),
+            hands: self.hands_before_bidding(),
+            scores: self.scores,
+            bid_sealed: self.sealed_bid.is_some(),
+        }
+    }
+}
+
+/// Bids by card number.
+impl game::MoveNotation for Goofspiel {
+    fn parse_move(&self, text: &str, player: game::PlayerEnum) -> Result<Move, String> {
+        let card = text.trim().parse::<u8>().map_err(|_| format!("Expected a card number, not '{}'", text))?;
+        let game_move = Move::new(card);
+        self.is_legal(game_move, player).map_err(|e| format!("Can't bid {}: {}", text, e))?;
+        Ok(game_move)
+    }
+
+    fn move_notation(game_move: &Move) -> String {
+        game_move.card.to_string()
+    }
+}
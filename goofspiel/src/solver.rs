@@ -0,0 +1,237 @@
+//! Optimal play, by working back from the end of the game: each round is a matrix game between
+//! the two bids, whose payoffs are the points for the prize plus the value of the rest of the
+//! game, and is solved exactly by linear programming. Payoffs are in points, so the value of the
+//! rest of the game doesn't depend on the score so far.
+//!
+//! There are about `4^size` positions to solve, so this is for small decks (up to 8 or so), e.g.
+//! to check how far the tree search's bids are from optimal.
+
+use std::collections::HashMap;
+
+use game::rng::random_f64;
+use game::{GameState, Player, PlayerEnum};
+
+use {cards, Goofspiel, Move};
+
+const EPSILON: f64 = 1e-9;
+
+/// Remembers the positions it has solved, so keep one around for a whole game (or many).
+#[derive(Debug, Default)]
+pub struct Solver {
+    /// The value to player one of the rest of the game, by hands and prizes left.
+    values: HashMap<([u32; 2], Vec<u8>), f64>,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Player one's final margin, in points, if both sides play optimally from here. A sealed bid
+    /// is ignored, as if both players were still to bid.
+    pub fn value(&mut self, state: &Goofspiel) -> f64 {
+        let scores = state.scores[0] as f64 - state.scores[1] as f64;
+        scores + self.rest_of_game(state.hands_before_bidding(), &state.prizes)
+    }
+
+    /// The optimal mix of bids for whoever's turn it is, as each bid and its probability. A sealed
+    /// bid is ignored, as it would be if both players really did bid at the same time.
+    pub fn strategy(&mut self, state: &Goofspiel) -> Vec<(Move, f64)> {
+        if state.prizes.is_empty() {
+            return Vec::new();
+        }
+        let (bids, payoffs) = self.round(state.hands_before_bidding(), &state.prizes);
+        let (_, one, two) = solve_matrix(&payoffs);
+        let (cards, probabilities) = match state.to_move() {
+            PlayerEnum::One => (&bids[0], one),
+            PlayerEnum::Two => (&bids[1], two),
+        };
+        cards.iter().map(|&card| Move::new(card)).zip(probabilities).collect()
+    }
+
+    fn rest_of_game(&mut self, hands: [u32; 2], prizes: &[u8]) -> f64 {
+        if prizes.is_empty() {
+            return 0.0;
+        }
+        let key = (hands, prizes.to_vec());
+        if let Some(&value) = self.values.get(&key) {
+            return value;
+        }
+        let (_, payoffs) = self.round(hands, prizes);
+        let (value, _, _) = solve_matrix(&payoffs);
+        self.values.insert(key, value);
+        value
+    }
+
+    /// Each player's bids for the first of `prizes`, and the payoffs to player one for each pair.
+    fn round(&mut self, hands: [u32; 2], prizes: &[u8]) -> ([Vec<u8>; 2], Vec<Vec<f64>>) {
+        let bids = [cards(hands[0]).collect::<Vec<_>>(), cards(hands[1]).collect::<Vec<_>>()];
+        let prize = prizes[0] as f64;
+        let payoffs = bids[0].iter().map(|&one| bids[1].iter().map(|&two| {
+            let points = if one > two {
+                prize
+            } else if two > one {
+                -prize
+            } else {
+                0.0
+            };
+            points + self.rest_of_game([hands[0] & !(1 << one), hands[1] & !(1 << two)], &prizes[1..])
+        }).collect()).collect();
+        (bids, payoffs)
+    }
+}
+
+/// The value to the row player (who maximises) of the zero-sum game with these payoffs, and both
+/// players' optimal strategies: the rows', then the columns'.
+///
+/// With every payoff made positive, the column player's optimal strategy is the `w`, scaled to sum
+/// to 1, which maximises `sum(w)` subject to `payoffs w <= 1` and `w >= 0`. That's solved with the
+/// simplex method, and the row player's strategy comes from its dual.
+fn solve_matrix(payoffs: &[Vec<f64>]) -> (f64, Vec<f64>, Vec<f64>) {
+    let (rows, columns) = (payoffs.len(), payoffs[0].len());
+    let lowest = payoffs.iter().flat_map(|row| row.iter()).cloned().fold(f64::INFINITY, f64::min);
+    let shift = 1.0 - lowest;
+
+    // A row per constraint, then one for the objective. The columns are `w`, then the slack
+    // variables, then the right hand side.
+    let width = columns + rows + 1;
+    let mut tableau = vec![vec![0.0; width]; rows + 1];
+    for i in 0..rows {
+        for j in 0..columns {
+            tableau[i][j] = payoffs[i][j] + shift;
+        }
+        tableau[i][columns + i] = 1.0;
+        tableau[i][width - 1] = 1.0;
+    }
+    for entry in &mut tableau[rows][..columns] {
+        *entry = -1.0;
+    }
+    let mut basis: Vec<usize> = (columns..columns + rows).collect();
+
+    // Bland's rule (the first improving column, and the first basis variable among ties), as the
+    // payoffs often tie and the method could otherwise cycle.
+    while let Some(entering) = (0..width - 1).find(|&j| tableau[rows][j] < -EPSILON) {
+        let candidates: Vec<usize> = (0..rows).filter(|&i| tableau[i][entering] > EPSILON).collect();
+        let ratio = |i: usize| tableau[i][width - 1] / tableau[i][entering];
+        let lowest = candidates.iter().map(|&i| ratio(i)).fold(f64::INFINITY, f64::min);
+        let leaving = candidates.into_iter().filter(|&i| ratio(i) <= lowest + EPSILON).min_by_key(|&i| basis[i])
+            .expect("Every payoff is positive, so the program is bounded");
+
+        let pivot = tableau[leaving][entering];
+        for x in tableau[leaving].iter_mut() {
+            *x /= pivot;
+        }
+        let pivot_row = tableau[leaving].clone();
+        for (i, row) in tableau.iter_mut().enumerate() {
+            let factor = row[entering];
+            if i != leaving && factor != 0.0 {
+                for (x, p) in row.iter_mut().zip(pivot_row.iter()) {
+                    *x -= factor * p;
+                }
+            }
+        }
+        basis[leaving] = entering;
+    }
+
+    let value = 1.0 / tableau[rows][width - 1];
+    let mut column_strategy = vec![0.0; columns];
+    for (i, &j) in basis.iter().enumerate() {
+        if j < columns {
+            column_strategy[j] = tableau[i][width - 1] * value;
+        }
+    }
+    let row_strategy = (0..rows).map(|i| tableau[rows][columns + i] * value).collect();
+    (value - shift, row_strategy, column_strategy)
+}
+
+/// Bids at random from the optimal mix, so it can't be exploited (on average), and doesn't look
+/// at a sealed bid.
+#[derive(Debug)]
+pub struct EquilibriumPlayer {
+    player: PlayerEnum,
+    solver: Solver,
+}
+
+impl EquilibriumPlayer {
+    pub fn new(player: PlayerEnum) -> Self {
+        Self { player, solver: Solver::new() }
+    }
+}
+
+impl Player<Goofspiel> for EquilibriumPlayer {
+    fn choose_move(&mut self, game: Goofspiel) -> Move {
+        let strategy = self.solver.strategy(&game);
+        let mut left = random_f64();
+        for &(game_move, probability) in strategy.iter() {
+            if left < probability {
+                return game_move;
+            }
+            left -= probability;
+        }
+        // Rounding can leave a little over
+        strategy.iter().rev().find(|&&(_, probability)| probability > 0.0).map(|x| x.0)
+            .or_else(|| game.all_legal_moves(self.player).next())
+            .expect("There were no legal moves")
+    }
+
+    fn inform_of_move_played(&mut self, _new_state: Goofspiel, _game_move: &Move) {
+        // noop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{} isn't {}", actual, expected);
+    }
+
+    fn assert_mixed(strategy: &[f64], expected: &[f64]) {
+        assert_eq!(strategy.len(), expected.len());
+        for (&actual, &expected) in strategy.iter().zip(expected) {
+            assert_close(actual, expected);
+        }
+    }
+
+    #[test]
+    fn solves_matching_pennies() {
+        let (value, one, two) = solve_matrix(&[vec![1.0, -1.0], vec![-1.0, 1.0]]);
+        assert_close(value, 0.0);
+        assert_mixed(&one, &[0.5, 0.5]);
+        assert_mixed(&two, &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn solves_rock_paper_scissors() {
+        let (value, one, two) = solve_matrix(&[
+            vec![0.0, -1.0, 1.0],
+            vec![1.0, 0.0, -1.0],
+            vec![-1.0, 1.0, 0.0],
+        ]);
+        assert_close(value, 0.0);
+        let third = 1.0 / 3.0;
+        assert_mixed(&one, &[third, third, third]);
+        assert_mixed(&two, &[third, third, third]);
+    }
+
+    #[test]
+    fn solves_games_with_a_saddle_point() {
+        let (value, one, two) = solve_matrix(&[vec![3.0, 1.0], vec![2.0, 0.0]]);
+        assert_close(value, 1.0);
+        assert_mixed(&one, &[1.0, 0.0]);
+        assert_mixed(&two, &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn the_start_is_even() {
+        let mut solver = Solver::new();
+        let start = Goofspiel::with_prizes(vec![3, 1, 4, 2]);
+        assert_close(solver.value(&start), 0.0);
+
+        let strategy = solver.strategy(&start);
+        assert_eq!(strategy.len(), 4);
+        assert!(strategy.iter().all(|&(_, probability)| probability >= -1e-9));
+        assert_close(strategy.iter().map(|x| x.1).sum(), 1.0);
+    }
+}
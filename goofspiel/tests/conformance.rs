@@ -0,0 +1,12 @@
+extern crate game;
+extern crate goofspiel;
+
+use game::testutil::check_game_state;
+use game::PlayerEnum;
+use goofspiel::Goofspiel;
+
+#[test]
+fn conforms() {
+    game::rng::seed_thread_rng(1);
+    check_game_state(&Goofspiel::new(5), PlayerEnum::One, &Default::default()).unwrap();
+}
@@ -0,0 +1,63 @@
+extern crate game;
+extern crate goofspiel;
+
+use game::{Conclusion, GameState, ImperfectInformation, PlayerEnum};
+use goofspiel::{Goofspiel, Move};
+
+/// Play each round's bids, player one's first.
+fn play(game: &mut Goofspiel, rounds: &[(u8, u8)]) {
+    for &(one, two) in rounds {
+        game.update(Move::new(one), PlayerEnum::One);
+        game.update(Move::new(two), PlayerEnum::Two);
+    }
+}
+
+#[test]
+fn the_higher_bid_wins_the_prize() {
+    let mut game = Goofspiel::with_prizes(vec![3, 1, 2]);
+    play(&mut game, &[(1, 3)]);
+    assert_eq!((game.score(PlayerEnum::One), game.score(PlayerEnum::Two)), (0, 3));
+    play(&mut game, &[(2, 2)]);
+    assert_eq!(game.prizes(), &[2]);
+    assert_eq!(game.hand(PlayerEnum::One), vec![3]);
+    assert_eq!(game.hand(PlayerEnum::Two), vec![1]);
+    assert_eq!(game.try_conclude(PlayerEnum::One), None);
+
+    play(&mut game, &[(3, 1)]);
+    assert_eq!((game.score(PlayerEnum::One), game.score(PlayerEnum::Two)), (2, 3));
+    assert_eq!(game.try_conclude(PlayerEnum::One), Some(Conclusion::Win(PlayerEnum::Two)));
+    assert_eq!(game.final_score(), Some(-1.0));
+}
+
+#[test]
+fn nobody_wins_a_tied_prize() {
+    let mut game = Goofspiel::with_prizes(vec![2, 1]);
+    play(&mut game, &[(2, 2), (1, 1)]);
+    assert_eq!((game.score(PlayerEnum::One), game.score(PlayerEnum::Two)), (0, 0));
+    assert_eq!(game.try_conclude(PlayerEnum::One), Some(Conclusion::Draw));
+    assert_eq!(game.final_score(), Some(0.0));
+}
+
+#[test]
+fn bids_are_spent_and_taken_in_turn() {
+    let mut game = Goofspiel::with_prizes(vec![1, 2, 3]);
+    assert!(game.is_move_legal(Move::new(2), PlayerEnum::Two).is_err());
+    assert!(game.is_move_legal(Move::new(4), PlayerEnum::One).is_err());
+    play(&mut game, &[(2, 1)]);
+    assert!(game.is_move_legal(Move::new(2), PlayerEnum::One).is_err());
+    assert!(game.is_move_legal(Move::new(2), PlayerEnum::Two).is_err());
+    assert!(game.is_move_legal(Move::new(3), PlayerEnum::One).is_ok());
+    let bids: Vec<u8> = game.all_legal_moves(PlayerEnum::One).map(|game_move| game_move.card()).collect();
+    assert_eq!(bids, vec![1, 3]);
+}
+
+#[test]
+fn nobody_can_see_the_sealed_bid() {
+    let mut one_bid = Goofspiel::with_prizes(vec![1, 2, 3]);
+    one_bid.update(Move::new(1), PlayerEnum::One);
+    let mut three_bid = Goofspiel::with_prizes(vec![1, 2, 3]);
+    three_bid.update(Move::new(3), PlayerEnum::One);
+    assert_ne!(one_bid, three_bid);
+    assert_eq!(one_bid.information_set(PlayerEnum::Two), three_bid.information_set(PlayerEnum::Two));
+    assert_eq!(one_bid.to_string(), three_bid.to_string());
+}
@@ -0,0 +1,349 @@
+//! Training a model for a game in generations, AlphaZero style: the best model so far plays
+//! itself to make training data, an external trainer trains a candidate on it, and the candidate
+//! replaces the best if it wins a match against it (decided by a sequential probability ratio
+//! test, or SPRT). The first generation plays without a model.
+//!
+//! Everything lives in one directory, so a run can be stopped and picked up again:
+//!
+//! - `checkpoint.txt`: the next generation to run, and the generation of the best model (or
+//!   `none`), as `generation <n>` and `best <n>` lines.
+//! - `samples-<n>.jsonl`: generation `n`'s self-play data, one position per line, as a JSON
+//!   object with `shape` and `features` (the position, from `FeatureEncoder::encode`), `policy`
+//!   (the share of the search's visits for each move index) and `value` (how the game went for
+//!   the player to move: 1 for a win, 0.5 for a draw and 0 for a loss). See `replay::Sample`.
+//!
+//! The trainer keeps its own models, by generation.
+
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use game::rng::random_f64;
+use game::{value, Adjudicator, CancellationToken, Conclusion, FeatureEncoder, GameState, Player, PlayerEnum};
+
+use evaluator::{Evaluator, EvaluatorPriors};
+use replay::Sample;
+use self_play::MctsConfig;
+use MonteCarloTreeSearchPlayer;
+
+/// Trains models from self-play data, outside the framework (e.g. by running a Python script).
+pub trait Trainer<V> {
+    /// Train a model for `generation` from `samples` (every generation's self-play data so far,
+    /// oldest first), keeping it so that `load` can find it again.
+    fn train(&mut self, generation: u32, samples: &[PathBuf]) -> Result<V, String>;
+
+    /// The model trained for `generation`.
+    fn load(&mut self, generation: u32) -> Result<V, String>;
+}
+
+/// A sequential probability ratio test of whether a candidate is `elo1` Elo stronger than what
+/// it's playing (rather than `elo0`), with false positive rate `alpha` and false negative rate
+/// `beta`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Self {
+            elo0: 0.0,
+            elo1: 30.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl Sprt {
+    /// The log likelihood ratio of the results so far (by the normal approximation).
+    pub fn llr(&self, wins: u32, draws: u32, losses: u32) -> f64 {
+        let games = (wins + draws + losses) as f64;
+        if games == 0.0 {
+            return 0.0;
+        }
+        let score = (wins as f64 + 0.5 * draws as f64) / games;
+        let variance = (wins as f64 * (1.0 - score).powi(2) + draws as f64 * (0.5 - score).powi(2) + losses as f64 * score.powi(2)) / games;
+        if variance <= 0.0 {
+            return 0.0;
+        }
+        let expected = |elo: f64| 1.0 / (1.0 + 10f64.powf(-elo / 400.0));
+        let (score0, score1) = (expected(self.elo0), expected(self.elo1));
+        games * (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance)
+    }
+
+    /// `Some(true)` if the candidate is stronger, `Some(false)` if it isn't, or `None` if it's
+    /// too soon to tell.
+    pub fn decide(&self, wins: u32, draws: u32, losses: u32) -> Option<bool> {
+        let llr = self.llr(wins, draws, losses);
+        if llr >= ((1.0 - self.beta) / self.alpha).ln() {
+            Some(true)
+        } else if llr <= (self.beta / (1.0 - self.alpha)).ln() {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// How to run the generations.
+#[derive(Debug, Clone)]
+pub struct AlphaZeroSettings<Game: GameState> {
+    /// Where every game starts, with player one to move.
+    pub start: Game,
+    /// How the players search (apart from their expansion policy).
+    pub search: MctsConfig<Game>,
+    /// See `EvaluatorPriors`.
+    pub value_visits: f64,
+    /// See `EvaluatorPriors`.
+    pub policy_visits: f64,
+    /// Self-play games per generation.
+    pub self_play_games: u32,
+    /// How many moves into each self-play game to choose moves at random, in proportion to
+    /// their visits, rather than the best, so the games differ.
+    pub sampled_moves: u32,
+    pub sprt: Sprt,
+    /// Give up on the candidate if the SPRT hasn't passed after this many games.
+    pub max_evaluation_games: u32,
+    /// Where to keep the checkpoint and the self-play data.
+    pub directory: PathBuf,
+}
+
+/// How a generation went.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationReport {
+    pub generation: u32,
+    /// Positions written for training.
+    pub samples: usize,
+    /// The candidate's results against the best model.
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub llr: f64,
+    /// Whether the candidate became the best model.
+    pub promoted: bool,
+}
+
+/// Runs the generations. See the module documentation.
+pub struct Orchestrator<Game: GameState, E, V, T> {
+    settings: AlphaZeroSettings<Game>,
+    encoder: E,
+    trainer: T,
+    generation: u32,
+    best_generation: Option<u32>,
+    best: Option<Arc<V>>,
+    cancellation: CancellationToken,
+}
+
+impl<Game, E, V, T> Orchestrator<Game, E, V, T>
+    where Game: GameState + Send + Sync,
+          <Game as GameState>::Move: Send + Sync,
+          E: FeatureEncoder<Game> + ::std::fmt::Debug + Clone + Send + Sync + 'static,
+          V: Evaluator + Send + Sync + 'static,
+          T: Trainer<V>,
+{
+    /// Start a run in `settings.directory`, or pick one up from its checkpoint (loading the best
+    /// model from `trainer`).
+    pub fn new(settings: AlphaZeroSettings<Game>, encoder: E, mut trainer: T) -> Result<Self, String> {
+        fs::create_dir_all(&settings.directory).map_err(|e| format!("Couldn't create {:?}: {}", settings.directory, e))?;
+        let (generation, best_generation) = read_checkpoint(&settings.directory.join("checkpoint.txt"))?;
+        let best = match best_generation {
+            Some(best_generation) => Some(Arc::new(trainer.load(best_generation)?)),
+            None => None,
+        };
+        Ok(Self { settings, encoder, trainer, generation, best_generation, best, cancellation: CancellationToken::new() })
+    }
+
+    /// Stop the generation being run when `token` is cancelled (apart from training, which the
+    /// trainer does in its own time). It fails, leaving the checkpoint as it was.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    /// The next generation to run.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// The generation of the best model, if there is one yet.
+    pub fn best_generation(&self) -> Option<u32> {
+        self.best_generation
+    }
+
+    /// Run a whole generation: self-play, training, evaluation and (maybe) promotion. The
+    /// checkpoint is only updated once it's all done, so an interrupted generation is rerun.
+    pub fn run_generation(&mut self) -> Result<GenerationReport, String> {
+        let generation = self.generation;
+        let samples_path = self.samples_path(generation);
+        let samples = self.self_play(&samples_path).map_err(|e| format!("Couldn't write {:?}: {}", samples_path, e))?;
+        if self.cancellation.is_cancelled() {
+            return Err(format!("Generation {} was cancelled during self-play", generation));
+        }
+
+        let all_samples: Vec<PathBuf> = (0..(generation + 1)).map(|g| self.samples_path(g)).filter(|path| path.exists()).collect();
+        let candidate = Arc::new(self.trainer.train(generation, &all_samples)?);
+
+        let (mut wins, mut draws, mut losses) = (0, 0, 0);
+        let mut passed = None;
+        for game in 0..self.settings.max_evaluation_games {
+            let candidate_side = if game % 2 == 0 { PlayerEnum::One } else { PlayerEnum::Two };
+            let best = self.best.clone();
+            let conclusion = match candidate_side {
+                PlayerEnum::One => self.play(Some(&candidate), best.as_ref()),
+                PlayerEnum::Two => self.play(best.as_ref(), Some(&candidate)),
+            };
+            match conclusion.ok_or_else(|| format!("Generation {} was cancelled during evaluation", generation))? {
+                Conclusion::Win(winner) if winner == candidate_side => wins += 1,
+                Conclusion::Win(_) => losses += 1,
+                Conclusion::Draw => draws += 1,
+            }
+            passed = self.settings.sprt.decide(wins, draws, losses);
+            if passed.is_some() {
+                break;
+            }
+        }
+
+        let promoted = passed == Some(true);
+        if promoted {
+            self.best = Some(candidate);
+            self.best_generation = Some(generation);
+        }
+        self.generation += 1;
+        write_checkpoint(&self.settings.directory.join("checkpoint.txt"), self.generation, self.best_generation)
+            .map_err(|e| format!("Couldn't write the checkpoint: {}", e))?;
+
+        Ok(GenerationReport {
+            generation,
+            samples,
+            wins,
+            draws,
+            losses,
+            llr: self.settings.sprt.llr(wins, draws, losses),
+            promoted,
+        })
+    }
+
+    fn samples_path(&self, generation: u32) -> PathBuf {
+        self.settings.directory.join(format!("samples-{}.jsonl", generation))
+    }
+
+    /// A player for `side`, guided by `model` if there is one.
+    fn player(&self, model: Option<&Arc<V>>, side: PlayerEnum) -> MonteCarloTreeSearchPlayer<Game> {
+        let mut mcts = self.settings.search.build(side);
+        mcts.set_cancellation_token(self.cancellation.clone());
+        if let Some(model) = model {
+            mcts.set_expansion_policy(EvaluatorPriors::new(self.encoder.clone(), model.clone(), self.settings.value_visits, self.settings.policy_visits));
+        }
+        mcts
+    }
+
+    /// Play a game between `one` and `two`'s models, unless it's cancelled first.
+    fn play(&self, one: Option<&Arc<V>>, two: Option<&Arc<V>>) -> Option<Conclusion> {
+        let mut adjudicator = Adjudicator::new(self.settings.start.clone(), self.player(one, PlayerEnum::One), self.player(two, PlayerEnum::Two));
+        while adjudicator.conclusion().is_none() {
+            if self.cancellation.is_cancelled() {
+                return None;
+            }
+            adjudicator.progress_one_turn();
+        }
+        adjudicator.conclusion()
+    }
+
+    /// Play the self-play games with the best model, writing every position to `path`. Returns
+    /// how many were written. Games cut short by cancellation aren't written.
+    fn self_play(&self, path: &PathBuf) -> io::Result<usize> {
+        let mut output = BufWriter::new(fs::File::create(path)?);
+        let mut count = 0;
+        for _ in 0..self.settings.self_play_games {
+            let mut players = [self.player(self.best.as_ref(), PlayerEnum::One), self.player(self.best.as_ref(), PlayerEnum::Two)];
+            let mut state = self.settings.start.clone();
+            let mut to_move = PlayerEnum::One;
+            let mut positions = Vec::new();
+            let conclusion = loop {
+                if let Some(conclusion) = state.try_conclude(to_move) {
+                    break conclusion;
+                }
+                if self.cancellation.is_cancelled() {
+                    output.flush()?;
+                    return Ok(count);
+                }
+                let side = if to_move == PlayerEnum::One { 0 } else { 1 };
+                let analysis = players[side].analyse(&state);
+                let total: u32 = analysis.policy.iter().map(|x| x.1).sum();
+                let mut policy = vec![0.0f32; self.encoder.move_count()];
+                for &(game_move, visits) in &analysis.policy {
+                    policy[self.encoder.move_index(&game_move, to_move)] = visits as f32 / total.max(1) as f32;
+                }
+                let game_move = if positions.len() < self.settings.sampled_moves as usize {
+                    sample_by_visits(&analysis.policy)
+                } else {
+                    analysis.best_move
+                };
+                let game_move = match game_move {
+                    Some(game_move) => game_move,
+                    None => break Conclusion::Draw,
+                };
+                positions.push((self.encoder.encode(&state, to_move), policy, to_move));
+
+                state.update(game_move, to_move);
+                for player in players.iter_mut() {
+                    player.inform_of_move_played(state.clone(), &game_move);
+                }
+                to_move = to_move.other();
+            };
+
+            for (features, policy, player) in positions {
+                let value = value::of_conclusion(conclusion, player) as f32;
+                let sample = Sample { shape: features.shape, features: features.data, policy, value };
+                writeln!(output, "{}", sample.to_json())?;
+                count += 1;
+            }
+        }
+        output.flush()?;
+        Ok(count)
+    }
+}
+
+/// A move chosen at random, in proportion to its visits.
+fn sample_by_visits<Move: Copy>(policy: &[(Move, u32)]) -> Option<Move> {
+    let total: u32 = policy.iter().map(|x| x.1).sum();
+    let mut target = random_f64() * total as f64;
+    for &(game_move, visits) in policy {
+        if target < visits as f64 {
+            return Some(game_move);
+        }
+        target -= visits as f64;
+    }
+    policy.first().map(|x| x.0)
+}
+
+/// The next generation and the best generation from the checkpoint at `path`, or a fresh start
+/// if there isn't one.
+fn read_checkpoint(path: &PathBuf) -> Result<(u32, Option<u32>), String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok((0, None)),
+        Err(e) => return Err(format!("Couldn't read {:?}: {}", path, e)),
+    };
+    let (mut generation, mut best) = (0, None);
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("generation"), Some(value)) => generation = value.parse().map_err(|e| format!("Bad generation in checkpoint: {}", e))?,
+            (Some("best"), Some("none")) => best = None,
+            (Some("best"), Some(value)) => best = Some(value.parse().map_err(|e| format!("Bad best generation in checkpoint: {}", e))?),
+            (None, _) => (),
+            _ => return Err(format!("Bad checkpoint line {:?}", line)),
+        }
+    }
+    Ok((generation, best))
+}
+
+fn write_checkpoint(path: &PathBuf, generation: u32, best: Option<u32>) -> io::Result<()> {
+    let best = best.map_or("none".to_string(), |best| best.to_string());
+    fs::write(path, format!("generation {}\nbest {}\n", generation, best))
+}
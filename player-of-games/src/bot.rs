@@ -0,0 +1,197 @@
+//! Answering move requests from bot platforms, where each request brings a position and how long
+//! the bot has left to reply, and a late (or missing) reply loses the game.
+//!
+//! The search's budget is chosen from the deadline, leaving a safety margin for getting the reply
+//! back. If there's no time to search, or the search panics, a move is still returned: the rollout
+//! policy's choice, or failing that any legal move. Each client can also be given a thinking
+//! allowance, so one busy client can't starve the rest.
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+use game::{CancellationToken, Clock, GameState, Player, PlayerEnum, SystemClock};
+
+use self_play::MctsConfig;
+use SearchBudget;
+
+/// How much of the time left to spend thinking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeadlineSettings {
+    /// Time kept back for everything but the search, e.g. sending the reply.
+    pub safety_margin: Duration,
+    /// The share of the time left (after the margin) to think for.
+    pub share: f64,
+    /// The longest to think about any move.
+    pub max_think: Duration,
+    /// Don't bother searching for less than this.
+    pub min_think: Duration,
+}
+
+impl Default for DeadlineSettings {
+    fn default() -> Self {
+        Self {
+            safety_margin: Duration::from_millis(250),
+            share: 0.9,
+            max_think: Duration::from_secs(10),
+            min_think: Duration::from_millis(5),
+        }
+    }
+}
+
+impl DeadlineSettings {
+    /// How long to think with `time_left` to reply.
+    pub fn think_time(&self, time_left: Duration) -> Duration {
+        let usable = time_left.checked_sub(self.safety_margin).unwrap_or_default();
+        Duration::from_secs_f64(usable.as_secs_f64() * self.share.clamp(0.0, 1.0)).min(self.max_think)
+    }
+}
+
+/// How much each client may have the bot think for: `rate` seconds of thinking per second, saved
+/// up to at most `burst`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Allowance {
+    pub rate: f64,
+    pub burst: Duration,
+}
+
+impl Default for Allowance {
+    fn default() -> Self {
+        Self {
+            rate: 0.5,
+            burst: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A client's thinking time left, as of a clock reading.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    available: f64,
+    updated: Duration,
+}
+
+/// Where a bot's move came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveSource {
+    Search,
+    /// There was no time (or allowance) to search, or the search failed.
+    Fallback,
+}
+
+/// The bot's reply to a move request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BotMove<Move> {
+    pub game_move: Move,
+    pub source: MoveSource,
+    /// How long the bot spent on the request.
+    pub thinking_time: Duration,
+}
+
+/// Chooses moves for bot platforms' requests, with a fresh engine set up as `config` (apart from
+/// its budget) each time.
+#[derive(Debug)]
+pub struct MoveBot<Game: GameState> {
+    config: MctsConfig<Game>,
+    deadline: DeadlineSettings,
+    allowance: Option<Allowance>,
+    buckets: HashMap<String, Bucket>,
+    clock: Box<dyn Clock + Send + Sync>,
+    cancellation: CancellationToken,
+}
+
+impl<Game: GameState> MoveBot<Game> {
+    pub fn new(config: MctsConfig<Game>, deadline: DeadlineSettings) -> Self {
+        Self {
+            config,
+            deadline,
+            allowance: None,
+            buckets: HashMap::new(),
+            clock: Box::new(SystemClock::new()),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// Limit how long each client may have the bot think for, or `None` (the default) for no
+    /// limit. Clients start with a full allowance.
+    pub fn set_allowance(&mut self, allowance: Option<Allowance>) {
+        self.allowance = allowance;
+        self.buckets.clear();
+    }
+
+    /// Time requests and allowances with `clock` rather than the system clock. The searches are
+    /// timed with the engines' own clocks.
+    pub fn set_clock<C: Clock + Send + Sync + 'static>(&mut self, clock: C) {
+        self.clock = Box::new(clock);
+        self.buckets.clear();
+    }
+
+    /// Cut searches short when `token` is cancelled, replying with the best move found so far.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    /// `client`'s thinking allowance left, if there's a limit.
+    pub fn allowance_left(&mut self, client: &str) -> Option<Duration> {
+        self.refill(client).map(Duration::from_secs_f64)
+    }
+
+    /// Choose a move for `to_move` in `state`, replying within `time_left`. This only fails if
+    /// the game is over, or there are no legal moves.
+    pub fn choose_move(&mut self, client: &str, state: &Game, to_move: PlayerEnum, time_left: Duration) -> Result<BotMove<<Game as GameState>::Move>, String> {
+        if state.try_conclude(to_move).is_some() {
+            return Err("The game is already over".to_string());
+        }
+        let start = self.clock.now();
+        let mut think = self.deadline.think_time(time_left);
+        if let Some(available) = self.refill(client) {
+            think = think.min(Duration::from_secs_f64(available));
+        }
+
+        let searched = if think >= self.deadline.min_think {
+            let mut engine = self.config.build(to_move);
+            engine.set_budget(SearchBudget::Time(think));
+            engine.set_cancellation_token(self.cancellation.clone());
+            panic::catch_unwind(AssertUnwindSafe(|| engine.analyse_as(state, to_move).best_move)).ok()
+                .and_then(|game_move| game_move)
+                .filter(|&game_move| state.is_move_legal(game_move, to_move).is_ok())
+        } else {
+            None
+        };
+        let (game_move, source) = match searched {
+            Some(game_move) => (game_move, MoveSource::Search),
+            None => (self.fallback(state, to_move).ok_or_else(|| "There are no legal moves".to_string())?, MoveSource::Fallback),
+        };
+
+        let thinking_time = self.clock.now().checked_sub(start).unwrap_or_default();
+        if let Some(bucket) = self.buckets.get_mut(client) {
+            bucket.available = (bucket.available - thinking_time.as_secs_f64()).max(0.0);
+        }
+        Ok(BotMove { game_move, source, thinking_time })
+    }
+
+    /// The rollout policy's move, or the first legal move if it fails.
+    fn fallback(&self, state: &Game, to_move: PlayerEnum) -> Option<<Game as GameState>::Move> {
+        let policy = &self.config.rollout_policy;
+        panic::catch_unwind(AssertUnwindSafe(|| policy.choose_move(state, to_move))).ok()
+            .and_then(|game_move| game_move)
+            .filter(|&game_move| state.is_move_legal(game_move, to_move).is_ok())
+            .or_else(|| state.all_legal_moves(to_move).next())
+    }
+
+    /// Top up `client`'s allowance for the time since it was last used, returning the seconds
+    /// available. Clients with full allowances are forgotten, so they don't pile up.
+    fn refill(&mut self, client: &str) -> Option<f64> {
+        let allowance = self.allowance?;
+        let now = self.clock.now();
+        let burst = allowance.burst.as_secs_f64();
+        for bucket in self.buckets.values_mut() {
+            let elapsed = now.checked_sub(bucket.updated).unwrap_or_default().as_secs_f64();
+            bucket.available = (bucket.available + elapsed * allowance.rate).min(burst);
+            bucket.updated = now;
+        }
+        self.buckets.retain(|_, bucket| bucket.available < burst);
+        let bucket = self.buckets.entry(client.to_string()).or_insert(Bucket { available: burst, updated: now });
+        Some(bucket.available)
+    }
+}
@@ -0,0 +1,264 @@
+//! Counterfactual regret minimisation, for games where the players can't see everything. The tree
+//! search plays each position as if it could see the whole state, which in card and bidding games
+//! means it plays as if it knew the other player's hand. CFR learns a strategy for each
+//! information set instead, by playing the game against itself and keeping track of how much it
+//! regrets not having played each move. On average, its strategy approaches an equilibrium.
+//!
+//! This is external sampling Monte Carlo CFR: each iteration, one player (taking turns) tries all
+//! their moves, while the other's moves are sampled from their current strategy.
+//!
+//! ```ignore
+//! let mut player = CfrPlayer::new(PlayerEnum::Two, Goofspiel::new(5));
+//! player.train(100_000);
+//! save(&player.profile());
+//! ```
+//!
+//! The games must always end (repeated positions would have it search forever), and chance has to
+//! be settled in the initial state.
+
+use std::collections::HashMap;
+
+use game::rng::random_f64;
+use game::{GameState, ImperfectInformation, Player, PlayerEnum};
+
+use reward::{RewardModel, WinLoss};
+
+/// What's been learnt about one information set.
+#[derive(Debug, Clone)]
+struct Regrets<Move> {
+    moves: Vec<Move>,
+    /// The total regret for not having played each move.
+    regrets: Vec<f64>,
+    /// The total probability of each move across the strategies so far, whose average is what
+    /// approaches an equilibrium.
+    strategy_sums: Vec<f64>,
+}
+
+impl<Move: Copy> Regrets<Move> {
+    fn new(moves: Vec<Move>) -> Self {
+        let count = moves.len();
+        Self {
+            moves,
+            regrets: vec![0.0; count],
+            strategy_sums: vec![0.0; count],
+        }
+    }
+
+    /// Regret matching: each move in proportion to its positive regret, or uniformly if none have
+    /// any.
+    fn current_strategy(&self) -> Vec<f64> {
+        normalise(self.regrets.iter().map(|&regret| regret.max(0.0)).collect())
+    }
+
+    fn average_strategy(&self) -> Vec<f64> {
+        normalise(self.strategy_sums.clone())
+    }
+}
+
+/// Scale `weights` to sum to 1, or make them uniform if they sum to nothing.
+fn normalise(mut weights: Vec<f64>) -> Vec<f64> {
+    let total: f64 = weights.iter().sum();
+    let count = weights.len() as f64;
+    for weight in weights.iter_mut() {
+        *weight = if total > 0.0 { *weight / total } else { 1.0 / count };
+    }
+    weights
+}
+
+/// An index into `weights`, picked at random in proportion to them.
+fn sample(weights: &[f64]) -> usize {
+    let mut left = random_f64();
+    for (i, &weight) in weights.iter().enumerate() {
+        if left < weight {
+            return i;
+        }
+        left -= weight;
+    }
+    // Rounding can leave a little over
+    weights.iter().rposition(|&weight| weight > 0.0).unwrap_or(0)
+}
+
+/// Each move with its total regret and strategy.
+type LearntMoves<Move> = Vec<(Move, f64, f64)>;
+
+/// Everything a `CfrPlayer` has learnt, from `CfrPlayer::profile`. With the `serialize` feature
+/// this can be saved with serde, e.g. to train once and play many times.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(bound(
+    serialize = "Game::InformationSet: ::serde::Serialize, <Game as GameState>::Move: ::serde::Serialize",
+    deserialize = "Game::InformationSet: ::serde::Deserialize<'de>, <Game as GameState>::Move: ::serde::Deserialize<'de>",
+)))]
+pub struct StrategyProfile<Game: ImperfectInformation> {
+    iterations: u64,
+    /// For each information set, each move with its total regret and strategy.
+    information_sets: Vec<(Game::InformationSet, LearntMoves<<Game as GameState>::Move>)>,
+}
+
+impl<Game: ImperfectInformation> StrategyProfile<Game> {
+    /// How many training iterations went into it.
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// How many information sets it has a strategy for.
+    pub fn len(&self) -> usize {
+        self.information_sets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.information_sets.is_empty()
+    }
+}
+
+/// Plays by a strategy learnt with CFR, from the start of the game, training some more before
+/// each move.
+pub struct CfrPlayer<Game: ImperfectInformation> {
+    player: PlayerEnum,
+    initial_state: Game,
+    iterations_per_move: u32,
+    iterations: u64,
+    reward_model: Box<dyn RewardModel<Game> + Send + Sync>,
+    information_sets: HashMap<Game::InformationSet, Regrets<<Game as GameState>::Move>>,
+}
+
+impl<Game: ImperfectInformation> CfrPlayer<Game> {
+    /// A player for `player`, in games starting from `initial_state` with player one to move.
+    /// Training always starts from there, as what's best in one information set depends on how
+    /// the game could have got there.
+    pub fn new(player: PlayerEnum, initial_state: Game) -> Self {
+        Self {
+            player,
+            initial_state,
+            iterations_per_move: 1000,
+            iterations: 0,
+            reward_model: Box::new(WinLoss),
+            information_sets: HashMap::new(),
+        }
+    }
+
+    /// How many iterations to train for before each move. Defaults to 1000.
+    pub fn set_iterations_per_move(&mut self, iterations: u32) {
+        self.iterations_per_move = iterations;
+    }
+
+    /// What the players are trying to maximise, e.g. `reward::ScoreMargin` to play for points
+    /// rather than just to win. Defaults to `reward::WinLoss`. Change this before training.
+    pub fn set_reward_model<R: RewardModel<Game> + Send + Sync + 'static>(&mut self, reward_model: R) {
+        self.reward_model = Box::new(reward_model);
+    }
+
+    /// How many iterations it's been trained for.
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// Train for `iterations` iterations. Each iteration plays through the game once for each
+    /// player, so both players' strategies improve.
+    pub fn train(&mut self, iterations: u32) {
+        let mut state = self.initial_state.clone();
+        for _ in 0..iterations {
+            for &traverser in [PlayerEnum::One, PlayerEnum::Two].iter() {
+                self.traverse(&mut state, PlayerEnum::One, traverser, 0);
+            }
+            self.iterations += 1;
+        }
+    }
+
+    /// The reward `traverser` expects from `state`, with `to_move` to play, updating the regrets
+    /// along the way.
+    fn traverse(&mut self, state: &mut Game, to_move: PlayerEnum, traverser: PlayerEnum, length: u32) -> f64 {
+        if let Some(conclusion) = state.try_conclude(to_move) {
+            return self.reward_model.reward(state, conclusion, length, traverser);
+        }
+
+        let information_set = state.information_set(to_move);
+        let strategy = {
+            let entry = self.information_sets.entry(information_set.clone())
+                .or_insert_with(|| Regrets::new(state.all_legal_moves(to_move).collect()));
+            entry.current_strategy()
+        };
+        let moves = self.information_sets[&information_set].moves.clone();
+
+        if to_move == traverser {
+            let rewards: Vec<f64> = moves.iter().map(|&game_move| {
+                state.with_move(game_move, to_move, |next| self.traverse(next, to_move.other(), traverser, length + 1))
+            }).collect();
+            let expected: f64 = rewards.iter().zip(strategy.iter()).map(|(reward, p)| reward * p).sum();
+            let entry = self.information_sets.get_mut(&information_set).expect("Just inserted");
+            for (regret, reward) in entry.regrets.iter_mut().zip(rewards) {
+                *regret += reward - expected;
+            }
+            expected
+        } else {
+            {
+                let entry = self.information_sets.get_mut(&information_set).expect("Just inserted");
+                for (sum, p) in entry.strategy_sums.iter_mut().zip(strategy.iter()) {
+                    *sum += p;
+                }
+            }
+            let game_move = moves[sample(&strategy)];
+            state.with_move(game_move, to_move, |next| self.traverse(next, to_move.other(), traverser, length + 1))
+        }
+    }
+
+    /// The learnt strategy for `player` in `state`, as each legal move and its probability. This is
+    /// uniform in information sets it hasn't seen.
+    pub fn strategy(&self, state: &Game, player: PlayerEnum) -> Vec<(<Game as GameState>::Move, f64)> {
+        match self.information_sets.get(&state.information_set(player)) {
+            Some(entry) => entry.moves.iter().cloned().zip(entry.average_strategy()).collect(),
+            None => {
+                let moves: Vec<_> = state.all_legal_moves(player).collect();
+                let p = 1.0 / moves.len() as f64;
+                moves.into_iter().map(|game_move| (game_move, p)).collect()
+            },
+        }
+    }
+
+    /// How many information sets it has learnt about.
+    pub fn information_sets(&self) -> usize {
+        self.information_sets.len()
+    }
+
+    /// Everything it's learnt, to be saved.
+    pub fn profile(&self) -> StrategyProfile<Game> {
+        StrategyProfile {
+            iterations: self.iterations,
+            information_sets: self.information_sets.iter().map(|(information_set, entry)| {
+                let moves = entry.moves.iter().zip(entry.regrets.iter()).zip(entry.strategy_sums.iter())
+                    .map(|((&game_move, &regret), &sum)| (game_move, regret, sum))
+                    .collect();
+                (information_set.clone(), moves)
+            }).collect(),
+        }
+    }
+
+    /// Carry on from a saved profile, replacing what it's learnt so far. The profile should be
+    /// for the same initial state.
+    pub fn restore(&mut self, profile: StrategyProfile<Game>) {
+        self.iterations = profile.iterations;
+        self.information_sets = profile.information_sets.into_iter().map(|(information_set, moves)| {
+            let entry = Regrets {
+                moves: moves.iter().map(|x| x.0).collect(),
+                regrets: moves.iter().map(|x| x.1).collect(),
+                strategy_sums: moves.iter().map(|x| x.2).collect(),
+            };
+            (information_set, entry)
+        }).collect();
+    }
+}
+
+impl<Game: ImperfectInformation> Player<Game> for CfrPlayer<Game> {
+    fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
+        let iterations = self.iterations_per_move;
+        self.train(iterations);
+
+        let strategy = self.strategy(&game, self.player);
+        let probabilities: Vec<f64> = strategy.iter().map(|x| x.1).collect();
+        strategy[sample(&probabilities)].0
+    }
+
+    fn inform_of_move_played(&mut self, _new_state: Game, _game_move: &<Game as GameState>::Move) {
+        // noop
+    }
+}
@@ -0,0 +1,160 @@
+//! Playing against the engine by text commands, e.g. from a Discord or IRC bot. Each channel has
+//! its own game, so many can be going at once:
+//!
+//! ```ignore
+//! let mut chat = ChatAdapter::new(TicTacToe::new(), MctsConfig::default());
+//! if let Some(reply) = chat.handle("#games", "move b2") {
+//!     send("#games", &reply);
+//! }
+//! ```
+//!
+//! The commands are `new [first|second]`, `move <move>`, `show`, `hint`, `resign` and `help`.
+//! Anything else gets no reply, so the adapter can listen to a whole channel.
+
+use std::collections::HashMap;
+
+use game::{Conclusion, GameState, MoveNotation, Notation, PlayerEnum};
+
+use self_play::MctsConfig;
+use sessions::{SessionId, SessionManager};
+
+const HELP: &str = "Commands: new [first|second], move <move>, show, hint, resign, help";
+
+/// Runs games in chat channels, with the engine set up as `config`.
+#[derive(Debug)]
+pub struct ChatAdapter<Game: GameState> {
+    config: MctsConfig<Game>,
+    sessions: SessionManager<Game>,
+    channels: HashMap<String, SessionId>,
+}
+
+impl<Game: GameState + Notation + MoveNotation> ChatAdapter<Game> {
+    /// Games start from `initial_state`.
+    pub fn new(initial_state: Game, config: MctsConfig<Game>) -> Self {
+        Self {
+            config,
+            sessions: SessionManager::new(initial_state),
+            channels: HashMap::new(),
+        }
+    }
+
+    /// The sessions behind the channels' games, e.g. to close idle ones.
+    pub fn sessions(&mut self) -> &mut SessionManager<Game> {
+        &mut self.sessions
+    }
+
+    /// The reply to `message` in `channel`, or `None` if it isn't a command.
+    pub fn handle(&mut self, channel: &str, message: &str) -> Option<String> {
+        let mut words = message.split_whitespace();
+        let command = words.next()?.to_lowercase();
+        let argument = words.collect::<Vec<_>>().join(" ");
+        Some(match command.as_str() {
+            "new" => self.new_game(channel, &argument),
+            "move" => self.play(channel, &argument),
+            "show" => self.show(channel),
+            "hint" => self.hint(channel),
+            "resign" => self.resign(channel),
+            "help" => HELP.to_string(),
+            _ => return None,
+        })
+    }
+
+    /// Start a game in `channel`, replacing any already going. The player moves first unless
+    /// they ask to go `second`.
+    fn new_game(&mut self, channel: &str, argument: &str) -> String {
+        let engine_plays = match argument {
+            "" | "first" => PlayerEnum::Two,
+            "second" => PlayerEnum::One,
+            _ => return format!("Say 'new first' or 'new second', not 'new {}'", argument),
+        };
+        if let Some(id) = self.channels.remove(channel) {
+            let _ = self.sessions.close(id);
+        }
+        let id = self.sessions.create(engine_plays, &self.config);
+        self.channels.insert(channel.to_string(), id);
+
+        let mut reply = "New game.".to_string();
+        if let Ok(Some(engine_move)) = self.sessions.advance(id, None) {
+            reply += &format!(" I play {}.", Game::move_notation(&engine_move));
+        }
+        reply + " " + &self.describe(id)
+    }
+
+    fn play(&mut self, channel: &str, text: &str) -> String {
+        if !self.channels.contains_key(channel) {
+            self.new_game(channel, "first");
+        }
+        let id = self.channels[channel];
+        let game_move = {
+            let session = self.sessions.get(id).expect("Channel has a session");
+            if session.waiting_for_engine() {
+                return "It's not your turn.".to_string();
+            }
+            match session.state().parse_move(text, session.to_move()) {
+                Ok(game_move) => game_move,
+                Err(e) => return e,
+            }
+        };
+
+        match self.sessions.advance(id, Some(game_move)) {
+            Ok(Some(engine_move)) => format!("I play {}. {}", Game::move_notation(&engine_move), self.describe(id)),
+            Ok(None) => self.describe(id),
+            Err(e) => e,
+        }
+    }
+
+    fn show(&mut self, channel: &str) -> String {
+        match self.channels.get(channel) {
+            Some(&id) => self.describe(id),
+            None => "No game here. Say 'new' to start one.".to_string(),
+        }
+    }
+
+    /// The engine's suggestion for the player, from a search of the position.
+    fn hint(&mut self, channel: &str) -> String {
+        let id = match self.channels.get(channel) {
+            Some(&id) => id,
+            None => return "No game here. Say 'new' to start one.".to_string(),
+        };
+        if self.sessions.get(id).map_or(true, |session| session.waiting_for_engine() || session.conclusion().is_some()) {
+            return "It's not your turn.".to_string();
+        }
+        match self.sessions.analyze(id) {
+            Ok(analysis) => match analysis.best_move {
+                Some(game_move) => format!("Try {}.", Game::move_notation(&game_move)),
+                None => "There's nothing to play.".to_string(),
+            },
+            Err(e) => e,
+        }
+    }
+
+    fn resign(&mut self, channel: &str) -> String {
+        match self.channels.remove(channel) {
+            Some(id) => {
+                let _ = self.sessions.close(id);
+                "You resign. Good game!".to_string()
+            },
+            None => "No game here.".to_string(),
+        }
+    }
+
+    /// The position and whose turn it is, or how the game ended. Finished games are closed.
+    fn describe(&mut self, id: SessionId) -> String {
+        let (position, engine_plays, to_move, conclusion) = match self.sessions.get(id) {
+            Some(session) => (session.state().to_notation(session.to_move()), session.engine_plays(), session.to_move(), session.conclusion()),
+            None => return "No game here.".to_string(),
+        };
+        let status = match conclusion {
+            Some(Conclusion::Win(winner)) if winner == engine_plays => "I win!",
+            Some(Conclusion::Win(_)) => "You win!",
+            Some(Conclusion::Draw) => "It's a draw.",
+            None if to_move == engine_plays => "My move.",
+            None => "Your move.",
+        };
+        if conclusion.is_some() {
+            let _ = self.sessions.close(id);
+            self.channels.retain(|_, &mut session| session != id);
+        }
+        format!("{} {}", position, status)
+    }
+}
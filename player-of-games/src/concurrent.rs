@@ -0,0 +1,324 @@
+//! The tree for tree-parallel search (see `MonteCarloTreeSearchPlayer::set_tree_parallelism`),
+//! where several threads select, expand, simulate and backpropagate through the same tree at once.
+//!
+//! Nothing locks the whole tree:
+//!
+//! - Statistics are atomic (see `NodeStats`), and updated without locking.
+//! - Game states are looked up in a map split into stripes, each with its own lock, so threads
+//!   expanding different states rarely wait for each other.
+//! - A node's children are a list which is replaced, rather than changed, to add a child, under
+//!   one of a set of locks striped across the nodes. Selection reads the lists without locking,
+//!   and replaced lists are freed by epoch-based reclamation, once no thread can still be reading
+//!   them.
+//! - Nodes come from a `NodePool`, which allocates them in large blocks, and keeps the blocks from
+//!   one search to the next.
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned};
+
+use expansion::Prior;
+use game;
+use node::NodeStats;
+use pool::{NodeId, NodePool};
+
+/// How many stripes the map of game states is split into.
+const STATE_STRIPES: usize = 64;
+
+/// How many locks the nodes' child lists share.
+const NODE_LOCKS: usize = 64;
+
+/// A node's links to its children: the move to each child, and the child.
+type ChildList<Game> = Vec<(<Game as game::GameState>::Move, NodeId)>;
+
+/// A game state in the concurrent tree.
+pub struct ConcurrentNode<Game: game::GameState> {
+    pub state: Arc<Game>,
+    /// The player whose turn it is in this state.
+    pub player: game::PlayerEnum,
+    /// Simulations which have passed through this node, with rewards for the player who moved
+    /// into it.
+    pub stats: NodeStats,
+    /// Made-up results from the expansion policy, counted towards the node's value.
+    pub prior: Option<Prior>,
+    /// Simulations passing through this node which haven't finished yet. They count as losses
+    /// until they do, to steer other threads elsewhere.
+    virtual_losses: AtomicU32,
+    /// Only ever replaced, never changed in place, so that it can be read without locking.
+    children: Atomic<ChildList<Game>>,
+}
+
+impl<Game: game::GameState> ConcurrentNode<Game> {
+    pub fn new(state: Arc<Game>, player: game::PlayerEnum, stats: NodeStats, prior: Option<Prior>) -> Self {
+        Self {
+            state,
+            player,
+            stats,
+            prior,
+            virtual_losses: AtomicU32::new(0),
+            children: Atomic::null(),
+        }
+    }
+
+    /// The node's children, as of some point during the call.
+    pub fn children<'g>(&self, guard: &'g Guard) -> &'g [(<Game as game::GameState>::Move, NodeId)] {
+        // Lists are only freed once every thread pinned when they were replaced has unpinned
+        match unsafe { self.children.load(Ordering::Acquire, guard).as_ref() } {
+            Some(children) => children,
+            None => &[],
+        }
+    }
+
+    /// The child reached by `game_move`, if it's been added.
+    pub fn child(&self, game_move: &<Game as game::GameState>::Move, guard: &Guard) -> Option<NodeId> {
+        self.children(guard).iter().find(|&&(m, _)| m == *game_move).map(|&(_, child)| child)
+    }
+
+    /// Count a simulation as passing through the node until `remove_virtual_loss`.
+    pub fn add_virtual_loss(&self) {
+        self.virtual_losses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn remove_virtual_loss(&self) {
+        self.virtual_losses.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// The average reward for the player who moved into this node, counting the prior, and
+    /// simulations still in progress as losses. `None` if nothing is known about it.
+    pub fn value_for_parent(&self) -> Option<f64> {
+        let visits = self.effective_visits();
+        if visits <= 0.0 {
+            return None;
+        }
+        let prior_reward = self.prior.map_or(0.0, |prior| prior.total_reward);
+        Some((self.stats.total_reward() + prior_reward) / visits)
+    }
+
+    fn effective_visits(&self) -> f64 {
+        let virtual_losses = self.virtual_losses.load(Ordering::Relaxed);
+        self.stats.visits() as f64 + virtual_losses as f64 + self.prior.map_or(0.0, |prior| prior.visits)
+    }
+
+    /// The legal move with the best UCT value, out of those which pass `filter`. Moves without a
+    /// child yet come first.
+    pub fn choose_move_by_uct_value<F, S>(&self, c: f64, tree: &ConcurrentTree<Game, S>, guard: &Guard, filter: F) -> Option<<Game as game::GameState>::Move>
+        where F: Fn(&<Game as game::GameState>::Move) -> bool
+    {
+        let children = self.children(guard);
+        let parent_visits = self.stats.visits() as f64 + self.virtual_losses.load(Ordering::Relaxed) as f64;
+        self.state.all_legal_moves(self.player).filter(|game_move| filter(game_move)).map(|game_move| {
+            let child = children.iter().find(|&&(m, _)| m == game_move).map(|&(_, child)| tree.node(child));
+            let uct_value = match child.and_then(|child| child.value_for_parent().map(|value| (child, value))) {
+                Some((child, value)) => value + c * (parent_visits.ln() / child.effective_visits()).sqrt(),
+                None => f64::MAX,
+            };
+            (game_move, uct_value)
+        }).max_by(|a, b| a.1.total_cmp(&b.1)).map(|x| x.0)
+    }
+}
+
+impl<Game: game::GameState> Drop for ConcurrentNode<Game> {
+    fn drop(&mut self) {
+        // Nodes are only dropped when nothing else can be using the tree
+        unsafe {
+            let children = self.children.load(Ordering::Relaxed, epoch::unprotected());
+            if !children.is_null() {
+                drop(children.into_owned());
+            }
+        }
+    }
+}
+
+/// One stripe of the map from game states to their nodes.
+type StateStripe<Game, S> = Mutex<HashMap<Arc<Game>, NodeId, S>>;
+
+/// The concurrent tree. See the module documentation.
+pub struct ConcurrentTree<Game: game::GameState, S> {
+    nodes: NodePool<ConcurrentNode<Game>>,
+    /// Where each game state's node is, split by the state's hash.
+    states: Box<[StateStripe<Game, S>]>,
+    /// Held while replacing a node's children, by the node's ID.
+    node_locks: Box<[Mutex<()>]>,
+    hasher: S,
+}
+
+impl<Game: game::GameState, S> Debug for ConcurrentTree<Game, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConcurrentTree").field("nodes", &self.nodes).finish()
+    }
+}
+
+impl<Game: game::GameState, S> ConcurrentTree<Game, S> {
+    pub fn node(&self, id: NodeId) -> &ConcurrentNode<Game> {
+        self.nodes.get(id)
+    }
+
+    /// How many nodes there are.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Every node, in the order they were added.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &ConcurrentNode<Game>)> {
+        (0..self.len() as NodeId).map(move |id| (id, self.node(id)))
+    }
+}
+
+impl<Game: game::GameState, S: BuildHasher + Default> Default for ConcurrentTree<Game, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Game: game::GameState, S: BuildHasher + Default> ConcurrentTree<Game, S> {
+    pub fn new() -> Self {
+        Self {
+            nodes: NodePool::new(),
+            states: (0..STATE_STRIPES).map(|_| Mutex::new(HashMap::default())).collect(),
+            node_locks: (0..NODE_LOCKS).map(|_| Mutex::new(())).collect(),
+            hasher: S::default(),
+        }
+    }
+
+    /// The node for `state`, if there is one.
+    pub fn find(&self, state: &Game) -> Option<NodeId> {
+        self.stripe(state).get(state).cloned()
+    }
+
+    /// Add `node`, unless its state already has one, and return the state's node (and whether it
+    /// was this one).
+    pub fn insert(&self, node: ConcurrentNode<Game>) -> (NodeId, bool) {
+        let mut stripe = self.stripe(&node.state);
+        if let Some(&existing) = stripe.get(&node.state) {
+            return (existing, false);
+        }
+        let state = node.state.clone();
+        let id = self.nodes.insert(node);
+        stripe.insert(state, id);
+        (id, true)
+    }
+
+    /// Link `child` to `parent` by `game_move`, unless the move is linked already. Returns the
+    /// child the move is linked to.
+    pub fn link(&self, parent: NodeId, game_move: <Game as game::GameState>::Move, child: NodeId) -> NodeId {
+        let guard = epoch::pin();
+        let node = self.node(parent);
+        let _lock = self.node_locks[parent as usize % NODE_LOCKS].lock().expect("Node lock poisoned");
+        let old = node.children.load(Ordering::Acquire, &guard);
+        let mut children = match unsafe { old.as_ref() } {
+            Some(children) => {
+                if let Some(&(_, existing)) = children.iter().find(|&&(m, _)| m == game_move) {
+                    return existing;
+                }
+                children.clone()
+            },
+            None => Vec::with_capacity(1),
+        };
+        children.push((game_move, child));
+        node.children.store(Owned::new(children), Ordering::Release);
+        if !old.is_null() {
+            // Threads still reading the old list pinned before it was replaced
+            unsafe { guard.defer_destroy(old) };
+        }
+        child
+    }
+
+    /// Drop every node, keeping their memory for the next search.
+    pub fn clear(&mut self) {
+        for stripe in self.states.iter_mut() {
+            stripe.get_mut().expect("State stripe poisoned").clear();
+        }
+        self.nodes.clear();
+    }
+
+    fn stripe(&self, state: &Game) -> ::std::sync::MutexGuard<'_, HashMap<Arc<Game>, NodeId, S>> {
+        // The maps themselves use the low bits of the same hash
+        let stripe = (self.hasher.hash_one(state) >> 32) as usize % STATE_STRIPES;
+        self.states[stripe].lock().expect("State stripe poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tic_tac_toe;
+
+    use std::thread;
+
+    use game::{GameState, PlayerEnum};
+    use node::DefaultState;
+
+    use self::tic_tac_toe::TicTacToe;
+    use super::*;
+
+    /// The board after the players take turns to play on `squares`, starting with player one.
+    fn after(squares: &[(usize, usize)]) -> TicTacToe {
+        let mut state = TicTacToe::new();
+        let mut player = PlayerEnum::One;
+        for &square in squares {
+            let game_move = state.all_legal_moves(player).find(|m| m.coordinates() == square).expect("Square taken");
+            state.update(game_move, player);
+            player = player.other();
+        }
+        state
+    }
+
+    fn node(state: TicTacToe) -> ConcurrentNode<TicTacToe> {
+        ConcurrentNode::new(Arc::new(state), PlayerEnum::One, NodeStats::default(), None)
+    }
+
+    #[test]
+    fn transpositions_share_a_node() {
+        let tree = ConcurrentTree::<TicTacToe, DefaultState>::new();
+        let state = after(&[(0, 0), (1, 1), (2, 2), (0, 2)]);
+        let transposed = after(&[(2, 2), (0, 2), (0, 0), (1, 1)]);
+
+        let (id, added) = tree.insert(node(state.clone()));
+        assert!(added);
+        assert_eq!(tree.find(&transposed), Some(id));
+        assert_eq!(tree.insert(node(transposed)), (id, false));
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree.find(&after(&[(0, 0)])), None);
+    }
+
+    #[test]
+    fn threads_link_children_at_once() {
+        let tree = ConcurrentTree::<TicTacToe, DefaultState>::new();
+        let (root, _) = tree.insert(node(TicTacToe::new()));
+        let moves: Vec<_> = TicTacToe::new().all_legal_moves(PlayerEnum::One).collect();
+        // Every thread adds every move, so most find it added already
+        let linked: Vec<Vec<_>> = thread::scope(|scope| {
+            let threads: Vec<_> = (0..8).map(|_| {
+                let (tree, moves) = (&tree, &moves);
+                scope.spawn(move || moves.iter().map(|&game_move| {
+                    let (child, _) = tree.insert(node(after(&[game_move.coordinates()])));
+                    (game_move, tree.link(root, game_move, child))
+                }).collect())
+            }).collect();
+            threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+        });
+
+        assert_eq!(tree.len(), 10);
+        let guard = epoch::pin();
+        assert_eq!(tree.node(root).children(&guard).len(), 9);
+        for (game_move, child) in linked.into_iter().flatten() {
+            assert_eq!(tree.node(root).child(&game_move, &guard), Some(child));
+            assert_eq!(*tree.node(child).state, after(&[game_move.coordinates()]));
+        }
+    }
+
+    #[test]
+    fn clearing_empties_the_tree() {
+        let mut tree = ConcurrentTree::<TicTacToe, DefaultState>::new();
+        let (root, _) = tree.insert(node(TicTacToe::new()));
+        let (child, _) = tree.insert(node(after(&[(1, 1)])));
+        let game_move = TicTacToe::new().all_legal_moves(PlayerEnum::One).next().unwrap();
+        tree.link(root, game_move, child);
+        tree.clear();
+        assert_eq!(tree.len(), 0);
+        assert_eq!(tree.find(&TicTacToe::new()), None);
+    }
+}
@@ -0,0 +1,187 @@
+//! Playing on online platforms (Lichess-style bot APIs) by long-polling their HTTP API for game
+//! events, keeping a session for each game, and sending the engine's moves back.
+//!
+//! Everything particular to a platform (its URLs, how its events are written and how moves are
+//! sent) is behind the `Platform` trait, so the connector itself works with any of them:
+//!
+//! ```ignore
+//! let mut connector = Connector::new(MyPlatform::new(token), MctsConfig::default());
+//! connector.run(&AtomicBool::new(false), |e| eprintln!("Polling failed: {}", e));
+//! ```
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use game::{GameState, PlayerEnum};
+use ureq;
+
+use self_play::MctsConfig;
+use sessions::Session;
+
+/// Something which happened on a platform.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlatformEvent<Game: GameState> {
+    /// A game the engine is playing in started (or was already going when the connector
+    /// started). Repeats for games already known are ignored.
+    GameStarted {
+        game_id: String,
+        state: Game,
+        to_move: PlayerEnum,
+        engine_plays: PlayerEnum,
+    },
+    /// A move was played in a game, by either side. The platform's echoes of the engine's own
+    /// moves are expected, and skipped.
+    MovePlayed {
+        game_id: String,
+        game_move: <Game as GameState>::Move,
+    },
+    /// A game ended, e.g. by resignation or on time.
+    GameFinished { game_id: String },
+}
+
+/// An HTTP request to make to a platform.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlatformRequest {
+    pub url: String,
+    pub body: String,
+}
+
+/// How to talk to one online platform.
+pub trait Platform<Game: GameState> {
+    /// The URL to long-poll for events. Its response is read a line at a time until it ends, so
+    /// it can be a stream (like Lichess's) or a single batch.
+    fn events_url(&self) -> String;
+
+    /// Headers for every request, e.g. for authorization.
+    fn headers(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// The events in one line of the events response. Blank lines (keep-alives) are skipped
+    /// before getting here.
+    fn parse_events(&mut self, line: &str) -> Result<Vec<PlatformEvent<Game>>, String>;
+
+    /// The request which plays `game_move` in `game_id`. It's POSTed.
+    fn move_request(&self, game_id: &str, game_move: &<Game as GameState>::Move) -> PlatformRequest;
+}
+
+/// Plays the engine's games on a platform, each in its own session.
+#[derive(Debug)]
+pub struct Connector<Game: GameState, P> {
+    platform: P,
+    config: MctsConfig<Game>,
+    agent: ureq::Agent,
+    games: HashMap<String, Session<Game>>,
+    /// The engine's moves in each game which the platform hasn't echoed back yet.
+    echoes: HashMap<String, Vec<<Game as GameState>::Move>>,
+    retry_delay: Duration,
+}
+
+impl<Game: GameState, P: Platform<Game>> Connector<Game, P> {
+    /// A connector to `platform`, with engines set up as `config`.
+    pub fn new(platform: P, config: MctsConfig<Game>) -> Self {
+        Self {
+            platform,
+            config,
+            agent: ureq::Agent::new(),
+            games: HashMap::new(),
+            echoes: HashMap::new(),
+            retry_delay: Duration::from_secs(5),
+        }
+    }
+
+    /// How long `run` waits before polling again after an error. The default is 5 seconds.
+    pub fn set_retry_delay(&mut self, retry_delay: Duration) {
+        self.retry_delay = retry_delay;
+    }
+
+    /// The games in progress, by platform game id.
+    pub fn games(&self) -> &HashMap<String, Session<Game>> {
+        &self.games
+    }
+
+    /// Poll for events until `stop` is set, starting again (after the retry delay) whenever the
+    /// connection ends or fails. The flag is checked between lines of the response. Each failure
+    /// is passed to `on_error`, e.g. to log it.
+    pub fn run<F: FnMut(&str)>(&mut self, stop: &AtomicBool, mut on_error: F) {
+        while !stop.load(Ordering::Relaxed) {
+            if let Err(e) = self.poll(stop) {
+                on_error(&e);
+                thread::sleep(self.retry_delay);
+            }
+        }
+    }
+
+    /// Make one long-polling request, handling the events in it as they arrive, until the
+    /// response ends (or `stop` is set).
+    pub fn poll(&mut self, stop: &AtomicBool) -> Result<(), String> {
+        let url = self.platform.events_url();
+        let response = self.request(self.agent.get(&url)).call()
+            .map_err(|e| format!("Couldn't get {}: {}", url, e))?;
+        for line in BufReader::new(response.into_reader()).lines() {
+            let line = line.map_err(|e| format!("Couldn't read events: {}", e))?;
+            if !line.trim().is_empty() {
+                for event in self.platform.parse_events(&line)? {
+                    self.handle_event(event)?;
+                }
+            }
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the games for `event`, replying with the engine's move if it's then its turn.
+    /// Events for unknown games are ignored.
+    pub fn handle_event(&mut self, event: PlatformEvent<Game>) -> Result<(), String> {
+        let game_id = match event {
+            PlatformEvent::GameStarted { game_id, state, to_move, engine_plays } => {
+                if !self.games.contains_key(&game_id) {
+                    self.games.insert(game_id.clone(), Session::from_position(state, to_move, engine_plays, &self.config));
+                }
+                game_id
+            },
+            PlatformEvent::MovePlayed { game_id, game_move } => {
+                if let Some(session) = self.games.get_mut(&game_id) {
+                    let echoes = self.echoes.entry(game_id.clone()).or_default();
+                    if echoes.first() == Some(&game_move) {
+                        echoes.remove(0);
+                    } else {
+                        session.play_move(game_move).map_err(|e| format!("Bad move {:?} in game {}: {}", game_move, game_id, e))?;
+                    }
+                }
+                game_id
+            },
+            PlatformEvent::GameFinished { game_id } => {
+                self.games.remove(&game_id);
+                self.echoes.remove(&game_id);
+                return Ok(());
+            },
+        };
+
+        let engine_move = self.games.get_mut(&game_id).and_then(|session| session.engine_move());
+        if let Some(game_move) = engine_move {
+            self.echoes.entry(game_id.clone()).or_default().push(game_move);
+            let request = self.platform.move_request(&game_id, &game_move);
+            self.request(self.agent.post(&request.url)).send_string(&request.body)
+                .map_err(|e| format!("Couldn't play {:?} in game {}: {}", game_move, game_id, e))?;
+        }
+        if self.games.get(&game_id).is_some_and(|session| session.conclusion().is_some()) {
+            self.games.remove(&game_id);
+            self.echoes.remove(&game_id);
+        }
+        Ok(())
+    }
+
+    /// `request` with the platform's headers.
+    fn request(&self, mut request: ureq::Request) -> ureq::Request {
+        for (name, value) in self.platform.headers() {
+            request = request.set(&name, &value);
+        }
+        request
+    }
+}
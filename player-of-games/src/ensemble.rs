@@ -0,0 +1,170 @@
+//! Playing by committee: several differently set up engines search the same position, and the
+//! move is agreed between them, so that one configuration's blind spots don't decide the game.
+
+use std::fmt;
+
+use game::{CancellationToken, GameState, Player, PlayerEnum};
+
+use self_play::MctsConfig;
+use {Analysis, HeatMap, MonteCarloTreeSearchPlayer};
+
+/// How an `EnsemblePlayer` agrees on a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnsembleVote {
+    /// Each engine votes for its best move, with its weight. The move with the most weight wins.
+    Majority,
+    /// Each engine's best move is a candidate, and is scored by the weighted average of every
+    /// engine's estimate of it (from those which searched it). The best scoring candidate wins,
+    /// so a move one engine likes but the others think loses is passed over.
+    CrossCheck,
+}
+
+/// What an engine found out about the position.
+type Findings<Game> = (Analysis<<Game as GameState>::Move>, HeatMap<<Game as GameState>::Move>);
+
+/// Searches each engine, returning what each found out.
+type AnalyseAll<Game> = fn(&mut [Member<Game>], &Game) -> Vec<Findings<Game>>;
+
+struct Member<Game: GameState> {
+    engine: MonteCarloTreeSearchPlayer<Game>,
+    weight: f64,
+}
+
+/// A player made up of several tree search engines. See `EnsembleVote` for how they decide.
+pub struct EnsemblePlayer<Game: GameState> {
+    player: PlayerEnum,
+    vote: EnsembleVote,
+    members: Vec<Member<Game>>,
+    analyse_all: AnalyseAll<Game>,
+    last_evaluation: Option<f64>,
+}
+
+impl<Game: GameState> fmt::Debug for EnsemblePlayer<Game> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EnsemblePlayer {{ player: {:?}, vote: {:?}, weights: {:?} }}", self.player, self.vote, self.members.iter().map(|member| member.weight).collect::<Vec<_>>())
+    }
+}
+
+impl<Game: GameState> EnsemblePlayer<Game> {
+    /// An ensemble with no engines yet. It needs at least one before it can play.
+    pub fn new(player: PlayerEnum, vote: EnsembleVote) -> Self {
+        Self {
+            player,
+            vote,
+            members: Vec::new(),
+            analyse_all: analyse_in_turn::<Game>,
+            last_evaluation: None,
+        }
+    }
+
+    /// An ensemble of engines set up from `configs`, each with its weight.
+    pub fn from_configs(player: PlayerEnum, vote: EnsembleVote, configs: &[(MctsConfig<Game>, f64)]) -> Self {
+        let mut ensemble = Self::new(player, vote);
+        for &(ref config, weight) in configs {
+            ensemble.add_engine(config.build(player), weight);
+        }
+        ensemble
+    }
+
+    /// Add an engine, whose say counts for `weight`. It should play the same side as the ensemble.
+    pub fn add_engine(&mut self, engine: MonteCarloTreeSearchPlayer<Game>, weight: f64) {
+        self.members.push(Member { engine, weight });
+    }
+
+    /// The engines, e.g. to change how one of them searches.
+    pub fn engines(&mut self) -> impl Iterator<Item = &mut MonteCarloTreeSearchPlayer<Game>> {
+        self.members.iter_mut().map(|member| &mut member.engine)
+    }
+
+    /// Agree on a move from what each engine found, returning it with the ensemble's estimate of
+    /// it (the weighted average over the engines which searched it).
+    fn decide(&self, results: &[Findings<Game>]) -> Option<(<Game as GameState>::Move, Option<f64>)> {
+        let mut candidates: Vec<<Game as GameState>::Move> = Vec::new();
+        for (analysis, _) in results {
+            if let Some(best_move) = analysis.best_move {
+                if !candidates.contains(&best_move) {
+                    candidates.push(best_move);
+                }
+            }
+        }
+
+        let estimate = |game_move: &<Game as GameState>::Move| {
+            let (mut total, mut weights) = (0.0, 0.0);
+            for (member, (_, heat_map)) in self.members.iter().zip(results) {
+                if let Some(win_rate) = heat_map.moves.iter().find(|heat| heat.game_move == *game_move).and_then(|heat| heat.win_rate) {
+                    total += member.weight * win_rate;
+                    weights += member.weight;
+                }
+            }
+            if weights > 0.0 { Some(total / weights) } else { None }
+        };
+        let score = |game_move: &<Game as GameState>::Move| match self.vote {
+            EnsembleVote::Majority => self.members.iter().zip(results)
+                .filter(|(_, (analysis, _))| analysis.best_move == Some(*game_move))
+                .map(|(member, _)| member.weight)
+                .sum(),
+            EnsembleVote::CrossCheck => estimate(game_move).unwrap_or(0.0),
+        };
+
+        // Ties go to the move of the engine added first.
+        let mut best: Option<(<Game as GameState>::Move, f64)> = None;
+        for game_move in candidates {
+            let value = score(&game_move);
+            if best.map_or(true, |(_, best_value)| value > best_value) {
+                best = Some((game_move, value));
+            }
+        }
+        best.map(|(game_move, _)| (game_move, estimate(&game_move)))
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<Game: GameState + Send + Sync> EnsemblePlayer<Game> where <Game as GameState>::Move: Send + Sync {
+    /// Search with all the engines at once, on rayon's global thread pool, rather than one after
+    /// the other (the default).
+    pub fn set_parallel(&mut self, parallel: bool) {
+        self.analyse_all = if parallel { analyse_in_parallel::<Game> } else { analyse_in_turn::<Game> };
+    }
+}
+
+fn analyse_in_turn<Game: GameState>(members: &mut [Member<Game>], game: &Game) -> Vec<Findings<Game>> {
+    members.iter_mut().map(|member| {
+        let analysis = member.engine.analyse(game);
+        (analysis, member.engine.heat_map(game))
+    }).collect()
+}
+
+#[cfg(feature = "parallel")]
+fn analyse_in_parallel<Game: GameState + Send + Sync>(members: &mut [Member<Game>], game: &Game) -> Vec<Findings<Game>> where <Game as GameState>::Move: Send + Sync {
+    use rayon::prelude::*;
+
+    members.par_iter_mut().map(|member| {
+        let analysis = member.engine.analyse(game);
+        (analysis, member.engine.heat_map(game))
+    }).collect()
+}
+
+impl<Game: GameState> Player<Game> for EnsemblePlayer<Game> {
+    fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
+        let results = (self.analyse_all)(&mut self.members, &game);
+        let (decision, evaluation) = self.decide(&results).expect("There were no legal moves, or no engines");
+        self.last_evaluation = evaluation;
+        decision
+    }
+
+    fn inform_of_move_played(&mut self, new_state: Game, game_move: &<Game as GameState>::Move) {
+        for member in self.members.iter_mut() {
+            member.engine.inform_of_move_played(new_state.clone(), game_move);
+        }
+    }
+
+    fn last_evaluation(&self) -> Option<f64> {
+        self.last_evaluation
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        for member in self.members.iter_mut() {
+            member.engine.set_cancellation_token(token.clone());
+        }
+    }
+}
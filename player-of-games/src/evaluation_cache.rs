@@ -0,0 +1,113 @@
+//! Remembering what the search found out about positions after they've been pruned from the tree,
+//! so that nothing is lost if the game gets back to them (e.g. by transposition).
+
+use std::collections::{HashMap, VecDeque};
+
+use game::{GameState, PlayerEnum};
+
+use expansion::Prior;
+use node::Node;
+
+/// How well an evaluation cache is doing, from `MonteCarloTreeSearchPlayer::evaluation_cache_stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Positions currently remembered.
+    pub entries: usize,
+    /// The most positions it will remember.
+    pub capacity: usize,
+    /// New nodes which started from a remembered evaluation.
+    pub hits: u64,
+    /// New nodes which didn't.
+    pub misses: u64,
+    /// Positions forgotten to make room for others.
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// The fraction of new nodes which started from a remembered evaluation, or 0 before any have.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+/// A summary of a node which has been removed from the tree.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    player: PlayerEnum,
+    prior: Prior,
+}
+
+/// Evaluations of positions which have left the tree, keyed by the position. Once full, the
+/// positions remembered longest ago are forgotten first.
+#[derive(Debug)]
+pub struct EvaluationCache<Game: GameState> {
+    entries: HashMap<Game, Entry>,
+    /// Every remembered position, oldest first.
+    order: VecDeque<Game>,
+    capacity: usize,
+    stats: CacheStats,
+}
+
+impl<Game: GameState> EvaluationCache<Game> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+            stats: CacheStats {
+                capacity,
+                ..CacheStats::default()
+            },
+        }
+    }
+
+    /// Remember what's known about `node`, which is leaving the tree. Everything it counts (its
+    /// visits and its prior, which may itself have come from here) replaces what was remembered.
+    pub fn store(&mut self, state: &Game, node: &Node<Game>) {
+        let prior = node.prior.unwrap_or(Prior { visits: 0.0, total_reward: 0.0 });
+        let visits = node.visits() as f64 + prior.visits;
+        if visits <= 0.0 || self.capacity == 0 {
+            return;
+        }
+        let entry = Entry {
+            player: node.player,
+            prior: Prior {
+                visits,
+                total_reward: node.stats.total_reward() + prior.total_reward,
+            },
+        };
+
+        if self.entries.insert(state.clone(), entry).is_none() {
+            self.order.push_back(state.clone());
+            while self.entries.len() > self.capacity {
+                let oldest = self.order.pop_front().expect("Cache order out of step");
+                self.entries.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+    }
+
+    /// What was remembered about `state`, with `player` to move, as a prior for a new node.
+    pub fn lookup(&mut self, state: &Game, player: PlayerEnum) -> Option<Prior> {
+        match self.entries.get(state) {
+            Some(entry) if entry.player == player => {
+                self.stats.hits += 1;
+                Some(entry.prior)
+            },
+            _ => {
+                self.stats.misses += 1;
+                None
+            },
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            ..self.stats
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Guiding the search with a trained model: an `Evaluator` scores positions (encoded by a
+//! `game::FeatureEncoder`) with a move policy and a value, and `EvaluatorPriors` turns those
+//! scores into priors for new nodes, so the search looks at the moves the model likes first.
+//!
+//! Model runtimes plug in by implementing `Evaluator`, as ONNX Runtime does with the `onnx`
+//! feature (see `onnx::OnnxEvaluator`). To share one model (e.g. on a GPU) between many searches
+//! at once, wrap it in a `BatchingEvaluator`.
+
+use std::fmt::{self, Debug};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use game::{value, FeatureEncoder, Features, GameState, PlayerEnum};
+
+use expansion::{ExpansionPolicy, Prior};
+
+/// A model's verdict on a position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// How likely each move is to be best, by the encoder's move index. Moves which aren't legal
+    /// are ignored, so this needn't add up to one over the legal moves.
+    pub policy: Vec<f32>,
+    /// The chance of winning for the player to move, from 0 to 1.
+    pub value: f32,
+}
+
+/// Something which evaluates encoded positions, e.g. a neural network.
+pub trait Evaluator: Debug {
+    /// Evaluate several positions at once, returning an evaluation for each, in order.
+    fn evaluate_batch(&self, inputs: &[Features]) -> Result<Vec<Evaluation>, String>;
+
+    fn evaluate(&self, input: Features) -> Result<Evaluation, String> {
+        self.evaluate_batch(&[input])?.pop().ok_or_else(|| "The evaluator returned nothing".to_string())
+    }
+}
+
+/// Shared evaluators, e.g. one model used by several players.
+impl<V: Evaluator + ?Sized> Evaluator for Arc<V> {
+    fn evaluate_batch(&self, inputs: &[Features]) -> Result<Vec<Evaluation>, String> {
+        (**self).evaluate_batch(inputs)
+    }
+}
+
+/// How a `BatchingEvaluator` groups requests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchSettings {
+    /// The most positions to evaluate at once. Requests bigger than this are still evaluated
+    /// whole.
+    pub max_batch: usize,
+    /// How long the first request in a batch may wait for others to join it.
+    pub max_latency: Duration,
+}
+
+impl Default for BatchSettings {
+    fn default() -> Self {
+        Self {
+            max_batch: 64,
+            max_latency: Duration::from_millis(2),
+        }
+    }
+}
+
+/// Positions to evaluate, and where to send the evaluations.
+type Request = (Vec<Features>, Sender<Result<Vec<Evaluation>, String>>);
+
+/// An evaluator shared between threads, which gathers up the positions sent to it from all of
+/// them and evaluates them in batches on a background thread, to keep a model's hardware busy.
+///
+/// Clone it to give each search (or session) its own handle. The background thread stops once
+/// every handle has been dropped.
+pub struct BatchingEvaluator {
+    requests: Mutex<Sender<Request>>,
+}
+
+impl Debug for BatchingEvaluator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BatchingEvaluator")
+    }
+}
+
+impl Clone for BatchingEvaluator {
+    fn clone(&self) -> Self {
+        Self {
+            requests: Mutex::new(self.requests.lock().expect("Evaluator poisoned").clone()),
+        }
+    }
+}
+
+impl BatchingEvaluator {
+    /// Start serving `evaluator` on a new thread.
+    pub fn spawn<V: Evaluator + Send + 'static>(evaluator: V, settings: BatchSettings) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || serve_batches(&evaluator, &receiver, settings));
+        Self {
+            requests: Mutex::new(sender),
+        }
+    }
+}
+
+impl Evaluator for BatchingEvaluator {
+    fn evaluate_batch(&self, inputs: &[Features]) -> Result<Vec<Evaluation>, String> {
+        let (sender, receiver) = mpsc::channel();
+        self.requests.lock().expect("Evaluator poisoned").send((inputs.to_vec(), sender))
+            .map_err(|_| "The evaluation thread has stopped".to_string())?;
+        receiver.recv().map_err(|_| "The evaluation thread has stopped".to_string())?
+    }
+}
+
+/// Evaluate requests from `receiver` in batches until every sender has gone.
+fn serve_batches<V: Evaluator>(evaluator: &V, receiver: &Receiver<Request>, settings: BatchSettings) {
+    while let Ok(first) = receiver.recv() {
+        let deadline = Instant::now() + settings.max_latency;
+        let mut size = first.0.len();
+        let mut batch = vec![first];
+        while size < settings.max_batch {
+            let wait = deadline.checked_duration_since(Instant::now()).unwrap_or_default();
+            match receiver.recv_timeout(wait) {
+                Ok(request) => {
+                    size += request.0.len();
+                    batch.push(request);
+                },
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        let inputs: Vec<Features> = batch.iter().flat_map(|request| request.0.iter().cloned()).collect();
+        match evaluator.evaluate_batch(&inputs) {
+            Ok(ref evaluations) if evaluations.len() == inputs.len() => {
+                let mut evaluations = evaluations.iter().cloned();
+                for (inputs, reply) in batch {
+                    // The searcher may have given up waiting, which is fine.
+                    let _ = reply.send(Ok(evaluations.by_ref().take(inputs.len()).collect()));
+                }
+            },
+            result => {
+                let error = match result {
+                    Ok(evaluations) => format!("The evaluator returned {} evaluations for {} positions", evaluations.len(), inputs.len()),
+                    Err(e) => e,
+                };
+                for (_, reply) in batch {
+                    let _ = reply.send(Err(error.clone()));
+                }
+            },
+        }
+    }
+}
+
+/// A probability for each legal move.
+type Policy<Game> = Vec<(<Game as GameState>::Move, f64)>;
+
+/// Priors from an evaluator: each new node starts with the model's value for its position,
+/// counted as `value_visits` visits, plus as many wins as the model's policy for the move (out
+/// of the legal moves) times `policy_visits`, so the moves the model likes look better at first.
+///
+/// Positions the evaluator fails on get no prior. This needs the search to say who made each
+/// move, which it does through `ExpansionPolicy::prior_for`.
+#[derive(Debug)]
+pub struct EvaluatorPriors<Game: GameState, E, V> {
+    pub encoder: E,
+    pub evaluator: V,
+    pub value_visits: f64,
+    pub policy_visits: f64,
+    /// The last parent's policy over its legal moves (normalised), since its children are
+    /// expanded one after another.
+    last_parent: Mutex<Option<(Game, Policy<Game>)>>,
+}
+
+impl<Game: GameState, E: FeatureEncoder<Game>, V: Evaluator> EvaluatorPriors<Game, E, V> {
+    pub fn new(encoder: E, evaluator: V, value_visits: f64, policy_visits: f64) -> Self {
+        Self {
+            encoder,
+            evaluator,
+            value_visits,
+            policy_visits,
+            last_parent: Mutex::new(None),
+        }
+    }
+
+    /// The policy for `game_move`, out of `player`'s legal moves in `parent`.
+    fn policy(&self, parent: &Game, game_move: &<Game as GameState>::Move, player: PlayerEnum) -> Option<f64> {
+        let mut last_parent = self.last_parent.lock().expect("Evaluator cache poisoned");
+        let cached = match *last_parent {
+            Some((ref state, _)) => state == parent,
+            None => false,
+        };
+        if !cached {
+            let evaluation = self.evaluator.evaluate(self.encoder.encode(parent, player)).ok()?;
+            let mut policy: Vec<(<Game as GameState>::Move, f64)> = parent.all_legal_moves(player).map(|m| {
+                let probability = evaluation.policy.get(self.encoder.move_index(&m, player)).map_or(0.0, |&p| p.max(0.0) as f64);
+                (m, probability)
+            }).collect();
+            let total: f64 = policy.iter().map(|x| x.1).sum();
+            let count = policy.len() as f64;
+            for entry in policy.iter_mut() {
+                entry.1 = if total > 0.0 { entry.1 / total } else { 1.0 / count };
+            }
+            *last_parent = Some((parent.clone(), policy));
+        }
+
+        let policy = &last_parent.as_ref().expect("Just cached").1;
+        policy.iter().find(|x| x.0 == *game_move).map(|x| x.1)
+    }
+}
+
+impl<Game: GameState, E: FeatureEncoder<Game> + Debug, V: Evaluator> ExpansionPolicy<Game> for EvaluatorPriors<Game, E, V> {
+    fn prior(&self, _parent: &Game, _game_move: &<Game as GameState>::Move, _grandparent_sibling: Option<Prior>) -> Option<Prior> {
+        None
+    }
+
+    fn prior_for(&self, parent: &Game, game_move: &<Game as GameState>::Move, player: PlayerEnum, _grandparent_sibling: Option<Prior>) -> Option<Prior> {
+        let policy = self.policy(parent, game_move, player)?;
+        let mut child = parent.clone();
+        child.update(*game_move, player);
+        // The model's value is for the player to move next, not the one who moved into the node.
+        let value = value::flip(self.evaluator.evaluate(self.encoder.encode(&child, player.other())).ok()?.value as f64);
+        let wins = self.policy_visits * policy;
+        Some(Prior {
+            visits: self.value_visits + wins,
+            total_reward: self.value_visits * value::clamp(value) + wins,
+        })
+    }
+}
@@ -0,0 +1,224 @@
+//! Tuning the weights of a `game::SoftmaxRollout` by evolution, with no machine learning needed:
+//! a population of candidate weights play each other, the best survive, and mutated copies of
+//! the survivors take the others' places.
+//!
+//! ```ignore
+//! let mut evolution = Evolution::new(ConnectFour::new(), ConnectFourWeights::default(), MctsConfig::default(), EvolutionSettings::default());
+//! let best = evolution.run();
+//! WeightStore::new("weights.txt").save("connect-four", &best.parameters())?;
+//! ```
+
+use std::f64::consts::PI;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use game::rng::random_f64;
+use game::{value, GameState, PlayerEnum, SoftmaxRollout, TunableWeights};
+
+use self_play::{MatchSpec, MctsConfig};
+
+/// How to run an evolution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvolutionSettings {
+    /// Candidates in each generation.
+    pub population: usize,
+    /// The best candidates kept for the next generation.
+    pub survivors: usize,
+    /// Games each pair of candidates plays, alternating who goes first.
+    pub games_per_pairing: u32,
+    /// How far mutation moves each parameter: by a normally distributed amount, with this
+    /// standard deviation relative to the parameter's size (or to 1, for small parameters).
+    pub mutation: f64,
+    /// The temperature of the candidates' rollouts.
+    pub temperature: f64,
+    /// Generations `run` goes through.
+    pub generations: u32,
+}
+
+impl Default for EvolutionSettings {
+    fn default() -> Self {
+        Self {
+            population: 8,
+            survivors: 3,
+            games_per_pairing: 2,
+            mutation: 0.2,
+            temperature: 1.0,
+            generations: 10,
+        }
+    }
+}
+
+/// A candidate and how it did in a generation's tournament.
+#[derive(Debug, Clone)]
+pub struct Candidate<W> {
+    pub weights: W,
+    /// Points per game: 1 for a win and a half for a draw.
+    pub score: f64,
+}
+
+/// A population of candidate weights for `Game`, being evolved.
+#[derive(Debug)]
+pub struct Evolution<Game: GameState, W> {
+    start: Game,
+    config: MctsConfig<Game>,
+    settings: EvolutionSettings,
+    population: Vec<W>,
+    generation: u32,
+}
+
+impl<Game: GameState, W: TunableWeights<Game> + Send + Sync + 'static> Evolution<Game, W> {
+    /// Evolve weights from `seed`, with games played from `start` by players set up as `config`
+    /// (apart from their rollout policy).
+    pub fn new(start: Game, seed: W, config: MctsConfig<Game>, settings: EvolutionSettings) -> Self {
+        let mut population = vec![seed.clone()];
+        while population.len() < settings.population.max(1) {
+            population.push(mutate(&seed, settings.mutation));
+        }
+        Self {
+            start,
+            config,
+            settings,
+            population,
+            generation: 0,
+        }
+    }
+
+    /// Generations run so far.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// The best candidate so far (or the seed, before any generations have been run).
+    pub fn best(&self) -> &W {
+        &self.population[0]
+    }
+
+    /// Run one generation: play a round robin, keep the best candidates and replace the rest
+    /// with mutants of them. Returns the round robin's results, best first.
+    pub fn step(&mut self) -> Vec<Candidate<W>> {
+        let count = self.population.len();
+        let mut points = vec![0.0; count];
+        let mut games = vec![0u32; count];
+        for i in 0..count {
+            for j in (i + 1)..count {
+                for game in 0..self.settings.games_per_pairing {
+                    let (one, two) = if game % 2 == 0 { (i, j) } else { (j, i) };
+                    let conclusion = MatchSpec::new(self.start.clone())
+                        .player_one(self.config_for(&self.population[one]))
+                        .player_two(self.config_for(&self.population[two]))
+                        .play()
+                        .conclusion;
+                    points[one] += value::of_conclusion(conclusion, PlayerEnum::One);
+                    points[two] += value::of_conclusion(conclusion, PlayerEnum::Two);
+                    games[one] += 1;
+                    games[two] += 1;
+                }
+            }
+        }
+
+        // The sort is stable, so on a tie the candidate that survived longest stays ahead.
+        let mut ranked: Vec<Candidate<W>> = self.population.iter().enumerate().map(|(i, weights)| Candidate {
+            weights: weights.clone(),
+            score: if games[i] > 0 { points[i] / games[i] as f64 } else { 0.0 },
+        }).collect();
+        ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).expect("Scores can't be NaN"));
+
+        let survivors = self.settings.survivors.max(1).min(ranked.len());
+        self.population = ranked[..survivors].iter().map(|candidate| candidate.weights.clone()).collect();
+        let mut parent = 0;
+        while self.population.len() < count {
+            let child = mutate(&self.population[parent % survivors], self.settings.mutation);
+            self.population.push(child);
+            parent += 1;
+        }
+        self.generation += 1;
+        ranked
+    }
+
+    /// Run the remaining generations, and return the best weights.
+    pub fn run(&mut self) -> W {
+        while self.generation < self.settings.generations {
+            self.step();
+        }
+        self.best().clone()
+    }
+
+    fn config_for(&self, weights: &W) -> MctsConfig<Game> {
+        MctsConfig {
+            rollout_policy: Arc::new(SoftmaxRollout { weights: weights.clone(), temperature: self.settings.temperature }),
+            ..self.config.clone()
+        }
+    }
+}
+
+/// `weights` with every parameter moved by a random amount. See `EvolutionSettings::mutation`.
+fn mutate<Game: GameState, W: TunableWeights<Game>>(weights: &W, mutation: f64) -> W {
+    let parameters: Vec<f64> = weights.parameters().iter().map(|&x| x + mutation * x.abs().max(1.0) * standard_normal()).collect();
+    weights.with_parameters(&parameters)
+}
+
+/// A normally distributed random number, by the Box-Muller transform.
+fn standard_normal() -> f64 {
+    let u = 1.0 - random_f64();
+    let v = random_f64();
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+/// The best weights found for each game, kept in a text file with a line per game: its name,
+/// then its parameters, separated by spaces.
+#[derive(Debug, Clone)]
+pub struct WeightStore {
+    path: PathBuf,
+}
+
+impl WeightStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The parameters saved for `game`, or `None` if there aren't any (e.g. no file yet).
+    pub fn load(&self, game: &str) -> io::Result<Option<Vec<f64>>> {
+        for (name, parameters) in self.read()? {
+            if name == game {
+                return Ok(Some(parameters));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Save `parameters` for `game`, replacing any saved before. Other games' are kept.
+    pub fn save(&self, game: &str, parameters: &[f64]) -> io::Result<()> {
+        if game.is_empty() || game.contains(char::is_whitespace) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Bad game name {:?}", game)));
+        }
+        let mut entries: Vec<(String, Vec<f64>)> = self.read()?.into_iter().filter(|entry| entry.0 != game).collect();
+        entries.push((game.to_string(), parameters.to_vec()));
+        let mut text = String::new();
+        for (name, parameters) in entries {
+            text += &name;
+            for parameter in parameters {
+                text += &format!(" {}", parameter);
+            }
+            text += "\n";
+        }
+        fs::write(&self.path, text)
+    }
+
+    fn read(&self) -> io::Result<Vec<(String, Vec<f64>)>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        text.lines().filter(|line| !line.trim().is_empty()).map(|line| {
+            let mut words = line.split_whitespace();
+            let name = words.next().expect("Line isn't empty").to_string();
+            let parameters = words.map(|word| word.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Bad weights for {}: {}", name, e)))?;
+            Ok((name, parameters))
+        }).collect()
+    }
+}
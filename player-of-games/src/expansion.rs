@@ -0,0 +1,60 @@
+//! Ways of giving newly expanded nodes some statistics to start from.
+
+use std::fmt::Debug;
+
+use game::{GameState, PlayerEnum};
+
+/// Made-up simulation results for a node, from the point of view of the player who moved into
+/// it. These count towards the node's value but not its number of (real) visits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Prior {
+    pub visits: f64,
+    pub total_reward: f64,
+}
+
+/// Decides what a node knows when it's first added to the tree.
+pub trait ExpansionPolicy<Game: GameState>: Debug {
+    /// The prior for the node reached by playing `game_move` from `parent`.
+    /// `grandparent_sibling` is what's known about the same move played from the grandparent
+    /// (where the same player was to move), if it's been tried there.
+    fn prior(&self, parent: &Game, game_move: &<Game as GameState>::Move, grandparent_sibling: Option<Prior>) -> Option<Prior>;
+
+    /// The same as `prior`, knowing that `player` made the move. This is what the search calls,
+    /// so policies which need to know whose move it was (e.g. to evaluate positions) can
+    /// override it.
+    fn prior_for(&self, parent: &Game, game_move: &<Game as GameState>::Move, _player: PlayerEnum, grandparent_sibling: Option<Prior>) -> Option<Prior> {
+        self.prior(parent, game_move, grandparent_sibling)
+    }
+}
+
+/// New nodes start with no information. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoPriors;
+
+impl<Game: GameState> ExpansionPolicy<Game> for NoPriors {
+    fn prior(&self, _parent: &Game, _game_move: &<Game as GameState>::Move, _grandparent_sibling: Option<Prior>) -> Option<Prior> {
+        None
+    }
+}
+
+/// Start new nodes off with the average result of the same move at the grandparent (the "history
+/// heuristic"), counted as at most `max_visits` visits so that real results soon take over.
+#[derive(Debug, Clone, Copy)]
+pub struct GrandparentPriors {
+    pub max_visits: f64,
+}
+
+impl<Game: GameState> ExpansionPolicy<Game> for GrandparentPriors {
+    fn prior(&self, _parent: &Game, _game_move: &<Game as GameState>::Move, grandparent_sibling: Option<Prior>) -> Option<Prior> {
+        let sibling = grandparent_sibling?;
+        if sibling.visits <= 0.0 {
+            return None;
+        }
+        let visits = sibling.visits.min(self.max_visits);
+        Some(Prior {
+            visits,
+            total_reward: sibling.total_reward / sibling.visits * visits,
+        })
+    }
+}
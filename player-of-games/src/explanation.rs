@@ -0,0 +1,58 @@
+use std::fmt::{self, Debug, Display};
+
+/// What led to the player's move being chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecisionSource {
+    /// It was the most searched move.
+    Search,
+    /// It was the only legal move.
+    OnlyMove,
+    /// The skill level picked it over the most searched move.
+    Weakened,
+    /// It was the opening book's move.
+    Book,
+    /// It was looked up in an endgame tablebase.
+    Tablebase,
+    /// A tactical check (e.g. the hybrid player's alpha-beta) found the more searched moves lose
+    /// by force, and passed over them for this one.
+    TacticalOverride,
+}
+
+/// Why the player chose its last move, in terms a person can follow.
+#[derive(Debug, Clone)]
+pub struct MoveExplanation<Move> {
+    pub chosen_move: Move,
+    pub source: DecisionSource,
+    /// The fraction of the search's simulations which went through this move.
+    pub visit_share: f64,
+    /// The estimated chance of winning after this move, from 0 to 1.
+    pub win_rate: Option<f64>,
+    /// The best of the other moves (the most searched one, or if something other than the search
+    /// picked a different move, the one it passed over), and its estimated chance of winning.
+    pub runner_up: Option<(Move, Option<f64>)>,
+    /// The reply the search expects from the opponent.
+    pub expected_reply: Option<Move>,
+}
+
+impl<Move: Debug> Display for MoveExplanation<Move> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent = |x: Option<f64>| x.map_or("unknown".to_string(), |x| format!("{:.0}%", x * 100.0));
+
+        match self.source {
+            DecisionSource::OnlyMove => write!(f, "Played {:?}, the only legal move", self.chosen_move)?,
+            DecisionSource::Search => write!(f, "Played {:?}, the most searched move", self.chosen_move)?,
+            DecisionSource::Weakened => write!(f, "Played {:?}, picked at the current skill level", self.chosen_move)?,
+            DecisionSource::Book => write!(f, "Played {:?}, from the opening book", self.chosen_move)?,
+            DecisionSource::Tablebase => write!(f, "Played {:?}, from the tablebase", self.chosen_move)?,
+            DecisionSource::TacticalOverride => write!(f, "Played {:?}, as the more searched moves lose by force", self.chosen_move)?,
+        }
+        write!(f, " ({:.0}% of simulations), with a win rate of {}.", self.visit_share * 100.0, percent(self.win_rate))?;
+        if let Some((ref runner_up, win_rate)) = self.runner_up {
+            write!(f, " The alternative, {:?}, had a win rate of {}.", runner_up, percent(win_rate))?;
+        }
+        if let Some(ref reply) = self.expected_reply {
+            write!(f, " Expecting {:?} in reply.", reply)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+//! How far a strategy for a game with hidden information is from an equilibrium: the best
+//! response to it is the strategy which takes the most off it, knowing exactly how it plays, and
+//! its exploitability is how much that gains. An equilibrium can't be exploited at all.
+//!
+//! This walks the whole game, so it's only for small games, e.g. to check that CFR is converging:
+//!
+//! ```ignore
+//! let mut player = CfrPlayer::new(PlayerEnum::One, Goofspiel::new(4));
+//! player.train(10_000);
+//! let report = exploitability(&Goofspiel::new(4), &mut player, &WinLoss);
+//! println!("{:.4}", report.exploitability);
+//! ```
+//!
+//! Players which don't give their strategy out, like the tree search, can be measured by sampling
+//! their moves with `SampledStrategy`.
+
+use std::collections::HashMap;
+
+use game::{GameState, ImperfectInformation, PlayerEnum};
+
+use cfr::CfrPlayer;
+use reward::RewardModel;
+
+/// A way of playing a game with hidden information, for both players.
+pub trait Strategy<Game: ImperfectInformation> {
+    /// How `player` plays in `state`: each of their legal moves, with its probability. This must
+    /// only depend on what they can see, i.e. `state`'s information set.
+    fn distribution(&mut self, state: &Game, player: PlayerEnum) -> Vec<(<Game as GameState>::Move, f64)>;
+}
+
+impl<Game: ImperfectInformation> Strategy<Game> for CfrPlayer<Game> {
+    fn distribution(&mut self, state: &Game, player: PlayerEnum) -> Vec<(<Game as GameState>::Move, f64)> {
+        self.strategy(state, player)
+    }
+}
+
+/// The chance of playing each move.
+type Distribution<Game> = Vec<(<Game as GameState>::Move, f64)>;
+
+/// A strategy estimated by asking for a move `samples` times in each information set, e.g. from
+/// a tree search player. Each information set is only sampled once, from the first of its states
+/// to be asked about.
+pub struct SampledStrategy<Game: ImperfectInformation, F> {
+    choose: F,
+    samples: u32,
+    distributions: HashMap<(PlayerEnum, Game::InformationSet), Distribution<Game>>,
+}
+
+impl<Game, F> SampledStrategy<Game, F>
+    where Game: ImperfectInformation,
+          F: FnMut(&Game, PlayerEnum) -> <Game as GameState>::Move
+{
+    /// `choose` picks a move for a player in a state.
+    pub fn new(samples: u32, choose: F) -> Self {
+        Self {
+            choose,
+            samples,
+            distributions: HashMap::new(),
+        }
+    }
+}
+
+impl<Game, F> Strategy<Game> for SampledStrategy<Game, F>
+    where Game: ImperfectInformation,
+          F: FnMut(&Game, PlayerEnum) -> <Game as GameState>::Move
+{
+    fn distribution(&mut self, state: &Game, player: PlayerEnum) -> Vec<(<Game as GameState>::Move, f64)> {
+        let key = (player, state.information_set(player));
+        if let Some(distribution) = self.distributions.get(&key) {
+            return distribution.clone();
+        }
+
+        let mut counts: Vec<(<Game as GameState>::Move, f64)> = state.all_legal_moves(player).map(|game_move| (game_move, 0.0)).collect();
+        for _ in 0..self.samples {
+            let game_move = (self.choose)(state, player);
+            if let Some(count) = counts.iter_mut().find(|x| x.0 == game_move) {
+                count.1 += 1.0;
+            }
+        }
+        for count in counts.iter_mut() {
+            count.1 /= self.samples as f64;
+        }
+        self.distributions.insert(key, counts.clone());
+        counts
+    }
+}
+
+/// The most that can be taken off a strategy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exploitability {
+    /// Player one's expected reward with a best response to player two's strategy.
+    pub best_response_one: f64,
+    /// Player two's expected reward with a best response to player one's strategy.
+    pub best_response_two: f64,
+    /// How much the best responses gain over the strategy playing itself, between them (assuming
+    /// the rewards always sum to 1, as with `reward::WinLoss`). Zero at an equilibrium.
+    pub nash_conv: f64,
+    /// Half of `nash_conv`: how much a best response gains, on average over the two players.
+    pub exploitability: f64,
+}
+
+/// How exploitable `strategy` is, in games starting from `initial_state` with player one to move.
+pub fn exploitability<Game, S>(initial_state: &Game, strategy: &mut S, reward_model: &dyn RewardModel<Game>) -> Exploitability
+    where Game: ImperfectInformation,
+          S: Strategy<Game>
+{
+    let best_response_one = best_response(initial_state, strategy, PlayerEnum::One, reward_model);
+    let best_response_two = best_response(initial_state, strategy, PlayerEnum::Two, reward_model);
+    let nash_conv = best_response_one + best_response_two - 1.0;
+    Exploitability {
+        best_response_one,
+        best_response_two,
+        nash_conv,
+        exploitability: nash_conv / 2.0,
+    }
+}
+
+/// The expected reward for `responder` playing a best response to the other player's `strategy`,
+/// in games starting from `initial_state` with player one to move.
+pub fn best_response<Game, S>(initial_state: &Game, strategy: &mut S, responder: PlayerEnum, reward_model: &dyn RewardModel<Game>) -> f64
+    where Game: ImperfectInformation,
+          S: Strategy<Game>
+{
+    let mut search = BestResponse {
+        strategy,
+        responder,
+        reward_model,
+        information_sets: HashMap::new(),
+        decisions: HashMap::new(),
+        values: HashMap::new(),
+    };
+    search.collect(initial_state, PlayerEnum::One, 0, 1.0);
+    search.value(initial_state, PlayerEnum::One, 0)
+}
+
+/// Working for `best_response`.
+struct BestResponse<'a, Game: ImperfectInformation, S: 'a> {
+    strategy: &'a mut S,
+    responder: PlayerEnum,
+    reward_model: &'a dyn RewardModel<Game>,
+    /// The responder's information sets, with the states in each which the other player's
+    /// strategy can reach, and the probability that it does.
+    information_sets: HashMap<Game::InformationSet, Vec<(Game, u32, f64)>>,
+    /// The responder's move in each information set, once decided.
+    decisions: HashMap<Game::InformationSet, <Game as GameState>::Move>,
+    /// The responder's expected reward from each state, by who's to move and how many moves in.
+    values: HashMap<(Game, PlayerEnum, u32), f64>,
+}
+
+impl<'a, Game: ImperfectInformation, S: Strategy<Game>> BestResponse<'a, Game, S> {
+    /// Find the responder's information sets under `state`, which the other player reaches with
+    /// probability `reach`.
+    fn collect(&mut self, state: &Game, to_move: PlayerEnum, length: u32, reach: f64) {
+        if state.try_conclude(to_move).is_some() {
+            return;
+        }
+        if to_move == self.responder {
+            self.information_sets.entry(state.information_set(to_move)).or_default().push((state.clone(), length, reach));
+            for game_move in state.all_legal_moves(to_move).collect::<Vec<_>>() {
+                let mut next = state.clone();
+                next.update(game_move, to_move);
+                self.collect(&next, to_move.other(), length + 1, reach);
+            }
+        } else {
+            for (game_move, p) in self.strategy.distribution(state, to_move) {
+                if p > 0.0 {
+                    let mut next = state.clone();
+                    next.update(game_move, to_move);
+                    self.collect(&next, to_move.other(), length + 1, reach * p);
+                }
+            }
+        }
+    }
+
+    /// The responder's expected reward from `state`.
+    fn value(&mut self, state: &Game, to_move: PlayerEnum, length: u32) -> f64 {
+        if let Some(conclusion) = state.try_conclude(to_move) {
+            return self.reward_model.reward(state, conclusion, length, self.responder);
+        }
+        let key = (state.clone(), to_move, length);
+        if let Some(&value) = self.values.get(&key) {
+            return value;
+        }
+
+        let value = if to_move == self.responder {
+            let game_move = self.decide(state.information_set(to_move));
+            let mut next = state.clone();
+            next.update(game_move, to_move);
+            self.value(&next, to_move.other(), length + 1)
+        } else {
+            let mut value = 0.0;
+            for (game_move, p) in self.strategy.distribution(state, to_move) {
+                if p > 0.0 {
+                    let mut next = state.clone();
+                    next.update(game_move, to_move);
+                    value += p * self.value(&next, to_move.other(), length + 1);
+                }
+            }
+            value
+        };
+        self.values.insert(key, value);
+        value
+    }
+
+    /// The responder's best move in an information set: the one with the highest expected reward
+    /// across its states, weighted by how likely the other player is to reach them. With perfect
+    /// recall, deciding this only needs decisions further on in the game.
+    fn decide(&mut self, information_set: Game::InformationSet) -> <Game as GameState>::Move {
+        if let Some(&game_move) = self.decisions.get(&information_set) {
+            return game_move;
+        }
+        let states = self.information_sets.get(&information_set).cloned().expect("Information sets are collected first");
+        let moves: Vec<_> = states[0].0.all_legal_moves(self.responder).collect();
+
+        let mut best = None;
+        for game_move in moves {
+            let mut total = 0.0;
+            for &(ref state, length, reach) in states.iter() {
+                let mut next = state.clone();
+                next.update(game_move, self.responder);
+                total += reach * self.value(&next, self.responder.other(), length + 1);
+            }
+            match best {
+                Some((_, best_total)) if best_total >= total => (),
+                _ => best = Some((game_move, total)),
+            }
+        }
+
+        let game_move = best.expect("There were no legal moves").0;
+        self.decisions.insert(information_set, game_move);
+        game_move
+    }
+}
@@ -0,0 +1,53 @@
+use game::board::{BoardTopology, Cell};
+
+/// How good each legal move looks after a search, e.g. for shading squares on a board.
+#[derive(Debug, Clone)]
+pub struct HeatMap<Move> {
+    pub moves: Vec<MoveHeat<Move>>,
+}
+
+/// One entry of a `HeatMap`.
+#[derive(Debug, Clone, Copy)]
+pub struct MoveHeat<Move> {
+    pub game_move: Move,
+    /// Simulations through this move, as a fraction of those through the most searched move, so
+    /// the best move scores 1 and unsearched moves score 0.
+    pub score: f64,
+    /// The estimated chance of winning after this move, if it's been searched.
+    pub win_rate: Option<f64>,
+}
+
+impl<Move> HeatMap<Move> {
+    /// Lay the scores out on a `width` by `height` grid, for games whose moves are made on a grid.
+    /// `square` gives the `(x, y)` square of a move, or `None` for moves which aren't on the grid.
+    /// Squares without a legal move are `None`.
+    pub fn to_grid<F>(&self, width: usize, height: usize, square: F) -> Vec<Vec<Option<f64>>>
+        where F: Fn(&Move) -> Option<(usize, usize)>
+    {
+        let mut grid = vec![vec![None; width]; height];
+        for heat in self.moves.iter() {
+            match square(&heat.game_move) {
+                Some((x, y)) if x < width && y < height => grid[y][x] = Some(heat.score),
+                _ => (),
+            }
+        }
+        grid
+    }
+
+    /// The scores by cell, for games played on a `game::board` topology. `cell` gives the cell of
+    /// a move, or `None` for moves which aren't on the board. Cells without a legal move are
+    /// `None`.
+    pub fn to_cells<B, F>(&self, board: &B, cell: F) -> Vec<Option<f64>>
+        where B: BoardTopology + ?Sized,
+              F: Fn(&Move) -> Option<Cell>
+    {
+        let mut cells = vec![None; board.cell_count()];
+        for heat in self.moves.iter() {
+            match cell(&heat.game_move) {
+                Some(cell) if cell < cells.len() => cells[cell] = Some(heat.score),
+                _ => (),
+            }
+        }
+        cells
+    }
+}
@@ -0,0 +1,170 @@
+//! The tree search plays strategically, but random simulations can miss a short forced loss which
+//! a shallow alpha-beta search would see straight away. A `HybridPlayer` lets the tree search pick
+//! its move, then checks it with alpha-beta, passing over it for the next most searched move if it
+//! loses by force.
+//!
+//! ```ignore
+//! let engine = MonteCarloTreeSearchPlayer::new(PlayerEnum::One, 2f64.sqrt());
+//! let tactics = AlphaBetaPlayer::new(PlayerEnum::One, AlphaBetaSettings { depth: 4, ..Default::default() });
+//! let player = HybridPlayer::new(PlayerEnum::One, engine, tactics);
+//! ```
+
+use game::{value, AlphaBetaPlayer, CancellationToken, GameState, Player, PlayerEnum};
+
+use {DecisionSource, MonteCarloTreeSearchPlayer, MoveExplanation};
+
+/// Tree search, with alpha-beta to veto tactical blunders.
+pub struct HybridPlayer<Game: GameState> {
+    player: PlayerEnum,
+    engine: MonteCarloTreeSearchPlayer<Game>,
+    tactics: AlphaBetaPlayer<Game>,
+    veto_below: f64,
+    vetoed: Vec<<Game as GameState>::Move>,
+    last_evaluation: Option<f64>,
+}
+
+impl<Game: GameState> HybridPlayer<Game> {
+    /// `engine` chooses moves, and `tactics` checks them. Both should play `player`.
+    pub fn new(player: PlayerEnum, engine: MonteCarloTreeSearchPlayer<Game>, tactics: AlphaBetaPlayer<Game>) -> Self {
+        Self {
+            player,
+            engine,
+            tactics,
+            veto_below: 0.01,
+            vetoed: Vec::new(),
+            last_evaluation: None,
+        }
+    }
+
+    /// Veto moves which alpha-beta values below this for the player. Without a heuristic, it only
+    /// gives values near 0 to moves which lose by force, so this defaults to 0.01.
+    pub fn set_veto_threshold(&mut self, veto_below: f64) {
+        self.veto_below = veto_below;
+    }
+
+    /// The tree search, e.g. to change its budget.
+    pub fn engine(&mut self) -> &mut MonteCarloTreeSearchPlayer<Game> {
+        &mut self.engine
+    }
+
+    /// The alpha-beta player checking the moves.
+    pub fn tactics(&mut self) -> &mut AlphaBetaPlayer<Game> {
+        &mut self.tactics
+    }
+
+    /// The moves vetoed last turn.
+    pub fn vetoed(&self) -> &[<Game as GameState>::Move] {
+        &self.vetoed
+    }
+
+    /// Why the player chose the last move it chose, if it's chosen one.
+    pub fn explain_last_move(&self) -> Option<&MoveExplanation<<Game as GameState>::Move>> {
+        self.engine.explain_last_move()
+    }
+
+    /// Where this turn's move came from, out of `moves` legal moves: the tree search, unless
+    /// alpha-beta vetoed its choice (but not every move).
+    fn source(&self, moves: usize) -> DecisionSource {
+        if moves == 1 {
+            DecisionSource::OnlyMove
+        } else if self.vetoed.is_empty() || self.vetoed.len() == moves {
+            DecisionSource::Search
+        } else {
+            DecisionSource::TacticalOverride
+        }
+    }
+}
+
+impl<Game: GameState> Player<Game> for HybridPlayer<Game> {
+    fn choose_move(&mut self, game: Game) -> <Game as GameState>::Move {
+        let player = self.player;
+        self.engine.analyse(&game);
+        let mut moves = self.engine.heat_map(&game).moves;
+        moves.sort_by(|a, b| b.score.partial_cmp(&a.score).expect("Scores are never NaN"));
+
+        self.vetoed.clear();
+        for heat in moves.iter() {
+            let mut next = game.clone();
+            next.update(heat.game_move, player);
+            let tactical_value = value::flip(self.tactics.evaluate(&next, player.other()));
+            if tactical_value >= self.veto_below {
+                self.last_evaluation = heat.win_rate.or(Some(tactical_value));
+                let source = self.source(moves.len());
+                self.engine.explain_move(&game, heat.game_move, source);
+                return heat.game_move;
+            }
+            self.vetoed.push(heat.game_move);
+        }
+
+        // Every move loses, so go with the tree search
+        let best = moves.first().expect("There were no legal moves");
+        self.last_evaluation = best.win_rate;
+        let source = self.source(moves.len());
+        self.engine.explain_move(&game, best.game_move, source);
+        best.game_move
+    }
+
+    fn inform_of_move_played(&mut self, new_state: Game, game_move: &<Game as GameState>::Move) {
+        self.engine.inform_of_move_played(new_state.clone(), game_move);
+        self.tactics.inform_of_move_played(new_state, game_move);
+    }
+
+    fn last_evaluation(&self) -> Option<f64> {
+        self.last_evaluation
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.engine.set_cancellation_token(token.clone());
+        self.tactics.set_cancellation_token(token);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tic_tac_toe;
+
+    use game::{AlphaBetaSettings, Heuristic, PlayerEnum};
+
+    use self::tic_tac_toe::{Move, Piece, TicTacToe};
+    use super::*;
+    use SearchBudget;
+
+    /// Crosses win with the centre, and lose without it.
+    #[derive(Debug)]
+    struct CentreOrBust;
+
+    impl Heuristic<TicTacToe> for CentreOrBust {
+        fn evaluate(&self, state: &TicTacToe, player: PlayerEnum) -> f64 {
+            let crosses_win = state.get(1, 1) == Some(Piece::Cross);
+            if crosses_win == (player == PlayerEnum::One) { 1.0 } else { 0.0 }
+        }
+    }
+
+    #[test]
+    fn explains_vetoed_moves_as_tactical_overrides() {
+        let mut engine = MonteCarloTreeSearchPlayer::new(PlayerEnum::One, 2f64.sqrt());
+        engine.set_budget(SearchBudget::Iterations(0));
+        let tactics = AlphaBetaPlayer::with_heuristic(PlayerEnum::One, AlphaBetaSettings { depth: 0, ..Default::default() }, CentreOrBust);
+        let mut player = HybridPlayer::new(PlayerEnum::One, engine, tactics);
+
+        let centre = Move::new(1, 1, Piece::Cross);
+        assert_eq!(player.choose_move(TicTacToe::new()), centre);
+        assert!(!player.vetoed().is_empty());
+        let explanation = player.explain_last_move().expect("No explanation");
+        assert_eq!((explanation.chosen_move, explanation.source), (centre, DecisionSource::TacticalOverride));
+        assert_eq!(explanation.runner_up.map(|x| x.0), player.vetoed().first().cloned());
+    }
+
+    #[test]
+    fn explains_moves_which_stand_as_searched() {
+        let mut engine = MonteCarloTreeSearchPlayer::new(PlayerEnum::One, 2f64.sqrt());
+        engine.set_budget(SearchBudget::Iterations(50));
+        let tactics = AlphaBetaPlayer::new(PlayerEnum::One, AlphaBetaSettings { depth: 2, ..Default::default() });
+        let mut player = HybridPlayer::new(PlayerEnum::One, engine, tactics);
+
+        let chosen = player.choose_move(TicTacToe::new());
+        assert!(player.vetoed().is_empty());
+        let explanation = player.explain_last_move().expect("No explanation");
+        assert_eq!((explanation.chosen_move, explanation.source), (chosen, DecisionSource::Search));
+    }
+}
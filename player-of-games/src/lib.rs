@@ -1,172 +1,1029 @@
+#[cfg(feature = "parallel")]
+extern crate core_affinity;
+#[cfg(feature = "parallel")]
+extern crate crossbeam_epoch;
 extern crate daggy;
 extern crate game;
+#[cfg(feature = "onnx")]
+extern crate ort;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "serialize")]
+extern crate serde;
+extern crate smallvec;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "connector")]
+extern crate ureq;
 
-use std::rc::{Rc, Weak};
-use std::collections::HashMap;
-use std::sync::RwLock;
-
-struct Node<Game: game::GameState> {
-    pub player: game::PlayerEnum,
-    pub local_attempts: u8,
-    pub local_wins: u8,
-    pub local_losses: u8,
-    /// Known children (some may be unknown)
-    pub children: HashMap<<Game as game::GameState>::Move, Game>,
-    /// Known parents - many may be unknown.
-    pub parents: HashMap<<Game as game::GameState>::Move, Game>,
-    debug_attempts: RwLock<u8>,
-    debug_wins: RwLock<u8>,
-    debug_losses: RwLock<u8>
+pub mod alphazero;
+pub mod bot;
+pub mod cfr;
+pub mod chat;
+#[cfg(feature = "parallel")]
+mod concurrent;
+#[cfg(feature = "connector")]
+pub mod connector;
+pub mod ensemble;
+#[cfg(feature = "evolution")]
+pub mod evolution;
+mod evaluation_cache;
+pub mod evaluator;
+pub mod expansion;
+pub mod exploitability;
+pub mod hybrid;
+mod explanation;
+mod heat_map;
+mod node;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+#[cfg(feature = "parallel")]
+mod pool;
+pub mod puzzles;
+pub mod replay;
+pub mod reward;
+mod rollout_tables;
+pub mod self_play;
+pub mod sessions;
+mod skill;
+mod small_map;
+mod snapshot;
+pub mod stability;
+mod stats;
+mod stopping;
+#[cfg(feature = "parallel")]
+mod threads;
+pub mod trace;
+mod tree_export;
+
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "parallel")]
+use concurrent::ConcurrentNode;
+use evaluation_cache::EvaluationCache;
+use expansion::{ExpansionPolicy, Prior};
+use game::Clock;
+use game::rng::random_f64;
+use node::Node;
+#[cfg(feature = "parallel")]
+use node::NodeStats;
+#[cfg(feature = "parallel")]
+use pool::NodeId;
+use reward::{CutoffMapping, RewardModel};
+use rollout_tables::RolloutTables;
+use stats::Counters;
+use stopping::RootChild;
+use trace::{PlayoutTrace, TraceWriter};
+
+pub use evaluation_cache::CacheStats;
+pub use explanation::{DecisionSource, MoveExplanation};
+pub use heat_map::{HeatMap, MoveHeat};
+pub use node::DefaultState;
+pub use rollout_tables::RolloutEnhancements;
+pub use skill::{AdaptiveSkill, SkillLevel};
+pub use snapshot::Snapshot;
+pub use stats::{SearchStats, Topology};
+pub use stopping::{AdaptiveBudget, KlStop};
+#[cfg(feature = "parallel")]
+pub use threads::{Pinning, ThreadSettings};
+pub use tree_export::TreeNodeExport;
+
+/// Limits on random simulations, for games which could otherwise go on forever.
+///
+/// A simulation which is cut off counts as a draw, unless the player has a cutoff heuristic to
+/// judge the position with instead.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct RolloutLimits {
+    /// Stop simulations after this many random moves.
+    pub max_depth: Option<u32>,
+    /// Stop simulations which get back to a position they've already been in.
+    pub detect_cycles: bool,
+}
+
+/// How much searching to do before choosing a move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum SearchBudget {
+    /// Run a fixed number of simulations. The search always runs until the root has a move to
+    /// choose, so this may run a simulation or two more than asked for, e.g. with a budget of 0.
+    Iterations(u32),
+    /// Keep running simulations until this much time has passed.
+    Time(Duration),
+    /// Keep running simulations until the player's cancellation token is cancelled.
+    UntilStopped,
 }
 
-impl<Game: game::GameState> std::fmt::Debug for Node<Game> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Node {{ player: {:?}, attempts: {}, wins: {}, losses: {}, children: {} }}", self.player, self.debug_attempts.read().unwrap(), self.debug_wins.read().unwrap(), self.debug_losses.read().unwrap(), self.children.len())
+/// What the search found out about a position.
+#[derive(Debug, Clone)]
+pub struct Analysis<Move> {
+    /// The move the search would play, if there are any legal moves.
+    pub best_move: Option<Move>,
+    /// Each explored move, along with how many simulations were run through it.
+    pub policy: Vec<(Move, u32)>,
+    /// The line of play the search currently expects, starting with the best move.
+    pub principal_variation: Vec<Move>,
+    /// The estimated chance of winning (from 0 to 1) after playing the best move.
+    pub evaluation: Option<f64>,
+    /// How many simulations have been run from the position, including from previous searches.
+    pub simulations: u32,
+}
+
+/// How often to report on a search in progress.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProgressInterval {
+    /// Every this many simulations.
+    Simulations(u32),
+    /// Every time this much time has passed.
+    Time(Duration),
+}
+
+/// A report on a search in progress.
+#[derive(Debug, Clone)]
+pub struct SearchProgress<Move> {
+    /// How long this search has been running for.
+    pub elapsed: Duration,
+    /// How many simulations this search has run so far.
+    pub iterations: u32,
+    /// What the search has found so far.
+    pub analysis: Analysis<Move>,
+    /// The top of the tree, if asked for with `set_progress_tree`.
+    pub tree: Option<TreeNodeExport>,
+}
+
+type ProgressFn<Move> = Box<dyn FnMut(&SearchProgress<Move>) + Send + Sync>;
+
+/// A callback for reporting progress. See `set_progress_callback`.
+struct ProgressCallback<Move> {
+    interval: ProgressInterval,
+    /// How many levels of the tree to include in reports.
+    tree_depth: Option<u32>,
+    callback: ProgressFn<Move>,
+}
+
+impl<Move> ::std::fmt::Debug for ProgressCallback<Move> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "ProgressCallback {{ interval: {:?}, tree_depth: {:?} }}", self.interval, self.tree_depth)
     }
 }
 
-impl<Game: game::GameState> Node<Game> {
-    fn new(player: game::PlayerEnum, parent: Option<(<Game as game::GameState>::Move, Game)>) -> Self {
+/// Summary of how big the search tree currently is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeSizeReport {
+    /// Number of explored game states.
+    pub nodes: usize,
+    /// Number of known parent -> child links.
+    pub edges: usize,
+    /// Rough estimate of the memory used by the tree, in bytes. This does not include any heap
+    /// memory owned by the game states themselves.
+    pub approx_bytes: usize,
+}
+
+/// The result of playing out one simulation from a leaf of the tree.
+struct Playout<Move> {
+    /// Number of moves from the root to the end of the simulation.
+    length: u32,
+    /// The rewards for player one and player two.
+    rewards: (f64, f64),
+    /// The moves played, if the rollout tables need them.
+    moves: Vec<(game::PlayerEnum, Move)>,
+}
+
+/// Runs several playouts from the same leaf at once. See `set_leaf_parallelism`.
+type ParallelPlayouts<Game, S> = fn(&MonteCarloTreeSearchPlayer<Game, S>, &Game, game::PlayerEnum, &[(game::PlayerEnum, <Game as game::GameState>::Move)], usize, u32) -> Vec<Playout<<Game as game::GameState>::Move>>;
+
+/// Searches with several threads sharing the tree. See `set_tree_parallelism`.
+type TreeParallelSearch<Game, S> = fn(&mut MonteCarloTreeSearchPlayer<Game, S>, &Game, u32);
+
+/// A player which uses Monte Carlo tree search to choose its moves.
+///
+/// This is `Send + Sync` whenever the game (and its moves) are, so it can be moved into worker
+/// threads. Wrap it in a `game::SharedPlayer` to share one player between threads.
+///
+/// Each game state in the tree is stored once, and shared by the links to it.
+///
+/// `S` is the hasher for looking up game states in the tree. The default is SipHash (with fixed
+/// keys), which is robust but slow for small states; see `with_hasher`.
+#[derive(Debug)]
+pub struct MonteCarloTreeSearchPlayer<Game: game::GameState, S = DefaultState> {
+    player: game::PlayerEnum,
+    c: f64,
+    budget: SearchBudget,
+    adaptive_budget: Option<AdaptiveBudget>,
+    early_stop: bool,
+    kl_stop: Option<KlStop>,
+    /// Budgets saved by adaptive and early stopping searches this game (as a fraction of one search's budget).
+    time_bank: f64,
+    explored_states: HashMap<Arc<Game>, Node<Game>, S>,
+    last_turn: Option<Game>,
+    last_evaluation: Option<f64>,
+    gc_threshold: Option<usize>,
+    evaluation_cache: Option<EvaluationCache<Game>>,
+    reward_model: Box<dyn RewardModel<Game> + Send + Sync>,
+    discount: f64,
+    rollout_limits: RolloutLimits,
+    cutoff_heuristic: Option<Box<dyn game::Heuristic<Game> + Send + Sync>>,
+    cutoff_mapping: CutoffMapping,
+    rollout_policy: Box<dyn game::RolloutPolicy<Game> + Send + Sync>,
+    rollout_tables: RolloutTables<<Game as game::GameState>::Move>,
+    expansion_policy: Box<dyn ExpansionPolicy<Game> + Send + Sync>,
+    forced_expansion: Option<u32>,
+    leaf_parallelism: Option<(u32, ParallelPlayouts<Game, S>)>,
+    tree_parallelism: Option<(u32, TreeParallelSearch<Game, S>)>,
+    /// Kept between tree-parallel searches, so that its memory can be reused.
+    #[cfg(feature = "parallel")]
+    concurrent_tree: Option<concurrent::ConcurrentTree<Game, S>>,
+    #[cfg(feature = "parallel")]
+    search_threads: Option<threads::SearchThreads>,
+    progress: Option<ProgressCallback<<Game as game::GameState>::Move>>,
+    cancellation: game::CancellationToken,
+    search_moves: Option<Vec<<Game as game::GameState>::Move>>,
+    excluded_moves: Vec<<Game as game::GameState>::Move>,
+    skill: SkillLevel,
+    adaptive_skill: Option<AdaptiveSkill>,
+    last_explanation: Option<MoveExplanation<<Game as game::GameState>::Move>>,
+    counters: Counters,
+    clock: Box<dyn Clock + Send + Sync>,
+    trace: Option<TraceWriter>,
+}
+
+impl<Game: game::GameState> MonteCarloTreeSearchPlayer<Game> {
+    pub fn new(player: game::PlayerEnum, c: f64) -> Self {
+        Self::with_hasher(player, c)
+    }
+}
+
+impl<Game: game::GameState, S: BuildHasher + Default> MonteCarloTreeSearchPlayer<Game, S> {
+    /// Create a player which hashes game states with `S`, e.g. a faster hasher such as FxHash for
+    /// games with small states:
+    ///
+    /// ```ignore
+    /// let player = MonteCarloTreeSearchPlayer::<ConnectFour, fxhash::FxBuildHasher>::with_hasher(PlayerEnum::One, 2f64.sqrt());
+    /// ```
+    pub fn with_hasher(player: game::PlayerEnum, c: f64) -> Self {
         Self {
             player,
-            local_attempts: 0,
-            local_wins: 0,
-            local_losses: 0,
-            children: HashMap::new(),
-            parents: {
-                let mut map = HashMap::new();
-                match parent {
-                    Some((k ,v)) => {
-                        map.insert(k, v);
-                    },
-                    _ => ()
-                }
-                map
-            },
-            debug_attempts: RwLock::new(0),
-            debug_wins: RwLock::new(0),
-            debug_losses: RwLock::new(0),
+            c,
+            budget: SearchBudget::Iterations(100),
+            adaptive_budget: None,
+            early_stop: false,
+            kl_stop: None,
+            time_bank: 0.0,
+            explored_states: HashMap::default(),
+            last_turn: None,
+            last_evaluation: None,
+            gc_threshold: None,
+            evaluation_cache: None,
+            reward_model: Box::new(reward::WinLoss),
+            discount: 1.0,
+            rollout_limits: RolloutLimits::default(),
+            cutoff_heuristic: None,
+            cutoff_mapping: CutoffMapping::default(),
+            rollout_policy: Box::new(game::UniformRollout),
+            rollout_tables: RolloutTables::new(),
+            expansion_policy: Box::new(expansion::NoPriors),
+            forced_expansion: None,
+            leaf_parallelism: None,
+            tree_parallelism: None,
+            #[cfg(feature = "parallel")]
+            concurrent_tree: None,
+            #[cfg(feature = "parallel")]
+            search_threads: None,
+            progress: None,
+            cancellation: game::CancellationToken::new(),
+            search_moves: None,
+            excluded_moves: Vec::new(),
+            skill: SkillLevel::full(),
+            adaptive_skill: None,
+            last_explanation: None,
+            counters: Counters::new(),
+            clock: Box::new(game::SystemClock::new()),
+            trace: None,
         }
     }
 
-    fn tree_attempts(&self, cache: &HashMap<Game, Node<Game>>) -> HashMap<Game, u8> {
-        let map = self.children.values().fold(HashMap::new(), |mut map, child| {
-            let child_node = cache.get(child).expect("Dangling pointer");
-            map.extend(child_node.tree_attempts(cache));
-            map.insert(child.clone(), child_node.local_attempts);
-            map
-        });
-        *self.debug_attempts.write().unwrap() = map.values().sum();
-        map
+    /// Set how much searching to do for each move. The default is 100 simulations.
+    pub fn set_budget(&mut self, budget: SearchBudget) {
+        self.budget = budget;
     }
 
-    fn attempts(&self, cache: &HashMap<Game, Node<Game>>) -> u8 {
-        self.tree_attempts(cache).values().sum()
+    pub fn budget(&self) -> SearchBudget {
+        self.budget
     }
 
-    fn tree_wins(&self, cache: &HashMap<Game, Node<Game>>) -> HashMap<Game, u8> {
-        let map = self.children.values().fold(HashMap::new(), |mut map, child| {
-            let child_node = cache.get(child).expect("Dangling pointer");
-            map.extend(child_node.tree_losses(cache));
-            map.insert(child.clone(), child_node.local_losses);
-            map
-        });
-        *self.debug_wins.write().unwrap() = map.values().sum();
-        map
+    /// Spend more or less than the budget on each search, depending on how clear the choice of
+    /// move is. See `AdaptiveBudget`. Pass `None` to always spend the budget (the default).
+    /// This empties the time bank.
+    pub fn set_adaptive_budget(&mut self, adaptive: Option<AdaptiveBudget>) {
+        self.adaptive_budget = adaptive;
+        self.time_bank = 0.0;
+    }
+
+    /// Stop searching as soon as the rest of the budget couldn't change which move is chosen,
+    /// because the most visited move is further ahead of the next than there are simulations
+    /// left. The time saved goes into the time bank. Off by default.
+    pub fn set_early_stop(&mut self, early_stop: bool) {
+        self.early_stop = early_stop;
+    }
+
+    /// Stop searching once the root's visits settle down, as well as when the budget runs out.
+    /// See `KlStop`. The time saved goes into the time bank. Pass `None` to turn this off (the
+    /// default).
+    pub fn set_kl_stop(&mut self, kl_stop: Option<KlStop>) {
+        self.kl_stop = kl_stop;
     }
 
-    fn wins(&self, cache: &HashMap<Game, Node<Game>>) -> u8 {
-        self.tree_wins(cache).values().sum()
+    /// How many budgets' worth adaptive and early stopping searches have saved so far this game,
+    /// for adaptive searches to spend later.
+    pub fn time_bank(&self) -> f64 {
+        self.time_bank
     }
 
-    fn tree_losses(&self, cache: &HashMap<Game, Node<Game>>) -> HashMap<Game, u8> {
-        let map = self.children.values().fold(HashMap::new(), |mut map, child| {
-            let child_node = cache.get(child).expect("Dangling pointer");
-            map.extend(child_node.tree_wins(cache));
-            map.insert(child.clone(), child_node.local_wins);
-            map
+    /// Change what the search considers a good result. Defaults to `reward::WinLoss`.
+    pub fn set_reward_model<R: RewardModel<Game> + Send + Sync + 'static>(&mut self, reward_model: R) {
+        self.reward_model = Box::new(reward_model);
+    }
+
+    /// Shrink rewards towards a draw by a factor of `gamma` for every move between a node and the
+    /// end of the simulation, so that the search prefers quicker wins and slower losses.
+    ///
+    /// `gamma` should be in (0, 1]. The default is 1, meaning no discount.
+    pub fn set_discount(&mut self, gamma: f64) {
+        self.discount = gamma;
+    }
+
+    /// Set limits on how long random simulations can go on for. Defaults to no limits.
+    pub fn set_rollout_limits(&mut self, limits: RolloutLimits) {
+        self.rollout_limits = limits;
+    }
+
+    /// Judge simulations which are cut off by the rollout limits with `heuristic`, rather than
+    /// counting them as draws.
+    pub fn set_cutoff_heuristic<H: game::Heuristic<Game> + Send + Sync + 'static>(&mut self, heuristic: H) {
+        self.cutoff_heuristic = Some(Box::new(heuristic));
+    }
+
+    /// Change how the cutoff heuristic's evaluations are turned into rewards. Defaults to taking
+    /// them as they are.
+    pub fn set_cutoff_mapping(&mut self, mapping: CutoffMapping) {
+        self.cutoff_mapping = mapping;
+    }
+
+    /// Change how moves are chosen in random simulations. Defaults to `game::UniformRollout`.
+    pub fn set_rollout_policy<P: game::RolloutPolicy<Game> + Send + Sync + 'static>(&mut self, rollout_policy: P) {
+        self.rollout_policy = Box::new(rollout_policy);
+    }
+
+    /// Turn on MAST and/or last-good-reply in random simulations. Both are off by default.
+    pub fn set_rollout_enhancements(&mut self, enhancements: RolloutEnhancements) {
+        self.rollout_tables.settings = enhancements;
+    }
+
+    /// Change what new nodes start off knowing. Defaults to `expansion::NoPriors`.
+    pub fn set_expansion_policy<P: ExpansionPolicy<Game> + Send + Sync + 'static>(&mut self, expansion_policy: P) {
+        self.expansion_policy = Box::new(expansion_policy);
+    }
+
+    /// When the search reaches a new node where a forcing move can be played (see
+    /// `GameState::is_forcing`), carry on expanding through forcing moves (picked at random), up
+    /// to `max_plies` of them, so that simulations start once the threats have been played out.
+    /// Off by default.
+    pub fn set_forced_expansion(&mut self, max_plies: Option<u32>) {
+        self.forced_expansion = max_plies;
+    }
+
+    /// Have `callback` told how each search is going, every `interval` (and not at all if the
+    /// search ends first), e.g. to show the engine "thinking" in a UI.
+    pub fn set_progress_callback<F>(&mut self, interval: ProgressInterval, callback: F)
+        where F: FnMut(&SearchProgress<<Game as game::GameState>::Move>) + Send + Sync + 'static
+    {
+        self.progress = Some(ProgressCallback {
+            interval,
+            tree_depth: None,
+            callback: Box::new(callback),
         });
-        *self.debug_losses.write().unwrap() = map.values().sum();
-        map
     }
 
-    fn losses(&self, cache: &HashMap<Game, Node<Game>>) -> u8 {
-        self.tree_losses(cache).values().sum()
+    /// Include the top `depth` levels of the tree in progress reports (see `export_tree`), e.g.
+    /// to animate a visualizer as the search goes. Pass `None` to leave it out (the default).
+    /// This does nothing without a progress callback.
+    pub fn set_progress_tree(&mut self, depth: Option<u32>) {
+        if let Some(ref mut progress) = self.progress {
+            progress.tree_depth = depth;
+        }
+    }
+
+    /// Deliberately play worse than the player can. This only affects the moves it plays, not
+    /// `analyse` or `evaluate_position`.
+    pub fn set_skill_level(&mut self, skill: SkillLevel) {
+        self.skill = skill;
+        self.adaptive_skill = None;
+    }
+
+    /// Adjust the skill level after every game passed to `record_game_result`, going by how the
+    /// opponent is doing.
+    pub fn set_adaptive_skill(&mut self, adaptive: AdaptiveSkill) {
+        self.skill = adaptive.skill_level();
+        self.adaptive_skill = Some(adaptive);
     }
 
-    fn uct_value(&self, parent_attempts: u8, c: f64, cache: &HashMap<Game, Node<Game>>) -> f64 {
-        let attempts = self.attempts(cache);
+    /// The adaptive skill settings, and how the opponent has done so far, if turned on.
+    pub fn adaptive_skill(&self) -> Option<&AdaptiveSkill> {
+        self.adaptive_skill.as_ref()
+    }
 
-        // If never explored, maximum exploration value
-        if attempts == 0 {
-            return std::f64::MAX;
+    /// Tell the player how a game it played ended, so that adaptive skill can take it into account.
+    /// This also empties the adaptive budget's time bank, ready for the next game.
+    pub fn record_game_result(&mut self, conclusion: game::Conclusion) {
+        self.time_bank = 0.0;
+        if let Some(ref mut adaptive) = self.adaptive_skill {
+            // The opponent's result
+            adaptive.record(game::value::flip(game::value::of_conclusion(conclusion, self.player)));
+            self.skill = adaptive.skill_level();
         }
+    }
+
+    /// Only consider `moves` at the root of the search (and only choose from them), like UCI's
+    /// `searchmoves`. This lasts until the next move is played. At least one of the moves must be
+    /// legal.
+    pub fn set_search_moves(&mut self, moves: Vec<<Game as game::GameState>::Move>) {
+        self.search_moves = Some(moves);
+    }
 
-        let exploitation_value = (self.wins(cache) as f64)/(attempts as f64);
-        let exploration_value = c * ( (parent_attempts as f64).ln() / (attempts as f64) ).sqrt();
+    /// Never consider `moves` at the root of the search, e.g. to find the best move other than
+    /// the one which was played. This lasts until the next move is played, and can be combined
+    /// with `set_search_moves`. There must be at least one legal move left.
+    pub fn set_excluded_moves(&mut self, moves: Vec<<Game as game::GameState>::Move>) {
+        self.excluded_moves = moves;
+    }
+
+    /// Whether `game_move` may be searched (and chosen) at the root.
+    fn searched_at_root(&self, game_move: &<Game as game::GameState>::Move) -> bool {
+        self.search_moves.as_ref().map_or(true, |moves| moves.contains(game_move)) && !self.excluded_moves.contains(game_move)
+    }
 
-//        println!("UCT value was {} = {} + {} for {:?}", exploitation_value + exploration_value, exploitation_value, exploration_value, self);
+    /// The root's moves which may be searched (including those not searched yet), in the order of
+    /// `all_legal_moves`.
+    fn root_children(&self, game: &Game) -> Vec<RootChild> {
+        let root = self.explored_states.get(game);
+        game.all_legal_moves(self.player).filter(|m| self.searched_at_root(m)).map(|m| {
+            match root.and_then(|node| node.children.get(&m)) {
+                Some(child) => {
+                    let child = self.explored_states.get(child).expect("Dangling pointer");
+                    RootChild { visits: child.visits(), value: child.value_for_parent() }
+                },
+                None => RootChild { visits: 0, value: None },
+            }
+        }).collect()
+    }
 
-        exploitation_value + exploration_value
+    /// Whether the search has found a move to choose at `game` (if there are any to find). A new
+    /// root takes two simulations: one to create it and one to expand a move.
+    fn root_expanded(&self, game: &Game) -> bool {
+        if self.nothing_to_search(game) {
+            return true;
+        }
+        self.explored_states.get(game).is_some_and(|root| root.children.iter().any(|(m, _)| self.searched_at_root(m)))
     }
 
-    fn choose_move_by_uct_value(&self, c: f64, game: &Game, cache: &HashMap<Game, Node<Game>>) -> Option<<Game as game::GameState>::Move> {
-        #[derive(PartialOrd, PartialEq)]
-        struct OrdF64(f64);
+    /// Whether there are no moves to search at the root `game`, so no move to find.
+    fn nothing_to_search(&self, game: &Game) -> bool {
+        game.try_conclude(self.player).is_some() || !game.all_legal_moves(self.player).any(|m| self.searched_at_root(&m))
+    }
 
-        impl OrdF64 {
-            fn new(x: f64) -> Self {
-                if x.is_nan() {
-                    panic!("x is NAN");
-                }
-                OrdF64(x)
+    /// Log every simulation from now on to `output`, e.g. a file. See the `trace` module.
+    pub fn set_trace<W: io::Write + Send + Sync + 'static>(&mut self, output: W) -> io::Result<()> {
+        self.finish_trace()?;
+        self.trace = Some(TraceWriter::new(output)?);
+        Ok(())
+    }
+
+    /// Stop logging simulations, flushing the log. Returns the first error writing it, if any.
+    pub fn finish_trace(&mut self) -> io::Result<()> {
+        self.trace.take().map_or(Ok(()), |trace| trace.finish())
+    }
+
+    /// Time searches with `clock` rather than the system clock.
+    pub fn set_clock<C: Clock + Send + Sync + 'static>(&mut self, clock: C) {
+        self.clock = Box::new(clock);
+    }
+
+    /// The clock searches are timed with.
+    pub fn clock(&self) -> &dyn Clock {
+        &*self.clock
+    }
+
+    /// A token which stops the current search (once it has a move to choose) when cancelled,
+    /// e.g. from another thread. Simulations in progress are cut off. It stays cancelled,
+    /// stopping every later search too, until it's replaced with `Player::set_cancellation_token`
+    /// (e.g. to share one token between every player in a tournament).
+    pub fn cancellation_token(&self) -> game::CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Search the given position (with this player to move) and report the results, without
+    /// committing to a move.
+    pub fn analyse(&mut self, game: &Game) -> Analysis<<Game as game::GameState>::Move> {
+        self.search(game);
+        self.analysis(game)
+    }
+
+    /// Like `analyse`, but with `to_move` to move rather than this player, e.g. to suggest a move
+    /// to the opponent.
+    pub fn analyse_as(&mut self, game: &Game, to_move: game::PlayerEnum) -> Analysis<<Game as game::GameState>::Move> {
+        let player = ::std::mem::replace(&mut self.player, to_move);
+        let analysis = self.analyse(game);
+        self.player = player;
+        analysis
+    }
+
+    /// Estimate the chance (from 0 to 1) that `to_move` wins from `game`, searching with `budget`
+    /// rather than the player's own budget, e.g. to annotate a game or decide when to resign.
+    ///
+    /// Whatever was learned is kept in the tree, so a later search of the same position benefits.
+    pub fn evaluate_position(&mut self, game: &Game, to_move: game::PlayerEnum, budget: SearchBudget) -> f64 {
+        let player = ::std::mem::replace(&mut self.player, to_move);
+        let own_budget = ::std::mem::replace(&mut self.budget, budget);
+        self.search(game);
+        self.player = player;
+        self.budget = own_budget;
+
+        // The best move's value is the best estimate. If there are no moves, the game's over, and
+        // the root's value (which is for the player who moved into it) is all there is.
+        let root = self.explored_states.get(game);
+        self.analysis(game).evaluation
+            .or_else(|| root.and_then(|node| node.value_for_parent()).map(game::value::flip))
+            .unwrap_or(game::value::DRAW)
+    }
+
+    /// How good each legal move from `game` looks, going by what's been searched so far (e.g. by
+    /// `analyse`).
+    pub fn heat_map(&self, game: &Game) -> HeatMap<<Game as game::GameState>::Move> {
+        let node = match self.explored_states.get(game) {
+            Some(node) => node,
+            None => return HeatMap { moves: Vec::new() },
+        };
+        let most_visits = node.children.values().map(|child| self.explored_states.get(child).expect("Dangling pointer").visits()).max().unwrap_or(0);
+
+        let moves = game.all_legal_moves(node.player).map(|game_move| {
+            let child = node.children.get(&game_move).map(|child| self.explored_states.get(child).expect("Dangling pointer"));
+            let (visits, win_rate) = child.map_or((0, None), |child| (child.visits(), child.value_for_parent()));
+            MoveHeat {
+                game_move,
+                score: visits as f64 / most_visits.max(1) as f64,
+                win_rate,
             }
+        }).collect();
+        HeatMap { moves }
+    }
+
+    /// How much of each kind of work the search has done, since the player was created or the
+    /// stats were last reset. This is only counted with the `stats` feature.
+    pub fn search_stats(&self) -> SearchStats {
+        SearchStats {
+            topology: self.topology(),
+            ..self.counters.snapshot()
+        }
+    }
+
+    fn topology(&self) -> Topology {
+        #[cfg(feature = "parallel")]
+        {
+            if self.leaf_parallelism.is_some() || self.tree_parallelism.is_some() {
+                return match self.search_threads {
+                    Some(ref threads) => threads.topology(),
+                    None => Topology { threads: rayon::current_num_threads(), ..Topology::default() },
+                };
+            }
+        }
+        // The playouts run on the searching thread
+        Topology { threads: 1, ..Topology::default() }
+    }
+
+    pub fn reset_search_stats(&mut self) {
+        self.counters = Counters::new();
+    }
+
+    /// Why the player chose the last move it chose, if it's chosen one.
+    pub fn explain_last_move(&self) -> Option<&MoveExplanation<<Game as game::GameState>::Move>> {
+        self.last_explanation.as_ref()
+    }
+
+    /// Record that `decision` was chosen from `game` by `source` rather than by `choose_move`,
+    /// e.g. from an opening book or a tablebase, for `explain_last_move`. What the tree knows
+    /// about the move (if anything) goes in the explanation too.
+    pub fn explain_move(&mut self, game: &Game, decision: <Game as game::GameState>::Move, source: DecisionSource) {
+        let policy = self.analysis(game).policy;
+        self.last_explanation = Some(self.explain(game, decision, &policy, source));
+    }
+
+    /// Describe choosing `decision` from `game`, whose root policy is `policy`, by `source`.
+    fn explain(&self, game: &Game, decision: <Game as game::GameState>::Move, policy: &[(<Game as game::GameState>::Move, u32)], source: DecisionSource) -> MoveExplanation<<Game as game::GameState>::Move> {
+        let node = self.explored_states.get(game);
+        // The child reached by a move, if it's been searched.
+        let child_state = |m: &<Game as game::GameState>::Move| node.and_then(|node| node.children.get(m));
+        // Its visits and value.
+        let child = |m: &<Game as game::GameState>::Move| child_state(m).map_or((0, None), |child| {
+            let child = self.explored_states.get(child).expect("Dangling pointer");
+            (child.visits(), child.value_for_parent())
+        });
+
+        let total_visits: u32 = policy.iter().map(|x| x.1).sum();
+        let runner_up = policy.iter().map(|x| x.0).find(|m| *m != decision);
+
+        MoveExplanation {
+            chosen_move: decision,
+            source,
+            visit_share: child(&decision).0 as f64 / total_visits.max(1) as f64,
+            win_rate: child(&decision).1,
+            runner_up: runner_up.map(|m| (m, child(&m).1)),
+            expected_reply: child_state(&decision).and_then(|child| self.principal_variation(child).first().cloned()),
+        }
+    }
+
+    /// What the tree currently says about `game`.
+    fn analysis(&self, game: &Game) -> Analysis<<Game as game::GameState>::Move> {
+        let mut policy: Vec<_> = match self.explored_states.get(game) {
+            Some(node) => node.children.iter().filter(|&(m, _)| self.searched_at_root(m)).map(|(m, child)| {
+                (*m, self.explored_states.get(child).expect("Dangling pointer").visits())
+            }).collect(),
+            None => Vec::new(),
+        };
+        policy.sort_by_key(|x| ::std::cmp::Reverse(x.1));
+
+        let root = self.explored_states.get(game);
+        let evaluation = root.and_then(|node| policy.first().and_then(|&(m, _)| node.children.get(&m)))
+            .and_then(|child| self.explored_states.get(child).expect("Dangling pointer").value_for_parent());
+
+        Analysis {
+            best_move: policy.first().map(|x| x.0),
+            policy,
+            principal_variation: self.principal_variation(game),
+            evaluation,
+            simulations: root.map_or(0, |node| node.visits()),
         }
+    }
 
-        impl Eq for OrdF64 {}
+    /// The tree below `game`, `depth` moves deep, for visualizers. See `TreeNodeExport::to_json`.
+    /// Positions reached again further down a line aren't followed again.
+    pub fn export_tree(&self, game: &Game, depth: u32) -> TreeNodeExport {
+        let root_player = self.explored_states.get(game).map_or(self.player, |node| node.player);
+        self.export_node(game, "root".to_string(), root_player, depth, &mut Vec::new())
+    }
+
+    fn export_node<'a>(&'a self, state: &'a Game, name: String, player: game::PlayerEnum, depth: u32, line: &mut Vec<&'a Game>) -> TreeNodeExport {
+        let node = self.explored_states.get(state);
+        let mut export = TreeNodeExport {
+            name,
+            player,
+            visits: node.map_or(0, |node| node.visits()),
+            value: node.and_then(|node| node.value_for_parent()),
+            children: Vec::new(),
+        };
+        let node = match node {
+            Some(node) if depth > 0 && !line.contains(&state) => node,
+            _ => return export,
+        };
+
+        line.push(state);
+        let at_root = line.len() == 1;
+        let mut children: Vec<_> = node.children.iter().filter(|&(m, _)| !at_root || self.searched_at_root(m)).collect();
+        children.sort_by_key(|&(_, child)| ::std::cmp::Reverse(self.explored_states.get(child).map_or(0, |child| child.visits())));
+        for (game_move, child) in children {
+            let child_player = self.explored_states.get(child).map_or(player.other(), |child| child.player);
+            export.children.push(self.export_node(&**child, format!("{:?}", game_move), child_player, depth - 1, line));
+        }
+        line.pop();
+        export
+    }
 
-        impl Ord for OrdF64 {
-            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                self.0.partial_cmp(&other.0).expect("f64 could not be compared")
+    /// Follow the most visited child from `game` until reaching the edge of the tree.
+    fn principal_variation(&self, game: &Game) -> Vec<<Game as game::GameState>::Move> {
+        let mut variation = Vec::new();
+        let mut seen = HashSet::new();
+        let mut state = game;
+        while seen.insert(state) {
+            let node = match self.explored_states.get(state) {
+                Some(x) => x,
+                None => break,
+            };
+            let at_root = variation.is_empty();
+            let best_child = node.children.iter().filter(|&(m, _)| !at_root || self.searched_at_root(m)).max_by_key(|&(_, child)| {
+                self.explored_states.get(child).expect("Dangling pointer").visits()
+            });
+            match best_child {
+                Some((m, child)) => {
+                    variation.push(*m);
+                    state = &**child;
+                }
+                None => break,
             }
         }
+        variation
+    }
 
-        let attempts = self.attempts(cache);
-        game.all_legal_moves(self.player).map(|game_move| {
-            // Try to find a child with this move
-            match self.children.get(&game_move) {
-                Some(child) => {
-                    // Get the UCT value for that child.
-                    // FIXME: this can choose an unknown child which is actually explored quite a lot...
-                    let uct_value = cache.get(child).expect("Dangling pointer").uct_value(attempts, c, cache);
-                    (game_move, uct_value)
+    /// Run simulations from `game` until the budget runs out.
+    fn search(&mut self, game: &Game) {
+        if let Some((threads, tree_parallel_search)) = self.tree_parallelism {
+            return tree_parallel_search(self, game, threads);
+        }
+        let start = self.clock.now();
+        let elapsed = |clock: &dyn Clock| clock.now().checked_sub(start).unwrap_or_default();
+        let mut iterations = 0;
+        let mut last_report = start;
+        // How much of the budget has been spent, as a fraction.
+        let spent = |iterations: u32, elapsed: Duration, budget: SearchBudget| match budget {
+            SearchBudget::Iterations(0) => Some(f64::INFINITY),
+            SearchBudget::Iterations(n) => Some(iterations as f64 / n as f64),
+            SearchBudget::Time(duration) if duration == Duration::default() => Some(f64::INFINITY),
+            SearchBudget::Time(duration) => Some(elapsed.as_secs_f64() / duration.as_secs_f64()),
+            SearchBudget::UntilStopped => None,
+        };
+        let mut kl_snapshot: Option<Vec<u32>> = None;
+        if let Some(ref mut trace) = self.trace {
+            trace.search_started();
+        }
+        loop {
+            let mut children = if self.adaptive_budget.is_some() || self.early_stop { self.root_children(game) } else { Vec::new() };
+            children.sort_by_key(|child| ::std::cmp::Reverse(child.visits));
+            let elapsed_now = elapsed(&*self.clock);
+            let exhausted = match (spent(iterations, elapsed_now, self.budget), self.adaptive_budget) {
+                (Some(spent), Some(adaptive)) => adaptive.should_stop(spent, self.time_bank, &children),
+                (Some(spent), None) => spent >= 1.0,
+                (None, _) => false,
+            };
+            let exhausted = exhausted || (self.early_stop && iterations > 0 && {
+                let limit = self.adaptive_budget.map_or(1.0, |adaptive| adaptive.limit(self.time_bank));
+                let remaining = match self.budget {
+                    SearchBudget::Iterations(n) => n as f64 * limit - iterations as f64,
+                    SearchBudget::Time(duration) => {
+                        let left = Duration::from_secs_f64(duration.as_secs_f64() * limit).checked_sub(elapsed_now).unwrap_or_default();
+                        stopping::simulations_in(left, iterations, elapsed_now)
+                    },
+                    SearchBudget::UntilStopped => f64::INFINITY,
+                };
+                stopping::lead_is_safe(&children, remaining)
+            });
+            let exhausted = exhausted || match self.kl_stop {
+                Some(kl) if kl.interval > 0 && iterations > 0 && iterations % kl.interval == 0 => {
+                    let visits: Vec<u32> = self.root_children(game).iter().map(|child| child.visits).collect();
+                    let settled = kl_snapshot.as_ref().is_some_and(|previous| kl.settled(previous, &visits));
+                    kl_snapshot = Some(visits);
+                    settled
+                },
+                _ => false,
+            };
+            let exhausted = exhausted || self.cancellation.is_cancelled();
+            // However small the budget, early the stop or soon the cancellation, there has to be
+            // a move to choose at the end
+            let exhausted = exhausted && self.root_expanded(game);
+            if exhausted {
+                break;
+            }
+            self.simulate(self.counters.clone_state(game));
+            iterations += 1;
+
+            let report_due = match self.progress {
+                Some(ProgressCallback { interval: ProgressInterval::Simulations(n), .. }) => n > 0 && iterations % n == 0,
+                Some(ProgressCallback { interval: ProgressInterval::Time(duration), .. }) => self.clock.now().checked_sub(last_report).unwrap_or_default() >= duration,
+                None => false,
+            };
+            if report_due {
+                last_report = self.clock.now();
+                let tree_depth = self.progress.as_ref().and_then(|progress| progress.tree_depth);
+                let progress = SearchProgress {
+                    elapsed: elapsed(&*self.clock),
+                    iterations,
+                    analysis: self.analysis(game),
+                    tree: tree_depth.map(|depth| self.export_tree(game, depth)),
+                };
+                if let Some(ref mut reporter) = self.progress {
+                    (reporter.callback)(&progress);
                 }
-                None => (game_move, std::f64::MAX)
             }
-        }).max_by_key(|&(a, x)| OrdF64::new(x)).map(|x| x.0)
+        }
+
+        if self.adaptive_budget.is_some() || self.early_stop || self.kl_stop.is_some() {
+            if let Some(spent) = spent(iterations, elapsed(&*self.clock), self.budget) {
+                self.time_bank = (self.time_bank + 1.0 - spent).max(0.0);
+            }
+        }
     }
 
-    fn is_leaf(&self) -> bool {
-        self.children.is_empty()
+    /// Run a single simulation: select and expand a node, then play randomly from there to the
+    /// end of the game (several times over, with leaf parallelism) and record the results against
+    /// every node on the way.
+    fn simulate(&mut self, game: Game) {
+        // Selection and expansion
+        let path = self.selection_and_expansion(game);
+        self.audit();
+
+        // Simulation
+        let leaf = path.last().expect("Selected an empty path");
+        let leaf_player = self.explored_states.get(leaf).expect("Dangling pointer!").player;
+        let tree_moves = match self.rollout_tables.settings.enabled() {
+            true => self.moves_along(&path),
+            false => Vec::new(),
+        };
+        let playouts = self.playouts(leaf, leaf_player, &tree_moves, path.len() - 1);
+        for playout in playouts.iter() {
+            self.rollout_tables.record(&playout.moves, playout.rewards);
+        }
+        if self.trace.is_some() {
+            let edges: Vec<u32> = path.iter().zip(self.moves_along(&path)).map(|(parent, (player, game_move))| {
+                parent.all_legal_moves(player).position(|m| m == game_move).expect("Tree move wasn't legal") as u32
+            }).collect();
+            let playout_traces: Vec<PlayoutTrace> = playouts.iter().map(|playout| PlayoutTrace { length: playout.length, rewards: playout.rewards }).collect();
+            if let Some(ref mut trace) = self.trace {
+                trace.simulation(&edges, &playout_traces);
+            }
+        }
+
+        // Backpropagation
+        for (depth, state) in path.iter().enumerate() {
+            let node = self.explored_states.get(state).expect("Dangling pointer!");
+            for playout in playouts.iter() {
+                node.stats.add(self.backed_up_reward(playout, node.player, depth));
+            }
+        }
     }
-}
 
-#[derive(Debug)]
-pub struct MonteCarloTreeSearchPlayer<Game: game::GameState> {
-    player: game::PlayerEnum,
-    c: f64,
-    explored_states: HashMap<Game, Node<Game>>,
-    last_turn: Option<Game>,
-}
+    /// Play out from `leaf`, once or (with leaf parallelism) several times over. See `playout`.
+    fn playouts(&self, leaf: &Game, leaf_player: game::PlayerEnum, tree_moves: &[(game::PlayerEnum, <Game as game::GameState>::Move)], depth: usize) -> Vec<Playout<<Game as game::GameState>::Move>> {
+        match self.leaf_parallelism {
+            Some((count, parallel_playouts)) => parallel_playouts(self, leaf, leaf_player, tree_moves, depth, count),
+            None => vec![self.playout(leaf, leaf_player, tree_moves, depth)],
+        }
+    }
 
-impl<Game: game::GameState> MonteCarloTreeSearchPlayer<Game> {
-    pub fn new(player: game::PlayerEnum, c: f64) -> Self {
-        Self {
-            player,
-            c,
-            explored_states: HashMap::new(),
-            last_turn: None,
+    /// The reward `playout` gives a node `depth` moves into the tree with `player` to move, for
+    /// the player who moved into it.
+    fn backed_up_reward(&self, playout: &Playout<<Game as game::GameState>::Move>, player: game::PlayerEnum, depth: usize) -> f64 {
+        let discount = self.discount.powi((playout.length - depth as u32) as i32);
+        let reward = match player.other() {
+            game::PlayerEnum::One => playout.rewards.0,
+            game::PlayerEnum::Two => playout.rewards.1,
+        };
+        game::value::towards_draw(reward, discount)
+    }
+
+    /// Play a simulation out from `leaf`, which is `depth` moves into the tree and was reached by
+    /// `tree_moves` (if the rollout tables need them).
+    fn playout(&self, leaf: &Game, leaf_player: game::PlayerEnum, tree_moves: &[(game::PlayerEnum, <Game as game::GameState>::Move)], depth: usize) -> Playout<<Game as game::GameState>::Move> {
+        let mut moves = tree_moves.to_vec();
+        let (final_state, conclusion, rollout_length) = rollout(self.counters.clone_state(leaf), leaf_player, &mut moves, self.rollout_limits, &self.rollout_tables, &*self.rollout_policy, &self.cancellation);
+        self.counters.rollout_plies(rollout_length);
+        let length = depth as u32 + rollout_length;
+        let rewards = match (conclusion, &self.cutoff_heuristic) {
+            (Some(conclusion), _) => {
+                let reward_for = |player| self.reward_model.reward(&final_state, conclusion, length, player);
+                (reward_for(game::PlayerEnum::One), reward_for(game::PlayerEnum::Two))
+            },
+            (None, Some(heuristic)) => self.cutoff_mapping.rewards((heuristic.evaluate(&final_state, game::PlayerEnum::One), heuristic.evaluate(&final_state, game::PlayerEnum::Two))),
+            (None, None) => (game::value::DRAW, game::value::DRAW),
+        };
+
+        Playout {
+            length,
+            rewards,
+            moves,
+        }
+    }
+
+    /// What's known about playing `game_move` from two states before the end of `path`.
+    fn grandparent_sibling(&self, path: &[Arc<Game>], game_move: &<Game as game::GameState>::Move) -> Option<Prior> {
+        let grandparent = path.len().checked_sub(2).map(|i| &path[i])?;
+        let sibling = self.explored_states.get(grandparent)?.children.get(game_move)?;
+        let sibling = self.explored_states.get(sibling).expect("Dangling pointer!");
+        Some(Prior {
+            visits: sibling.visits() as f64,
+            total_reward: sibling.stats.total_reward(),
+        })
+    }
+
+    /// The moves (and who played them) between each state on a path through the tree.
+    fn moves_along(&self, path: &[Arc<Game>]) -> Vec<(game::PlayerEnum, <Game as game::GameState>::Move)> {
+        path.windows(2).map(|pair| {
+            let parent = self.explored_states.get(&pair[0]).expect("Dangling pointer!");
+            let game_move = parent.children.iter().find(|&(_, child)| *child == pair[1]).expect("Path isn't connected").0;
+            (parent.player, *game_move)
+        }).collect()
+    }
+
+    /// Number of explored game states. This is cheaper than `tree_size`.
+    pub fn node_count(&self) -> usize {
+        self.explored_states.len()
+    }
+
+    /// Report how many nodes and edges the search tree holds, and roughly how much memory that
+    /// takes up.
+    pub fn tree_size(&self) -> TreeSizeReport {
+        // Each hash map entry costs roughly its key, its value and a control byte. Links kept inline
+        // are already counted in the size of the node. Each state is stored once, along with its
+        // reference counts.
+        let link_bytes = std::mem::size_of::<<Game as game::GameState>::Move>() + std::mem::size_of::<Arc<Game>>() + 1;
+        let node_bytes = std::mem::size_of::<Arc<Game>>() + std::mem::size_of::<Node<Game>>() + 1;
+        let state_bytes = std::mem::size_of::<Game>() + 2 * std::mem::size_of::<usize>();
+
+        let mut edges = 0;
+        let mut approx_bytes = self.explored_states.capacity() * node_bytes + self.explored_states.len() * state_bytes;
+        for node in self.explored_states.values() {
+            edges += node.children.len();
+            approx_bytes += (node.children.heap_capacity() + node.parents.heap_capacity()) * link_bytes;
+        }
+
+        TreeSizeReport {
+            nodes: self.explored_states.len(),
+            edges,
+            approx_bytes,
+        }
+    }
+
+    /// List the moves available from `root` whose subtrees hold the most nodes, heaviest first.
+    ///
+    /// At most `count` moves are returned. Subtrees can share nodes (the same game state can be
+    /// reached by different orders of moves), so the sizes may add up to more than the tree size.
+    pub fn heaviest_subtrees(&self, root: &Game, count: usize) -> Vec<(<Game as game::GameState>::Move, usize)> {
+        let root_node = match self.explored_states.get(root) {
+            Some(x) => x,
+            None => return Vec::new(),
+        };
+
+        let mut subtrees: Vec<_> = root_node.children.iter().map(|(m, child)| {
+            (*m, self.subtree_size(child))
+        }).collect();
+        subtrees.sort_by_key(|x| ::std::cmp::Reverse(x.1));
+        subtrees.truncate(count);
+        subtrees
+    }
+
+    /// Count the distinct nodes reachable from (and including) `state`.
+    fn subtree_size(&self, state: &Game) -> usize {
+        self.reachable_states(state).len()
+    }
+
+    /// Find all the known states reachable from (and including) `state`.
+    fn reachable_states<'a>(&'a self, state: &'a Game) -> HashSet<&'a Game> {
+        let mut seen = HashSet::new();
+        let mut to_visit = vec![state];
+        while let Some(state) = to_visit.pop() {
+            if !seen.insert(state) {
+                continue;
+            }
+            if let Some(node) = self.explored_states.get(state) {
+                to_visit.extend(node.children.values().map(|child| &**child));
+            }
         }
+        seen
+    }
+
+    /// Only collect garbage automatically once the tree holds more than `threshold` nodes. Pass
+    /// `None` to turn automatic collection off (the default).
+    pub fn set_garbage_collection_threshold(&mut self, threshold: Option<usize>) {
+        self.gc_threshold = threshold;
+    }
+
+    /// Remember the evaluations of up to `capacity` positions after they're pruned from the tree,
+    /// and start new nodes for those positions from what was remembered (in place of the
+    /// expansion policy's prior). This helps in games where positions can be reached by
+    /// different orders of moves. Pass `None` to turn this off (the default).
+    pub fn set_evaluation_cache(&mut self, capacity: Option<usize>) {
+        self.evaluation_cache = capacity.map(EvaluationCache::new);
+    }
+
+    /// How well the evaluation cache is doing, if there is one.
+    pub fn evaluation_cache_stats(&self) -> Option<CacheStats> {
+        self.evaluation_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Free every node that can't be reached from `root`.
+    ///
+    /// Pruning after each move only removes what it can prove is unreachable, and can leave whole
+    /// components behind. This does a full sweep instead, so it's slower but never leaks. Returns
+    /// the number of nodes freed.
+    pub fn collect_garbage(&mut self, root: &Game) -> usize {
+        let reachable: HashSet<Game> = self.reachable_states(root).into_iter().cloned().collect();
+        let nodes_before = self.explored_states.len();
+
+        let cache = &mut self.evaluation_cache;
+        self.explored_states.retain(|state, node| {
+            let keep = reachable.contains(&**state);
+            if let (false, Some(cache)) = (keep, cache.as_mut()) {
+                cache.store(state, node);
+            }
+            keep
+        });
+        for node in self.explored_states.values_mut() {
+            node.parents.retain(|parent, _| reachable.contains(&**parent));
+        }
+
+        self.audit();
+        nodes_before - self.explored_states.len()
     }
 
     /// Check that the following laws are obeyed
@@ -190,21 +1047,24 @@ impl<Game: game::GameState> MonteCarloTreeSearchPlayer<Game> {
 //        }
     }
 
-    fn remove_tree(&mut self, game_state: Game) {
+    fn remove_tree(&mut self, game_state: Arc<Game>) {
         // Remove this node
         let node = match self.explored_states.remove(&game_state) {
             Some(x) => x,
             None => return
         };
+        if let Some(ref mut cache) = self.evaluation_cache {
+            cache.store(&game_state, &node);
+        }
 
         // Remove node as child from all parents (... should be none)
-        for (m, parent) in node.parents.iter() {
-            self.explored_states.get_mut(parent).expect("Dangling pointer").children.remove(m);
+        for parent in node.parents.keys() {
+            self.explored_states.get_mut(parent).expect("Dangling pointer").children.retain(|_, child| **child != *game_state);
         }
 
         // Remove node as parent from all children
-        for (m, child) in node.children.iter() {
-            self.explored_states.get_mut(child).expect("Dangling pointer").parents.remove(m);
+        for child in node.children.values() {
+            self.explored_states.get_mut(child).expect("Dangling pointer").parents.remove(&game_state);
         }
 
         // Iterate into orphans
@@ -223,7 +1083,7 @@ impl<Game: game::GameState> MonteCarloTreeSearchPlayer<Game> {
     /// This is allowed to be pretty slow, as we only do this once.
     fn pruning(&mut self, current_state: Option<Game>, game_move: &<Game as game::GameState>::Move) {
         let current_state = match current_state {
-            Some(x) => x,
+            Some(x) => Arc::new(x),
             None => return
         };
 
@@ -232,15 +1092,18 @@ impl<Game: game::GameState> MonteCarloTreeSearchPlayer<Game> {
             Some(x) => x,
             None => return
         };
+        if let Some(ref mut cache) = self.evaluation_cache {
+            cache.store(&current_state, &current_node);
+        }
 
         // Remove self as child from all parents (... should be none)
-        for (m, parent) in current_node.parents.iter() {
-            self.explored_states.get_mut(parent).expect("Dangling pointer").children.remove(m);
+        for parent in current_node.parents.keys() {
+            self.explored_states.get_mut(parent).expect("Dangling pointer").children.retain(|_, child| **child != *current_state);
         }
 
         // Remove self as parent from all children
-        for (m, child) in current_node.children.iter() {
-            self.explored_states.get_mut(child).expect("Dangling pointer").parents.remove(m);
+        for child in current_node.children.values() {
+            self.explored_states.get_mut(child).expect("Dangling pointer").parents.remove(&current_state);
         }
 
         // Remove any unrealized children who are now orphans. Hopefully, if our pruning is good,
@@ -256,7 +1119,7 @@ impl<Game: game::GameState> MonteCarloTreeSearchPlayer<Game> {
         }
     }
 
-    /// Select the next node to look at.
+    /// Select the next node to look at, returning the path of states leading to it.
     ///
     /// Starting with the current game state, do the following:
     ///
@@ -264,109 +1127,495 @@ impl<Game: game::GameState> MonteCarloTreeSearchPlayer<Game> {
     /// 2) Choose one of its legal moves using the uct value
     /// 3) If the move corresponds to a child, then repeat from step 2 for that child. Otherwise,
     ///    create a node for that child and select it.
-    fn selection_and_expansion(&mut self, game: Game) -> Game {
-        let mut current_parent: Option<(<Game as game::GameState>::Move, Game)> = None;
-        let mut current_state = game;
+    ///
+    /// Selection also stops at states where the game is over, and if it comes back round to a
+    /// state already on the path.
+    fn selection_and_expansion(&mut self, game: Game) -> Vec<Arc<Game>> {
+        let mut path: Vec<Arc<Game>> = Vec::new();
+        let mut current_parent: Option<(<Game as game::GameState>::Move, Arc<Game>)> = None;
+        let mut current_state = self.intern(game);
         let mut current_player = self.player;
+        let mut forced_plies = 0;
 
         loop {
+            self.counters.selection_step();
+            if path.contains(&current_state) {
+                return path;
+            }
+
             // Create the current state, if it doesn't already exist.
-            if self.explored_states.get(&current_state).is_none() {
-                self.explored_states.insert(current_state.clone(), Node::new(current_player, current_parent.clone()));
+            if !self.explored_states.contains_key(&current_state) {
+                self.counters.expansion();
+                let mut node = Node::new(current_player, current_parent.clone());
+                let cached = self.evaluation_cache.as_mut().and_then(|cache| cache.lookup(&current_state, current_player));
+                if cached.is_some() {
+                    node.prior = cached;
+                } else if let Some((ref game_move, ref parent)) = current_parent {
+                    node.prior = self.expansion_policy.prior_for(parent, game_move, current_player.other(), self.grandparent_sibling(&path, game_move));
+                }
+                self.explored_states.insert(current_state.clone(), node);
             } else {
-                match current_parent.clone() {
-                    Some((game_move, parent)) => {
-                        self.explored_states.get_mut(&current_state).unwrap().parents.insert(game_move, parent);
-                    },
-                    None => ()
+                self.counters.cache_hit();
+                if let Some((game_move, parent)) = current_parent.clone() {
+                    self.explored_states.get_mut(&current_state).unwrap().parents.insert(parent, game_move);
                 }
             }
 
             // Make sure that the parent points to this move
-            match current_parent {
-                Some((game_move, state)) => {
-                    self.explored_states.get_mut(&state).expect("Blah").children.insert(game_move, current_state.clone());
-                }
-                _ => ()
+            if let Some((game_move, state)) = current_parent {
+                self.explored_states.get_mut(&state).expect("Blah").children.insert(game_move, current_state.clone());
             }
 
-            // If this is a leaf with 0 attempts, or there are no legal moves, use this. Else choose a legal move.
-            let chosen_move = {
-                let mut current_node = self.explored_states.get(&current_state).unwrap();
+            path.push(current_state.clone());
+
+            // If this has never been simulated from, or the game is over, use this. Else choose a legal move.
+            let (chosen_move, known_child) = {
+                let current_node = self.explored_states.get(&current_state).unwrap();
 
-                if current_node.is_leaf() && current_node.local_attempts == 0 {
-                    return current_state;
+                if current_state.try_conclude(current_player).is_some() {
+                    return path;
                 }
 
-                let chosen_move = current_node.choose_move_by_uct_value(self.c, &current_state, &self.explored_states);
+                let at_root = path.len() == 1;
+                let chosen_move = if current_node.visits() == 0 {
+                    match self.forced_expansion {
+                        Some(max_plies) if forced_plies < max_plies && !at_root => {
+                            forced_plies += 1;
+                            forcing_move(&*current_state, current_player)
+                        },
+                        _ => None,
+                    }
+                } else {
+                    current_node.choose_move_by_uct_value(self.c, &current_state, &self.explored_states, |m| !at_root || self.searched_at_root(m))
+                };
 
                 match chosen_move {
-                    Some(chosen_move) => chosen_move,
-                    None => return current_state,
+                    Some(chosen_move) => (chosen_move, current_node.children.get(&chosen_move).cloned()),
+                    None => return path,
                 }
             };
 
-            // Got a new move, iterate down
-            current_parent = Some((chosen_move, current_state.clone()));
-            current_state.update(chosen_move, current_player);
+            // Got a new move, iterate down. Only make the new state if it's not already linked.
+            let next_state = match known_child {
+                Some(child) => child,
+                None => {
+                    let mut next_state = self.counters.clone_state(&*current_state);
+                    next_state.update(chosen_move, current_player);
+                    self.intern(next_state)
+                }
+            };
+            current_parent = Some((chosen_move, current_state));
+            current_state = next_state;
             current_player = current_player.other();
         }
     }
+
+    /// The tree's shared copy of `state`, or a new one if it's not in the tree.
+    fn intern(&self, state: Game) -> Arc<Game> {
+        match self.explored_states.get_key_value(&state) {
+            Some((shared, _)) => shared.clone(),
+            None => Arc::new(state),
+        }
+    }
 }
 
-impl<Game: game::GameState> game::Player<Game> for MonteCarloTreeSearchPlayer<Game> {
-    fn choose_move(&mut self, game: Game) -> <Game as game::GameState>::Move {
-        // FIXME: time based rather than fixed number of searches.
-        for _ in 1..100 {
-            // selection and expansion
-            let state_to_explore = self.selection_and_expansion(game.clone());
-            self.audit();
-
-            let node_to_explore = self.explored_states.get_mut(&state_to_explore).expect("Dangling pointer!");
-
-            // Simulation and backpropogation
-            let mut state = state_to_explore;
-            let mut player = game::RandomPlayer(node_to_explore.player);
-            loop {
-                let current_player = player.0;
-
-                match (state.try_conclude(current_player), node_to_explore.player) {
-                    (Some(game::Conclusion::Win(game::PlayerEnum::One)), game::PlayerEnum::One) | (Some(game::Conclusion::Win(game::PlayerEnum::Two)), game::PlayerEnum::Two) => {
-                        node_to_explore.local_wins += 1;
-                        node_to_explore.local_attempts += 1;
-                        break;
-                    }
-                    (Some(game::Conclusion::Win(_)), _) => {
-                        node_to_explore.local_losses += 1;
-                        node_to_explore.local_attempts += 1;
-                        break;
-                    }
-                    (Some(game::Conclusion::Draw), _) => {
-                        // FIXME: count draws as neither win nor loss???
-                        node_to_explore.local_attempts += 1;
-                        break;
-                    }
-                    (None, _) => ()
-                }
+/// One of `player`'s forcing moves in `state`, picked at random, if there are any.
+fn forcing_move<Game: game::GameState>(state: &Game, player: game::PlayerEnum) -> Option<<Game as game::GameState>::Move> {
+    let forcing: Vec<_> = state.all_legal_moves(player).filter(|game_move| state.is_forcing(game_move, player)).collect();
+    if forcing.is_empty() {
+        return None;
+    }
+    let index = ((random_f64() * forcing.len() as f64) as usize).min(forcing.len() - 1);
+    Some(forcing[index])
+}
 
-                state.update_with_closure(|state| player.choose_move(state.clone()), current_player);
-                player = game::RandomPlayer(current_player.other());
+#[cfg(feature = "parallel")]
+impl<Game: game::GameState + Send + Sync, S: BuildHasher + Default + Send + Sync> MonteCarloTreeSearchPlayer<Game, S> where <Game as game::GameState>::Move: Send + Sync {
+    /// Play out `count` simulations in parallel from each leaf the search selects, on rayon's
+    /// global thread pool, and backpropagate them together. The search budget still counts
+    /// leaves, so each iteration does `count` times the work. A `count` of 1 turns this off.
+    pub fn set_leaf_parallelism(&mut self, count: u32) {
+        self.leaf_parallelism = match count {
+            0 | 1 => None,
+            count => Some((count, parallel_playouts::<Game, S>)),
+        };
+    }
+
+    /// Search with `threads` threads sharing one tree, each selecting, expanding, playing out and
+    /// backpropagating by itself, with virtual losses to keep them from all searching the same
+    /// line. The search budget counts simulations across all the threads. A `threads` of 1 turns
+    /// this off. This can be combined with leaf parallelism.
+    ///
+    /// The threads come from rayon's global pool, or the player's own (see `set_search_threads`),
+    /// so only as many as the pool has run at once. Each search copies what's known below the root
+    /// into a tree built for sharing between threads, and copies the results back after.
+    /// Adaptive budgets, early and KL stopping, forced expansion, the evaluation cache, the rollout
+    /// enhancements' tables, progress reports and traces only work without tree parallelism.
+    pub fn set_tree_parallelism(&mut self, threads: u32) {
+        self.tree_parallelism = match threads {
+            0 | 1 => None,
+            threads => Some((threads, tree_parallel_search::<Game, S>)),
+        };
+    }
+
+    /// Run the parallel playouts on threads of the player's own, pinned to cores as `settings`
+    /// say, rather than on rayon's global pool. This is for big machines with several sockets,
+    /// where threads wandering between sockets lose time fetching memory from the other socket.
+    /// Pass `None` to go back to the global pool. See `search_stats` for how the threads turned out.
+    pub fn set_search_threads(&mut self, settings: Option<ThreadSettings>) -> Result<(), String> {
+        self.search_threads = match settings {
+            Some(settings) => Some(threads::SearchThreads::new(settings)?),
+            None => None,
+        };
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<Game: game::GameState + Send + Sync, S: BuildHasher + Default + Send + Sync> MonteCarloTreeSearchPlayer<Game, S> where <Game as game::GameState>::Move: Send + Sync {
+    /// Copy what's known below `game` into `tree`, returning the root's node there.
+    fn copy_to_concurrent_tree(&self, tree: &concurrent::ConcurrentTree<Game, S>, game: &Game) -> NodeId {
+        let reachable: Vec<(&Arc<Game>, &Node<Game>)> = self.reachable_states(game).into_iter()
+            .filter_map(|state| self.explored_states.get_key_value(state))
+            .collect();
+        for &(state, node) in reachable.iter() {
+            tree.insert(ConcurrentNode::new(state.clone(), node.player, node.stats.clone(), node.prior));
+        }
+        for &(state, node) in reachable.iter() {
+            let parent = tree.find(state).expect("Dangling pointer");
+            for (&game_move, child) in node.children.iter() {
+                tree.link(parent, game_move, tree.find(child).expect("Dangling pointer"));
             }
         }
+        match tree.find(game) {
+            Some(root) => root,
+            None => tree.insert(ConcurrentNode::new(Arc::new(game.clone()), self.player, NodeStats::default(), None)).0,
+        }
+    }
+
+    /// Copy the results of a tree-parallel search back into the player's tree.
+    fn copy_from_concurrent_tree(&mut self, tree: &concurrent::ConcurrentTree<Game, S>) {
+        for (_, node) in tree.nodes() {
+            let stats = node.stats.clone();
+            match self.explored_states.get_mut(&*node.state) {
+                Some(existing) => {
+                    existing.stats = stats;
+                    existing.prior = node.prior;
+                },
+                None => {
+                    let mut new = Node::new(node.player, None);
+                    new.stats = stats;
+                    new.prior = node.prior;
+                    self.explored_states.insert(node.state.clone(), new);
+                },
+            }
+        }
+        let guard = crossbeam_epoch::pin();
+        for (_, node) in tree.nodes() {
+            for &(game_move, child) in node.children(&guard) {
+                let child = &tree.node(child).state;
+                self.explored_states.get_mut(&*node.state).expect("Dangling pointer").children.insert(game_move, child.clone());
+                self.explored_states.get_mut(&**child).expect("Dangling pointer").parents.insert(node.state.clone(), game_move);
+            }
+        }
+    }
+
+    /// Whether a tree-parallel search has found a move to choose at `root`. See `root_expanded`.
+    fn concurrent_root_expanded(&self, tree: &concurrent::ConcurrentTree<Game, S>, root: NodeId) -> bool {
+        let root = tree.node(root);
+        self.nothing_to_search(&root.state) || root.children(&crossbeam_epoch::pin()).iter().any(|(m, _)| self.searched_at_root(m))
+    }
+
+    /// One thread's simulation in a tree-parallel search, from selection to backpropagation.
+    fn concurrent_simulation(&self, tree: &concurrent::ConcurrentTree<Game, S>, root: NodeId) {
+        let path = self.concurrent_selection_and_expansion(tree, root);
+
+        let leaf = tree.node(*path.last().expect("Selected an empty path"));
+        let playouts = self.playouts(&leaf.state, leaf.player, &[], path.len() - 1);
+
+        for (depth, &id) in path.iter().enumerate() {
+            let node = tree.node(id);
+            for playout in playouts.iter() {
+                node.stats.add(self.backed_up_reward(playout, node.player, depth));
+            }
+            node.remove_virtual_loss();
+        }
+    }
+
+    /// Select a path from `root` down to a node which hasn't been simulated from (or where the
+    /// game is over), adding a virtual loss to each node on it. The same as
+    /// `selection_and_expansion`, but for any number of threads at once.
+    fn concurrent_selection_and_expansion(&self, tree: &concurrent::ConcurrentTree<Game, S>, root: NodeId) -> Vec<NodeId> {
+        let guard = crossbeam_epoch::pin();
+        let mut path = vec![root];
+        tree.node(root).add_virtual_loss();
+        loop {
+            self.counters.selection_step();
+            let current = *path.last().expect("Selected an empty path");
+            let node = tree.node(current);
+            if node.stats.visits() == 0 || node.state.try_conclude(node.player).is_some() {
+                return path;
+            }
+
+            let at_root = path.len() == 1;
+            let chosen_move = match node.choose_move_by_uct_value(self.c, tree, &guard, |m| !at_root || self.searched_at_root(m)) {
+                Some(chosen_move) => chosen_move,
+                None => return path,
+            };
+            let child = match node.child(&chosen_move, &guard) {
+                Some(child) => {
+                    self.counters.cache_hit();
+                    child
+                },
+                None => {
+                    let mut next_state = self.counters.clone_state(&*node.state);
+                    next_state.update(chosen_move, node.player);
+                    let child = match tree.find(&next_state) {
+                        Some(child) => {
+                            self.counters.cache_hit();
+                            child
+                        },
+                        None => {
+                            let grandparent_sibling = path.len().checked_sub(2)
+                                .and_then(|i| tree.node(path[i]).child(&chosen_move, &guard))
+                                .map(|sibling| Prior {
+                                    visits: tree.node(sibling).stats.visits() as f64,
+                                    total_reward: tree.node(sibling).stats.total_reward(),
+                                });
+                            let prior = self.expansion_policy.prior_for(&node.state, &chosen_move, node.player, grandparent_sibling);
+                            let (child, added) = tree.insert(ConcurrentNode::new(Arc::new(next_state), node.player.other(), NodeStats::default(), prior));
+                            match added {
+                                true => self.counters.expansion(),
+                                false => self.counters.cache_hit(),
+                            }
+                            child
+                        },
+                    };
+                    tree.link(current, chosen_move, child)
+                },
+            };
+
+            if path.contains(&child) {
+                return path;
+            }
+            tree.node(child).add_virtual_loss();
+            path.push(child);
+        }
+    }
+}
+
+/// Search `game` with `threads` threads sharing a concurrent tree. See `set_tree_parallelism`.
+#[cfg(feature = "parallel")]
+fn tree_parallel_search<Game: game::GameState + Send + Sync, S: BuildHasher + Default + Send + Sync>(
+    player: &mut MonteCarloTreeSearchPlayer<Game, S>,
+    game: &Game,
+    threads: u32,
+) where <Game as game::GameState>::Move: Send + Sync {
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    let mut tree = player.concurrent_tree.take().unwrap_or_default();
+    let root = player.copy_to_concurrent_tree(&tree, game);
+    {
+        let player = &*player;
+        let start = player.clock.now();
+        let simulations = AtomicU32::new(0);
+        let search = || (0..threads).into_par_iter().for_each(|_| loop {
+            let simulation = simulations.fetch_add(1, Ordering::Relaxed);
+            let exhausted = player.cancellation.is_cancelled() || match player.budget {
+                SearchBudget::Iterations(n) => simulation >= n,
+                SearchBudget::Time(duration) => player.clock.now().checked_sub(start).unwrap_or_default() >= duration,
+                SearchBudget::UntilStopped => false,
+            };
+            // As in the ordinary search, there has to be a move to choose at the end
+            if exhausted && player.concurrent_root_expanded(&tree, root) {
+                break;
+            }
+            player.concurrent_simulation(&tree, root);
+        });
+        match player.search_threads {
+            Some(ref threads) => threads.install(search),
+            None => search(),
+        }
+    }
+    player.copy_from_concurrent_tree(&tree);
+    tree.clear();
+    player.concurrent_tree = Some(tree);
+}
+
+#[cfg(feature = "parallel")]
+fn parallel_playouts<Game: game::GameState + Send + Sync, S: BuildHasher + Default + Send + Sync>(
+    player: &MonteCarloTreeSearchPlayer<Game, S>,
+    leaf: &Game,
+    leaf_player: game::PlayerEnum,
+    tree_moves: &[(game::PlayerEnum, <Game as game::GameState>::Move)],
+    depth: usize,
+    count: u32,
+) -> Vec<Playout<<Game as game::GameState>::Move>> where <Game as game::GameState>::Move: Send + Sync {
+    use rayon::prelude::*;
+
+    let playouts = || (0..count).into_par_iter().map(|_| player.playout(leaf, leaf_player, tree_moves, depth)).collect();
+    match player.search_threads {
+        Some(ref threads) => threads.install(playouts),
+        None => playouts(),
+    }
+}
+
+/// Play from `state` by the rollout policy, with `player` to move, until the game ends (including
+/// by the game's draw rules) or a limit is hit. Also returns how many moves that took. The
+/// conclusion is `None` if the simulation was cut off.
+///
+/// If the rollout enhancements are on, `moves` are the moves that led to `state`, and the moves
+/// played are added to them. Cancelling `cancellation` cuts the simulation off.
+///
+/// The draw rules only see the moves made in this simulation, not how the game got here.
+fn rollout<Game: game::GameState>(
+    mut state: Game,
+    mut player: game::PlayerEnum,
+    moves: &mut Vec<(game::PlayerEnum, <Game as game::GameState>::Move)>,
+    limits: RolloutLimits,
+    tables: &RolloutTables<<Game as game::GameState>::Move>,
+    policy: &dyn game::RolloutPolicy<Game>,
+    cancellation: &game::CancellationToken,
+) -> (Game, Option<game::Conclusion>, u32) {
+    let mut previous = moves.last().map(|x| x.1);
+    let mut length = 0;
+    let record_moves = tables.settings.enabled();
+    let mut seen = HashSet::new();
+    let mut draw_tracker = game::DrawTracker::new(&state, player);
+    loop {
+        if let Some(conclusion) = draw_tracker.try_conclude(&state, player) {
+            return (state, Some(conclusion), length);
+        }
+        if limits.max_depth.is_some_and(|max_depth| length >= max_depth) || cancellation.is_cancelled() {
+            return (state, None, length);
+        }
+        if limits.detect_cycles && !seen.insert((state.clone(), player)) {
+            return (state, None, length);
+        }
+
+        let game_move = tables.choose_move(&state, player, previous, policy).expect("There were no legal moves");
+        draw_tracker.play(&mut state, game_move, player);
+        if record_moves {
+            moves.push((player, game_move));
+        }
+        previous = Some(game_move);
+        player = player.other();
+        length += 1;
+    }
+}
+
+impl<Game: game::GameState, S: BuildHasher + Default> game::Player<Game> for MonteCarloTreeSearchPlayer<Game, S> {
+    fn choose_move(&mut self, game: Game) -> <Game as game::GameState>::Move {
+        let budget = self.budget;
+        self.budget = self.skill.scale_budget(budget);
+        self.search(&game);
+        self.budget = budget;
+
+        // Pick the child with the most simulations made (at full strength).
+        let policy = self.analysis(&game).policy;
+        let decision = self.skill.choose_move(&policy).expect("There were no legal moves");
+        let source = match (policy.len(), policy.first()) {
+            (1, _) => DecisionSource::OnlyMove,
+            (_, Some(&(best, _))) if best == decision => DecisionSource::Search,
+            _ => DecisionSource::Weakened,
+        };
+        self.last_explanation = Some(self.explain(&game, decision, &policy, source));
 
-        // Pick the child with the most simulations made.
         let current_node = self.explored_states.get(&game).expect("Bleh");
-        let decision = current_node.children.iter().map(|(m, child)| {
-            (m, self.explored_states.get(child).unwrap().attempts(&self.explored_states))
-        }).max_by_key(|&(m, x)| x).unwrap().0.clone();
+        let chosen_child = current_node.children.get(&decision).expect("Dangling pointer");
+        self.last_evaluation = self.explored_states.get(chosen_child).expect("Dangling pointer").value_for_parent();
 
-        println!("Made decision: {:?}.\n\n{:?}", decision, self);
         decision
     }
 
+    fn last_evaluation(&self) -> Option<f64> {
+        self.last_evaluation
+    }
+
     fn inform_of_move_played(&mut self, new_state: Game, game_move: &<Game as game::GameState>::Move) {
         let last_turn = self.last_turn.take();
-        self.last_turn = Some(new_state);
-        self.pruning(last_turn, game_move);
+        self.last_turn = Some(new_state.clone());
+        if Game::draw_rules().repetitions.is_some() {
+            // Positions can repeat, so the tree can have cycles, which pruning can't tell are
+            // unreachable. Sweep instead.
+            self.collect_garbage(&new_state);
+        } else {
+            self.pruning(last_turn, game_move);
+        }
+        self.rollout_tables.decay();
+        self.search_moves = None;
+        self.excluded_moves.clear();
+
+        match self.gc_threshold {
+            Some(threshold) if self.explored_states.len() > threshold => {
+                self.collect_garbage(&new_state);
+            },
+            _ => ()
+        }
     }
+
+    fn set_cancellation_token(&mut self, token: game::CancellationToken) {
+        self.cancellation = token;
+    }
+}
+
+/// Add the players defined in this crate to `registry`: "mcts", which takes the parameters `c`
+/// (exploration constant), and either `iterations` or `millis` (the search budget), and "hybrid",
+/// which takes `c`, `iterations` and `depth` (of the alpha-beta search checking its moves).
+pub fn register_players<Game: game::GameState>(registry: &mut game::PlayerRegistry<Game>) {
+    registry.register("mcts", |player, params| {
+        let c = game::registry::param_or(params, "c", 2f64.sqrt())?;
+        let mut mcts = MonteCarloTreeSearchPlayer::new(player, c);
+        mcts.set_discount(game::registry::param_or(params, "gamma", 1.0)?);
+        mcts.set_rollout_limits(RolloutLimits {
+            max_depth: match params.contains_key("max_depth") {
+                true => Some(game::registry::param_or(params, "max_depth", 0)?),
+                false => None,
+            },
+            detect_cycles: game::registry::param_or(params, "detect_cycles", false)?,
+        });
+        mcts.set_rollout_enhancements(RolloutEnhancements {
+            mast_temperature: match params.contains_key("mast") {
+                true => Some(game::registry::param_or(params, "mast", 1.0)?),
+                false => None,
+            },
+            last_good_reply: game::registry::param_or(params, "lgr", false)?,
+            decay: game::registry::param_or(params, "decay", 1.0)?,
+        });
+        if params.contains_key("forced") {
+            mcts.set_forced_expansion(Some(game::registry::param_or(params, "forced", 0)?));
+        }
+        if params.contains_key("grandparent_prior") {
+            mcts.set_expansion_policy(expansion::GrandparentPriors { max_visits: game::registry::param_or(params, "grandparent_prior", 0.0)? });
+        }
+        if params.contains_key("score_scale") {
+            mcts.set_reward_model(reward::ScoreMargin { scale: game::registry::param_or(params, "score_scale", 1.0)? });
+        }
+        mcts.set_skill_level(game::registry::param_or(params, "skill", SkillLevel::full())?);
+        match (params.contains_key("iterations"), params.contains_key("millis")) {
+            (true, true) => return Err("Give either iterations or millis, not both".to_string()),
+            (true, false) => mcts.set_budget(SearchBudget::Iterations(game::registry::param_or(params, "iterations", 0)?)),
+            (false, true) => mcts.set_budget(SearchBudget::Time(Duration::from_millis(game::registry::param_or(params, "millis", 0)?))),
+            (false, false) => (),
+        }
+        Ok(Box::new(mcts))
+    });
+    registry.register("hybrid", |player, params| {
+        let mut engine = MonteCarloTreeSearchPlayer::new(player, game::registry::param_or(params, "c", 2f64.sqrt())?);
+        engine.set_budget(SearchBudget::Iterations(game::registry::param_or(params, "iterations", 100)?));
+        let tactics = game::AlphaBetaPlayer::new(player, game::AlphaBetaSettings {
+            depth: game::registry::param_or(params, "depth", 4)?,
+            ..Default::default()
+        });
+        Ok(Box::new(hybrid::HybridPlayer::new(player, engine, tactics)))
+    });
+}
+
+/// Compile-time check that the player stays `Send + Sync` whenever the game it's playing is.
+#[allow(dead_code)]
+fn assert_send_sync<Game: game::GameState + Send + Sync>() where <Game as game::GameState>::Move: Send + Sync {
+    fn is_send_sync<T: Send + Sync>() {}
+    is_send_sync::<MonteCarloTreeSearchPlayer<Game>>();
+    is_send_sync::<game::SharedPlayer<MonteCarloTreeSearchPlayer<Game>>>();
 }
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{BuildHasher, BuildHasherDefault};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use expansion::Prior;
+use game;
+use small_map::SmallMap;
+
+/// The maps making up the tree. These use a fixed hasher (rather than a randomly keyed one), so
+/// that they're iterated in the same order every time and seeded searches are repeatable.
+pub type Map<K, V> = HashMap<K, V, DefaultState>;
+
+/// The default hasher for the tree: SipHash, with fixed keys.
+pub type DefaultState = BuildHasherDefault<DefaultHasher>;
+
+/// A node's links to its children, keyed by the move to the child. Room for a few is kept inline.
+pub type Children<Game> = SmallMap<<Game as game::GameState>::Move, Arc<Game>, [(<Game as game::GameState>::Move, Arc<Game>); 4]>;
+
+/// A node's links to its parents, keyed by the parent, with the move from it. (Keying by the move
+/// wouldn't do: in games with transpositions, the same move can lead here from different parents.)
+/// Nearly all nodes have just one.
+pub type Parents<Game> = SmallMap<Arc<Game>, <Game as game::GameState>::Move, [(Arc<Game>, <Game as game::GameState>::Move); 1]>;
+
+/// Rewards are stored in fixed point, with this many units per reward of 1. Rewards are at most
+/// 1, so this leaves room for 2^32 visits.
+const REWARD_SCALE: f64 = (1u64 << 32) as f64;
+
+/// A node's simulation statistics, which can be read and updated through a shared reference so
+/// that threads searching the same tree don't need to lock it.
+///
+/// Each counter is updated atomically, but separately, with relaxed ordering: a reader can see a
+/// visit before the reward that goes with it. That only ever makes the node look briefly worse
+/// than it is (much like a virtual loss), which is harmless to selection. Once the updating
+/// threads have been joined, the statistics are exact.
+#[derive(Default)]
+pub struct NodeStats {
+    visits: AtomicU32,
+    total_reward: AtomicU64,
+}
+
+impl NodeStats {
+    /// Statistics which already have `visits` simulations, with rewards adding up to
+    /// `total_reward`.
+    pub fn new(visits: u32, total_reward: f64) -> Self {
+        Self {
+            visits: AtomicU32::new(visits),
+            total_reward: AtomicU64::new((total_reward.max(0.0) * REWARD_SCALE) as u64),
+        }
+    }
+
+    /// Number of simulations which have passed through this node.
+    pub fn visits(&self) -> u32 {
+        self.visits.load(Ordering::Relaxed)
+    }
+
+    /// Sum of the rewards of those simulations.
+    pub fn total_reward(&self) -> f64 {
+        self.total_reward.load(Ordering::Relaxed) as f64 / REWARD_SCALE
+    }
+
+    /// Record a simulation with a reward between 0 and 1.
+    pub fn add(&self, reward: f64) {
+        self.visits.fetch_add(1, Ordering::Relaxed);
+        self.total_reward.fetch_add((reward.clamp(0.0, 1.0) * REWARD_SCALE) as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clone for NodeStats {
+    fn clone(&self) -> Self {
+        Self {
+            visits: AtomicU32::new(self.visits()),
+            total_reward: AtomicU64::new(self.total_reward.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// A game state which the search knows about.
+pub struct Node<Game: game::GameState> {
+    /// The player whose turn it is in this state.
+    pub player: game::PlayerEnum,
+    /// Simulations which have passed through this node. Rewards are for the player who moved
+    /// into this node (i.e. the opponent of `player`).
+    pub stats: NodeStats,
+    /// Made-up results from the expansion policy, counted towards the node's value.
+    pub prior: Option<Prior>,
+    /// Known children (some may be unknown)
+    pub children: Children<Game>,
+    /// Known parents - many may be unknown.
+    pub parents: Parents<Game>,
+}
+
+impl<Game: game::GameState> ::std::fmt::Debug for Node<Game> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "Node {{ player: {:?}, visits: {}, value: {:?}, children: {} }}", self.player, self.visits(), self.value_for_parent(), self.children.len())
+    }
+}
+
+impl<Game: game::GameState> Node<Game> {
+    pub fn new(player: game::PlayerEnum, parent: Option<(<Game as game::GameState>::Move, Arc<Game>)>) -> Self {
+        Self {
+            player,
+            stats: NodeStats::default(),
+            prior: None,
+            children: SmallMap::new(),
+            parents: {
+                let mut map = SmallMap::new();
+                if let Some((k, v)) = parent {
+                    map.insert(v, k);
+                }
+                map
+            },
+        }
+    }
+
+    pub fn visits(&self) -> u32 {
+        self.stats.visits()
+    }
+
+    /// The average reward of simulations through this node (including the prior), for the player
+    /// who moved into it. This is from 0 (always lost) to 1 (always won), or `None` if nothing is
+    /// known about it.
+    pub fn value_for_parent(&self) -> Option<f64> {
+        let visits = self.effective_visits();
+        if visits <= 0.0 {
+            return None;
+        }
+        let prior_reward = self.prior.map_or(0.0, |prior| prior.total_reward);
+        Some((self.stats.total_reward() + prior_reward) / visits)
+    }
+
+    /// Real visits plus the prior's made-up ones.
+    fn effective_visits(&self) -> f64 {
+        self.visits() as f64 + self.prior.map_or(0.0, |prior| prior.visits)
+    }
+
+    fn uct_value(&self, parent_visits: u32, c: f64) -> f64 {
+        let exploitation_value = match self.value_for_parent() {
+            Some(x) => x,
+            // If never explored, maximum exploration value
+            None => return f64::MAX,
+        };
+        let exploration_value = c * ( (parent_visits as f64).ln() / self.effective_visits() ).sqrt();
+
+        exploitation_value + exploration_value
+    }
+
+    /// The legal move with the best UCT value, out of those which pass `filter`.
+    pub fn choose_move_by_uct_value<F, S>(&self, c: f64, game: &Game, cache: &HashMap<Arc<Game>, Node<Game>, S>, filter: F) -> Option<<Game as game::GameState>::Move>
+        where F: Fn(&<Game as game::GameState>::Move) -> bool,
+              S: BuildHasher
+    {
+        #[derive(PartialEq)]
+        struct OrdF64(f64);
+
+        impl OrdF64 {
+            fn new(x: f64) -> Self {
+                if x.is_nan() {
+                    panic!("x is NAN");
+                }
+                OrdF64(x)
+            }
+        }
+
+        impl Eq for OrdF64 {}
+
+        impl Ord for OrdF64 {
+            fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+                self.0.partial_cmp(&other.0).expect("f64 could not be compared")
+            }
+        }
+
+        impl PartialOrd for OrdF64 {
+            fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        game.all_legal_moves(self.player).filter(|game_move| filter(game_move)).map(|game_move| {
+            // Try to find a child with this move
+            match self.children.get(&game_move) {
+                Some(child) => {
+                    let uct_value = cache.get(child).expect("Dangling pointer").uct_value(self.visits(), c);
+                    (game_move, uct_value)
+                }
+                None => (game_move, f64::MAX)
+            }
+        }).max_by_key(|&(_, x)| OrdF64::new(x)).map(|x| x.0)
+    }
+}
@@ -0,0 +1,131 @@
+//! Evaluating positions with an ONNX model in ONNX Runtime, through `ort`. The runtime is loaded
+//! when the first model is, from `ORT_DYLIB_PATH` if that's set and the library path otherwise.
+//!
+//! The model takes one input, a batch of positions shaped `[batch, ...]` (the encoder's shape
+//! after the batch size), and gives two outputs, in order: the policy, shaped
+//! `[batch, move_count]`, and the value, shaped `[batch]` or `[batch, 1]`, from 0 to 1 for the
+//! player to move.
+
+use std::fmt::{self, Debug};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use game::Features;
+use ort::session::Session;
+use ort::value::Tensor;
+
+use evaluator::{Evaluation, Evaluator};
+
+/// A model loaded into ONNX Runtime. Batches are run one at a time; to share a model between
+/// searches, wrap it in a `BatchingEvaluator`.
+pub struct OnnxEvaluator {
+    path: PathBuf,
+    session: Mutex<Session>,
+}
+
+impl Debug for OnnxEvaluator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OnnxEvaluator").field("path", &self.path).finish()
+    }
+}
+
+impl OnnxEvaluator {
+    /// Load the model at `path`. Panics if ONNX Runtime itself can't be loaded.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let session = Session::builder()
+            .and_then(|builder| builder.commit_from_file(&path))
+            .map_err(|e| format!("Couldn't load the model from {}: {}", path.display(), e))?;
+        if session.inputs.len() != 1 || session.outputs.len() != 2 {
+            return Err(format!(
+                "The model in {} has {} inputs and {} outputs, not one input and two outputs (policy and value)",
+                path.display(), session.inputs.len(), session.outputs.len()));
+        }
+        Ok(Self {
+            path,
+            session: Mutex::new(session),
+        })
+    }
+}
+
+impl Evaluator for OnnxEvaluator {
+    fn evaluate_batch(&self, inputs: &[Features]) -> Result<Vec<Evaluation>, String> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let input = Tensor::from_array(batch(inputs)?).map_err(|e| format!("Couldn't make the model's input: {}", e))?;
+        let mut session = self.session.lock().expect("Model session poisoned");
+        let outputs = session.run(ort::inputs![input]).map_err(|e| format!("The model failed: {}", e))?;
+        let (policy_shape, policy) = outputs[0].try_extract_tensor::<f32>()
+            .map_err(|e| format!("Couldn't read the model's policy: {}", e))?;
+        let (_, value) = outputs[1].try_extract_tensor::<f32>()
+            .map_err(|e| format!("Couldn't read the model's value: {}", e))?;
+        split_batch(inputs.len(), policy_shape, policy, value)
+    }
+}
+
+/// `inputs` as one tensor's shape and data, with the batch size first.
+fn batch(inputs: &[Features]) -> Result<(Vec<i64>, Vec<f32>), String> {
+    let shape = &inputs[0].shape;
+    let size: usize = shape.iter().product();
+    let mut data = Vec::with_capacity(size * inputs.len());
+    for input in inputs {
+        if input.shape != *shape || input.data.len() != size {
+            return Err(format!("Positions of different shapes ({:?} and {:?}) can't be batched", shape, input.shape));
+        }
+        data.extend_from_slice(&input.data);
+    }
+    let shape = Some(inputs.len()).into_iter().chain(shape.iter().cloned()).map(|n| n as i64).collect();
+    Ok((shape, data))
+}
+
+/// Split the model's outputs for a batch of `size` into an evaluation for each position.
+fn split_batch(size: usize, policy_shape: &[i64], policy: &[f32], value: &[f32]) -> Result<Vec<Evaluation>, String> {
+    let moves = match *policy_shape {
+        [batch, moves] if batch as usize == size => moves as usize,
+        _ => return Err(format!("The model's policy has shape {:?}, not [{}, moves]", policy_shape, size)),
+    };
+    if policy.len() != size * moves {
+        return Err(format!("The model's policy has {} entries, not {}", policy.len(), size * moves));
+    }
+    if value.len() != size {
+        return Err(format!("The model gave {} values for {} positions", value.len(), size));
+    }
+    Ok(value.iter().enumerate().map(|(i, &value)| Evaluation {
+        policy: policy[i * moves..(i + 1) * moves].to_vec(),
+        value,
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(shape: Vec<usize>, data: Vec<f32>) -> Features {
+        Features { shape, data }
+    }
+
+    #[test]
+    fn batches_positions_of_one_shape() {
+        let inputs = vec![features(vec![2, 1], vec![1.0, 2.0]), features(vec![2, 1], vec![3.0, 4.0])];
+        assert_eq!(batch(&inputs), Ok((vec![2, 2, 1], vec![1.0, 2.0, 3.0, 4.0])));
+
+        let mixed = vec![features(vec![2], vec![1.0, 2.0]), features(vec![1, 2], vec![3.0, 4.0])];
+        assert!(batch(&mixed).is_err());
+        let short = vec![features(vec![2], vec![1.0, 2.0]), features(vec![2], vec![3.0])];
+        assert!(batch(&short).is_err());
+    }
+
+    #[test]
+    fn splits_outputs_by_position() {
+        let evaluations = split_batch(2, &[2, 3], &[0.1, 0.2, 0.7, 0.5, 0.5, 0.0], &[0.9, 0.25]).unwrap();
+        assert_eq!(evaluations, vec![
+            Evaluation { policy: vec![0.1, 0.2, 0.7], value: 0.9 },
+            Evaluation { policy: vec![0.5, 0.5, 0.0], value: 0.25 },
+        ]);
+
+        assert!(split_batch(3, &[2, 3], &[0.0; 6], &[0.5; 3]).is_err());
+        assert!(split_batch(2, &[6], &[0.0; 6], &[0.5; 2]).is_err());
+        assert!(split_batch(2, &[2, 3], &[0.0; 6], &[0.5; 1]).is_err());
+    }
+}
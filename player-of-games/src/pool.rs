@@ -0,0 +1,205 @@
+//! A pool of nodes for the concurrent tree. Nodes are allocated in blocks, each twice the size of
+//! the last, so a search making millions of nodes allocates a few dozen times rather than millions,
+//! and a node never moves once it's made. Clearing the pool keeps the blocks, so the next search
+//! reuses them without allocating at all.
+//!
+//! Nodes are added through a shared reference, so that search threads can add them at the same
+//! time, but only dropped through a mutable one, when no search is running.
+
+use std::cell::UnsafeCell;
+use std::fmt::{self, Debug};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+/// Refers to a node in a pool.
+pub type NodeId = u32;
+
+/// The first block holds 2^FIRST_BLOCK_BITS nodes.
+const FIRST_BLOCK_BITS: u32 = 10;
+
+/// Enough blocks for every `NodeId`.
+const BLOCKS: usize = (32 - FIRST_BLOCK_BITS + 1) as usize;
+
+type Slot<T> = UnsafeCell<Option<T>>;
+
+pub struct NodePool<T> {
+    /// Block `k` holds `2^(FIRST_BLOCK_BITS + k)` nodes. Blocks are allocated when first needed,
+    /// and kept until the pool is dropped.
+    blocks: [AtomicPtr<Slot<T>>; BLOCKS],
+    /// How many nodes have been added since the pool was last cleared. Nodes are handed out in
+    /// order, so these are the nodes in use.
+    len: AtomicUsize,
+}
+
+// Nodes are written by the thread adding them, before anything else can know their ID, and only
+// ever read through shared references afterwards.
+unsafe impl<T: Send> Send for NodePool<T> {}
+unsafe impl<T: Send + Sync> Sync for NodePool<T> {}
+
+impl<T> Debug for NodePool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NodePool").field("len", &self.len()).field("capacity", &self.capacity()).finish()
+    }
+}
+
+impl<T> NodePool<T> {
+    pub fn new() -> Self {
+        Self {
+            blocks: Default::default(),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Add `node` to the pool.
+    pub fn insert(&self, node: T) -> NodeId {
+        let id = self.len.fetch_add(1, Ordering::Relaxed);
+        assert!(id <= NodeId::MAX as usize, "The node pool is full");
+        let slot = self.slot(id as NodeId);
+        // No other thread has this ID yet, so nothing else can be using the slot
+        unsafe {
+            *slot.get() = Some(node);
+        }
+        id as NodeId
+    }
+
+    /// The node with ID `id`. Panics if it's not in the pool.
+    pub fn get(&self, id: NodeId) -> &T {
+        assert!((id as usize) < self.len(), "Node {} isn't in the pool", id);
+        // Whoever passed `id` here got it after the node was added
+        unsafe { (*self.slot(id).get()).as_ref().expect("Node hasn't been added yet") }
+    }
+
+    /// How many nodes are in the pool.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed).min(NodeId::MAX as usize + 1)
+    }
+
+    /// How many nodes the pool has room for without allocating.
+    pub fn capacity(&self) -> usize {
+        (0..BLOCKS).filter(|&block| !self.blocks[block].load(Ordering::Relaxed).is_null()).map(block_size).sum()
+    }
+
+    /// Drop every node, keeping the memory for the next ones.
+    pub fn clear(&mut self) {
+        let len = self.len();
+        for id in 0..len {
+            // Nothing can be borrowing the nodes while the pool is borrowed mutably
+            unsafe {
+                *self.slot(id as NodeId).get() = None;
+            }
+        }
+        *self.len.get_mut() = 0;
+    }
+
+    /// The slot for `id`, allocating its block if need be.
+    fn slot(&self, id: NodeId) -> &Slot<T> {
+        let (block, offset) = locate(id);
+        let mut slots = self.blocks[block].load(Ordering::Acquire);
+        if slots.is_null() {
+            let new = allocate_block::<T>(block_size(block));
+            slots = match self.blocks[block].compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => new,
+                Err(existing) => {
+                    // Another thread allocated the block first
+                    unsafe { free_block(new, block_size(block)) };
+                    existing
+                },
+            };
+        }
+        unsafe { &*slots.add(offset) }
+    }
+}
+
+impl<T> Drop for NodePool<T> {
+    fn drop(&mut self) {
+        for block in 0..BLOCKS {
+            let slots = *self.blocks[block].get_mut();
+            if !slots.is_null() {
+                unsafe { free_block(slots, block_size(block)) };
+            }
+        }
+    }
+}
+
+/// The block holding `id`, and where in it.
+fn locate(id: NodeId) -> (usize, usize) {
+    let position = id as u64 + (1 << FIRST_BLOCK_BITS);
+    let top_bit = 63 - position.leading_zeros();
+    ((top_bit - FIRST_BLOCK_BITS) as usize, (position - (1 << top_bit)) as usize)
+}
+
+fn block_size(block: usize) -> usize {
+    1 << (FIRST_BLOCK_BITS as usize + block)
+}
+
+fn allocate_block<T>(size: usize) -> *mut Slot<T> {
+    let slots: Box<[Slot<T>]> = (0..size).map(|_| UnsafeCell::new(None)).collect();
+    Box::into_raw(slots) as *mut Slot<T>
+}
+
+/// Free a block made by `allocate_block`, which nothing else may still be using.
+unsafe fn free_block<T>(slots: *mut Slot<T>, size: usize) {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(slots, size)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn blocks_double_in_size() {
+        assert_eq!(locate(0), (0, 0));
+        assert_eq!(locate(1023), (0, 1023));
+        assert_eq!(locate(1024), (1, 0));
+        assert_eq!(locate(3071), (1, 2047));
+        assert_eq!(locate(3072), (2, 0));
+        assert_eq!(locate(NodeId::MAX).0, BLOCKS - 1);
+    }
+
+    #[test]
+    fn hands_back_what_was_added() {
+        let pool = NodePool::new();
+        let ids: Vec<NodeId> = (0..5000).map(|i| pool.insert(i * 2)).collect();
+        assert_eq!(pool.len(), 5000);
+        for (i, &id) in ids.iter().enumerate() {
+            assert_eq!(*pool.get(id), i * 2);
+        }
+        assert_eq!(pool.capacity(), 1024 + 2048 + 4096);
+    }
+
+    #[test]
+    fn clearing_drops_the_nodes_and_keeps_the_memory() {
+        let counted = Rc::new(());
+        let mut pool = NodePool::new();
+        for _ in 0..2000 {
+            pool.insert(counted.clone());
+        }
+        let capacity = pool.capacity();
+        pool.clear();
+        assert_eq!(Rc::strong_count(&counted), 1);
+        assert_eq!(pool.len(), 0);
+
+        pool.insert(counted.clone());
+        assert_eq!(pool.capacity(), capacity);
+        drop(pool);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+
+    #[test]
+    fn threads_can_add_at_once() {
+        let pool = NodePool::new();
+        let added: Vec<Vec<(NodeId, usize)>> = thread::scope(|scope| {
+            let threads: Vec<_> = (0..8).map(|thread| {
+                let pool = &pool;
+                scope.spawn(move || (0..10_000).map(|i| (pool.insert(thread * 10_000 + i), thread * 10_000 + i)).collect())
+            }).collect();
+            threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+        });
+        assert_eq!(pool.len(), 80_000);
+        for (id, value) in added.into_iter().flatten() {
+            assert_eq!(*pool.get(id), value);
+        }
+    }
+}
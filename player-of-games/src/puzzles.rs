@@ -0,0 +1,102 @@
+//! Finding puzzles in played games: positions where the player to move can win, but only with one
+//! particular move.
+
+use game::{GameState, PlayerEnum};
+
+use {MonteCarloTreeSearchPlayer, SearchBudget};
+
+/// A position with exactly one winning move.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(bound(
+    serialize = "Game: ::serde::Serialize, <Game as GameState>::Move: ::serde::Serialize",
+    deserialize = "Game: ::serde::Deserialize<'de>, <Game as GameState>::Move: ::serde::Deserialize<'de>",
+)))]
+pub struct Puzzle<Game: GameState> {
+    pub state: Game,
+    pub to_move: PlayerEnum,
+    /// The winning move.
+    pub solution: <Game as GameState>::Move,
+    /// How many simulations a fresh search needed to find the solution. Bigger is harder.
+    pub difficulty: u32,
+}
+
+/// How thoroughly to check for puzzles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PuzzleSettings {
+    /// Simulations to spend on deciding whether a position is a puzzle. Puzzles found are only
+    /// as sound as this search.
+    pub iterations: u32,
+    /// How sure the search has to be that the best move wins, from 0 to 1.
+    pub win_threshold: f64,
+    /// How good the best of the other moves can look, at most, for the position to count.
+    pub others_threshold: f64,
+}
+
+impl Default for PuzzleSettings {
+    fn default() -> Self {
+        Self {
+            iterations: 20000,
+            win_threshold: 0.9,
+            others_threshold: 0.5,
+        }
+    }
+}
+
+/// Go through a game, played from `start`, and pick out the positions which are puzzles. Positions
+/// are checked whether or not the move played in the game was the solution.
+pub fn find_puzzles<Game: GameState>(start: &Game, moves: &[(PlayerEnum, <Game as GameState>::Move)], settings: PuzzleSettings) -> Vec<Puzzle<Game>> {
+    let mut puzzles = Vec::new();
+    let mut state = start.clone();
+    for &(player, game_move) in moves {
+        if let Some(puzzle) = check_position(&state, player, settings) {
+            puzzles.push(puzzle);
+        }
+        state.update(game_move, player);
+    }
+    puzzles
+}
+
+/// The puzzle at `state`, if it is one.
+pub fn check_position<Game: GameState>(state: &Game, to_move: PlayerEnum, settings: PuzzleSettings) -> Option<Puzzle<Game>> {
+    let budget = SearchBudget::Iterations(settings.iterations);
+
+    // Does the best move win?
+    let mut mcts = MonteCarloTreeSearchPlayer::new(to_move, 2f64.sqrt());
+    mcts.set_budget(budget);
+    let analysis = mcts.analyse(state);
+    let solution = analysis.best_move?;
+    if analysis.evaluation? < settings.win_threshold {
+        return None;
+    }
+
+    // Does anything else? (With only one legal move, there's nothing to find.)
+    state.all_legal_moves(to_move).nth(1)?;
+    mcts.set_excluded_moves(vec![solution]);
+    match mcts.analyse(state).evaluation {
+        Some(evaluation) if evaluation <= settings.others_threshold => (),
+        _ => return None,
+    }
+
+    Some(Puzzle {
+        state: state.clone(),
+        to_move,
+        solution,
+        difficulty: difficulty(state, to_move, solution, settings.iterations),
+    })
+}
+
+/// The smallest budget (doubling from 16 simulations) with which a fresh search plays `solution`,
+/// up to `max_iterations`.
+fn difficulty<Game: GameState>(state: &Game, to_move: PlayerEnum, solution: <Game as GameState>::Move, max_iterations: u32) -> u32 {
+    let mut iterations = 16;
+    while iterations < max_iterations {
+        let mut mcts = MonteCarloTreeSearchPlayer::new(to_move, 2f64.sqrt());
+        mcts.set_budget(SearchBudget::Iterations(iterations));
+        if mcts.analyse(state).best_move == Some(solution) {
+            return iterations;
+        }
+        iterations *= 2;
+    }
+    max_iterations
+}
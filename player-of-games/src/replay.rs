@@ -0,0 +1,361 @@
+//! A replay buffer for self-play training data, kept on disk in shards, for trainers to draw
+//! samples from (or to read the shards themselves).
+//!
+//! Each shard is a `shard-<n>.jsonl` file with one sample per line, in the same format as the
+//! `alphazero` module's self-play data. The newest shard is written to until it's full, and the
+//! oldest are deleted once the buffer holds more than its capacity.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt::Write as FmtWrite;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use game::rng::random_f64;
+
+/// One training position: the encoded position, the share of the search's visits for each move
+/// index, and how the game went for the player to move (1 for a win, 0.5 for a draw and 0 for a
+/// loss).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub shape: Vec<usize>,
+    pub features: Vec<f32>,
+    pub policy: Vec<f32>,
+    pub value: f32,
+}
+
+impl Sample {
+    /// As a line of JSON: `{"shape":[...],"features":[...],"policy":[...],"value":...}`.
+    pub fn to_json(&self) -> String {
+        format!("{{\"shape\":{},\"features\":{},\"policy\":{},\"value\":{}}}", json_list(&self.shape), json_list(&self.features), json_list(&self.policy), self.value)
+    }
+
+    /// Read a sample written by `to_json`.
+    pub fn from_json(line: &str) -> Result<Self, String> {
+        let (mut shape, mut features, mut policy, mut value) = (None, None, None, None);
+        for (key, numbers) in parse_fields(line)? {
+            match key.as_str() {
+                "shape" => shape = Some(numbers.iter().map(|&x| x as usize).collect()),
+                "features" => features = Some(numbers.iter().map(|&x| x as f32).collect()),
+                "policy" => policy = Some(numbers.iter().map(|&x| x as f32).collect()),
+                "value" => value = numbers.first().map(|&x| x as f32),
+                _ => (),
+            }
+        }
+        let missing = |name: &str| format!("Sample has no {}: {}", name, line);
+        Ok(Sample {
+            shape: shape.ok_or_else(|| missing("shape"))?,
+            features: features.ok_or_else(|| missing("features"))?,
+            policy: policy.ok_or_else(|| missing("policy"))?,
+            value: value.ok_or_else(|| missing("value"))?,
+        })
+    }
+
+    /// Identifies the position, for deduplication.
+    fn position_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.shape.hash(&mut hasher);
+        for x in &self.features {
+            x.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+fn json_list<T: ::std::fmt::Display>(values: &[T]) -> String {
+    let mut json = "[".to_string();
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write!(json, "{}", value).expect("Writing to a string failed");
+    }
+    json.push(']');
+    json
+}
+
+/// The fields of a flat JSON object whose values are numbers or lists of numbers. A number comes
+/// back as a list of one.
+fn parse_fields(line: &str) -> Result<Vec<(String, Vec<f64>)>, String> {
+    let bad = || format!("Bad sample: {}", line);
+    let body = line.trim();
+    if !body.starts_with('{') || !body.ends_with('}') {
+        return Err(bad());
+    }
+    let mut rest = body[1..body.len() - 1].trim();
+    let mut fields = Vec::new();
+    while !rest.is_empty() {
+        if !rest.starts_with('"') {
+            return Err(bad());
+        }
+        let key_end = rest[1..].find('"').ok_or_else(bad)? + 1;
+        let key = rest[1..key_end].to_string();
+        rest = rest[key_end + 1..].trim_start();
+        if !rest.starts_with(':') {
+            return Err(bad());
+        }
+        rest = rest[1..].trim_start();
+        let (value, after) = if rest.starts_with('[') {
+            let end = rest.find(']').ok_or_else(bad)?;
+            (&rest[1..end], &rest[end + 1..])
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+        let numbers = value.split(',').map(str::trim).filter(|x| !x.is_empty())
+            .map(|x| x.parse::<f64>().map_err(|_| bad()))
+            .collect::<Result<Vec<_>, _>>()?;
+        fields.push((key, numbers));
+        rest = after.trim_start();
+        if rest.starts_with(',') {
+            rest = rest[1..].trim_start();
+        }
+    }
+    Ok(fields)
+}
+
+/// How big a replay buffer gets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplaySettings {
+    /// The most samples to keep. Whole shards are deleted, oldest first, to stay under this.
+    pub capacity: usize,
+    /// Samples per shard.
+    pub shard_size: usize,
+    /// Skip samples of positions already in the buffer.
+    pub deduplicate: bool,
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        Self {
+            capacity: 500_000,
+            shard_size: 10_000,
+            deduplicate: true,
+        }
+    }
+}
+
+/// How to choose samples from a replay buffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    /// Every sample is equally likely.
+    Uniform,
+    /// Samples are chosen in proportion to their priority to the power of `alpha` (see
+    /// `ReplayBuffer::set_priority`), so 0 is uniform.
+    Prioritized { alpha: f64 },
+}
+
+/// Where a sample is, and how often to train on it.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    id: u64,
+    shard: u64,
+    offset: u64,
+    hash: u64,
+    priority: f64,
+}
+
+/// Self-play samples on disk. Priorities are only kept in memory, and start at 1 for samples
+/// found when the buffer is opened.
+#[derive(Debug)]
+pub struct ReplayBuffer {
+    directory: PathBuf,
+    settings: ReplaySettings,
+    /// Every sample, oldest first.
+    entries: Vec<Entry>,
+    positions: HashSet<u64>,
+    next_id: u64,
+    /// The shard being written, and how long it is.
+    writer: Option<(u64, BufWriter<File>, u64)>,
+    /// Every shard, oldest first.
+    shards: Vec<u64>,
+}
+
+impl ReplayBuffer {
+    /// Open the buffer in `directory`, picking up any shards already there.
+    pub fn open<P: Into<PathBuf>>(directory: P, settings: ReplaySettings) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        let mut shards: Vec<u64> = fs::read_dir(&directory)?.filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            if name.starts_with("shard-") && name.ends_with(".jsonl") {
+                name["shard-".len()..name.len() - ".jsonl".len()].parse().ok()
+            } else {
+                None
+            }
+        }).collect();
+        shards.sort();
+
+        let mut buffer = Self {
+            directory,
+            settings,
+            entries: Vec::new(),
+            positions: HashSet::new(),
+            next_id: 0,
+            writer: None,
+            shards: Vec::new(),
+        };
+        for shard in shards {
+            let mut reader = BufReader::new(File::open(buffer.shard_path(shard))?);
+            let mut offset = 0;
+            let mut line = String::new();
+            loop {
+                line.clear();
+                let read = reader.read_line(&mut line)?;
+                if read == 0 {
+                    break;
+                }
+                let sample = Sample::from_json(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                buffer.index(shard, offset, sample.position_hash());
+                offset += read as u64;
+            }
+            buffer.shards.push(shard);
+        }
+        Ok(buffer)
+    }
+
+    /// How many samples the buffer holds.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The shard files, oldest first, e.g. for an external trainer to read whole.
+    pub fn shard_paths(&self) -> Vec<PathBuf> {
+        self.shards.iter().map(|&shard| self.shard_path(shard)).collect()
+    }
+
+    /// Add `sample`, returning its id, or `None` if it was skipped as a duplicate.
+    pub fn push(&mut self, sample: &Sample) -> io::Result<Option<u64>> {
+        let hash = sample.position_hash();
+        if self.settings.deduplicate && self.positions.contains(&hash) {
+            return Ok(None);
+        }
+
+        let full = self.writer.as_ref().map_or(true, |writer| {
+            self.entries.iter().rev().take_while(|entry| entry.shard == writer.0).count() >= self.settings.shard_size.max(1)
+        });
+        if full {
+            self.rotate()?;
+        }
+        let (shard, offset) = {
+            let writer = self.writer.as_mut().expect("No shard to write to");
+            let line = sample.to_json() + "\n";
+            writer.1.write_all(line.as_bytes())?;
+            let offset = writer.2;
+            writer.2 += line.len() as u64;
+            (writer.0, offset)
+        };
+        Ok(Some(self.index(shard, offset, hash)))
+    }
+
+    /// Add every sample in a file of them, e.g. an `alphazero` generation's self-play data.
+    /// Returns how many were added.
+    pub fn import<P: Into<PathBuf>>(&mut self, path: P) -> io::Result<usize> {
+        let mut added = 0;
+        for line in BufReader::new(File::open(path.into())?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let sample = Sample::from_json(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if self.push(&sample)?.is_some() {
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Set how often the sample `id` should be chosen by prioritized sampling, e.g. by how badly
+    /// a model did on it. Ids which have been deleted are ignored.
+    pub fn set_priority(&mut self, id: u64, priority: f64) {
+        if let Ok(i) = self.entries.binary_search_by_key(&id, |entry| entry.id) {
+            self.entries[i].priority = priority.max(0.0);
+        }
+    }
+
+    /// Choose `count` samples (with replacement), returning each with its id.
+    pub fn sample(&mut self, count: usize, sampling: Sampling) -> io::Result<Vec<(u64, Sample)>> {
+        if let Some(ref mut writer) = self.writer {
+            writer.1.flush()?;
+        }
+        if self.entries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let weights: Vec<f64> = match sampling {
+            Sampling::Uniform => vec![1.0; self.entries.len()],
+            Sampling::Prioritized { alpha } => self.entries.iter().map(|entry| entry.priority.powf(alpha)).collect(),
+        };
+        let total: f64 = weights.iter().sum();
+        let mut chosen = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut target = random_f64() * total;
+            let mut index = self.entries.len() - 1;
+            for (i, weight) in weights.iter().enumerate() {
+                if target < *weight {
+                    index = i;
+                    break;
+                }
+                target -= weight;
+            }
+            let entry = self.entries[index];
+            chosen.push((entry.id, self.read(entry)?));
+        }
+        Ok(chosen)
+    }
+
+    /// Write out everything pushed so far.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.writer {
+            Some(ref mut writer) => writer.1.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn shard_path(&self, shard: u64) -> PathBuf {
+        self.directory.join(format!("shard-{}.jsonl", shard))
+    }
+
+    fn index(&mut self, shard: u64, offset: u64, hash: u64) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push(Entry { id, shard, offset, hash, priority: 1.0 });
+        self.positions.insert(hash);
+        id
+    }
+
+    /// Start a new shard, and delete the oldest ones if there are too many samples.
+    fn rotate(&mut self) -> io::Result<()> {
+        if let Some((_, mut writer, _)) = self.writer.take() {
+            writer.flush()?;
+        }
+        let shard = self.shards.last().map_or(0, |last| last + 1);
+        let file = OpenOptions::new().create(true).append(true).open(self.shard_path(shard))?;
+        self.writer = Some((shard, BufWriter::new(file), 0));
+        self.shards.push(shard);
+
+        while self.entries.len() + self.settings.shard_size > self.settings.capacity && self.shards.len() > 1 {
+            let oldest = self.shards.remove(0);
+            let removed = self.entries.iter().take_while(|entry| entry.shard == oldest).count();
+            for entry in self.entries.drain(..removed) {
+                self.positions.remove(&entry.hash);
+            }
+            fs::remove_file(self.shard_path(oldest))?;
+        }
+        Ok(())
+    }
+
+    fn read(&self, entry: Entry) -> io::Result<Sample> {
+        let mut file = File::open(self.shard_path(entry.shard))?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line)?;
+        Sample::from_json(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt::Debug;
+
+use game::{value, Conclusion, GameState, PlayerEnum};
+use game::rng::random_f64;
+
+/// Decides how good the end of a simulation was, which is what the search tries to maximise.
+pub trait RewardModel<Game: GameState>: Debug {
+    /// The reward for `player` when a simulation ends in `final_state`, `length` moves after the
+    /// position being searched. Rewards should be between 0 (as bad as a loss) and 1 (as good as
+    /// a win).
+    fn reward(&self, final_state: &Game, conclusion: Conclusion, length: u32, player: PlayerEnum) -> f64;
+}
+
+/// Score 1 for a win, 0 for a loss and a half for a draw. This is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WinLoss;
+
+impl<Game: GameState> RewardModel<Game> for WinLoss {
+    fn reward(&self, _final_state: &Game, conclusion: Conclusion, _length: u32, player: PlayerEnum) -> f64 {
+        value::of_conclusion(conclusion, player)
+    }
+}
+
+/// Score by the game's final margin, for games which report one.
+///
+/// A margin of `scale` (or more) in a player's favour is as good as a certain win, and a margin
+/// of zero is as good as a draw. Games without a final score are scored as `WinLoss`.
+#[derive(Clone, Copy, Debug)]
+pub struct ScoreMargin {
+    pub scale: f64,
+}
+
+impl<Game: GameState> RewardModel<Game> for ScoreMargin {
+    fn reward(&self, final_state: &Game, conclusion: Conclusion, length: u32, player: PlayerEnum) -> f64 {
+        match final_state.final_score() {
+            Some(score) => {
+                let score = match player {
+                    PlayerEnum::One => score,
+                    PlayerEnum::Two => -score,
+                };
+                value::clamp(value::DRAW + score / (2.0 * self.scale))
+            }
+            None => WinLoss.reward(final_state, conclusion, length, player),
+        }
+    }
+}
+
+/// Prefer quick wins (and slow losses): each move a simulation takes costs `penalty` from the
+/// winner's reward, and is added to the loser's, but never past a draw.
+#[derive(Clone, Copy, Debug)]
+pub struct LengthPenalty {
+    pub penalty: f64,
+}
+
+impl<Game: GameState> RewardModel<Game> for LengthPenalty {
+    fn reward(&self, final_state: &Game, conclusion: Conclusion, length: u32, player: PlayerEnum) -> f64 {
+        value::by_distance(WinLoss.reward(final_state, conclusion, length, player), length, self.penalty)
+    }
+}
+
+/// How a heuristic's evaluation becomes a probability of winning. See `CutoffMapping`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum WinProbability {
+    /// The evaluation already is one. This is the default.
+    AsIs,
+    /// Pass the evaluation through a logistic curve centred on a half, for heuristics which are
+    /// too timid (or too bold) about who's winning. The bigger `steepness`, the surer.
+    Logistic { steepness: f64 },
+}
+
+/// What a cut off simulation backs up. See `CutoffMapping`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum CutoffBackup {
+    /// The probability of winning itself. This is the default.
+    Expected,
+    /// A win or a loss, drawn at random with that probability, so that cut off simulations look
+    /// just like finished ones to the win/loss statistics.
+    Sample,
+}
+
+/// How simulations cut off by the rollout limits are scored from the cutoff heuristic's
+/// evaluation of where they stopped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct CutoffMapping {
+    pub win_probability: WinProbability,
+    pub backup: CutoffBackup,
+}
+
+impl Default for CutoffMapping {
+    fn default() -> Self {
+        Self {
+            win_probability: WinProbability::AsIs,
+            backup: CutoffBackup::Expected,
+        }
+    }
+}
+
+impl CutoffMapping {
+    /// The rewards for player one and player two, from the heuristic's evaluations for each.
+    pub fn rewards(&self, evaluations: (f64, f64)) -> (f64, f64) {
+        let probability = |evaluation: f64| match self.win_probability {
+            WinProbability::AsIs => evaluation,
+            WinProbability::Logistic { steepness } => 1.0 / (1.0 + (-steepness * (evaluation - 0.5)).exp()),
+        };
+        let (one, two) = (probability(evaluations.0), probability(evaluations.1));
+        match self.backup {
+            CutoffBackup::Expected => (one, two),
+            CutoffBackup::Sample => {
+                // The evaluations for each player needn't add up to one, so meet in the middle.
+                if random_f64() < (one + value::flip(two)) / 2.0 { (1.0, 0.0) } else { (0.0, 1.0) }
+            },
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use game::{self, value, GameState, PlayerEnum, RolloutPolicy};
+
+/// Cheap ways for random simulations to learn from each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct RolloutEnhancements {
+    /// Move-Average Sampling Technique: keep the average reward of each move (whenever and by
+    /// whichever player it was played), and choose moves in simulations by a softmax of those
+    /// averages with this temperature.
+    pub mast_temperature: Option<f64>,
+    /// Last-Good-Reply: remember the reply to each move which last won a simulation, and play it
+    /// again whenever it's legal. Replies which lose are forgotten.
+    pub last_good_reply: bool,
+    /// Between turns the MAST statistics are scaled down by this factor, so that the old
+    /// position's statistics don't swamp the new one's.
+    pub decay: f64,
+}
+
+impl Default for RolloutEnhancements {
+    fn default() -> Self {
+        Self {
+            mast_temperature: None,
+            last_good_reply: false,
+            decay: 1.0,
+        }
+    }
+}
+
+impl RolloutEnhancements {
+    pub fn enabled(&self) -> bool {
+        self.mast_temperature.is_some() || self.last_good_reply
+    }
+}
+
+/// A move's total reward and number of plays, as learnt by MAST.
+pub type MastEntry<Move> = (PlayerEnum, Move, f64, f64);
+
+/// The last good reply to a move.
+pub type ReplyEntry<Move> = (PlayerEnum, Move, Move);
+
+/// What the enhancements have learnt so far.
+#[derive(Debug)]
+pub struct RolloutTables<Move> {
+    pub settings: RolloutEnhancements,
+    /// Total reward and (decayed) number of plays of each move.
+    mast: HashMap<(PlayerEnum, Move), (f64, f64)>,
+    /// The last good reply to each move, keyed by the player replying.
+    replies: HashMap<(PlayerEnum, Move), Move>,
+}
+
+impl<Move: ::std::hash::Hash + Eq + Copy> RolloutTables<Move> {
+    pub fn new() -> Self {
+        Self {
+            settings: RolloutEnhancements::default(),
+            mast: HashMap::new(),
+            replies: HashMap::new(),
+        }
+    }
+
+    /// Choose `player`'s move in a simulation, falling back to `policy` if the enhancements have
+    /// nothing to say. `previous` is the move just played by the opponent.
+    pub fn choose_move<Game>(&self, state: &Game, player: PlayerEnum, previous: Option<Move>, policy: &dyn RolloutPolicy<Game>) -> Option<Move>
+        where Game: GameState<Move = Move>
+    {
+        if self.settings.last_good_reply {
+            let reply = previous.and_then(|previous| self.replies.get(&(player, previous)));
+            if let Some(&reply) = reply {
+                if state.all_legal_moves(player).any(|m| m == reply) {
+                    return Some(reply);
+                }
+            }
+        }
+
+        match self.settings.mast_temperature {
+            Some(temperature) => game::SoftmaxRollout { weights: MastWeights(self), temperature }.choose_move(state, player),
+            None => policy.choose_move(state, player),
+        }
+    }
+
+    /// Learn from a finished simulation, given every move played in it (in order) and the
+    /// rewards it gave each player.
+    pub fn record(&mut self, moves: &[(PlayerEnum, Move)], rewards: (f64, f64)) {
+        let reward_for = |player| match player {
+            PlayerEnum::One => rewards.0,
+            PlayerEnum::Two => rewards.1,
+        };
+
+        if self.settings.mast_temperature.is_some() {
+            for &(player, game_move) in moves {
+                let entry = self.mast.entry((player, game_move)).or_insert((0.0, 0.0));
+                entry.0 += reward_for(player);
+                entry.1 += 1.0;
+            }
+        }
+
+        if self.settings.last_good_reply {
+            for pair in moves.windows(2) {
+                let ((_, previous), (player, reply)) = (pair[0], pair[1]);
+                let reward = reward_for(player);
+                if reward > value::DRAW {
+                    self.replies.insert((player, previous), reply);
+                } else if reward < value::DRAW && self.replies.get(&(player, previous)) == Some(&reply) {
+                    self.replies.remove(&(player, previous));
+                }
+            }
+        }
+    }
+
+    /// Everything learnt so far: the total reward and number of plays of each move, and the last
+    /// good reply to each move.
+    pub fn learnt(&self) -> (Vec<MastEntry<Move>>, Vec<ReplyEntry<Move>>) {
+        let mast = self.mast.iter().map(|(&(player, game_move), &(total, count))| (player, game_move, total, count)).collect();
+        let replies = self.replies.iter().map(|(&(player, previous), &reply)| (player, previous, reply)).collect();
+        (mast, replies)
+    }
+
+    /// Replace what's been learnt with something returned by `learnt`.
+    pub fn set_learnt(&mut self, mast: Vec<(PlayerEnum, Move, f64, f64)>, replies: Vec<(PlayerEnum, Move, Move)>) {
+        self.mast = mast.into_iter().map(|(player, game_move, total, count)| ((player, game_move), (total, count))).collect();
+        self.replies = replies.into_iter().map(|(player, previous, reply)| ((player, previous), reply)).collect();
+    }
+
+    /// Fade out what's been learnt so far, ready for a new turn.
+    pub fn decay(&mut self) {
+        let decay = self.settings.decay;
+        if decay == 1.0 {
+            return;
+        }
+        for entry in self.mast.values_mut() {
+            entry.0 *= decay;
+            entry.1 *= decay;
+        }
+    }
+
+    /// The average reward of a move, or the best possible reward if it's never been played, so
+    /// that new moves get tried.
+    fn mast_value(&self, player: PlayerEnum, game_move: Move) -> f64 {
+        match self.mast.get(&(player, game_move)) {
+            Some(&(total, count)) if count > 0.0 => total / count,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Scores moves by their MAST values.
+struct MastWeights<'a, Move: 'a>(&'a RolloutTables<Move>);
+
+impl<'a, Move> ::std::fmt::Debug for MastWeights<'a, Move> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "MastWeights")
+    }
+}
+
+impl<'a, Game: GameState> game::MoveWeights<Game> for MastWeights<'a, <Game as GameState>::Move> {
+    fn score(&self, _state: &Game, game_move: &<Game as GameState>::Move, player: PlayerEnum) -> f64 {
+        self.0.mast_value(player, *game_move)
+    }
+}
@@ -0,0 +1,140 @@
+//! Matches between two tree search players which can be configured differently, e.g. to A/B test
+//! a change of parameters.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use game::{self, Adjudicator, CancellationToken, Conclusion, GameObserver, GameState, Player, PlayerEnum, RolloutPolicy};
+
+use {MonteCarloTreeSearchPlayer, RolloutEnhancements, RolloutLimits, SearchBudget};
+
+/// How to set up one tree search player.
+#[derive(Debug, Clone)]
+pub struct MctsConfig<Game: GameState> {
+    /// Exploration constant.
+    pub c: f64,
+    pub budget: SearchBudget,
+    /// See `MonteCarloTreeSearchPlayer::set_discount`.
+    pub discount: f64,
+    pub rollout_limits: RolloutLimits,
+    pub rollout_policy: Arc<dyn RolloutPolicy<Game> + Send + Sync>,
+    pub rollout_enhancements: RolloutEnhancements,
+}
+
+impl<Game: GameState> Default for MctsConfig<Game> {
+    /// The same settings as a freshly created player.
+    fn default() -> Self {
+        Self {
+            c: 2f64.sqrt(),
+            budget: SearchBudget::Iterations(100),
+            discount: 1.0,
+            rollout_limits: RolloutLimits::default(),
+            rollout_policy: Arc::new(game::UniformRollout),
+            rollout_enhancements: RolloutEnhancements::default(),
+        }
+    }
+}
+
+impl<Game: GameState> MctsConfig<Game> {
+    /// Create a player with these settings.
+    pub fn build(&self, player: PlayerEnum) -> MonteCarloTreeSearchPlayer<Game> {
+        let mut mcts = MonteCarloTreeSearchPlayer::new(player, self.c);
+        mcts.set_budget(self.budget);
+        mcts.set_discount(self.discount);
+        mcts.set_rollout_limits(self.rollout_limits);
+        mcts.set_rollout_policy(self.rollout_policy.clone());
+        mcts.set_rollout_enhancements(self.rollout_enhancements);
+        mcts
+    }
+}
+
+/// A match to be played, built up from the starting position and each player's settings, e.g.
+///
+/// ```ignore
+/// let record = MatchSpec::new(TicTacToe::new())
+///     .player_one(MctsConfig { c: 1.0, ..MctsConfig::default() })
+///     .player_two(MctsConfig { budget: SearchBudget::Iterations(500), ..MctsConfig::default() })
+///     .play();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MatchSpec<Game: GameState> {
+    start: Game,
+    player_one: MctsConfig<Game>,
+    player_two: MctsConfig<Game>,
+}
+
+impl<Game: GameState> MatchSpec<Game> {
+    /// A match from `start`, with both players on the default settings.
+    pub fn new(start: Game) -> Self {
+        Self {
+            start,
+            player_one: MctsConfig::default(),
+            player_two: MctsConfig::default(),
+        }
+    }
+
+    pub fn player_one(mut self, config: MctsConfig<Game>) -> Self {
+        self.player_one = config;
+        self
+    }
+
+    pub fn player_two(mut self, config: MctsConfig<Game>) -> Self {
+        self.player_two = config;
+        self
+    }
+
+    /// Play the match through to the end.
+    pub fn play(&self) -> MatchRecord<Game> {
+        self.play_until_cancelled(&CancellationToken::new()).expect("Match was cancelled")
+    }
+
+    /// Play the match through to the end, or until `cancellation` is cancelled, which also stops
+    /// the players' searches. Returns `None` if it was cancelled.
+    pub fn play_until_cancelled(&self, cancellation: &CancellationToken) -> Option<MatchRecord<Game>> {
+        let mut player_one = self.player_one.build(PlayerEnum::One);
+        let mut player_two = self.player_two.build(PlayerEnum::Two);
+        player_one.set_cancellation_token(cancellation.clone());
+        player_two.set_cancellation_token(cancellation.clone());
+        let mut adjudicator = Adjudicator::new(self.start.clone(), player_one, player_two);
+        let moves = Rc::new(RefCell::new(Vec::new()));
+        adjudicator.add_observer(MoveRecorder(moves.clone()));
+        while adjudicator.conclusion().is_none() {
+            if cancellation.is_cancelled() {
+                return None;
+            }
+            adjudicator.progress_one_turn();
+        }
+
+        let moves = moves.borrow().clone();
+        Some(MatchRecord {
+            start: self.start.clone(),
+            player_one: self.player_one.clone(),
+            player_two: self.player_two.clone(),
+            moves,
+            conclusion: adjudicator.conclusion().expect("Game should be over"),
+            final_score: adjudicator.final_score(),
+        })
+    }
+}
+
+/// A finished match, along with how each player was set up.
+#[derive(Debug, Clone)]
+pub struct MatchRecord<Game: GameState> {
+    pub start: Game,
+    pub player_one: MctsConfig<Game>,
+    pub player_two: MctsConfig<Game>,
+    /// Every move played, in order.
+    pub moves: Vec<(PlayerEnum, <Game as GameState>::Move)>,
+    pub conclusion: Conclusion,
+    pub final_score: Option<f64>,
+}
+
+/// Notes down the moves of a match as they're played.
+struct MoveRecorder<Move>(Rc<RefCell<Vec<(PlayerEnum, Move)>>>);
+
+impl<Game: GameState> GameObserver<Game> for MoveRecorder<<Game as GameState>::Move> {
+    fn on_move(&mut self, player: PlayerEnum, game_move: &<Game as GameState>::Move, _new_state: &Game, _evaluation: Option<f64>) {
+        self.0.borrow_mut().push((player, *game_move));
+    }
+}
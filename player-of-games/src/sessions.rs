@@ -0,0 +1,642 @@
+//! Hosting many games against the engine at once, each with its own tree and settings, e.g. behind
+//! a server or a bot.
+//!
+//! Engine moves can be made in one go (`SessionManager::advance`), or a slice at a time across all
+//! the sessions waiting for one (`SessionManager::run_slice`), so that one long search doesn't
+//! hold up everyone else's.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use game::{self, Conclusion, DrawTracker, GameState, PlayerEnum};
+
+use self_play::MctsConfig;
+use {Analysis, DecisionSource, MonteCarloTreeSearchPlayer, SearchBudget, Snapshot};
+
+/// Simulations per slice when making an engine move in one go. This only matters for time
+/// budgets, which are checked between slices.
+const BLOCKING_SLICE: u32 = 100;
+
+/// Identifies a session within its `SessionManager`.
+pub type SessionId = u64;
+
+/// Caps on how much a session's engine can use, whatever its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SessionLimits {
+    /// Stop searching once the tree has this many nodes. The tree is also garbage collected
+    /// between moves once it's this big.
+    pub max_nodes: Option<usize>,
+    /// Think for at most this long about each move.
+    pub max_move_time: Option<Duration>,
+}
+
+/// How far the engine has got with the move it's thinking about.
+#[derive(Debug, Clone, Copy, Default)]
+struct MoveProgress {
+    iterations: u32,
+    elapsed: Duration,
+}
+
+/// A game in progress between a client and the engine.
+#[derive(Debug)]
+pub struct Session<Game: GameState> {
+    state: Game,
+    to_move: PlayerEnum,
+    conclusion: Option<Conclusion>,
+    engine_plays: PlayerEnum,
+    engine: MonteCarloTreeSearchPlayer<Game>,
+    draw_tracker: DrawTracker<Game>,
+    limits: SessionLimits,
+    progress: MoveProgress,
+    thinking_time: Duration,
+    /// When the session was last used, by the engine's clock.
+    last_active: Duration,
+}
+
+/// A session's state, from `Session::snapshot`, e.g. to save open games when a server shuts
+/// down. With the `serialize` feature this can be saved with serde.
+///
+/// Progress towards the engine's next move is kept in its tree, but the budget for the move starts
+/// again from scratch once restored.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(bound(
+    serialize = "Game: ::serde::Serialize, <Game as GameState>::Move: ::serde::Serialize",
+    deserialize = "Game: ::serde::Deserialize<'de>, <Game as GameState>::Move: ::serde::Deserialize<'de>",
+)))]
+pub struct SessionSnapshot<Game: GameState> {
+    state: Game,
+    to_move: PlayerEnum,
+    conclusion: Option<Conclusion>,
+    engine_plays: PlayerEnum,
+    engine: Snapshot<Game>,
+    draw_tracker: DrawTracker<Game>,
+    limits: SessionLimits,
+    thinking_time: Duration,
+}
+
+impl<Game: GameState> Session<Game> {
+    /// A game from `state`, with player one to move, and the engine set up as `config`.
+    pub fn new(state: Game, engine_plays: PlayerEnum, config: &MctsConfig<Game>) -> Self {
+        Self::from_position(state, PlayerEnum::One, engine_plays, config)
+    }
+
+    /// A game from `state`, with `to_move` to move, e.g. one partway through a game. If the game
+    /// is already over there, the session starts out concluded.
+    pub fn from_position(state: Game, to_move: PlayerEnum, engine_plays: PlayerEnum, config: &MctsConfig<Game>) -> Self {
+        let engine = config.build(engine_plays);
+        let draw_tracker = DrawTracker::new(&state, to_move);
+        let conclusion = draw_tracker.try_conclude(&state, to_move);
+        Self {
+            last_active: engine.clock().now(),
+            draw_tracker,
+            state,
+            to_move: if conclusion.is_some() { to_move.other() } else { to_move },
+            conclusion,
+            engine_plays,
+            engine,
+            limits: SessionLimits::default(),
+            progress: MoveProgress::default(),
+            thinking_time: Duration::from_secs(0),
+        }
+    }
+
+    /// Carry on a session from `snapshot`, with the engine set up as `config` apart from what the
+    /// snapshot covers (see `Snapshot`).
+    pub fn restore(snapshot: SessionSnapshot<Game>, config: &MctsConfig<Game>) -> Result<Self, String> {
+        let mut engine = config.build(snapshot.engine_plays);
+        engine.restore(snapshot.engine)?;
+        let mut session = Self {
+            last_active: engine.clock().now(),
+            state: snapshot.state,
+            to_move: snapshot.to_move,
+            conclusion: snapshot.conclusion,
+            engine_plays: snapshot.engine_plays,
+            engine,
+            draw_tracker: snapshot.draw_tracker,
+            limits: SessionLimits::default(),
+            progress: MoveProgress::default(),
+            thinking_time: snapshot.thinking_time,
+        };
+        session.set_limits(snapshot.limits);
+        Ok(session)
+    }
+
+    /// Everything needed to carry on this session later, with `restore`.
+    pub fn snapshot(&self) -> SessionSnapshot<Game> {
+        SessionSnapshot {
+            state: self.state.clone(),
+            to_move: self.to_move,
+            conclusion: self.conclusion,
+            engine_plays: self.engine_plays,
+            engine: self.engine.snapshot(),
+            draw_tracker: self.draw_tracker.clone(),
+            limits: self.limits,
+            thinking_time: self.thinking_time,
+        }
+    }
+
+    pub fn state(&self) -> &Game {
+        &self.state
+    }
+
+    /// Whose turn it is. Once the game is over, this is the player who made the last move.
+    pub fn to_move(&self) -> PlayerEnum {
+        self.to_move
+    }
+
+    pub fn conclusion(&self) -> Option<Conclusion> {
+        self.conclusion
+    }
+
+    /// The final score, once the game is over, if the game has one.
+    pub fn final_score(&self) -> Option<f64> {
+        self.conclusion.and_then(|_| self.state.final_score())
+    }
+
+    pub fn engine_plays(&self) -> PlayerEnum {
+        self.engine_plays
+    }
+
+    /// The engine, e.g. to look at its tree or change how it searches. Session times are kept by
+    /// the engine's clock.
+    pub fn engine(&mut self) -> &mut MonteCarloTreeSearchPlayer<Game> {
+        &mut self.engine
+    }
+
+    /// Cap what the engine can use from now on. No limits are set to begin with.
+    pub fn set_limits(&mut self, limits: SessionLimits) {
+        self.limits = limits;
+        self.engine.set_garbage_collection_threshold(limits.max_nodes);
+    }
+
+    pub fn limits(&self) -> SessionLimits {
+        self.limits
+    }
+
+    /// Whether it's the engine's turn in a game which isn't over.
+    pub fn waiting_for_engine(&self) -> bool {
+        self.conclusion.is_none() && self.to_move == self.engine_plays
+    }
+
+    /// How long the engine has spent searching in this session.
+    pub fn thinking_time(&self) -> Duration {
+        self.thinking_time
+    }
+
+    /// How long since the session was last used, e.g. to close abandoned sessions.
+    pub fn idle_time(&self) -> Duration {
+        self.now().checked_sub(self.last_active).unwrap_or_default()
+    }
+
+    /// Play the client's move.
+    pub fn play_move(&mut self, game_move: <Game as GameState>::Move) -> Result<(), String> {
+        if self.to_move == self.engine_plays {
+            return Err("It's the engine's turn".to_string());
+        }
+        self.apply(game_move)
+    }
+
+    /// Let the engine move, if it's its turn. Returns the move it played.
+    ///
+    /// An engine searching `UntilStopped`, with no time limit and a token which isn't cancelled,
+    /// would never finish, so this plays nothing and returns `None`. Search for its move with
+    /// `think` (or `SessionManager::run_slice`) instead, cancelling its token to finish.
+    pub fn engine_move(&mut self) -> Option<<Game as GameState>::Move> {
+        if !self.waiting_for_engine() || self.searches_forever() {
+            return None;
+        }
+        while !self.search_slice(BLOCKING_SLICE) {}
+        self.play_searched_move()
+    }
+
+    /// Whether the search for the engine's move only ends when its token is cancelled.
+    fn searches_forever(&self) -> bool {
+        self.engine.budget() == SearchBudget::UntilStopped
+            && self.limits.max_move_time.is_none()
+            && !self.engine.cancellation_token().is_cancelled()
+    }
+
+    /// Play `game_move` for the engine instead of searching for one, e.g. from an opening book.
+    /// The engine's `explain_last_move` gives `source` as where it came from.
+    pub fn play_engine_move(&mut self, game_move: <Game as GameState>::Move, source: DecisionSource) -> Result<(), String> {
+        if !self.waiting_for_engine() {
+            return Err("It's not the engine's turn".to_string());
+        }
+        let state = self.state.clone();
+        self.apply(game_move)?;
+        self.engine.explain_move(&state, game_move, source);
+        self.progress = MoveProgress::default();
+        Ok(())
+    }
+
+    /// Search for up to `iterations` simulations towards the engine's move, if it's its turn.
+    /// Once the engine's budget (or the session's limits) are used up, or its cancellation token
+    /// is cancelled, the move is played and returned.
+    ///
+    /// Time budgets count only the time spent searching for this session, and are checked after
+    /// each slice, so they can be overrun by up to a slice.
+    pub fn think(&mut self, iterations: u32) -> Option<<Game as GameState>::Move> {
+        if self.waiting_for_engine() && self.search_slice(iterations) {
+            self.play_searched_move()
+        } else {
+            None
+        }
+    }
+
+    /// Search some more for the engine's move. Returns whether the search is finished.
+    fn search_slice(&mut self, iterations: u32) -> bool {
+        let budget = self.engine.budget();
+        let remaining = match budget {
+            SearchBudget::Iterations(n) => n.saturating_sub(self.progress.iterations),
+            SearchBudget::Time(_) | SearchBudget::UntilStopped => iterations,
+        };
+        let slice = iterations.min(remaining);
+        if slice > 0 && !self.over_limits() {
+            let start = self.now();
+            self.engine.set_budget(SearchBudget::Iterations(slice));
+            self.engine.analyse_as(&self.state, self.to_move);
+            self.engine.set_budget(budget);
+            let elapsed = self.now().checked_sub(start).unwrap_or_default();
+            self.progress.iterations += slice;
+            self.progress.elapsed += elapsed;
+            self.thinking_time += elapsed;
+        }
+        self.last_active = self.now();
+
+        self.over_limits() || self.engine.cancellation_token().is_cancelled() || match budget {
+            SearchBudget::Iterations(n) => self.progress.iterations >= n,
+            SearchBudget::Time(duration) => self.progress.elapsed >= duration,
+            SearchBudget::UntilStopped => false,
+        }
+    }
+
+    fn over_limits(&self) -> bool {
+        self.limits.max_nodes.is_some_and(|max| self.engine.node_count() >= max)
+            || self.limits.max_move_time.is_some_and(|max| self.progress.elapsed >= max)
+    }
+
+    /// Play the best move found by the search so far.
+    fn play_searched_move(&mut self) -> Option<<Game as GameState>::Move> {
+        // A search cut short by the limits might not have got started, but even with no budget,
+        // the engine searches until it has a move.
+        let budget = self.engine.budget();
+        self.engine.set_budget(SearchBudget::Iterations(0));
+        let analysis = self.engine.analyse_as(&self.state, self.to_move);
+        self.engine.set_budget(budget);
+        self.progress = MoveProgress::default();
+
+        let game_move = analysis.best_move?;
+        let source = if analysis.policy.len() == 1 { DecisionSource::OnlyMove } else { DecisionSource::Search };
+        self.engine.explain_move(&self.state, game_move, source);
+        self.apply(game_move).expect("Engine chose an illegal move");
+        Some(game_move)
+    }
+
+    /// Search the current position, for whichever player is to move, without playing a move.
+    pub fn analyze(&mut self) -> Analysis<<Game as GameState>::Move> {
+        let start = self.now();
+        let analysis = self.engine.analyse_as(&self.state, self.to_move);
+        self.thinking_time += self.now().checked_sub(start).unwrap_or_default();
+        self.last_active = self.now();
+        analysis
+    }
+
+    fn now(&self) -> Duration {
+        self.engine.clock().now()
+    }
+
+    fn apply(&mut self, game_move: <Game as GameState>::Move) -> Result<(), String> {
+        self.last_active = self.now();
+        if self.conclusion.is_some() {
+            return Err("The game is over".to_string());
+        }
+        self.state.is_move_legal(game_move, self.to_move).map_err(|e| e.to_string())?;
+
+        self.draw_tracker.play(&mut self.state, game_move, self.to_move);
+        game::Player::inform_of_move_played(&mut self.engine, self.state.clone(), &game_move);
+
+        let next_player = self.to_move.other();
+        match self.draw_tracker.try_conclude(&self.state, next_player) {
+            Some(conclusion) => self.conclusion = Some(conclusion),
+            None => self.to_move = next_player,
+        }
+        Ok(())
+    }
+}
+
+/// Many independent sessions of one game, addressed by id.
+#[derive(Debug)]
+pub struct SessionManager<Game: GameState> {
+    initial_state: Game,
+    sessions: HashMap<SessionId, Session<Game>>,
+    next_id: SessionId,
+}
+
+impl<Game: GameState> SessionManager<Game> {
+    /// Sessions start from `initial_state`.
+    pub fn new(initial_state: Game) -> Self {
+        Self {
+            initial_state,
+            sessions: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Start a new game, with the engine playing `engine_plays` and set up as `config`.
+    pub fn create(&mut self, engine_plays: PlayerEnum, config: &MctsConfig<Game>) -> SessionId {
+        let initial_state = self.initial_state.clone();
+        self.create_from_position(initial_state, PlayerEnum::One, engine_plays, config)
+    }
+
+    /// Start a new game from `state`, with `to_move` to move, rather than from the initial state.
+    pub fn create_from_position(&mut self, state: Game, to_move: PlayerEnum, engine_plays: PlayerEnum, config: &MctsConfig<Game>) -> SessionId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.sessions.insert(id, Session::from_position(state, to_move, engine_plays, config));
+        id
+    }
+
+    /// Carry on a session saved with `snapshot`, under the same id. Fails if the id is taken.
+    pub fn restore(&mut self, id: SessionId, snapshot: SessionSnapshot<Game>, config: &MctsConfig<Game>) -> Result<(), String> {
+        if self.sessions.contains_key(&id) {
+            return Err(format!("Session {} is already open", id));
+        }
+        self.sessions.insert(id, Session::restore(snapshot, config)?);
+        self.next_id = self.next_id.max(id + 1);
+        Ok(())
+    }
+
+    /// Every open session's state, by id, in the order they were created, e.g. to `restore` them
+    /// after a restart.
+    pub fn snapshot(&self) -> Vec<(SessionId, SessionSnapshot<Game>)> {
+        self.ids().into_iter().map(|id| (id, self.sessions[&id].snapshot())).collect()
+    }
+
+    pub fn get(&self, id: SessionId) -> Option<&Session<Game>> {
+        self.sessions.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: SessionId) -> Option<&mut Session<Game>> {
+        self.sessions.get_mut(&id)
+    }
+
+    /// Play the client's move (if given), then the engine's reply (if it's the engine's turn).
+    /// Returns the engine's move, which is `None` for an engine which searches until stopped: see
+    /// `Session::engine_move`.
+    pub fn advance(&mut self, id: SessionId, game_move: Option<<Game as GameState>::Move>) -> Result<Option<<Game as GameState>::Move>, String> {
+        let session = self.session(id)?;
+        if let Some(game_move) = game_move {
+            session.play_move(game_move)?;
+        }
+        Ok(session.engine_move())
+    }
+
+    /// Search a session's current position, without playing a move. The search is kept, so the
+    /// engine's next move benefits from it.
+    pub fn analyze(&mut self, id: SessionId) -> Result<Analysis<<Game as GameState>::Move>, String> {
+        let session = self.session(id)?;
+        if session.conclusion.is_some() {
+            return Err("The game is over".to_string());
+        }
+        Ok(session.analyze())
+    }
+
+    /// Give each session waiting for an engine move a slice of `iterations` simulations, in turn.
+    /// Returns the moves played by engines which finished thinking.
+    ///
+    /// Calling this in a loop shares the search time evenly between the sessions, however big
+    /// their budgets.
+    pub fn run_slice(&mut self, iterations: u32) -> Vec<(SessionId, <Game as GameState>::Move)> {
+        let mut played = Vec::new();
+        for id in self.waiting() {
+            if let Some(game_move) = self.sessions.get_mut(&id).and_then(|session| session.think(iterations)) {
+                played.push((id, game_move));
+            }
+        }
+        played
+    }
+
+    /// The ids of the sessions waiting for an engine move, in the order they were created.
+    pub fn waiting(&self) -> Vec<SessionId> {
+        self.ids().into_iter().filter(|id| self.sessions[id].waiting_for_engine()).collect()
+    }
+
+    /// End a session, returning it.
+    pub fn close(&mut self, id: SessionId) -> Result<Session<Game>, String> {
+        self.sessions.remove(&id).ok_or_else(|| format!("No such session: {}", id))
+    }
+
+    /// End the sessions which haven't been used for `max_idle`, e.g. games the client walked away
+    /// from. Returns their ids.
+    pub fn close_idle(&mut self, max_idle: Duration) -> Vec<SessionId> {
+        let idle: Vec<SessionId> = self.ids().into_iter().filter(|id| self.sessions[id].idle_time() >= max_idle).collect();
+        for id in &idle {
+            self.sessions.remove(id);
+        }
+        idle
+    }
+
+    /// The ids of all open sessions, in the order they were created.
+    pub fn ids(&self) -> Vec<SessionId> {
+        let mut ids: Vec<SessionId> = self.sessions.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    fn session(&mut self, id: SessionId) -> Result<&mut Session<Game>, String> {
+        self.sessions.get_mut(&id).ok_or_else(|| format!("No such session: {}", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tic_tac_toe;
+
+    use std::time::Duration;
+
+    use game::{GameState, MockClock, PlayerEnum};
+
+    use self::tic_tac_toe::TicTacToe;
+    use super::*;
+
+    fn manager() -> SessionManager<TicTacToe> {
+        SessionManager::new(TicTacToe::new())
+    }
+
+    fn any_move(session: &Session<TicTacToe>) -> <TicTacToe as GameState>::Move {
+        session.state().all_legal_moves(session.to_move()).next().expect("No legal moves")
+    }
+
+    #[test]
+    fn explains_where_engine_moves_came_from() {
+        let mut sessions = manager();
+        let id = sessions.create(PlayerEnum::One, &MctsConfig::default());
+        let session = sessions.get_mut(id).unwrap();
+
+        let book_move = any_move(session);
+        session.play_engine_move(book_move, DecisionSource::Book).unwrap();
+        let explanation = session.engine().explain_last_move().cloned().unwrap();
+        assert_eq!((explanation.chosen_move, explanation.source), (book_move, DecisionSource::Book));
+        assert!(session.play_engine_move(any_move(session), DecisionSource::Book).is_err());
+
+        let reply = any_move(session);
+        session.play_move(reply).unwrap();
+        let searched = session.engine_move().unwrap();
+        let explanation = session.engine().explain_last_move().cloned().unwrap();
+        assert_eq!((explanation.chosen_move, explanation.source), (searched, DecisionSource::Search));
+        assert!(explanation.visit_share > 0.0);
+    }
+
+    #[test]
+    fn creates_independent_sessions() {
+        let mut sessions = manager();
+        let config = MctsConfig::default();
+        let first = sessions.create(PlayerEnum::One, &config);
+        let second = sessions.create(PlayerEnum::Two, &config);
+        assert_ne!(first, second);
+        assert_eq!(sessions.ids(), vec![first, second]);
+        assert_eq!(sessions.waiting(), vec![first]);
+
+        // The engine moves first in one, and the client in the other
+        assert!(sessions.advance(first, None).unwrap().is_some());
+        let game_move = any_move(sessions.get(second).unwrap());
+        assert!(sessions.advance(second, Some(game_move)).unwrap().is_some());
+        assert_ne!(sessions.get(first).unwrap().state(), &TicTacToe::new());
+        assert_eq!(sessions.advance(first, None), Ok(None), "The client should have to move");
+
+        assert!(sessions.close(first).is_ok());
+        assert!(sessions.close(first).is_err());
+        assert!(sessions.advance(first, None).is_err());
+        assert_eq!(sessions.len(), 1);
+    }
+
+    #[test]
+    fn closes_idle_sessions() {
+        let mut sessions = manager();
+        let clock = MockClock::new();
+        let config = MctsConfig::default();
+        let ids: Vec<SessionId> = (0..2).map(|_| sessions.create(PlayerEnum::Two, &config)).collect();
+        for &id in &ids {
+            sessions.get_mut(id).unwrap().engine().set_clock(clock.clone());
+        }
+        let game_move = any_move(sessions.get(ids[0]).unwrap());
+        sessions.advance(ids[0], Some(game_move)).unwrap();
+
+        clock.advance(Duration::from_secs(60));
+        let game_move = any_move(sessions.get(ids[1]).unwrap());
+        sessions.advance(ids[1], Some(game_move)).unwrap();
+        clock.advance(Duration::from_secs(30));
+
+        assert_eq!(sessions.close_idle(Duration::from_secs(60)), vec![ids[0]]);
+        assert_eq!(sessions.ids(), vec![ids[1]]);
+    }
+
+    #[test]
+    fn restores_sessions_from_snapshots() {
+        let mut sessions = manager();
+        let config = MctsConfig::default();
+        let first = sessions.create(PlayerEnum::One, &config);
+        let second = sessions.create(PlayerEnum::Two, &config);
+        sessions.advance(first, None).unwrap();
+        let limits = SessionLimits { max_nodes: Some(1000), max_move_time: None };
+        sessions.get_mut(second).unwrap().set_limits(limits);
+
+        let mut restored = manager();
+        for (id, snapshot) in sessions.snapshot() {
+            restored.restore(id, snapshot, &config).unwrap();
+        }
+        assert_eq!(restored.ids(), vec![first, second]);
+        for &id in &[first, second] {
+            let (before, after) = (sessions.get_mut(id).unwrap(), restored.get_mut(id).unwrap());
+            assert_eq!(after.state(), before.state());
+            assert_eq!(after.to_move(), before.to_move());
+            assert_eq!(after.thinking_time(), before.thinking_time());
+            assert_eq!(after.engine().node_count(), before.engine().node_count());
+        }
+        assert_eq!(restored.get(second).unwrap().limits(), limits);
+
+        // The restored games carry on, and new sessions don't reuse their ids
+        let game_move = any_move(restored.get(first).unwrap());
+        assert!(restored.advance(first, Some(game_move)).unwrap().is_some());
+        assert!(restored.create(PlayerEnum::One, &config) > second);
+        assert!(restored.restore(first, sessions.get(first).unwrap().snapshot(), &config).is_err());
+    }
+
+    #[test]
+    fn node_limit_cuts_searches_short() {
+        let mut sessions = manager();
+        let config = MctsConfig { budget: SearchBudget::Iterations(1_000_000), ..MctsConfig::default() };
+        let id = sessions.create(PlayerEnum::One, &config);
+        sessions.get_mut(id).unwrap().set_limits(SessionLimits { max_nodes: Some(50), max_move_time: None });
+        assert!(sessions.advance(id, None).unwrap().is_some());
+        // The limit is checked between slices, so it can be overrun by up to a slice
+        assert!(sessions.get_mut(id).unwrap().engine().node_count() <= 50 + BLOCKING_SLICE as usize);
+    }
+
+    #[test]
+    fn tiny_budgets_still_move() {
+        let mut sessions = manager();
+        for &budget in &[SearchBudget::Iterations(0), SearchBudget::Iterations(1), SearchBudget::Time(Duration::from_secs(0))] {
+            let id = sessions.create(PlayerEnum::One, &MctsConfig { budget, ..MctsConfig::default() });
+            assert!(sessions.advance(id, None).unwrap().is_some(), "No move with {:?}", budget);
+        }
+    }
+
+    #[test]
+    fn slices_share_out_the_searching() {
+        let mut sessions = manager();
+        let config = MctsConfig { budget: SearchBudget::Iterations(300), ..MctsConfig::default() };
+        let heavy = sessions.create(PlayerEnum::One, &MctsConfig { budget: SearchBudget::Iterations(1_000_000), ..MctsConfig::default() });
+        let light = sessions.create(PlayerEnum::One, &config);
+        let mut played = Vec::new();
+        for _ in 0..3 {
+            played.extend(sessions.run_slice(100));
+        }
+        assert_eq!(played.iter().map(|x| x.0).collect::<Vec<_>>(), vec![light]);
+        assert_eq!(sessions.waiting(), vec![heavy]);
+    }
+
+    #[test]
+    fn cancelling_ends_the_search() {
+        let mut sessions = manager();
+        let id = sessions.create(PlayerEnum::One, &MctsConfig { budget: SearchBudget::UntilStopped, ..MctsConfig::default() });
+        let cancellation = game::CancellationToken::new();
+        game::Player::set_cancellation_token(sessions.get_mut(id).unwrap().engine(), cancellation.clone());
+        assert!(sessions.run_slice(10).is_empty());
+        cancellation.cancel();
+        assert_eq!(sessions.run_slice(10).len(), 1);
+    }
+
+    #[test]
+    fn unbounded_searches_are_left_to_slices() {
+        let mut sessions = manager();
+        let id = sessions.create(PlayerEnum::One, &MctsConfig { budget: SearchBudget::UntilStopped, ..MctsConfig::default() });
+        assert_eq!(sessions.advance(id, None).unwrap(), None);
+        assert_eq!(sessions.waiting(), vec![id]);
+
+        let cancellation = game::CancellationToken::new();
+        game::Player::set_cancellation_token(sessions.get_mut(id).unwrap().engine(), cancellation.clone());
+        assert!(sessions.run_slice(10).is_empty());
+        cancellation.cancel();
+        assert!(sessions.advance(id, None).unwrap().is_some());
+    }
+
+    #[test]
+    fn time_limit_cuts_searches_short() {
+        let mut sessions = manager();
+        let config = MctsConfig { budget: SearchBudget::UntilStopped, ..MctsConfig::default() };
+        let id = sessions.create(PlayerEnum::One, &config);
+        sessions.get_mut(id).unwrap().set_limits(SessionLimits { max_nodes: None, max_move_time: Some(Duration::from_millis(20)) });
+        assert!(sessions.advance(id, None).unwrap().is_some());
+    }
+}
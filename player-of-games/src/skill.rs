@@ -0,0 +1,166 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use game::rng::random_f64;
+
+use SearchBudget;
+
+/// The fewest simulations a scaled budget is cut down to: a search of a new position takes one
+/// to create the root and another to find a move to play.
+const MIN_ITERATIONS: u32 = 2;
+
+/// How well the player plays, so that the same engine can give easy, medium or hard opponents.
+/// The default is full strength.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SkillLevel {
+    /// The fraction of the search budget to actually use, from 0 to 1.
+    pub budget_fraction: f64,
+    /// Rather than always playing the most searched move, play moves with probability in
+    /// proportion to `visits ^ (1 / temperature)`. At 0, the most searched move is always played.
+    pub temperature: f64,
+    /// The chance of playing one of the other searched moves (chosen uniformly) instead.
+    pub blunder_chance: f64,
+}
+
+impl SkillLevel {
+    pub fn easy() -> Self {
+        Self { budget_fraction: 0.05, temperature: 1.0, blunder_chance: 0.2 }
+    }
+
+    pub fn medium() -> Self {
+        Self { budget_fraction: 0.25, temperature: 0.5, blunder_chance: 0.05 }
+    }
+
+    pub fn hard() -> Self {
+        Self { budget_fraction: 0.6, temperature: 0.25, blunder_chance: 0.0 }
+    }
+
+    pub fn full() -> Self {
+        Self { budget_fraction: 1.0, temperature: 0.0, blunder_chance: 0.0 }
+    }
+
+    /// The part of `budget` to use. Searches which run until stopped are left alone, and iteration
+    /// budgets are left with enough simulations to find a move.
+    pub fn scale_budget(&self, budget: SearchBudget) -> SearchBudget {
+        match budget {
+            SearchBudget::Iterations(n) => SearchBudget::Iterations(((n as f64 * self.budget_fraction) as u32).max(MIN_ITERATIONS)),
+            SearchBudget::Time(duration) => SearchBudget::Time(Duration::from_secs_f64(duration.as_secs_f64() * self.budget_fraction)),
+            SearchBudget::UntilStopped => SearchBudget::UntilStopped,
+        }
+    }
+
+    /// Pick a move from a search's root policy, which is sorted from most to least searched.
+    pub fn choose_move<Move: Copy>(&self, policy: &[(Move, u32)]) -> Option<Move> {
+        let &(best, _) = policy.first()?;
+        if policy.len() > 1 && random_f64() < self.blunder_chance {
+            let i = 1 + (random_f64() * (policy.len() - 1) as f64) as usize;
+            return Some(policy[i.min(policy.len() - 1)].0);
+        }
+        if self.temperature <= 0.0 {
+            return Some(best);
+        }
+
+        let weights: Vec<f64> = policy.iter().map(|&(_, visits)| (visits as f64).powf(1.0 / self.temperature)).collect();
+        let mut target = random_f64() * weights.iter().sum::<f64>();
+        for (&(game_move, _), weight) in policy.iter().zip(weights) {
+            if target < weight {
+                return Some(game_move);
+            }
+            target -= weight;
+        }
+        Some(best)
+    }
+}
+
+impl Default for SkillLevel {
+    fn default() -> Self {
+        Self::full()
+    }
+}
+
+impl FromStr for SkillLevel {
+    type Err = String;
+
+    /// One of "easy", "medium", "hard" or "full".
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "easy" => Ok(Self::easy()),
+            "medium" => Ok(Self::medium()),
+            "hard" => Ok(Self::hard()),
+            "full" => Ok(Self::full()),
+            _ => Err(format!("Unknown skill level: {}", s)),
+        }
+    }
+}
+
+/// Adjusts the skill level between games to keep the opponent's score near a target, e.g. so a
+/// learner wins about half their games however good they get.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct AdaptiveSkill {
+    /// The score (1 for a win, a half for a draw) the opponent should average, from 0 to 1.
+    pub target_score: f64,
+    /// How far to move the strength after each game, for each point the opponent's result was off
+    /// target.
+    pub step: f64,
+    strength: f64,
+    games: u32,
+    opponent_points: f64,
+}
+
+impl AdaptiveSkill {
+    /// Start at half strength.
+    pub fn new(target_score: f64) -> Self {
+        Self {
+            target_score,
+            step: 0.1,
+            strength: 0.5,
+            games: 0,
+            opponent_points: 0.0,
+        }
+    }
+
+    /// Take a game into account, in which the opponent scored `opponent_score`.
+    pub fn record(&mut self, opponent_score: f64) {
+        self.games += 1;
+        self.opponent_points += opponent_score;
+        self.strength = (self.strength + self.step * (opponent_score - self.target_score)).clamp(0.0, 1.0);
+    }
+
+    /// From 0 (weakest) to 1 (full strength).
+    pub fn strength(&self) -> f64 {
+        self.strength
+    }
+
+    /// The opponent's average score so far, if any games have been recorded.
+    pub fn opponent_score(&self) -> Option<f64> {
+        match self.games {
+            0 => None,
+            games => Some(self.opponent_points / games as f64),
+        }
+    }
+
+    /// The skill level for the current strength, somewhere between a fraction of `easy` and
+    /// `full`.
+    pub fn skill_level(&self) -> SkillLevel {
+        let lerp = |weakest: f64, strongest: f64| weakest + (strongest - weakest) * self.strength;
+        SkillLevel {
+            budget_fraction: lerp(0.02, 1.0),
+            temperature: lerp(1.5, 0.0),
+            blunder_chance: lerp(0.3, 0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_budgets_can_still_find_a_move() {
+        assert_eq!(SkillLevel::easy().scale_budget(SearchBudget::Iterations(10)), SearchBudget::Iterations(MIN_ITERATIONS));
+        assert_eq!(SkillLevel::easy().scale_budget(SearchBudget::Iterations(1000)), SearchBudget::Iterations(50));
+        assert_eq!(SkillLevel::full().scale_budget(SearchBudget::UntilStopped), SearchBudget::UntilStopped);
+    }
+}
@@ -0,0 +1,230 @@
+//! A map for the handful of links most nodes have.
+//!
+//! Most nodes only have a few children and one or two parents, so a hash map each is a lot of
+//! allocation for not much. Small maps keep their entries inline in the node, and search them
+//! linearly. Maps which grow past `LINEAR_SEARCH_LIMIT` entries switch to a hash map.
+//!
+//! The entries aren't sorted: `GameState::Move` is only `Hash + Eq`, and parents are keyed by
+//! states, so there's no order to keep them in without asking every game for one. Up to the limit
+//! a linear search is no slower than a binary one would be anyway.
+
+use std::collections::hash_map;
+use std::hash::Hash;
+use std::ops::Index;
+use std::slice;
+
+use smallvec::{self, Array, SmallVec};
+
+use node::Map;
+
+/// How many entries to search linearly before switching to a hash map.
+const LINEAR_SEARCH_LIMIT: usize = 16;
+
+/// A map keeping up to the size of `A` entries inline.
+pub enum SmallMap<K, V, A: Array<Item = (K, V)>> {
+    Inline(SmallVec<A>),
+    Hashed(Map<K, V>),
+}
+
+impl<K: Hash + Eq, V, A: Array<Item = (K, V)>> SmallMap<K, V, A> {
+    pub fn new() -> Self {
+        SmallMap::Inline(SmallVec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        match *self {
+            SmallMap::Inline(ref entries) => entries.len(),
+            SmallMap::Hashed(ref map) => map.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many entries there's room for outside the map itself, i.e. on the heap.
+    pub fn heap_capacity(&self) -> usize {
+        match *self {
+            SmallMap::Inline(ref entries) if entries.spilled() => entries.capacity(),
+            SmallMap::Inline(_) => 0,
+            SmallMap::Hashed(ref map) => map.capacity(),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        match *self {
+            SmallMap::Inline(ref entries) => entries.iter().find(|entry| entry.0 == *key).map(|entry| &entry.1),
+            SmallMap::Hashed(ref map) => map.get(key),
+        }
+    }
+
+    /// Add an entry, returning the old value for `key` if there was one.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let entries = match *self {
+            SmallMap::Inline(ref mut entries) => entries,
+            SmallMap::Hashed(ref mut map) => return map.insert(key, value),
+        };
+        if let Some(entry) = entries.iter_mut().find(|entry| entry.0 == key) {
+            return Some(::std::mem::replace(&mut entry.1, value));
+        }
+        if entries.len() < LINEAR_SEARCH_LIMIT {
+            entries.push((key, value));
+            return None;
+        }
+
+        let mut map: Map<K, V> = entries.drain(..).collect();
+        map.insert(key, value);
+        *self = SmallMap::Hashed(map);
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        match *self {
+            SmallMap::Inline(ref mut entries) => {
+                let index = entries.iter().position(|entry| entry.0 == *key)?;
+                Some(entries.remove(index).1)
+            }
+            SmallMap::Hashed(ref mut map) => map.remove(key),
+        }
+    }
+
+    /// Keep only the entries for which `keep` returns true.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut keep: F) {
+        match *self {
+            SmallMap::Inline(ref mut entries) => entries.retain(|entry| keep(&entry.0, &mut entry.1)),
+            SmallMap::Hashed(ref mut map) => map.retain(|k, v| keep(k, v)),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        match *self {
+            SmallMap::Inline(ref entries) => Iter::Inline(entries.iter()),
+            SmallMap::Hashed(ref map) => Iter::Hashed(map.iter()),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|entry| entry.0)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|entry| entry.1)
+    }
+}
+
+impl<K: Hash + Eq, V, A: Array<Item = (K, V)>> Default for SmallMap<K, V, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq, V, A: Array<Item = (K, V)>> Index<&K> for SmallMap<K, V, A> {
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("No entry for key")
+    }
+}
+
+pub enum Iter<'a, K: 'a, V: 'a> {
+    Inline(slice::Iter<'a, (K, V)>),
+    Hashed(hash_map::Iter<'a, K, V>),
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        match *self {
+            Iter::Inline(ref mut entries) => entries.next().map(|entry| (&entry.0, &entry.1)),
+            Iter::Hashed(ref mut entries) => entries.next(),
+        }
+    }
+}
+
+pub enum IntoIter<K, V, A: Array<Item = (K, V)>> {
+    Inline(smallvec::IntoIter<A>),
+    Hashed(hash_map::IntoIter<K, V>),
+}
+
+impl<K, V, A: Array<Item = (K, V)>> Iterator for IntoIter<K, V, A> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        match *self {
+            IntoIter::Inline(ref mut entries) => entries.next(),
+            IntoIter::Hashed(ref mut entries) => entries.next(),
+        }
+    }
+}
+
+impl<K, V, A: Array<Item = (K, V)>> IntoIterator for SmallMap<K, V, A> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, A>;
+
+    fn into_iter(self) -> IntoIter<K, V, A> {
+        match self {
+            SmallMap::Inline(entries) => IntoIter::Inline(entries.into_iter()),
+            SmallMap::Hashed(map) => IntoIter::Hashed(map.into_iter()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestMap = SmallMap<u32, u32, [(u32, u32); 4]>;
+
+    fn filled(count: u32) -> TestMap {
+        let mut map = TestMap::new();
+        for key in 0..count {
+            assert_eq!(map.insert(key, key * 10), None);
+        }
+        map
+    }
+
+    #[test]
+    fn inserts_replaces_and_looks_up_inline() {
+        let mut map = filled(3);
+        assert_eq!(map.heap_capacity(), 0);
+        assert_eq!(map.insert(1, 11), Some(10));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(map[&2], 20);
+        assert_eq!(map.get(&3), None);
+        assert_eq!(map.remove(&0), Some(0));
+        assert_eq!(map.remove(&0), None);
+        assert_eq!(map.keys().cloned().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn keeps_every_entry_across_the_switch_to_a_hash_map() {
+        for &count in &[LINEAR_SEARCH_LIMIT as u32, LINEAR_SEARCH_LIMIT as u32 + 1, 3 * LINEAR_SEARCH_LIMIT as u32] {
+            let mut map = filled(count);
+            match map {
+                SmallMap::Inline(_) => assert!(count as usize <= LINEAR_SEARCH_LIMIT),
+                SmallMap::Hashed(_) => assert!(count as usize > LINEAR_SEARCH_LIMIT),
+            }
+            assert_eq!(map.len(), count as usize);
+            for key in 0..count {
+                assert_eq!(map.get(&key), Some(&(key * 10)));
+                assert_eq!(map.insert(key, key * 10 + 1), Some(key * 10));
+            }
+            assert_eq!(map.len(), count as usize);
+            assert_eq!(map.get(&count), None);
+
+            map.retain(|key, _| key % 2 == 0);
+            let mut entries: Vec<(u32, u32)> = map.into_iter().collect();
+            entries.sort();
+            assert_eq!(entries, (0..count).filter(|key| key % 2 == 0).map(|key| (key, key * 10 + 1)).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn replacing_at_the_limit_does_not_switch() {
+        let mut map = filled(LINEAR_SEARCH_LIMIT as u32);
+        assert_eq!(map.insert(0, 1), Some(0));
+        assert!(matches!(map, SmallMap::Inline(_)));
+    }
+}
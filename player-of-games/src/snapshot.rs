@@ -0,0 +1,170 @@
+//! Saving everything a tree search player has built up, so that it can carry on later (e.g. after
+//! the server hosting it restarts) without losing its search.
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+
+use game::{self, GameState, PlayerEnum};
+
+use expansion::Prior;
+use node::{Node, NodeStats};
+use {AdaptiveSkill, MonteCarloTreeSearchPlayer, RolloutEnhancements, RolloutLimits, SearchBudget, SkillLevel};
+
+/// A tree search player's state, from `MonteCarloTreeSearchPlayer::snapshot`. With the
+/// `serialize` feature this can be saved with serde.
+///
+/// This covers the tree, the settings, what the rollout enhancements have learnt and the random
+/// number generator. It doesn't cover the policies and callbacks which can be plugged into the
+/// player (reward model, rollout policy, cutoff heuristic, expansion policy, leaf parallelism,
+/// search threads and progress callback): those should be set up again on the player being
+/// restored into. Nor does it cover the evaluation cache, which is left as it is.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(bound(
+    serialize = "Game: ::serde::Serialize, <Game as GameState>::Move: ::serde::Serialize",
+    deserialize = "Game: ::serde::Deserialize<'de>, <Game as GameState>::Move: ::serde::Deserialize<'de>",
+)))]
+pub struct Snapshot<Game: GameState> {
+    player: PlayerEnum,
+    c: f64,
+    budget: SearchBudget,
+    discount: f64,
+    rollout_limits: RolloutLimits,
+    rollout_enhancements: RolloutEnhancements,
+    gc_threshold: Option<usize>,
+    skill: SkillLevel,
+    adaptive_skill: Option<AdaptiveSkill>,
+    search_moves: Option<Vec<<Game as GameState>::Move>>,
+    excluded_moves: Vec<<Game as GameState>::Move>,
+    last_turn: Option<Game>,
+    last_evaluation: Option<f64>,
+    /// Every node in the tree. Links between nodes are indices into this list.
+    nodes: Vec<NodeSnapshot<Game>>,
+    mast: Vec<(PlayerEnum, <Game as GameState>::Move, f64, f64)>,
+    replies: Vec<(PlayerEnum, <Game as GameState>::Move, <Game as GameState>::Move)>,
+    /// See `game::rng::thread_rng_state`.
+    rng: Option<[u32; 4]>,
+}
+
+impl<Game: GameState> Snapshot<Game> {
+    /// How many nodes the saved tree has.
+    pub fn nodes(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+/// One node of a saved tree.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(bound(
+    serialize = "Game: ::serde::Serialize, <Game as GameState>::Move: ::serde::Serialize",
+    deserialize = "Game: ::serde::Deserialize<'de>, <Game as GameState>::Move: ::serde::Deserialize<'de>",
+)))]
+struct NodeSnapshot<Game: GameState> {
+    state: Game,
+    player: PlayerEnum,
+    visits: u32,
+    total_reward: f64,
+    prior: Option<Prior>,
+    children: Vec<(<Game as GameState>::Move, usize)>,
+}
+
+impl<Game: game::GameState, S: BuildHasher + Default> MonteCarloTreeSearchPlayer<Game, S> {
+    /// Capture everything needed to carry on searching later, with `restore`. The random number
+    /// generator captured is this thread's.
+    pub fn snapshot(&self) -> Snapshot<Game> {
+        let index: HashMap<&Game, usize> = self.explored_states.keys().enumerate().map(|(i, state)| (&**state, i)).collect();
+        let nodes = self.explored_states.iter().map(|(state, node)| NodeSnapshot {
+            state: (**state).clone(),
+            player: node.player,
+            visits: node.stats.visits(),
+            total_reward: node.stats.total_reward(),
+            prior: node.prior,
+            children: node.children.iter().map(|(&game_move, child)| (game_move, *index.get(&**child).expect("Dangling pointer"))).collect(),
+        }).collect();
+        let (mast, replies) = self.rollout_tables.learnt();
+
+        Snapshot {
+            player: self.player,
+            c: self.c,
+            budget: self.budget,
+            discount: self.discount,
+            rollout_limits: self.rollout_limits,
+            rollout_enhancements: self.rollout_tables.settings,
+            gc_threshold: self.gc_threshold,
+            skill: self.skill,
+            adaptive_skill: self.adaptive_skill,
+            search_moves: self.search_moves.clone(),
+            excluded_moves: self.excluded_moves.clone(),
+            last_turn: self.last_turn.clone(),
+            last_evaluation: self.last_evaluation,
+            nodes,
+            mast,
+            replies,
+            rng: game::rng::thread_rng_state(),
+        }
+    }
+
+    /// Carry on from a snapshot, replacing this player's tree and settings (apart from those the
+    /// snapshot doesn't cover). This thread's random number generator carries on from the
+    /// snapshot's too.
+    ///
+    /// Fails, leaving the player as it was, if the snapshot's tree doesn't hang together.
+    pub fn restore(&mut self, snapshot: Snapshot<Game>) -> Result<(), String> {
+        let count = snapshot.nodes.len();
+        let mut parents = vec![Vec::new(); count];
+        for (i, node) in snapshot.nodes.iter().enumerate() {
+            for &(game_move, child) in &node.children {
+                if child >= count {
+                    return Err(format!("Node {} has a child {}, but there are only {} nodes", i, child, count));
+                }
+                parents[child].push((game_move, i));
+            }
+        }
+
+        let mut states = Vec::with_capacity(count);
+        let mut nodes = Vec::with_capacity(count);
+        for node in snapshot.nodes {
+            let NodeSnapshot { state, player, visits, total_reward, prior, children } = node;
+            states.push(Arc::new(state));
+            let mut restored = Node::new(player, None);
+            restored.stats = NodeStats::new(visits, total_reward);
+            restored.prior = prior;
+            nodes.push((restored, children));
+        }
+
+        let mut explored_states = HashMap::default();
+        for (((mut node, children), parents), state) in nodes.into_iter().zip(parents).zip(&states) {
+            for (game_move, child) in children {
+                node.children.insert(game_move, states[child].clone());
+            }
+            for (game_move, parent) in parents {
+                node.parents.insert(states[parent].clone(), game_move);
+            }
+            explored_states.insert(state.clone(), node);
+        }
+        if explored_states.len() != count {
+            return Err("The same state appears in more than one node".to_string());
+        }
+
+        self.player = snapshot.player;
+        self.c = snapshot.c;
+        self.budget = snapshot.budget;
+        self.discount = snapshot.discount;
+        self.rollout_limits = snapshot.rollout_limits;
+        self.rollout_tables.settings = snapshot.rollout_enhancements;
+        self.rollout_tables.set_learnt(snapshot.mast, snapshot.replies);
+        self.gc_threshold = snapshot.gc_threshold;
+        self.skill = snapshot.skill;
+        self.adaptive_skill = snapshot.adaptive_skill;
+        self.search_moves = snapshot.search_moves;
+        self.excluded_moves = snapshot.excluded_moves;
+        self.last_turn = snapshot.last_turn;
+        self.last_evaluation = snapshot.last_evaluation;
+        self.last_explanation = None;
+        self.explored_states = explored_states;
+        game::rng::set_thread_rng_state(snapshot.rng);
+        Ok(())
+    }
+}
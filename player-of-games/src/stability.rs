@@ -0,0 +1,98 @@
+//! How a search's choice of move holds up as it's given more simulations, e.g. to choose a budget
+//! for a game, or to spot positions where a deeper search changes its mind (horizon effects).
+
+use std::fmt;
+
+use game::{GameState, PlayerEnum};
+
+use self_play::MctsConfig;
+use SearchBudget;
+
+/// What the search made of the position after one of the budgets.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct StabilityStep<Move> {
+    /// Simulations run so far.
+    pub simulations: u32,
+    pub best_move: Option<Move>,
+    /// The estimated chance of winning (from 0 to 1) after the best move.
+    pub evaluation: Option<f64>,
+    /// Whether the best move is different from the previous budget's.
+    pub changed: bool,
+}
+
+/// The search's best move at each budget, from `decision_stability`. Shown as a table, one line
+/// per budget.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct StabilityReport<Move> {
+    pub steps: Vec<StabilityStep<Move>>,
+}
+
+impl<Move: Copy + PartialEq> StabilityReport<Move> {
+    /// The best move after the biggest budget.
+    pub fn final_move(&self) -> Option<Move> {
+        self.steps.last().and_then(|step| step.best_move)
+    }
+
+    /// How many times the best move changed.
+    pub fn changes(&self) -> usize {
+        self.steps.iter().filter(|step| step.changed).count()
+    }
+
+    /// The smallest budget from which the search never changed its mind again, i.e. enough
+    /// simulations for this position (as far as the biggest budget can tell).
+    pub fn settled_at(&self) -> Option<u32> {
+        let unchanged = self.steps.iter().rev().take_while(|step| !step.changed).count();
+        let first_settled = self.steps.len().saturating_sub(unchanged + 1);
+        self.steps.get(first_settled).map(|step| step.simulations)
+    }
+}
+
+impl<Move: fmt::Debug + Copy + PartialEq> fmt::Display for StabilityReport<Move> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for step in &self.steps {
+            let evaluation = step.evaluation.map_or("-".to_string(), |x| format!("{:.3}", x));
+            writeln!(f, "{:>10}  {:<20}  {:>6}{}", step.simulations, format!("{:?}", step.best_move), evaluation, if step.changed { "  changed" } else { "" })?;
+        }
+        write!(f, "{} change(s), settled at {:?} simulations", self.changes(), self.settled_at())
+    }
+}
+
+/// Search `state`, with `to_move` to move, up to each of `budgets` simulations in turn (which
+/// should be increasing), and report the best move after each. The search carries on from one
+/// budget to the next, so this costs as much as a single search with the biggest budget.
+///
+/// The engine is set up as `config`, apart from its budget.
+pub fn decision_stability<Game: GameState>(state: &Game, to_move: PlayerEnum, budgets: &[u32], config: &MctsConfig<Game>) -> StabilityReport<<Game as GameState>::Move> {
+    let mut mcts = config.build(to_move);
+    let mut steps: Vec<StabilityStep<<Game as GameState>::Move>> = Vec::new();
+    let mut simulations = 0;
+    for &budget in budgets {
+        mcts.set_budget(SearchBudget::Iterations(budget.saturating_sub(simulations)));
+        let analysis = mcts.analyse(state);
+        simulations = simulations.max(budget);
+        let changed = steps.last().is_some_and(|previous| previous.best_move != analysis.best_move);
+        steps.push(StabilityStep {
+            simulations,
+            best_move: analysis.best_move,
+            evaluation: analysis.evaluation,
+            changed,
+        });
+    }
+    StabilityReport { steps }
+}
+
+/// Budgets doubling from `from` up to `to` (inclusive), for `decision_stability`.
+pub fn doubling_budgets(from: u32, to: u32) -> Vec<u32> {
+    let mut budgets = Vec::new();
+    let mut budget = from.max(1);
+    while budget <= to {
+        budgets.push(budget);
+        budget = match budget.checked_mul(2) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    budgets
+}
@@ -0,0 +1,118 @@
+//! Counters of what the search spends its time on, for profiling. They're only kept with the
+//! `stats` feature, so that they cost nothing otherwise.
+
+#[cfg(feature = "stats")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How much of each kind of work the search has done. Without the `stats` feature, these are
+/// always zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Steps taken down the tree while selecting leaves.
+    pub selection_steps: u64,
+    /// New nodes added to the tree.
+    pub expansions: u64,
+    /// States reached during selection which were already in the tree.
+    pub cache_hits: u64,
+    /// Moves played in random simulations.
+    pub rollout_plies: u64,
+    /// Game states cloned by the tree search (not counting simulations).
+    pub state_clones: u64,
+    /// The threads the playouts run on. This is filled in whether or not the `stats` feature is on.
+    pub topology: Topology,
+}
+
+/// The threads the playouts run on, as they turned out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Topology {
+    /// 1 without leaf parallelism, when the playouts run on the searching thread.
+    pub threads: usize,
+    /// How many of the threads are pinned to a core.
+    pub pinned: usize,
+    /// How many NUMA nodes the process's cores are on, which the threads were pinned across. 0 on
+    /// rayon's global pool, where nobody looked.
+    pub numa_nodes: usize,
+}
+
+/// The live counters behind `SearchStats`. These can be updated from several threads at once.
+#[cfg(feature = "stats")]
+#[derive(Debug, Default)]
+pub struct Counters {
+    selection_steps: AtomicU64,
+    expansions: AtomicU64,
+    cache_hits: AtomicU64,
+    rollout_plies: AtomicU64,
+    state_clones: AtomicU64,
+}
+
+#[cfg(feature = "stats")]
+impl Counters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn selection_step(&self) {
+        self.selection_steps.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn expansion(&self) {
+        self.expansions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn rollout_plies(&self, plies: u32) {
+        self.rollout_plies.fetch_add(plies as u64, Ordering::Relaxed);
+    }
+
+    /// Clone `state`, counting it.
+    pub fn clone_state<T: Clone>(&self, state: &T) -> T {
+        self.state_clones.fetch_add(1, Ordering::Relaxed);
+        state.clone()
+    }
+
+    pub fn snapshot(&self) -> SearchStats {
+        SearchStats {
+            selection_steps: self.selection_steps.load(Ordering::Relaxed),
+            expansions: self.expansions.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            rollout_plies: self.rollout_plies.load(Ordering::Relaxed),
+            state_clones: self.state_clones.load(Ordering::Relaxed),
+            topology: Topology::default(),
+        }
+    }
+}
+
+#[cfg(not(feature = "stats"))]
+#[derive(Debug, Default)]
+pub struct Counters;
+
+#[cfg(not(feature = "stats"))]
+impl Counters {
+    pub fn new() -> Self {
+        Counters
+    }
+
+    #[inline(always)]
+    pub fn selection_step(&self) {}
+
+    #[inline(always)]
+    pub fn expansion(&self) {}
+
+    #[inline(always)]
+    pub fn cache_hit(&self) {}
+
+    #[inline(always)]
+    pub fn rollout_plies(&self, _plies: u32) {}
+
+    #[inline(always)]
+    pub fn clone_state<T: Clone>(&self, state: &T) -> T {
+        state.clone()
+    }
+
+    pub fn snapshot(&self) -> SearchStats {
+        SearchStats::default()
+    }
+}
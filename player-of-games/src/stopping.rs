@@ -0,0 +1,198 @@
+//! Rules for ending a search sooner (or later) than its budget says, going by how the moves at
+//! the root are doing.
+
+use std::time::Duration;
+
+/// What the search knows about one of the root's moves.
+#[derive(Debug, Clone, Copy)]
+pub struct RootChild {
+    pub visits: u32,
+    /// The move's estimated chance of winning, if it's been searched.
+    pub value: Option<f64>,
+}
+
+impl RootChild {
+    /// The value, give or take `confidence` standard errors.
+    fn bounds(&self, confidence: f64) -> (f64, f64) {
+        match self.value {
+            Some(value) if self.visits > 0 => {
+                let error = confidence * (value * (1.0 - value) / self.visits as f64).sqrt();
+                (value - error, value + error)
+            },
+            _ => (0.0, 1.0),
+        }
+    }
+}
+
+/// Spend less than the budget when one move is clearly best, and more when the best two moves
+/// are too close to call, so that a fixed amount of time for a whole game goes where it's needed.
+///
+/// The search stops once it's spent `min_fraction` of its budget if the most searched move's
+/// value is clearly better than the runner up's (their values, give or take `confidence`
+/// standard errors, don't overlap). Otherwise it carries on, past the end of the budget if need
+/// be, until that happens or it's spent `max_fraction` of the budget. This applies to iteration
+/// and time budgets, but not to searches which run until stopped.
+///
+/// Going over budget is paid for out of a time bank, which is what earlier searches in the same
+/// game saved by stopping early, so the game as a whole still takes no more than its budget per
+/// move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct AdaptiveBudget {
+    pub min_fraction: f64,
+    pub max_fraction: f64,
+    /// E.g. 2 for roughly 95% confidence.
+    pub confidence: f64,
+}
+
+impl Default for AdaptiveBudget {
+    fn default() -> Self {
+        Self {
+            min_fraction: 0.25,
+            max_fraction: 2.0,
+            confidence: 2.0,
+        }
+    }
+}
+
+impl AdaptiveBudget {
+    /// The most of the budget (as a fraction) a search may spend, with `banked` budgets saved.
+    pub fn limit(&self, banked: f64) -> f64 {
+        self.max_fraction.min(1.0 + banked.max(0.0))
+    }
+
+    /// Whether to stop, having spent `spent` of the budget (as a fraction) with `banked` budgets
+    /// saved, and with `children` sorted from most to least visited.
+    pub fn should_stop(&self, spent: f64, banked: f64, children: &[RootChild]) -> bool {
+        if spent >= self.limit(banked) {
+            return true;
+        }
+        spent >= self.min_fraction && self.clearly_best(children)
+    }
+
+    fn clearly_best(&self, children: &[RootChild]) -> bool {
+        match (children.first(), children.get(1)) {
+            (Some(best), Some(runner_up)) => best.bounds(self.confidence).0 > runner_up.bounds(self.confidence).1,
+            // There's no choice to make.
+            _ => true,
+        }
+    }
+}
+
+/// Whether the most visited move will still be the most visited (and so chosen) however
+/// `remaining` more simulations go, with `children` sorted from most to least visited.
+pub fn lead_is_safe(children: &[RootChild], remaining: f64) -> bool {
+    match (children.first(), children.get(1)) {
+        (Some(best), Some(runner_up)) => (best.visits - runner_up.visits) as f64 > remaining,
+        // The only move is safe, once it's been searched at all
+        (Some(best), None) => best.visits > 0,
+        _ => false,
+    }
+}
+
+/// How many more simulations can be run in `left`, going by how long `iterations` took.
+pub fn simulations_in(left: Duration, iterations: u32, elapsed: Duration) -> f64 {
+    if iterations == 0 {
+        return f64::INFINITY;
+    }
+    left.as_secs_f64() * iterations as f64 / elapsed.as_secs_f64()
+}
+
+/// Stop once the share of simulations going to each of the root's moves has settled: every
+/// `interval` simulations, the root's visits are compared with the last time, and the search
+/// stops if the KL divergence between the two is below `epsilon`.
+///
+/// This gives analysis of a consistent quality whatever the position (e.g. with a search which
+/// runs until stopped), and saves time in positions where nothing much separates the moves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct KlStop {
+    pub interval: u32,
+    pub epsilon: f64,
+}
+
+impl KlStop {
+    /// Whether the visits have settled, from `previous` to `current` (each move's visits, in
+    /// the same order).
+    pub fn settled(&self, previous: &[u32], current: &[u32]) -> bool {
+        divergence(previous, current) < self.epsilon
+    }
+}
+
+/// The KL divergence of the `current` distribution of visits from the `previous` one. Each
+/// move counts one more visit than it has, so that moves with none don't divide by zero.
+pub fn divergence(previous: &[u32], current: &[u32]) -> f64 {
+    let total = |visits: &[u32]| visits.iter().map(|&x| x as f64 + 1.0).sum::<f64>();
+    let (previous_total, current_total) = (total(previous), total(current));
+    previous.iter().zip(current).map(|(&q, &p)| {
+        let p = (p as f64 + 1.0) / current_total;
+        let q = (q as f64 + 1.0) / previous_total;
+        p * (p / q).ln()
+    }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn child(visits: u32, value: f64) -> RootChild {
+        RootChild { visits, value: Some(value) }
+    }
+
+    #[test]
+    fn banked_budget_extends_the_limit() {
+        let adaptive = AdaptiveBudget::default();
+        assert_eq!(adaptive.limit(0.0), 1.0);
+        assert_eq!(adaptive.limit(0.5), 1.5);
+        assert_eq!(adaptive.limit(5.0), adaptive.max_fraction);
+        assert_eq!(adaptive.limit(-1.0), 1.0);
+    }
+
+    #[test]
+    fn stops_early_only_with_a_clear_leader() {
+        let adaptive = AdaptiveBudget::default();
+        let clear = [child(900, 0.9), child(100, 0.2)];
+        let close = [child(500, 0.55), child(480, 0.54)];
+        assert!(!adaptive.should_stop(0.1, 0.0, &clear), "Stopped before the minimum");
+        assert!(adaptive.should_stop(0.3, 0.0, &clear));
+        assert!(!adaptive.should_stop(0.3, 0.0, &close));
+        assert!(!adaptive.should_stop(1.2, 1.0, &close), "Didn't use the bank");
+        assert!(adaptive.should_stop(1.0, 0.0, &close), "Went over budget with nothing banked");
+        assert!(adaptive.should_stop(2.0, 5.0, &close), "Went past the maximum");
+    }
+
+    #[test]
+    fn unsearched_moves_are_never_clearly_worse() {
+        let adaptive = AdaptiveBudget::default();
+        let children = [child(10, 0.9), RootChild { visits: 0, value: None }];
+        assert!(!adaptive.should_stop(0.5, 0.0, &children));
+    }
+
+    #[test]
+    fn lead_is_safe_when_it_cant_be_caught() {
+        let children = [child(100, 0.6), child(40, 0.5)];
+        assert!(lead_is_safe(&children, 59.0));
+        assert!(!lead_is_safe(&children, 60.0));
+        assert!(!lead_is_safe(&[], 0.0));
+    }
+
+    #[test]
+    fn only_move_is_safe_once_searched() {
+        assert!(lead_is_safe(&[child(1, 0.5)], 1000.0));
+        assert!(!lead_is_safe(&[RootChild { visits: 0, value: None }], 1000.0));
+    }
+
+    #[test]
+    fn estimates_simulations_left_from_the_rate_so_far() {
+        assert_eq!(simulations_in(Duration::from_secs(2), 100, Duration::from_secs(1)), 200.0);
+        assert_eq!(simulations_in(Duration::from_secs(2), 0, Duration::from_secs(0)), f64::INFINITY);
+    }
+
+    #[test]
+    fn settles_once_the_visits_stop_shifting() {
+        let kl = KlStop { interval: 100, epsilon: 0.01 };
+        assert_eq!(divergence(&[10, 20, 30], &[10, 20, 30]), 0.0);
+        assert!(kl.settled(&[100, 200, 300], &[110, 210, 320]));
+        assert!(!kl.settled(&[100, 200, 300], &[400, 200, 300]));
+    }
+}
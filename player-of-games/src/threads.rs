@@ -0,0 +1,174 @@
+//! Where the parallel playouts run. By default they share rayon's global pool, and the operating
+//! system moves its threads between cores as it likes. On a machine with several sockets, a
+//! playout can then run on one socket while the state it's playing out sits in another's memory.
+//!
+//! `SearchThreads` is a pool of the player's own, with each thread pinned to a core. Each thread
+//! clones the leaf it plays out from itself, so with the usual first-touch memory policy its
+//! states are allocated on its own NUMA node, and stay there.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use core_affinity::{self, CoreId};
+use rayon;
+
+use stats::Topology;
+
+/// Which cores the search threads are pinned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pinning {
+    /// Leave it to the operating system.
+    None,
+    /// Fill one NUMA node before moving onto the next, so that a pool no bigger than a node stays
+    /// on one socket.
+    Compact,
+    /// Take a core from each NUMA node in turn, to use every socket's memory bandwidth.
+    Scatter,
+}
+
+impl FromStr for Pinning {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Pinning::None),
+            "compact" => Ok(Pinning::Compact),
+            "scatter" => Ok(Pinning::Scatter),
+            _ => Err(format!("Unknown pinning {:?}: expected none, compact or scatter", s)),
+        }
+    }
+}
+
+/// How to set up the search threads. See `MonteCarloTreeSearchPlayer::set_search_threads`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThreadSettings {
+    /// How many threads to run playouts on. 0 means one for each core the process may use.
+    pub threads: usize,
+    pub pinning: Pinning,
+}
+
+impl Default for ThreadSettings {
+    fn default() -> Self {
+        Self {
+            threads: 0,
+            pinning: Pinning::Compact,
+        }
+    }
+}
+
+/// A thread pool for playouts, with its threads pinned to cores.
+#[derive(Debug)]
+pub struct SearchThreads {
+    pool: rayon::ThreadPool,
+    /// How many threads have been pinned so far. Threads pin themselves as they start, and
+    /// pinning can fail (e.g. if the core has been taken away from the process).
+    pinned: Arc<AtomicUsize>,
+    numa_nodes: usize,
+}
+
+impl SearchThreads {
+    pub fn new(settings: ThreadSettings) -> Result<Self, String> {
+        let nodes = numa_nodes();
+        let cores: Vec<CoreId> = match settings.pinning {
+            Pinning::None => Vec::new(),
+            Pinning::Compact => nodes.concat(),
+            Pinning::Scatter => {
+                let widest = nodes.iter().map(|node| node.len()).max().unwrap_or(0);
+                (0..widest).flat_map(|i| nodes.iter().filter_map(move |node| node.get(i).cloned())).collect()
+            },
+        };
+        let threads = match settings.threads {
+            // Zero leaves it to rayon if we couldn't find any cores
+            0 => nodes.iter().map(|node| node.len()).sum(),
+            threads => threads,
+        };
+
+        let pinned = Arc::new(AtomicUsize::new(0));
+        let pinned_by_threads = pinned.clone();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("playouts-{}", index))
+            .start_handler(move |index| {
+                // With more threads than cores, the cores are shared out again from the start
+                if !cores.is_empty() && core_affinity::set_for_current(cores[index % cores.len()]) {
+                    pinned_by_threads.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .build()
+            .map_err(|error| format!("Couldn't start the search threads: {}", error))?;
+
+        Ok(Self {
+            pool,
+            pinned,
+            numa_nodes: nodes.len(),
+        })
+    }
+
+    /// Run `op` in the pool, so that rayon's parallel iterators in it use these threads.
+    pub fn install<R: Send, Op: FnOnce() -> R + Send>(&self, op: Op) -> R {
+        self.pool.install(op)
+    }
+
+    pub fn topology(&self) -> Topology {
+        Topology {
+            threads: self.pool.current_num_threads(),
+            pinned: self.pinned.load(Ordering::Relaxed),
+            numa_nodes: self.numa_nodes,
+        }
+    }
+}
+
+/// The cores this process may use, grouped by NUMA node. Where the nodes can't be found, all the
+/// cores are put in one.
+fn numa_nodes() -> Vec<Vec<CoreId>> {
+    let cores = core_affinity::get_core_ids().unwrap_or_default();
+    let nodes: Vec<Vec<CoreId>> = node_cpus().iter()
+        .map(|cpus| cores.iter().filter(|core| cpus.contains(&core.id)).cloned().collect::<Vec<_>>())
+        .filter(|node| !node.is_empty())
+        .collect();
+    match nodes.is_empty() {
+        true => vec![cores],
+        false => nodes,
+    }
+}
+
+/// The CPUs in each NUMA node, as Linux lists them under `/sys/devices/system/node`.
+#[cfg(target_os = "linux")]
+fn node_cpus() -> Vec<Vec<usize>> {
+    use std::fs;
+
+    let entries = match fs::read_dir("/sys/devices/system/node") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut nodes: Vec<(usize, Vec<usize>)> = entries.filter_map(|entry| {
+        let entry = entry.ok()?;
+        let number = entry.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+        let cpus = fs::read_to_string(entry.path().join("cpulist")).ok()?;
+        Some((number, parse_cpu_list(cpus.trim())?))
+    }).collect();
+    nodes.sort_by_key(|&(number, _)| number);
+    nodes.into_iter().map(|(_, cpus)| cpus).collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn node_cpus() -> Vec<Vec<usize>> {
+    Vec::new()
+}
+
+/// Parse a list of CPUs like `0-3,8-11`.
+#[cfg(target_os = "linux")]
+fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for range in list.split(',').filter(|range| !range.is_empty()) {
+        let mut ends = range.splitn(2, '-');
+        let first: usize = ends.next()?.parse().ok()?;
+        let last: usize = match ends.next() {
+            Some(last) => last.parse().ok()?,
+            None => first,
+        };
+        cpus.extend(first..=last);
+    }
+    Some(cpus)
+}
@@ -0,0 +1,303 @@
+//! A record of every simulation a search runs, written to a compact binary log as it goes, and
+//! the tools to read it back and work out what the tree should have ended up knowing. This is
+//! for tracking down bugs in the search itself, e.g. rewards being backed up for the wrong player.
+//!
+//! Turn it on with `MonteCarloTreeSearchPlayer::set_trace`. Moves are recorded as their position
+//! in `all_legal_moves` from the state they're played in, so the log doesn't depend on the game.
+//!
+//! The log is a header (`POGTRACE` and a version byte), followed by records, each starting with a
+//! tag byte:
+//!
+//! - 1: a search started.
+//! - 2: a simulation: the number of moves down the tree, each move, the number of playouts, then
+//!   for each playout its length and the rewards for player one and player two (as `f32`s).
+//!
+//! Counts, moves and lengths are LEB128 varints, and floats are little endian.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufWriter, Read, Write};
+
+const MAGIC: &[u8; 8] = b"POGTRACE";
+const VERSION: u8 = 1;
+const SEARCH_TAG: u8 = 1;
+const SIMULATION_TAG: u8 = 2;
+
+/// One playout from the leaf of a simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayoutTrace {
+    /// Number of moves from the root to the end of the playout.
+    pub length: u32,
+    /// The rewards for player one and player two.
+    pub rewards: (f64, f64),
+}
+
+/// One simulation: the moves taken down the tree, and how the playouts from there went.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationTrace {
+    /// Each move's position in `all_legal_moves` from the state it was played in.
+    pub path: Vec<u32>,
+    pub playouts: Vec<PlayoutTrace>,
+}
+
+/// An entry in the log.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceRecord {
+    /// A search started, from the position the following simulations start at.
+    Search,
+    Simulation(SimulationTrace),
+}
+
+/// Writes a trace log. Once a write fails, the rest of the log is dropped, and the error is
+/// returned from `finish`.
+pub struct TraceWriter {
+    output: BufWriter<Box<dyn Write + Send + Sync>>,
+    error: Option<io::Error>,
+}
+
+impl fmt::Debug for TraceWriter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TraceWriter {{ error: {:?} }}", self.error)
+    }
+}
+
+impl TraceWriter {
+    /// Start a log in `output`, writing its header.
+    pub fn new<W: Write + Send + Sync + 'static>(output: W) -> io::Result<Self> {
+        let mut output = BufWriter::new(Box::new(output) as Box<dyn Write + Send + Sync>);
+        output.write_all(MAGIC)?;
+        output.write_all(&[VERSION])?;
+        Ok(Self { output, error: None })
+    }
+
+    pub fn search_started(&mut self) {
+        self.record(|output| output.write_all(&[SEARCH_TAG]));
+    }
+
+    pub fn simulation(&mut self, path: &[u32], playouts: &[PlayoutTrace]) {
+        self.record(|output| {
+            output.write_all(&[SIMULATION_TAG])?;
+            write_varint(output, path.len() as u32)?;
+            for &index in path {
+                write_varint(output, index)?;
+            }
+            write_varint(output, playouts.len() as u32)?;
+            for playout in playouts {
+                write_varint(output, playout.length)?;
+                output.write_all(&(playout.rewards.0 as f32).to_le_bytes())?;
+                output.write_all(&(playout.rewards.1 as f32).to_le_bytes())?;
+            }
+            Ok(())
+        });
+    }
+
+    /// Flush the log, returning the first error writing it, if there was one.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.output.flush(),
+        }
+    }
+
+    fn record<F: FnOnce(&mut dyn Write) -> io::Result<()>>(&mut self, write: F) {
+        if self.error.is_none() {
+            if let Err(e) = write(&mut self.output) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+fn write_varint(output: &mut dyn Write, mut value: u32) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return output.write_all(&[byte]);
+        }
+        output.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// Reads the records back out of a trace log.
+#[derive(Debug)]
+pub struct TraceReader<R: Read> {
+    input: R,
+}
+
+impl<R: Read> TraceReader<R> {
+    /// Start reading `input`, checking its header.
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0; 9];
+        input.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a trace log"));
+        }
+        if header[8] != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unsupported trace log version {}", header[8])));
+        }
+        Ok(Self { input })
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0];
+        self.input.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn read_varint(&mut self) -> io::Result<u32> {
+        let mut value: u32 = 0;
+        for shift in (0..5).map(|x| x * 7) {
+            let byte = self.read_byte()?;
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::InvalidData, "Varint too long"))
+    }
+
+    fn read_f32(&mut self) -> io::Result<f64> {
+        let mut bytes = [0; 4];
+        self.input.read_exact(&mut bytes)?;
+        Ok(f32::from_le_bytes(bytes) as f64)
+    }
+
+    fn read_simulation(&mut self) -> io::Result<SimulationTrace> {
+        let path_length = self.read_varint()?;
+        let path = (0..path_length).map(|_| self.read_varint()).collect::<io::Result<Vec<_>>>()?;
+        let playout_count = self.read_varint()?;
+        let playouts = (0..playout_count).map(|_| {
+            Ok(PlayoutTrace {
+                length: self.read_varint()?,
+                rewards: (self.read_f32()?, self.read_f32()?),
+            })
+        }).collect::<io::Result<Vec<_>>>()?;
+        Ok(SimulationTrace { path, playouts })
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = io::Result<TraceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tag = match self.read_byte() {
+            Ok(tag) => tag,
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(match tag {
+            SEARCH_TAG => Ok(TraceRecord::Search),
+            SIMULATION_TAG => self.read_simulation().map(TraceRecord::Simulation),
+            tag => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown trace record {}", tag))),
+        })
+    }
+}
+
+/// The simulations through one node of the tree, and their total (undiscounted) rewards.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EdgeStats {
+    /// Playouts through the node.
+    pub visits: u64,
+    /// Total rewards for player one and player two.
+    pub rewards: (f64, f64),
+}
+
+impl EdgeStats {
+    /// The average rewards for player one and player two, if the node has been visited.
+    pub fn mean_rewards(&self) -> Option<(f64, f64)> {
+        match self.visits {
+            0 => None,
+            visits => Some((self.rewards.0 / visits as f64, self.rewards.1 / visits as f64)),
+        }
+    }
+}
+
+/// What one search's simulations add up to.
+#[derive(Debug, Clone, Default)]
+pub struct SearchTrace {
+    pub simulations: u64,
+    /// The most moves any simulation took down the tree.
+    pub max_depth: u32,
+    total_depth: u64,
+    /// Every node reached, keyed by the moves from the root (the root's key is empty).
+    pub nodes: HashMap<Vec<u32>, EdgeStats>,
+}
+
+impl SearchTrace {
+    fn add(&mut self, simulation: &SimulationTrace) {
+        self.simulations += 1;
+        self.max_depth = self.max_depth.max(simulation.path.len() as u32);
+        self.total_depth += simulation.path.len() as u64;
+        for depth in 0..(simulation.path.len() + 1) {
+            let stats = self.nodes.entry(simulation.path[..depth].to_vec()).or_default();
+            for playout in &simulation.playouts {
+                stats.visits += 1;
+                stats.rewards.0 += playout.rewards.0;
+                stats.rewards.1 += playout.rewards.1;
+            }
+        }
+    }
+
+    /// The average number of moves simulations took down the tree.
+    pub fn mean_depth(&self) -> f64 {
+        match self.simulations {
+            0 => 0.0,
+            simulations => self.total_depth as f64 / simulations as f64,
+        }
+    }
+
+    /// The node reached by `path` from the root.
+    pub fn node(&self, path: &[u32]) -> Option<&EdgeStats> {
+        self.nodes.get(path)
+    }
+
+    /// The moves tried from the node reached by `path`, in the order of `all_legal_moves`.
+    pub fn children(&self, path: &[u32]) -> Vec<(u32, EdgeStats)> {
+        let mut children: Vec<(u32, EdgeStats)> = self.nodes.iter()
+            .filter(|&(key, _)| key.len() == path.len() + 1 && key.starts_with(path))
+            .map(|(key, stats)| (key[path.len()], *stats))
+            .collect();
+        children.sort_by_key(|x| x.0);
+        children
+    }
+}
+
+/// The statistics a trace log adds up to, search by search. Shown as a summary of each search
+/// and its root's moves.
+#[derive(Debug, Clone, Default)]
+pub struct TraceAnalysis {
+    pub searches: Vec<SearchTrace>,
+}
+
+impl TraceAnalysis {
+    /// Read a whole trace log.
+    pub fn from_reader<R: Read>(input: R) -> io::Result<Self> {
+        let mut analysis = TraceAnalysis::default();
+        for record in TraceReader::new(input)? {
+            match record? {
+                TraceRecord::Search => analysis.searches.push(SearchTrace::default()),
+                TraceRecord::Simulation(simulation) => {
+                    if analysis.searches.is_empty() {
+                        analysis.searches.push(SearchTrace::default());
+                    }
+                    analysis.searches.last_mut().expect("No search").add(&simulation);
+                },
+            }
+        }
+        Ok(analysis)
+    }
+}
+
+impl fmt::Display for TraceAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, search) in self.searches.iter().enumerate() {
+            writeln!(f, "Search {}: {} simulations, depth {:.1} on average, {} at most", i + 1, search.simulations, search.mean_depth(), search.max_depth)?;
+            for (index, stats) in search.children(&[]) {
+                let (one, two) = stats.mean_rewards().unwrap_or((0.0, 0.0));
+                writeln!(f, "  move {:>3}: {:>8} visits, mean rewards {:.3} / {:.3}", index, stats.visits, one, two)?;
+            }
+        }
+        Ok(())
+    }
+}
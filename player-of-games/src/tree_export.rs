@@ -0,0 +1,59 @@
+//! The top of the search tree as nested nodes, in the JSON shape that d3's `hierarchy` (and
+//! similar web visualizers) read directly.
+
+use std::fmt::Write;
+
+use game::PlayerEnum;
+
+/// A node of the exported tree, from `MonteCarloTreeSearchPlayer::export_tree`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeNodeExport {
+    /// The move which led here (as its `Debug` output), or "root".
+    pub name: String,
+    /// Who's to move.
+    pub player: PlayerEnum,
+    pub visits: u32,
+    /// The estimated chance of winning for whoever played the move which led here, if it's been
+    /// searched.
+    pub value: Option<f64>,
+    /// The moves searched from here, most visited first. Empty at the depth limit.
+    pub children: Vec<TreeNodeExport>,
+}
+
+impl TreeNodeExport {
+    /// As JSON: `{"name": ..., "player": "One", "visits": ..., "value": ..., "children": [...]}`,
+    /// with a `null` value if there isn't one.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json);
+        json
+    }
+
+    fn write_json(&self, json: &mut String) {
+        json.push_str("{\"name\":");
+        write_json_string(json, &self.name);
+        let value = self.value.filter(|x| x.is_finite()).map_or("null".to_string(), |x| x.to_string());
+        write!(json, ",\"player\":\"{:?}\",\"visits\":{},\"value\":{},\"children\":[", self.player, self.visits, value).expect("Writing to a string failed");
+        for (i, child) in self.children.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            child.write_json(json);
+        }
+        json.push_str("]}");
+    }
+}
+
+fn write_json_string(json: &mut String, text: &str) {
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).expect("Writing to a string failed"),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
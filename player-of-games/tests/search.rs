@@ -0,0 +1,57 @@
+extern crate game;
+extern crate player_of_games;
+extern crate tic_tac_toe;
+
+use game::{GameState, Notation, Player, PlayerEnum};
+use player_of_games::{AdaptiveBudget, KlStop, MonteCarloTreeSearchPlayer, SearchBudget, SkillLevel};
+use tic_tac_toe::TicTacToe;
+
+fn player(budget: SearchBudget) -> MonteCarloTreeSearchPlayer<TicTacToe> {
+    let mut player = MonteCarloTreeSearchPlayer::new(PlayerEnum::One, 2f64.sqrt());
+    player.set_budget(budget);
+    player
+}
+
+fn assert_plays_legal_move(mut player: MonteCarloTreeSearchPlayer<TicTacToe>) {
+    let game = TicTacToe::new();
+    let chosen = player.choose_move(game.clone());
+    assert!(game.is_move_legal(chosen, PlayerEnum::One).is_ok(), "Chose illegal move {:?}", chosen);
+}
+
+#[test]
+fn tiny_iteration_budgets_still_choose_a_move() {
+    for &n in &[0, 1, 2] {
+        assert_plays_legal_move(player(SearchBudget::Iterations(n)));
+    }
+}
+
+#[test]
+fn zero_time_budget_still_chooses_a_move() {
+    assert_plays_legal_move(player(SearchBudget::Time(Default::default())));
+}
+
+#[test]
+fn weak_players_still_choose_a_move() {
+    let mut weak = player(SearchBudget::Iterations(10));
+    weak.set_skill_level(SkillLevel::easy());
+    assert_plays_legal_move(weak);
+}
+
+#[test]
+fn stopping_early_with_one_move_still_chooses_it() {
+    let (game, to_move) = TicTacToe::from_notation("XOX/XOO/OX_").unwrap();
+    assert_eq!(game.all_legal_moves(to_move).count(), 1);
+    let mut early = player(SearchBudget::Iterations(100));
+    early.set_early_stop(true);
+    early.set_adaptive_budget(Some(AdaptiveBudget::default()));
+    early.set_kl_stop(Some(KlStop { interval: 1, epsilon: 1.0 }));
+    let chosen = early.choose_move(game.clone());
+    assert!(game.is_move_legal(chosen, to_move).is_ok());
+}
+
+#[test]
+fn cancelled_players_still_choose_a_move() {
+    let player = player(SearchBudget::UntilStopped);
+    player.cancellation_token().cancel();
+    assert_plays_legal_move(player);
+}
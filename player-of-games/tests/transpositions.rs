@@ -0,0 +1,26 @@
+extern crate game;
+extern crate goofspiel;
+extern crate player_of_games;
+
+use game::{Adjudicator, PlayerEnum};
+use goofspiel::Goofspiel;
+use player_of_games::{MonteCarloTreeSearchPlayer, SearchBudget};
+
+fn player(player: PlayerEnum) -> MonteCarloTreeSearchPlayer<Goofspiel> {
+    let mut player = MonteCarloTreeSearchPlayer::new(player, 2f64.sqrt());
+    player.set_budget(SearchBudget::Iterations(1000));
+    player
+}
+
+/// In Goofspiel, swapping two of a player's bids between rounds can reach the same position, with
+/// the other player's last bid the same move from both parents. Pruning after each move has to
+/// unlink such a node from both, or searching the next move runs into a dangling link.
+#[test]
+fn pruning_keeps_transpositions_linked() {
+    game::rng::seed_thread_rng(1);
+    for _ in 0..3 {
+        let mut adjudicator = Adjudicator::new(Goofspiel::new(5), player(PlayerEnum::One), player(PlayerEnum::Two));
+        adjudicator.play_to_end();
+        assert!(adjudicator.conclusion().is_some());
+    }
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "parallel")]
+
+extern crate game;
+extern crate player_of_games;
+extern crate tic_tac_toe;
+
+use game::{Conclusion, GameState, Notation, Player, PlayerEnum};
+use player_of_games::{MonteCarloTreeSearchPlayer, SearchBudget};
+use tic_tac_toe::TicTacToe;
+
+fn player(to_move: PlayerEnum, budget: SearchBudget) -> MonteCarloTreeSearchPlayer<TicTacToe> {
+    let mut player = MonteCarloTreeSearchPlayer::new(to_move, 2f64.sqrt());
+    player.set_budget(budget);
+    player.set_tree_parallelism(4);
+    player
+}
+
+#[test]
+fn tiny_budgets_still_choose_a_move() {
+    let game = TicTacToe::new();
+    for &n in &[0, 1, 2] {
+        let chosen = player(PlayerEnum::One, SearchBudget::Iterations(n)).choose_move(game.clone());
+        assert!(game.is_move_legal(chosen, PlayerEnum::One).is_ok(), "Chose illegal move {:?}", chosen);
+    }
+
+    let mut cancelled = player(PlayerEnum::One, SearchBudget::UntilStopped);
+    cancelled.cancellation_token().cancel();
+    let chosen = cancelled.choose_move(game.clone());
+    assert!(game.is_move_legal(chosen, PlayerEnum::One).is_ok(), "Chose illegal move {:?}", chosen);
+}
+
+#[test]
+fn the_budget_counts_every_thread() {
+    let mut player = player(PlayerEnum::One, SearchBudget::Iterations(500));
+    let game = TicTacToe::new();
+    assert_eq!(player.analyse(&game).simulations, 500);
+    // What the threads found is kept for the next search
+    assert_eq!(player.analyse(&game).simulations, 1000);
+    assert!(player.node_count() > 100);
+}
+
+#[test]
+fn finds_the_winning_move() {
+    let (game, to_move) = TicTacToe::from_notation("XX_/OO_/___").unwrap();
+    let chosen = player(to_move, SearchBudget::Iterations(2000)).choose_move(game.clone());
+    let mut after = game.clone();
+    after.update(chosen, to_move);
+    assert_eq!(after.try_conclude(to_move.other()), Some(Conclusion::Win(to_move)), "Chose {:?}", chosen);
+}
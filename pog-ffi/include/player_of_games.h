@@ -0,0 +1,36 @@
+/* C API for the player-of-games engine. Build the pog-ffi crate, and link against libpog_ffi. */
+
+#ifndef PLAYER_OF_GAMES_H
+#define PLAYER_OF_GAMES_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define POG_OK 0
+#define POG_ERR_NULL (-1)
+#define POG_ERR_BAD_POSITION (-2)
+#define POG_ERR_GAME_OVER (-3)
+#define POG_ERR_PANIC (-4)
+
+typedef struct PogEngine PogEngine;
+
+/* Create an engine with exploration constant c, running `iterations` simulations per move.
+ * Returns NULL on failure. */
+PogEngine *pog_engine_new(double c, unsigned int iterations);
+
+/* Set the tic-tac-toe position, e.g. "X_O/_X_/__O" (rows separated by '/'). */
+int pog_engine_set_position(PogEngine *engine, const char *position);
+
+/* Search the current position and write the best move's coordinates (0 to 2). x is the row in
+ * the position string and y is the column. */
+int pog_engine_best_move(PogEngine *engine, int *x, int *y);
+
+/* Free an engine. NULL is allowed. */
+void pog_engine_free(PogEngine *engine);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,138 @@
+//! C-compatible API for embedding the engine in other languages.
+//!
+//! The engine plays tic-tac-toe. Positions are passed as strings of three rows separated by `/`,
+//! using `X`, `O` and `_` for crosses, noughts and empty squares, e.g. `"X_O/_X_/__O"`. Whose
+//! turn it is follows from the number of pieces on the board. See `include/player_of_games.h`.
+//!
+//! This is a crate of its own so that the engine itself isn't built as a C library, or tied to
+//! tic-tac-toe.
+//!
+//! All functions return 0 on success and a negative number on failure; they never unwind into
+//! the caller.
+
+extern crate game;
+extern crate player_of_games;
+extern crate tic_tac_toe;
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_double, c_int, c_uint};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use game::{GameState, Notation, PlayerEnum};
+use tic_tac_toe::TicTacToe;
+
+use player_of_games::{MonteCarloTreeSearchPlayer, SearchBudget};
+
+pub const POG_OK: c_int = 0;
+pub const POG_ERR_NULL: c_int = -1;
+pub const POG_ERR_BAD_POSITION: c_int = -2;
+pub const POG_ERR_GAME_OVER: c_int = -3;
+pub const POG_ERR_PANIC: c_int = -4;
+
+/// An engine, and the position it's been asked to look at.
+pub struct PogEngine {
+    c: f64,
+    budget: SearchBudget,
+    position: TicTacToe,
+    to_move: PlayerEnum,
+    player: MonteCarloTreeSearchPlayer<TicTacToe>,
+}
+
+impl PogEngine {
+    fn new_player(&self) -> MonteCarloTreeSearchPlayer<TicTacToe> {
+        let mut player = MonteCarloTreeSearchPlayer::new(self.to_move, self.c);
+        player.set_budget(self.budget);
+        player
+    }
+}
+
+fn catch<F: FnOnce() -> c_int>(f: F) -> c_int {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(POG_ERR_PANIC)
+}
+
+/// Create an engine with exploration constant `c` which runs `iterations` simulations per move.
+/// The engine starts at the empty board. Returns null on failure.
+#[no_mangle]
+pub extern "C" fn pog_engine_new(c: c_double, iterations: c_uint) -> *mut PogEngine {
+    panic::catch_unwind(|| {
+        let mut engine = PogEngine {
+            c,
+            budget: SearchBudget::Iterations(iterations),
+            position: TicTacToe::new(),
+            to_move: PlayerEnum::One,
+            player: MonteCarloTreeSearchPlayer::new(PlayerEnum::One, c),
+        };
+        engine.player = engine.new_player();
+        Box::into_raw(Box::new(engine))
+    }).unwrap_or(ptr::null_mut())
+}
+
+/// Set the position the engine should look at, as a null-terminated string.
+///
+/// # Safety
+///
+/// `engine` must be null or from `pog_engine_new` and not yet freed, and `position` must be null
+/// or point to a null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pog_engine_set_position(engine: *mut PogEngine, position: *const c_char) -> c_int {
+    if engine.is_null() || position.is_null() {
+        return POG_ERR_NULL;
+    }
+    let engine = &mut *engine;
+    let position = CStr::from_ptr(position);
+    catch(move || {
+        let (position, to_move) = match position.to_str().ok().and_then(|x| TicTacToe::from_notation(x).ok()) {
+            Some(x) => x,
+            None => return POG_ERR_BAD_POSITION,
+        };
+        if to_move != engine.to_move {
+            engine.to_move = to_move;
+            engine.player = engine.new_player();
+        }
+        engine.position = position;
+        POG_OK
+    })
+}
+
+/// Search the current position and write the best move's coordinates (each 0 to 2) to `x` and
+/// `y`. `x` is the row in the position string and `y` is the column.
+///
+/// # Safety
+///
+/// `engine` must be null or from `pog_engine_new` and not yet freed, and `x` and `y` must each be
+/// null or point to a writable `int`.
+#[no_mangle]
+pub unsafe extern "C" fn pog_engine_best_move(engine: *mut PogEngine, x: *mut c_int, y: *mut c_int) -> c_int {
+    if engine.is_null() || x.is_null() || y.is_null() {
+        return POG_ERR_NULL;
+    }
+    let engine = &mut *engine;
+    catch(move || {
+        if engine.position.try_conclude(engine.to_move).is_some() {
+            return POG_ERR_GAME_OVER;
+        }
+        // The search always finds a move when there is one, but fall back on any legal move
+        // rather than report a game which isn't over as over
+        let best_move = match engine.player.analyse(&engine.position).best_move.or_else(|| engine.position.all_legal_moves(engine.to_move).next()) {
+            Some(x) => x,
+            None => return POG_ERR_GAME_OVER,
+        };
+        let (best_x, best_y) = best_move.coordinates();
+        *x = best_x as c_int;
+        *y = best_y as c_int;
+        POG_OK
+    })
+}
+
+/// Free an engine created by `pog_engine_new`. Passing null is allowed and does nothing.
+///
+/// # Safety
+///
+/// `engine` must be null or from `pog_engine_new`, and mustn't be used again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pog_engine_free(engine: *mut PogEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
@@ -0,0 +1,44 @@
+extern crate pog_ffi;
+
+use std::ffi::CString;
+use std::os::raw::c_int;
+
+use pog_ffi::*;
+
+fn best_move(iterations: u32, position: &str) -> (c_int, c_int, c_int) {
+    let engine = pog_engine_new(2f64.sqrt(), iterations);
+    assert!(!engine.is_null());
+    let position = CString::new(position).unwrap();
+    let (mut x, mut y) = (-1, -1);
+    let result = unsafe {
+        assert_eq!(pog_engine_set_position(engine, position.as_ptr()), POG_OK);
+        let result = pog_engine_best_move(engine, &mut x, &mut y);
+        pog_engine_free(engine);
+        result
+    };
+    (result, x, y)
+}
+
+#[test]
+fn tiny_budgets_still_find_a_move() {
+    for &iterations in &[0, 1] {
+        let (result, x, y) = best_move(iterations, "___/___/___");
+        assert_eq!(result, POG_OK);
+        assert!((0..3).contains(&x) && (0..3).contains(&y));
+    }
+}
+
+#[test]
+fn finished_games_are_reported() {
+    assert_eq!(best_move(100, "XXX/OO_/___").0, POG_ERR_GAME_OVER);
+}
+
+#[test]
+fn bad_positions_are_rejected() {
+    let engine = pog_engine_new(2f64.sqrt(), 10);
+    let position = CString::new("XX").unwrap();
+    unsafe {
+        assert_eq!(pog_engine_set_position(engine, position.as_ptr()), POG_ERR_BAD_POSITION);
+        pog_engine_free(engine);
+    }
+}
@@ -0,0 +1,36 @@
+//! Errors raised by the Python code the engine calls back into.
+//!
+//! The engine's traits can't fail, so when a callback raises, the error is kept to one side and
+//! the engine is unwound back to where it was called from Python, which raises the error there.
+
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+
+use pyo3::prelude::*;
+
+thread_local! {
+    static RAISED: RefCell<Option<PyErr>> = const { RefCell::new(None) };
+}
+
+/// What the engine is unwound with when a callback raises.
+struct CallbackRaised;
+
+/// `result`'s value, or if it's an error, unwind to `raise_callback_errors` to raise it.
+pub fn or_raise<T>(result: PyResult<T>) -> T {
+    result.unwrap_or_else(|error| {
+        RAISED.with(|raised| *raised.borrow_mut() = Some(error));
+        // Not `panic!`, which would print a message for what is the Python code's error
+        panic::resume_unwind(Box::new(CallbackRaised))
+    })
+}
+
+/// Run `f`, which may call back into Python, raising any error from those callbacks.
+pub fn raise_callback_errors<T, F: FnOnce() -> T>(f: F) -> PyResult<T> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => Ok(value),
+        Err(payload) => match payload.is::<CallbackRaised>() {
+            true => Err(RAISED.with(|raised| raised.borrow_mut().take()).expect("Callback error went missing")),
+            false => panic::resume_unwind(payload),
+        },
+    }
+}
@@ -0,0 +1,60 @@
+//! Python bindings, so that games written in Python can be searched by the engine.
+
+// The pyo3 macros generate impls inside functions, which newer compilers warn about.
+#![allow(non_local_definitions)]
+
+extern crate game;
+extern crate player_of_games;
+extern crate pyo3;
+
+mod errors;
+mod players;
+mod state;
+
+use pyo3::prelude::*;
+
+use errors::raise_callback_errors;
+use players::{MctsPlayer, PyPlayer};
+use state::{conclusion_number, PyGame};
+
+/// Plays a game between two players, as `game::Adjudicator` does.
+#[pyclass(unsendable)]
+struct Adjudicator {
+    inner: game::Adjudicator<PyGame, PyPlayer, PyPlayer>,
+}
+
+#[pymethods]
+impl Adjudicator {
+    #[new]
+    fn new(py: Python, state: PyObject, player_one: PyObject, player_two: PyObject) -> PyResult<Self> {
+        Ok(Adjudicator {
+            inner: game::Adjudicator::new(PyGame::new(py, state)?, PyPlayer(player_one), PyPlayer(player_two)),
+        })
+    }
+
+    fn progress_one_turn(&mut self) -> PyResult<()> {
+        raise_callback_errors(|| self.inner.progress_one_turn())
+    }
+
+    /// `None` while the game goes on, then `0` for a draw or the winner (1 or 2).
+    fn conclusion(&self) -> Option<u8> {
+        self.inner.conclusion().map(conclusion_number)
+    }
+
+    /// Play the game to the end, returning the conclusion.
+    fn play(&mut self) -> PyResult<u8> {
+        loop {
+            if let Some(conclusion) = self.inner.conclusion() {
+                return Ok(conclusion_number(conclusion));
+            }
+            self.progress_one_turn()?;
+        }
+    }
+}
+
+#[pymodule]
+fn pog(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add_class::<MctsPlayer>()?;
+    module.add_class::<Adjudicator>()?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use game::{self, GameState};
+use player_of_games::{MonteCarloTreeSearchPlayer, SearchBudget};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use errors::{or_raise, raise_callback_errors};
+use state::{player_from_number, PyGame};
+
+/// Monte Carlo tree search player, searching a game implemented in Python.
+#[pyclass]
+pub struct MctsPlayer {
+    inner: MonteCarloTreeSearchPlayer<PyGame>,
+}
+
+#[pymethods]
+impl MctsPlayer {
+    #[new]
+    #[pyo3(signature = (player, c = ::std::f64::consts::SQRT_2, iterations = 100))]
+    fn new(player: u8, c: f64, iterations: u32) -> PyResult<Self> {
+        let mut inner = MonteCarloTreeSearchPlayer::new(player_from_number(player)?, c);
+        inner.set_budget(SearchBudget::Iterations(iterations));
+        Ok(MctsPlayer {
+            inner,
+        })
+    }
+
+    fn choose_move(&mut self, py: Python, state: PyObject) -> PyResult<i64> {
+        let state = PyGame::new(py, state)?;
+        raise_callback_errors(|| game::Player::choose_move(&mut self.inner, state))
+    }
+
+    fn inform_of_move_played(&mut self, py: Python, state: PyObject, game_move: i64) -> PyResult<()> {
+        let state = PyGame::new(py, state)?;
+        raise_callback_errors(|| game::Player::inform_of_move_played(&mut self.inner, state, &game_move))
+    }
+
+    /// Search `state` without committing to a move. Returns a dict with the `best_move`, the
+    /// `policy` (a list of `(move, visits)` pairs) and the `principal_variation`.
+    fn analyse(&mut self, py: Python, state: PyObject) -> PyResult<PyObject> {
+        let state = PyGame::new(py, state)?;
+        let analysis = raise_callback_errors(|| self.inner.analyse(&state))?;
+        let result = PyDict::new(py);
+        result.set_item("best_move", analysis.best_move)?;
+        result.set_item("policy", analysis.policy)?;
+        result.set_item("principal_variation", analysis.principal_variation)?;
+        Ok(result.into())
+    }
+}
+
+/// Any Python object with `choose_move(state)` and `inform_of_move_played(state, move)` methods.
+pub struct PyPlayer(pub PyObject);
+
+impl game::Player<PyGame> for PyPlayer {
+    fn choose_move(&mut self, game: PyGame) -> i64 {
+        or_raise(Python::with_gil(|py| {
+            self.0.call_method1(py, "choose_move", (game.object(),))?.extract(py)
+        }))
+    }
+
+    fn inform_of_move_played(&mut self, new_state: PyGame, game_move: &<PyGame as GameState>::Move) {
+        or_raise(Python::with_gil(|py| {
+            self.0.call_method1(py, "inform_of_move_played", (new_state.object(), *game_move)).map(|_| ())
+        }))
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use game::{Conclusion, GameState, PlayerEnum};
+use pyo3::prelude::*;
+
+use errors::or_raise;
+
+/// The number Python code uses for each player.
+pub fn player_number(player: PlayerEnum) -> u8 {
+    match player {
+        PlayerEnum::One => 1,
+        PlayerEnum::Two => 2,
+    }
+}
+
+pub fn player_from_number(player: u8) -> PyResult<PlayerEnum> {
+    match player {
+        1 => Ok(PlayerEnum::One),
+        2 => Ok(PlayerEnum::Two),
+        _ => Err(pyo3::exceptions::PyValueError::new_err("Player must be 1 or 2")),
+    }
+}
+
+pub fn conclusion_number(conclusion: Conclusion) -> u8 {
+    match conclusion {
+        Conclusion::Draw => 0,
+        Conclusion::Win(player) => player_number(player),
+    }
+}
+
+/// A game state implemented in Python. Moves are ints.
+///
+/// The Python object is treated as immutable, so cloning just takes another reference to it.
+#[derive(Clone)]
+pub struct PyGame {
+    state: PyObject,
+    hash: isize,
+}
+
+impl PyGame {
+    pub fn new(py: Python, state: PyObject) -> PyResult<Self> {
+        let hash = state.as_ref(py).hash()?;
+        Ok(PyGame {
+            state,
+            hash,
+        })
+    }
+
+    pub fn object(&self) -> &PyObject {
+        &self.state
+    }
+}
+
+impl Hash for PyGame {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state)
+    }
+}
+
+impl PartialEq for PyGame {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && or_raise(Python::with_gil(|py| {
+            self.state.as_ref(py).eq(other.state.as_ref(py))
+        }))
+    }
+}
+
+impl Eq for PyGame {}
+
+impl fmt::Debug for PyGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Python::with_gil(|py| match self.state.as_ref(py).repr() {
+            Ok(repr) => write!(f, "{}", repr),
+            Err(_) => write!(f, "<unrepresentable game state>"),
+        })
+    }
+}
+
+impl GameState for PyGame {
+    type Move = i64;
+
+    fn update(&mut self, game_move: i64, player: PlayerEnum) {
+        let new_state = Python::with_gil(|py| {
+            let new_state = self.state.call_method1(py, "play", (game_move, player_number(player)))?;
+            PyGame::new(py, new_state)
+        });
+        *self = or_raise(new_state);
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: PlayerEnum) -> Box<dyn Iterator<Item = i64> + 'a> {
+        let moves: Vec<i64> = or_raise(Python::with_gil(|py| {
+            self.state.call_method1(py, "legal_moves", (player_number(player),))?.extract(py)
+        }));
+        Box::new(moves.into_iter())
+    }
+
+    fn try_conclude(&self, next_player: PlayerEnum) -> Option<Conclusion> {
+        let conclusion = Python::with_gil(|py| {
+            match self.state.call_method1(py, "conclusion", (player_number(next_player),))?.extract(py)? {
+                None => Ok(None),
+                Some(0) => Ok(Some(Conclusion::Draw)),
+                Some(1) => Ok(Some(Conclusion::Win(PlayerEnum::One))),
+                Some(2) => Ok(Some(Conclusion::Win(PlayerEnum::Two))),
+                Some(x) => Err(pyo3::exceptions::PyValueError::new_err(format!("conclusion() returned {}, expected None, 0, 1 or 2", x))),
+            }
+        });
+        or_raise(conclusion)
+    }
+}
@@ -0,0 +1,366 @@
+//! Quoridor, for two players: each races their pawn to the far side of a 9x9 board, and can
+//! instead place a wall to hold the other up. A wall may never cut a pawn off from its goal, so
+//! every wall placement is checked with a search for paths.
+//!
+//! Player one starts in the middle of the bottom row (row 0) and wins on reaching the top row.
+//! Player two starts in the middle of the top row and wins on reaching the bottom row.
+
+extern crate game;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::VecDeque;
+use std::fmt;
+
+pub const SIZE: usize = 9;
+/// Walls each player starts with.
+pub const WALLS: u8 = 10;
+
+/// Which way a wall runs.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Orientation {
+    /// Between two rows, across two columns.
+    Horizontal,
+    /// Between two columns, across two rows.
+    Vertical,
+}
+
+/// A turn: either a pawn move or a wall.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Move {
+    /// Move the pawn to the square in column `x` and row `y` (including jumps).
+    Step { x: usize, y: usize },
+    /// Place a wall centred on the corner above and to the right of the square in column `x` and
+    /// row `y`. Both are from 0 to 7.
+    Wall { x: usize, y: usize, orientation: Orientation },
+}
+
+/// The four directions a pawn can step in.
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+
+#[derive(Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Quoridor {
+    /// Each player's pawn, as (column, row).
+    pawns: [(usize, usize); 2],
+    walls_left: [u8; 2],
+    /// The walls, indexed by column and then row of their centre.
+    horizontal: [[bool; SIZE - 1]; SIZE - 1],
+    vertical: [[bool; SIZE - 1]; SIZE - 1],
+    to_move: game::PlayerEnum,
+}
+
+fn index(player: game::PlayerEnum) -> usize {
+    match player {
+        game::PlayerEnum::One => 0,
+        game::PlayerEnum::Two => 1,
+    }
+}
+
+/// The row `player` is trying to reach.
+fn goal_row(player: game::PlayerEnum) -> usize {
+    match player {
+        game::PlayerEnum::One => SIZE - 1,
+        game::PlayerEnum::Two => 0,
+    }
+}
+
+impl Default for Quoridor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Quoridor {
+    pub fn new() -> Self {
+        Self {
+            pawns: [(SIZE / 2, 0), (SIZE / 2, SIZE - 1)],
+            walls_left: [WALLS; 2],
+            horizontal: [[false; SIZE - 1]; SIZE - 1],
+            vertical: [[false; SIZE - 1]; SIZE - 1],
+            to_move: game::PlayerEnum::One,
+        }
+    }
+
+    /// The player whose turn it is.
+    pub fn to_move(&self) -> game::PlayerEnum {
+        self.to_move
+    }
+
+    /// `player`'s pawn, as (column, row).
+    pub fn pawn(&self, player: game::PlayerEnum) -> (usize, usize) {
+        self.pawns[index(player)]
+    }
+
+    pub fn walls_left(&self, player: game::PlayerEnum) -> u8 {
+        self.walls_left[index(player)]
+    }
+
+    fn wall(&self, x: isize, y: isize, orientation: Orientation) -> bool {
+        if x < 0 || y < 0 || x >= SIZE as isize - 1 || y >= SIZE as isize - 1 {
+            return false;
+        }
+        match orientation {
+            Orientation::Horizontal => self.horizontal[x as usize][y as usize],
+            Orientation::Vertical => self.vertical[x as usize][y as usize],
+        }
+    }
+
+    /// The square one step from `(x, y)` in direction `(dx, dy)`, if it's on the board and no
+    /// wall is in the way.
+    fn step(&self, (x, y): (usize, usize), (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+        let (x, y) = (x as isize, y as isize);
+        let (nx, ny) = (x + dx, y + dy);
+        if nx < 0 || ny < 0 || nx >= SIZE as isize || ny >= SIZE as isize {
+            return None;
+        }
+        let blocked = match (dx, dy) {
+            (0, 1) => self.wall(x, y, Orientation::Horizontal) || self.wall(x - 1, y, Orientation::Horizontal),
+            (0, -1) => self.wall(x, y - 1, Orientation::Horizontal) || self.wall(x - 1, y - 1, Orientation::Horizontal),
+            (1, 0) => self.wall(x, y, Orientation::Vertical) || self.wall(x, y - 1, Orientation::Vertical),
+            _ => self.wall(x - 1, y, Orientation::Vertical) || self.wall(x - 1, y - 1, Orientation::Vertical),
+        };
+        if blocked {
+            None
+        } else {
+            Some((nx as usize, ny as usize))
+        }
+    }
+
+    /// The squares `player`'s pawn can move to: a step, or a jump over the other pawn (straight
+    /// on, or to either side if there's a wall or the edge behind it).
+    fn pawn_moves(&self, player: game::PlayerEnum) -> Vec<(usize, usize)> {
+        let me = self.pawn(player);
+        let other = self.pawn(player.other());
+        let mut squares = Vec::new();
+        for &direction in DIRECTIONS.iter() {
+            let next = match self.step(me, direction) {
+                Some(next) => next,
+                None => continue,
+            };
+            if next != other {
+                squares.push(next);
+            } else if let Some(jump) = self.step(other, direction) {
+                squares.push(jump);
+            } else {
+                for &side in DIRECTIONS.iter().filter(|d| d.0 * direction.0 + d.1 * direction.1 == 0) {
+                    if let Some(diagonal) = self.step(other, side) {
+                        squares.push(diagonal);
+                    }
+                }
+            }
+        }
+        squares
+    }
+
+    /// Whether `player` could still walk to their goal, by a breadth first search.
+    fn has_path(&self, player: game::PlayerEnum) -> bool {
+        let goal = goal_row(player);
+        let start = self.pawn(player);
+        let mut seen = [[false; SIZE]; SIZE];
+        seen[start.0][start.1] = true;
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(square) = queue.pop_front() {
+            if square.1 == goal {
+                return true;
+            }
+            for &direction in DIRECTIONS.iter() {
+                if let Some((x, y)) = self.step(square, direction) {
+                    if !seen[x][y] {
+                        seen[x][y] = true;
+                        queue.push_back((x, y));
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn is_legal(&self, game_move: Move, player: game::PlayerEnum) -> Result<(), String> {
+        if player != self.to_move {
+            return Err(format!("{:?} playing out of turn", player));
+        }
+
+        match game_move {
+            Move::Step { x, y } => {
+                if self.pawn_moves(player).contains(&(x, y)) {
+                    Ok(())
+                } else {
+                    Err("The pawn can't get there".to_string())
+                }
+            },
+            Move::Wall { x, y, orientation } => {
+                if self.walls_left(player) == 0 {
+                    return Err("No walls left".to_string());
+                }
+                if x >= SIZE - 1 || y >= SIZE - 1 {
+                    return Err("Wall out of bounds".to_string());
+                }
+                let (x, y) = (x as isize, y as isize);
+                let overlaps = self.wall(x, y, Orientation::Horizontal) || self.wall(x, y, Orientation::Vertical) || match orientation {
+                    Orientation::Horizontal => self.wall(x - 1, y, orientation) || self.wall(x + 1, y, orientation),
+                    Orientation::Vertical => self.wall(x, y - 1, orientation) || self.wall(x, y + 1, orientation),
+                };
+                if overlaps {
+                    return Err("Wall overlaps another".to_string());
+                }
+
+                let mut walled = self.clone();
+                walled.place_wall(x as usize, y as usize, orientation);
+                if !walled.has_path(game::PlayerEnum::One) || !walled.has_path(game::PlayerEnum::Two) {
+                    return Err("Wall cuts a pawn off from its goal".to_string());
+                }
+                Ok(())
+            },
+        }
+    }
+
+    fn place_wall(&mut self, x: usize, y: usize, orientation: Orientation) {
+        match orientation {
+            Orientation::Horizontal => self.horizontal[x][y] = true,
+            Orientation::Vertical => self.vertical[x][y] = true,
+        }
+    }
+}
+
+impl fmt::Debug for Quoridor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Quoridor {{")?;
+        writeln!(f, "{}", self)?;
+        write!(f, "}}")
+    }
+}
+
+/// Drawn from the top row down, with `1` and `2` for the pawns, `-` and `|` for walls, and the
+/// walls each player has left underneath.
+impl fmt::Display for Quoridor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in (0..SIZE).rev() {
+            if y + 1 < SIZE {
+                for x in 0..SIZE {
+                    let wall = self.step((x, y), (0, 1)).is_none();
+                    write!(f, "{} ", if wall { '-' } else { ' ' })?;
+                }
+                writeln!(f)?;
+            }
+            for x in 0..SIZE {
+                let square = if (x, y) == self.pawns[0] {
+                    '1'
+                } else if (x, y) == self.pawns[1] {
+                    '2'
+                } else {
+                    '.'
+                };
+                let wall = x + 1 < SIZE && self.step((x, y), (1, 0)).is_none();
+                write!(f, "{}{}", square, if wall { '|' } else { ' ' })?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "Walls: {} and {}", self.walls_left[0], self.walls_left[1])
+    }
+}
+
+impl game::GameState for Quoridor {
+    type Move = Move;
+
+    fn update(&mut self, game_move: Self::Move, player: game::PlayerEnum) {
+        self.is_legal(game_move, player).expect("Move not legal");
+
+        match game_move {
+            Move::Step { x, y } => self.pawns[index(player)] = (x, y),
+            Move::Wall { x, y, orientation } => {
+                self.place_wall(x, y, orientation);
+                self.walls_left[index(player)] -= 1;
+            },
+        }
+        self.to_move = player.other();
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: game::PlayerEnum) -> Box<dyn Iterator<Item = Move> + 'a> {
+        let steps = self.pawn_moves(player).into_iter().map(|(x, y)| Move::Step { x, y });
+        let walls = (0..SIZE - 1).flat_map(|x| (0..SIZE - 1).flat_map(move |y| {
+            vec![Orientation::Horizontal, Orientation::Vertical].into_iter().map(move |orientation| Move::Wall { x, y, orientation })
+        }));
+        Box::new(steps.chain(walls).filter(move |game_move| self.is_legal(*game_move, player).is_ok()))
+    }
+
+    fn is_move_legal(&self, game_move: Self::Move, player: game::PlayerEnum) -> Result<(), game::IllegalMove> {
+        self.is_legal(game_move, player).map_err(game::IllegalMove)
+    }
+
+    fn try_conclude(&self, next_player: game::PlayerEnum) -> Option<game::Conclusion> {
+        if self.pawns[0].1 == goal_row(game::PlayerEnum::One) {
+            return Some(game::Conclusion::Win(game::PlayerEnum::One))
+        }
+        if self.pawns[1].1 == goal_row(game::PlayerEnum::Two) {
+            return Some(game::Conclusion::Win(game::PlayerEnum::Two))
+        }
+
+        // The pawns can't both be stuck, but one can be, with only walls to play once they've run
+        // out. That's a draw.
+        if self.all_legal_moves(next_player).next().is_none() {
+            return Some(game::Conclusion::Draw)
+        }
+
+        // Otherwise, the game goes on
+        None
+    }
+
+    fn draw_rules() -> game::DrawRules {
+        game::DrawRules {
+            repetitions: Some(3),
+            // Fifty moves each
+            moves_without_progress: Some(100),
+        }
+    }
+
+    /// Walls and steps towards the goal.
+    fn is_progress(&self, game_move: &Self::Move, player: game::PlayerEnum) -> bool {
+        match *game_move {
+            Move::Step { y, .. } => {
+                let from = self.pawn(player).1 as isize;
+                let goal = goal_row(player) as isize;
+                (goal - y as isize).abs() < (goal - from).abs()
+            },
+            Move::Wall { .. } => true,
+        }
+    }
+}
+
+/// Squares are a letter for the column (a to i) and a number for the row (1 to 9), e.g. "e2".
+/// Walls are the square below and to the left of their centre, then `h` or `v`, e.g. "e3h".
+impl game::MoveNotation for Quoridor {
+    fn parse_move(&self, text: &str, player: game::PlayerEnum) -> Result<Move, String> {
+        let bad = || format!("Expected a square like e2, or a wall like e3h, not '{}'", text);
+        let chars: Vec<char> = text.trim().chars().collect();
+        if chars.len() < 2 || chars.len() > 3 {
+            return Err(bad());
+        }
+        let x = (chars[0] as usize).wrapping_sub('a' as usize);
+        let y = (chars[1] as usize).wrapping_sub('1' as usize);
+        if x >= SIZE || y >= SIZE {
+            return Err(bad());
+        }
+        let game_move = match chars.get(2) {
+            None => Move::Step { x, y },
+            Some('h') => Move::Wall { x, y, orientation: Orientation::Horizontal },
+            Some('v') => Move::Wall { x, y, orientation: Orientation::Vertical },
+            Some(_) => return Err(bad()),
+        };
+        self.is_legal(game_move, player).map_err(|e| format!("Can't play {}: {}", text, e))?;
+        Ok(game_move)
+    }
+
+    fn move_notation(game_move: &Move) -> String {
+        let square = |x: usize, y: usize| format!("{}{}", (b'a' + x as u8) as char, y + 1);
+        match *game_move {
+            Move::Step { x, y } => square(x, y),
+            Move::Wall { x, y, orientation: Orientation::Horizontal } => square(x, y) + "h",
+            Move::Wall { x, y, orientation: Orientation::Vertical } => square(x, y) + "v",
+        }
+    }
+}
@@ -0,0 +1,13 @@
+extern crate game;
+extern crate quoridor;
+
+use game::testutil::{check_game_state, ConformanceSettings};
+use game::PlayerEnum;
+use quoridor::Quoridor;
+
+#[test]
+fn conforms() {
+    game::rng::seed_thread_rng(1);
+    let settings = ConformanceSettings { games: 2, max_plies: 40, max_compared: 100 };
+    check_game_state(&Quoridor::new(), PlayerEnum::One, &settings).unwrap();
+}
@@ -0,0 +1,102 @@
+extern crate game;
+extern crate quoridor;
+
+use game::{Conclusion, GameState, PlayerEnum};
+use quoridor::{Move, Orientation, Quoridor};
+
+fn wall(x: usize, y: usize, orientation: Orientation) -> Move {
+    Move::Wall { x, y, orientation }
+}
+
+/// Play `moves`, taking turns from player one.
+fn play(moves: &[Move]) -> Quoridor {
+    let mut game = Quoridor::new();
+    for &game_move in moves {
+        let player = game.to_move();
+        game.update(game_move, player);
+    }
+    game
+}
+
+#[test]
+fn walls_cannot_overlap_or_cross() {
+    let game = play(&[wall(3, 3, Orientation::Horizontal)]);
+    let player = PlayerEnum::Two;
+    // The same wall, one sharing half of it, and one crossing it at its centre.
+    for &game_move in &[
+        wall(3, 3, Orientation::Horizontal),
+        wall(2, 3, Orientation::Horizontal),
+        wall(4, 3, Orientation::Horizontal),
+        wall(3, 3, Orientation::Vertical),
+    ] {
+        assert!(game.is_move_legal(game_move, player).is_err(), "{:?} was allowed", game_move);
+    }
+    // Walls which only meet it end on are fine.
+    for &game_move in &[
+        wall(1, 3, Orientation::Horizontal),
+        wall(5, 3, Orientation::Horizontal),
+        wall(3, 2, Orientation::Vertical),
+        wall(4, 3, Orientation::Vertical),
+    ] {
+        assert!(game.is_move_legal(game_move, player).is_ok(), "{:?} wasn't allowed", game_move);
+    }
+    assert!(game.is_move_legal(wall(8, 0, Orientation::Vertical), player).is_err());
+}
+
+#[test]
+fn walls_block_steps_and_are_used_up() {
+    let game = play(&[wall(4, 0, Orientation::Horizontal), Move::Step { x: 4, y: 7 }]);
+    assert_eq!(game.walls_left(PlayerEnum::One), quoridor::WALLS - 1);
+    assert_eq!(game.walls_left(PlayerEnum::Two), quoridor::WALLS);
+    assert!(game.is_move_legal(Move::Step { x: 4, y: 1 }, PlayerEnum::One).is_err());
+    assert!(game.is_move_legal(Move::Step { x: 3, y: 0 }, PlayerEnum::One).is_ok());
+}
+
+#[test]
+fn walls_cannot_cut_a_pawn_off() {
+    // Walls either side of player one's pawn leave only a way up, which the last wall would close.
+    let game = play(&[wall(2, 0, Orientation::Vertical), wall(4, 0, Orientation::Vertical)]);
+    let last = wall(3, 0, Orientation::Horizontal);
+    assert!(game.is_move_legal(last, PlayerEnum::One).is_err());
+    assert!(game.all_legal_moves(PlayerEnum::One).all(|game_move| game_move != last));
+
+    // Once the pawn has stepped up, the way is closed one row higher, and the other player can't
+    // close it either.
+    let game = play(&[wall(2, 0, Orientation::Vertical), wall(4, 0, Orientation::Vertical), Move::Step { x: 4, y: 1 }]);
+    assert!(game.is_move_legal(wall(3, 0, Orientation::Horizontal), PlayerEnum::Two).is_ok());
+    assert!(game.is_move_legal(wall(3, 1, Orientation::Horizontal), PlayerEnum::Two).is_err());
+}
+
+#[test]
+fn pawns_jump_each_other() {
+    let mut moves = Vec::new();
+    for y in 1..4 {
+        moves.push(Move::Step { x: 4, y });
+        moves.push(Move::Step { x: 4, y: 8 - y });
+    }
+    moves.push(Move::Step { x: 4, y: 4 });
+    // The pawns now face each other on e5 and e6, so player two jumps to e4.
+    let game = play(&moves);
+    assert!(game.is_move_legal(Move::Step { x: 4, y: 4 }, PlayerEnum::Two).is_err());
+    assert!(game.is_move_legal(Move::Step { x: 4, y: 3 }, PlayerEnum::Two).is_ok());
+    assert_eq!(game.try_conclude(PlayerEnum::Two), None);
+}
+
+#[test]
+fn reaching_the_far_row_wins() {
+    // Player one walks up the a file and player two down the i file, so they never meet.
+    let mut game = Quoridor::new();
+    let mut path_one: Vec<(usize, usize)> = (0..4).rev().map(|x| (x, 0)).collect();
+    path_one.extend((1..9).map(|y| (0, y)));
+    let mut path_two: Vec<(usize, usize)> = (5..9).map(|x| (x, 8)).collect();
+    path_two.extend((0..8).rev().map(|y| (8, y)));
+    for (i, &(x, y)) in path_one.iter().enumerate() {
+        assert_eq!(game.try_conclude(PlayerEnum::One), None);
+        game.update(Move::Step { x, y }, PlayerEnum::One);
+        if i + 1 < path_one.len() {
+            let (x, y) = path_two[i];
+            game.update(Move::Step { x, y }, PlayerEnum::Two);
+        }
+    }
+    assert_eq!(game.try_conclude(PlayerEnum::Two), Some(Conclusion::Win(PlayerEnum::One)));
+}
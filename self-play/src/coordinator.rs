@@ -0,0 +1,164 @@
+//! Hands out games to workers and collects their records.
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use serde_json;
+
+use game::{Conclusion, PlayerEnum};
+
+use protocol::{self, Assignment, CoordinatorMessage, GameRecord, WorkerMessage};
+
+/// How the games have gone so far.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tally {
+    pub player_one_wins: u32,
+    pub player_two_wins: u32,
+    pub draws: u32,
+}
+
+impl Tally {
+    fn add(&mut self, conclusion: Conclusion) {
+        match conclusion {
+            Conclusion::Win(PlayerEnum::One) => self.player_one_wins += 1,
+            Conclusion::Win(PlayerEnum::Two) => self.player_two_wins += 1,
+            Conclusion::Draw => self.draws += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.player_one_wins + self.player_two_wins + self.draws
+    }
+}
+
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} games: player one won {}, player two won {}, {} drawn", self.games(), self.player_one_wins, self.player_two_wins, self.draws)
+    }
+}
+
+struct State {
+    pending: VecDeque<Assignment>,
+    done: HashSet<u64>,
+    total: usize,
+    tally: Tally,
+    output: Option<File>,
+}
+
+impl State {
+    fn finished(&self) -> bool {
+        self.done.len() == self.total
+    }
+
+    fn record(&mut self, record: GameRecord) -> io::Result<()> {
+        if !self.done.insert(record.assignment.id) {
+            return Ok(());
+        }
+        self.tally.add(record.conclusion);
+        println!("Game {} ({}/{}): {:?}", record.assignment.id, self.done.len(), self.total, record.conclusion);
+        if let Some(ref mut output) = self.output {
+            let line = serde_json::to_string(&record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            writeln!(output, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hands out a fixed set of games, each to one worker at a time. Games whose worker disconnects
+/// before finishing are handed out again.
+pub struct Coordinator {
+    state: Arc<(Mutex<State>, Condvar)>,
+}
+
+impl Coordinator {
+    /// Coordinate the playing of `assignments`, writing each record as a line of JSON to `output`
+    /// (if given).
+    pub fn new(assignments: Vec<Assignment>, output: Option<File>) -> Self {
+        let state = State {
+            total: assignments.len(),
+            pending: assignments.into_iter().collect(),
+            done: HashSet::new(),
+            tally: Tally::default(),
+            output,
+        };
+        Self {
+            state: Arc::new((Mutex::new(state), Condvar::new())),
+        }
+    }
+
+    /// Serve workers connecting to `listener` until every game has been played.
+    pub fn run(self, listener: TcpListener) -> Tally {
+        let state = self.state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let state = state.clone();
+                match stream {
+                    Ok(stream) => {
+                        thread::spawn(move || serve_worker(stream, &state));
+                    }
+                    Err(e) => eprintln!("Failed to accept worker: {}", e),
+                }
+            }
+        });
+
+        let (ref lock, ref condvar) = *self.state;
+        let mut state = lock.lock().expect("Coordinator state poisoned");
+        while !state.finished() {
+            state = condvar.wait(state).expect("Coordinator state poisoned");
+        }
+        state.tally
+    }
+}
+
+fn serve_worker(stream: TcpStream, state: &(Mutex<State>, Condvar)) {
+    let peer = stream.peer_addr().map(|x| x.to_string()).unwrap_or_else(|_| "unknown".to_string());
+    let mut current = None;
+    if let Err(e) = talk_to_worker(stream, state, &mut current) {
+        eprintln!("Lost worker {}: {}", peer, e);
+    }
+
+    // Anything the worker didn't finish is someone else's job now.
+    if let Some(assignment) = current {
+        let (ref lock, _) = *state;
+        lock.lock().expect("Coordinator state poisoned").pending.push_front(assignment);
+    }
+}
+
+fn talk_to_worker(stream: TcpStream, state: &(Mutex<State>, Condvar), current: &mut Option<Assignment>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    let (ref lock, ref condvar) = *state;
+
+    loop {
+        let message = match protocol::receive(&mut reader)? {
+            Some(message) => message,
+            None if current.is_some() => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "disconnected mid-game")),
+            None => return Ok(()),
+        };
+        let reply = {
+            let mut state = lock.lock().expect("Coordinator state poisoned");
+            match message {
+                WorkerMessage::Ready => match state.pending.pop_front() {
+                    Some(assignment) => {
+                        *current = Some(assignment.clone());
+                        CoordinatorMessage::Assign(assignment)
+                    }
+                    None if state.finished() => CoordinatorMessage::Finished,
+                    None => CoordinatorMessage::Wait,
+                },
+                WorkerMessage::Result(record) => {
+                    *current = None;
+                    state.record(record)?;
+                    condvar.notify_all();
+                    continue;
+                }
+            }
+        };
+        protocol::send(&mut writer, &reply)?;
+    }
+}
@@ -0,0 +1,133 @@
+//! Self-play spread over many processes (and machines). A coordinator hands out games to workers
+//! over TCP, and collects the records of the games they play.
+
+extern crate connect_four;
+extern crate game;
+extern crate player_of_games;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate tic_tac_toe;
+
+mod coordinator;
+mod protocol;
+mod worker;
+
+use std::fs::File;
+use std::net::TcpListener;
+use std::process;
+use std::thread;
+
+use coordinator::Coordinator;
+use protocol::Assignment;
+
+const USAGE: &str = "Usage:
+    pog-selfplay coordinator [--listen ADDRESS] [--game NAME] [--games N] [--player-one SPEC] [--player-two SPEC] [--seed N] [--output FILE]
+    pog-selfplay worker [--connect ADDRESS] [--threads N]";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+/// The value of each `--flag value` pair in `args`, for the given flags.
+fn parse_flags(args: Vec<String>, flags: &mut [(&str, &mut String)]) {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match flags.iter_mut().find(|&&mut (flag, _)| flag == arg) {
+            Some(&mut (_, ref mut target)) => **target = args.next().unwrap_or_else(|| usage()),
+            None => usage(),
+        }
+    }
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> T {
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("Bad value for {}: {}", flag, value);
+        process::exit(2);
+    })
+}
+
+fn coordinator(args: Vec<String>) {
+    let mut listen = "127.0.0.1:7878".to_string();
+    let mut game = "tictactoe".to_string();
+    let mut games = "100".to_string();
+    let mut player_one = "mcts".to_string();
+    let mut player_two = "mcts".to_string();
+    let mut seed = "0".to_string();
+    let mut output = String::new();
+    parse_flags(args, &mut [
+        ("--listen", &mut listen),
+        ("--game", &mut game),
+        ("--games", &mut games),
+        ("--player-one", &mut player_one),
+        ("--player-two", &mut player_two),
+        ("--seed", &mut seed),
+        ("--output", &mut output),
+    ]);
+
+    if !worker::GAMES.contains(&game.as_str()) {
+        eprintln!("Unknown game '{}', expected one of: {}", game, worker::GAMES.join(", "));
+        process::exit(2);
+    }
+    let games: u64 = parse("--games", &games);
+    let seed: u64 = parse("--seed", &seed);
+    let assignments = (0..games).map(|id| Assignment {
+        id,
+        game: game.clone(),
+        player_one: player_one.clone(),
+        player_two: player_two.clone(),
+        seed: seed.wrapping_add(id),
+    }).collect();
+
+    let output = if output.is_empty() {
+        None
+    } else {
+        Some(File::create(&output).unwrap_or_else(|e| {
+            eprintln!("Couldn't create {}: {}", output, e);
+            process::exit(1);
+        }))
+    };
+    let listener = TcpListener::bind(&listen).unwrap_or_else(|e| {
+        eprintln!("Couldn't listen on {}: {}", listen, e);
+        process::exit(1);
+    });
+    println!("Waiting for workers on {}", listen);
+    let tally = Coordinator::new(assignments, output).run(listener);
+    println!("{}", tally);
+}
+
+fn worker(args: Vec<String>) {
+    let mut connect = "127.0.0.1:7878".to_string();
+    let mut threads = "1".to_string();
+    parse_flags(args, &mut [
+        ("--connect", &mut connect),
+        ("--threads", &mut threads),
+    ]);
+
+    let threads: usize = parse("--threads", &threads);
+    let handles: Vec<_> = (0..threads).map(|_| {
+        let connect = connect.clone();
+        thread::spawn(move || worker::run(&connect))
+    }).collect();
+    for handle in handles {
+        if let Err(e) = handle.join().expect("Worker thread panicked") {
+            eprintln!("Worker failed: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        usage();
+    }
+    let mode = args.remove(0);
+    match mode.as_str() {
+        "coordinator" => coordinator(args),
+        "worker" => worker(args),
+        _ => usage(),
+    }
+}
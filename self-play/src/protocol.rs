@@ -0,0 +1,64 @@
+//! The messages passed between the coordinator and its workers.
+//!
+//! Each message is one line of JSON. A worker says it's `Ready`, and the coordinator replies with
+//! an `Assign`ment, `Wait` (everything's handed out, but some games may need handing out again if
+//! their worker disappears) or `Finished`. Once the game is played the worker sends back its
+//! `Result`, and then says it's ready again.
+
+use std::io::{self, BufRead, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json;
+
+use game::Conclusion;
+
+/// One game to be played.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Assignment {
+    pub id: u64,
+    pub game: String,
+    /// Player specs, as accepted by `PlayerRegistry::create_from_spec`.
+    pub player_one: String,
+    pub player_two: String,
+    /// For the worker's random number generator, so that the game can be replayed.
+    pub seed: u64,
+}
+
+/// A finished game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub assignment: Assignment,
+    /// The moves played, as `[player, move]` pairs, in the game's own serde encoding.
+    pub moves: serde_json::Value,
+    pub conclusion: Conclusion,
+    pub final_score: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum WorkerMessage {
+    Ready,
+    Result(GameRecord),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum CoordinatorMessage {
+    Assign(Assignment),
+    Wait,
+    Finished,
+}
+
+pub fn send<W: Write, T: Serialize>(writer: &mut W, message: &T) -> io::Result<()> {
+    let line = serde_json::to_string(message).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    writeln!(writer, "{}", line)?;
+    writer.flush()
+}
+
+/// Read the next message, or `None` if the other end has hung up.
+pub fn receive<R: BufRead, T: DeserializeOwned>(reader: &mut R) -> io::Result<Option<T>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    serde_json::from_str(&line).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,89 @@
+//! Plays the games handed out by a coordinator.
+
+use std::cell::RefCell;
+use std::io::{self, BufReader};
+use std::net::TcpStream;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json;
+
+use connect_four::ConnectFour;
+use game::{self, Adjudicator, GameObserver, GameState, PlayerEnum, PlayerRegistry};
+use player_of_games;
+use tic_tac_toe::TicTacToe;
+
+use protocol::{self, Assignment, CoordinatorMessage, GameRecord, WorkerMessage};
+
+/// The games which workers know how to play.
+pub const GAMES: &[&str] = &["tictactoe", "connect4"];
+
+/// Work through assignments from the coordinator at `address` until it says it's finished.
+pub fn run(address: &str) -> io::Result<()> {
+    let stream = TcpStream::connect(address)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    loop {
+        protocol::send(&mut writer, &WorkerMessage::Ready)?;
+        let assignment = match protocol::receive(&mut reader)? {
+            Some(CoordinatorMessage::Assign(assignment)) => assignment,
+            Some(CoordinatorMessage::Wait) => {
+                thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+            Some(CoordinatorMessage::Finished) | None => return Ok(()),
+        };
+        let record = play(assignment).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        protocol::send(&mut writer, &WorkerMessage::Result(record))?;
+    }
+}
+
+/// Play one assigned game.
+pub fn play(assignment: Assignment) -> Result<GameRecord, String> {
+    match assignment.game.as_str() {
+        "tictactoe" => play_game(TicTacToe::new(), assignment),
+        "connect4" => play_game(ConnectFour::new(), assignment),
+        other => Err(format!("Unknown game '{}', expected one of: {}", other, GAMES.join(", "))),
+    }
+}
+
+fn play_game<Game>(start: Game, assignment: Assignment) -> Result<GameRecord, String>
+    where Game: GameState,
+          <Game as GameState>::Move: Serialize
+{
+    let mut players = PlayerRegistry::with_defaults();
+    player_of_games::register_players(&mut players);
+    let player_one = players.create_from_spec(&assignment.player_one, PlayerEnum::One)?;
+    let player_two = players.create_from_spec(&assignment.player_two, PlayerEnum::Two)?;
+
+    game::rng::seed_thread_rng(assignment.seed);
+    let mut adjudicator = Adjudicator::new_boxed(start, player_one, player_two);
+    let moves = Rc::new(RefCell::new(Vec::new()));
+    adjudicator.add_observer(MoveRecorder(moves.clone()));
+    let conclusion = loop {
+        if let Some(conclusion) = adjudicator.conclusion() {
+            break conclusion;
+        }
+        adjudicator.progress_one_turn();
+    };
+
+    let moves = serde_json::to_value(&*moves.borrow()).map_err(|e| e.to_string())?;
+    Ok(GameRecord {
+        assignment,
+        moves,
+        conclusion,
+        final_score: adjudicator.final_score(),
+    })
+}
+
+/// Notes down the moves of a game as they're played.
+struct MoveRecorder<Move>(Rc<RefCell<Vec<(PlayerEnum, Move)>>>);
+
+impl<Game: GameState> GameObserver<Game> for MoveRecorder<<Game as GameState>::Move> {
+    fn on_move(&mut self, player: PlayerEnum, game_move: &<Game as GameState>::Move, _new_state: &Game, _evaluation: Option<f64>) {
+        self.0.borrow_mut().push((player, *game_move));
+    }
+}
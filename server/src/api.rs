@@ -0,0 +1,124 @@
+//! The JSON bodies of requests to (and responses from) the server.
+
+use game::{Conclusion, PlayerEnum};
+use player_of_games::SearchBudget;
+
+use std::time::Duration;
+
+/// How long the engine may think for, e.g. `{"iterations": 1000}` or `{"millis": 500}`. If no
+/// budget is given, the engine's default is used.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Budget {
+    Iterations(u32),
+    Millis(u64),
+}
+
+impl Budget {
+    pub fn to_search_budget(self) -> SearchBudget {
+        match self {
+            Budget::Iterations(n) => SearchBudget::Iterations(n),
+            Budget::Millis(millis) => SearchBudget::Time(Duration::from_millis(millis)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyzeRequest<Game> {
+    pub state: Game,
+    pub to_move: PlayerEnum,
+    pub budget: Option<Budget>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MoveVisits<Move> {
+    #[serde(rename = "move")]
+    pub game_move: Move,
+    pub visits: u32,
+    /// Fraction of all the root's simulations which went through this move.
+    pub share: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyzeResponse<Move> {
+    pub best_move: Option<Move>,
+    pub policy: Vec<MoveVisits<Move>>,
+    pub principal_variation: Vec<Move>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlayRequest<Move> {
+    /// Omit to start a new session.
+    pub session: Option<u64>,
+    /// Which side the engine plays in a new session. Defaults to player two.
+    pub engine_plays: Option<PlayerEnum>,
+    pub budget: Option<Budget>,
+    #[serde(rename = "move")]
+    pub game_move: Option<Move>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayResponse<Game, Move> {
+    pub session: u64,
+    pub state: Game,
+    pub to_move: PlayerEnum,
+    /// The move the engine replied with, if it had a turn.
+    pub engine_move: Option<Move>,
+    pub conclusion: Option<Conclusion>,
+    /// The final margin in player one's favour, for finished games which are scored.
+    pub final_score: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloseRequest {
+    pub session: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CloseResponse {
+    pub session: u64,
+    /// How long the engine spent thinking over the whole game.
+    pub thinking_millis: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+/// A position in an opening book, and the move to play in it.
+#[derive(Debug, Deserialize)]
+pub struct BookEntry<Game, Move> {
+    pub state: Game,
+    pub to_move: PlayerEnum,
+    #[serde(rename = "move")]
+    pub game_move: Move,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReloadResponse {
+    /// The rollout weights now in use, if there's a weights file.
+    pub weights: Option<Vec<f64>>,
+    /// Positions in the opening book.
+    pub book_positions: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MoveRequest<Game> {
+    pub state: Game,
+    pub to_move: PlayerEnum,
+    /// How long until the reply must arrive.
+    pub time_left_millis: u64,
+    /// Who's asking, for sharing out thinking time fairly. Requests without one share an
+    /// allowance.
+    pub client: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MoveResponse<Move> {
+    #[serde(rename = "move")]
+    pub game_move: Move,
+    /// Whether the move was searched for, rather than a quick fallback.
+    pub searched: bool,
+    pub thinking_millis: u64,
+}
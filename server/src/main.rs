@@ -0,0 +1,441 @@
+extern crate connect_four;
+extern crate game;
+extern crate player_of_games;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate tic_tac_toe;
+extern crate tiny_http;
+
+mod api;
+mod reload;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use connect_four::{ConnectFour, ConnectFourWeights};
+use game::{signals, CancellationToken, GameState, Player, PlayerEnum, RolloutPolicy, SoftmaxRollout, TunableWeights};
+use player_of_games::{DecisionSource, MonteCarloTreeSearchPlayer, SearchBudget};
+use player_of_games::bot::{Allowance, DeadlineSettings, MoveBot, MoveSource};
+use player_of_games::self_play::MctsConfig;
+use player_of_games::sessions::{SessionId, SessionLimits, SessionManager, SessionSnapshot};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tic_tac_toe::{TicTacToe, TicTacToeWeights};
+
+use api::{AnalyzeRequest, AnalyzeResponse, CloseRequest, CloseResponse, ErrorResponse, MoveRequest, MoveResponse, MoveVisits, PlayRequest, PlayResponse, ReloadResponse};
+use reload::{ReloadConfig, Reloadable};
+
+/// Simulations each session waiting for an engine move gets in turn, between requests.
+const SLICE: u32 = 100;
+
+/// The most nodes a session's tree may grow to, unless `--max-nodes` says otherwise.
+const DEFAULT_MAX_NODES: usize = 1_000_000;
+
+/// The longest the engine may think about a move, unless `--max-move-millis` says otherwise.
+const DEFAULT_MAX_MOVE_TIME: Duration = Duration::from_secs(10);
+
+/// Where open sessions are saved on shutdown, and restored from on startup, unless `--sessions`
+/// says otherwise.
+const DEFAULT_SESSIONS_FILE: &str = "sessions.json";
+
+/// How to answer a request.
+enum Reply {
+    Json(String),
+    /// Answer once the engine has moved in this session.
+    AfterEngineMove(SessionId),
+}
+
+/// Serves requests about one particular game.
+struct Handler<Game: GameState> {
+    sessions: SessionManager<Game>,
+    /// Caps on the engine's searches, whatever budget the client asks for.
+    limits: SessionLimits,
+    /// Answers one-off move requests from bot platforms.
+    bot: MoveBot<Game>,
+    reloadable: Reloadable<Game>,
+    /// Cancelled when the server is shutting down, to cut the search in progress short.
+    cancellation: CancellationToken,
+}
+
+impl<Game> Handler<Game>
+    where Game: GameState + Serialize + DeserializeOwned,
+          <Game as GameState>::Move: Serialize + DeserializeOwned
+{
+    fn new(initial_state: Game, limits: SessionLimits, reloadable: Reloadable<Game>, cancellation: CancellationToken) -> Self {
+        let config = MctsConfig {
+            rollout_policy: Arc::new(reloadable.rollout()),
+            ..MctsConfig::default()
+        };
+        let mut bot = MoveBot::new(config, DeadlineSettings::default());
+        bot.set_allowance(Some(Allowance::default()));
+        bot.set_cancellation_token(cancellation.clone());
+        Self {
+            sessions: SessionManager::new(initial_state),
+            limits,
+            bot,
+            reloadable,
+            cancellation,
+        }
+    }
+
+    /// Route a request, returning how to reply or an error status and message.
+    fn handle(&mut self, method: &tiny_http::Method, url: &str, body: &str) -> Result<Reply, (u16, String)> {
+        match (method, url) {
+            (&tiny_http::Method::Post, "/analyze") => self.analyze(parse(body)?).map(Reply::Json),
+            (&tiny_http::Method::Post, "/play") => self.play(parse(body)?),
+            (&tiny_http::Method::Post, "/close") => self.close(parse(body)?).map(Reply::Json),
+            (&tiny_http::Method::Post, "/move") => self.bot_move(parse(body)?).map(Reply::Json),
+            (&tiny_http::Method::Post, "/reload") => self.reload().map(Reply::Json),
+            _ => Err((404, format!("No such endpoint: {} {}", method, url))),
+        }
+    }
+
+    /// `budget`, cut down to the limits. Iteration budgets are capped by the node limit, as each
+    /// simulation adds at most a node or so to the tree.
+    fn capped(&self, budget: SearchBudget) -> SearchBudget {
+        match (budget, self.limits) {
+            (SearchBudget::Iterations(n), SessionLimits { max_nodes: Some(max), .. }) => SearchBudget::Iterations(n.min(max.min(u32::MAX as usize) as u32)),
+            (SearchBudget::Time(duration), SessionLimits { max_move_time: Some(max), .. }) => SearchBudget::Time(duration.min(max)),
+            (budget, _) => budget,
+        }
+    }
+
+    fn analyze(&mut self, request: AnalyzeRequest<Game>) -> Result<String, (u16, String)> {
+        if request.state.try_conclude(request.to_move).is_some() {
+            return Err((400, "The game is already over".to_string()));
+        }
+
+        let mut engine = MonteCarloTreeSearchPlayer::new(request.to_move, 2f64.sqrt());
+        engine.set_rollout_policy(self.reloadable.rollout());
+        engine.set_cancellation_token(self.cancellation.clone());
+        if let Some(budget) = request.budget {
+            engine.set_budget(self.capped(budget.to_search_budget()));
+        }
+        let analysis = engine.analyse(&request.state);
+
+        let total_visits: u32 = analysis.policy.iter().map(|x| x.1).sum();
+        to_json(&AnalyzeResponse {
+            best_move: analysis.best_move,
+            policy: analysis.policy.into_iter().map(|(game_move, visits)| MoveVisits {
+                game_move,
+                visits,
+                share: visits as f64 / total_visits.max(1) as f64,
+            }).collect(),
+            principal_variation: analysis.principal_variation,
+        })
+    }
+
+    /// Play the client's move, if any. The engine's reply is searched for a slice at a time
+    /// between requests, alongside the other sessions, unless it's in the book.
+    fn play(&mut self, request: PlayRequest<<Game as GameState>::Move>) -> Result<Reply, (u16, String)> {
+        let id = match request.session {
+            Some(id) => id,
+            None => {
+                let mut config = self.session_config();
+                if let Some(budget) = request.budget {
+                    config.budget = budget.to_search_budget();
+                }
+                let id = self.sessions.create(request.engine_plays.unwrap_or(PlayerEnum::Two), &config);
+                self.set_up_session(id);
+                id
+            }
+        };
+        let session = match self.sessions.get_mut(id) {
+            Some(session) => session,
+            None => return Err((404, format!("No such session: {}", id))),
+        };
+
+        if let Some(game_move) = request.game_move {
+            session.play_move(game_move).map_err(|e| (400, e))?;
+        }
+        let book_move = self.reloadable.book_move(session.state(), session.to_move())
+            .filter(|&game_move| session.play_engine_move(game_move, DecisionSource::Book).is_ok());
+        if session.waiting_for_engine() {
+            return Ok(Reply::AfterEngineMove(id));
+        }
+        self.play_response(id, book_move).map(Reply::Json)
+    }
+
+    /// How to set up sessions' engines, apart from their budgets.
+    fn session_config(&self) -> MctsConfig<Game> {
+        MctsConfig {
+            rollout_policy: Arc::new(self.reloadable.rollout()),
+            ..MctsConfig::default()
+        }
+    }
+
+    /// Apply the server's limits and cancellation token to a new or restored session.
+    fn set_up_session(&mut self, id: SessionId) {
+        let session = self.sessions.get_mut(id).expect("New session went missing");
+        session.set_limits(self.limits);
+        session.engine().set_cancellation_token(self.cancellation.clone());
+    }
+
+    /// Carry on the sessions saved in `path` when the server last shut down, if there are any.
+    /// Returns how many there were.
+    fn restore_sessions(&mut self, path: &Path) -> Result<usize, String> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(format!("Couldn't read {}: {}", path.display(), e)),
+        };
+        let snapshots: Vec<(SessionId, SessionSnapshot<Game>)> = serde_json::from_str(&json)
+            .map_err(|e| format!("Bad sessions in {}: {}", path.display(), e))?;
+        let config = self.session_config();
+        let count = snapshots.len();
+        for (id, snapshot) in snapshots {
+            self.sessions.restore(id, snapshot, &config).map_err(|e| format!("Couldn't restore session {}: {}", id, e))?;
+            self.set_up_session(id);
+        }
+        Ok(count)
+    }
+
+    /// Save the open sessions to `path`, to carry on with when the server next starts.
+    fn save_sessions(&self, path: &Path) -> Result<usize, String> {
+        let snapshots = self.sessions.snapshot();
+        let json = serde_json::to_string(&snapshots).map_err(|e| format!("Couldn't encode sessions: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))?;
+        Ok(snapshots.len())
+    }
+
+    /// Where a session has got to, after the engine played `engine_move` (if it had a turn).
+    fn play_response(&self, id: SessionId, engine_move: Option<<Game as GameState>::Move>) -> Result<String, (u16, String)> {
+        let session = match self.sessions.get(id) {
+            Some(session) => session,
+            None => return Err((404, format!("No such session: {}", id))),
+        };
+        to_json(&PlayResponse {
+            session: id,
+            state: session.state(),
+            to_move: session.to_move(),
+            engine_move,
+            conclusion: session.conclusion(),
+            final_score: session.final_score(),
+        })
+    }
+
+    /// Reply to a bot platform's move request within its deadline.
+    fn bot_move(&mut self, request: MoveRequest<Game>) -> Result<String, (u16, String)> {
+        let client = request.client.unwrap_or_default();
+        let time_left = Duration::from_millis(request.time_left_millis);
+        let reply = self.bot.choose_move(&client, &request.state, request.to_move, time_left).map_err(|e| (400, e))?;
+        to_json(&MoveResponse {
+            game_move: reply.game_move,
+            searched: reply.source == MoveSource::Search,
+            thinking_millis: reply.thinking_time.as_millis() as u64,
+        })
+    }
+
+    /// Reload the weights and the opening book. Sessions carry on, with the new ones.
+    fn reload(&mut self) -> Result<String, (u16, String)> {
+        self.reloadable.reload().map_err(|e| (500, e))?;
+        to_json(&ReloadResponse {
+            weights: self.reloadable.weights().map(|weights| weights.to_vec()),
+            book_positions: self.reloadable.book_size(),
+        })
+    }
+
+    fn close(&mut self, request: CloseRequest) -> Result<String, (u16, String)> {
+        let session = self.sessions.close(request.session).map_err(|e| (404, e))?;
+        to_json(&CloseResponse {
+            session: request.session,
+            thinking_millis: session.thinking_time().as_millis() as u64,
+        })
+    }
+}
+
+fn parse<T: DeserializeOwned>(body: &str) -> Result<T, (u16, String)> {
+    serde_json::from_str(body).map_err(|e| (400, format!("Bad request body: {}", e)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, (u16, String)> {
+    serde_json::to_string(value).map_err(|e| (500, format!("Couldn't encode response: {}", e)))
+}
+
+/// Reply to `request` with `result`, as JSON.
+fn respond(request: tiny_http::Request, result: Result<String, (u16, String)>) {
+    let (status, json) = match result {
+        Ok(json) => (200, json),
+        Err((status, error)) => (status, serde_json::to_string(&ErrorResponse { error }).unwrap()),
+    };
+    let json_header: tiny_http::Header = "Content-Type: application/json".parse().unwrap();
+    let response = tiny_http::Response::from_string(json)
+        .with_status_code(status)
+        .with_header(json_header);
+    if let Err(e) = request.respond(response) {
+        println!("Failed to respond: {}", e);
+    }
+}
+
+/// Answer requests about one particular game until the server shuts down.
+fn serve<Game>(server: tiny_http::Server, initial_state: Game, limits: SessionLimits, reload: ReloadConfig<Game>, sessions_file: &Path)
+    where Game: GameState + Serialize + DeserializeOwned,
+          <Game as GameState>::Move: Serialize + DeserializeOwned
+{
+    let reloadable = Reloadable::new(reload).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let cancellation = CancellationToken::new();
+    signals::cancel_on_shutdown(cancellation.clone());
+    let mut handler = Handler::new(initial_state, limits, reloadable, cancellation.clone());
+    match handler.restore_sessions(sessions_file) {
+        Ok(0) => (),
+        Ok(count) => println!("Restored {} open sessions from {}", count, sessions_file.display()),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    // Set by SIGHUP, to reload between requests
+    let reload_requested = Arc::new(AtomicBool::new(false));
+    if let Err(e) = signals::flag_on_hangup(reload_requested.clone()) {
+        println!("SIGHUP won't reload: {}", e);
+    }
+    // Requests to /play waiting for the engine's reply, by session
+    let mut awaiting: HashMap<SessionId, tiny_http::Request> = HashMap::new();
+
+    loop {
+        // A request being handled when the signal came has been answered by now
+        if cancellation.is_cancelled() {
+            break;
+        }
+        if reload_requested.swap(false, Ordering::SeqCst) {
+            match handler.reloadable.reload() {
+                Ok(()) => println!("Reloaded"),
+                Err(e) => println!("Failed to reload: {}", e),
+            }
+        }
+        // Wake up now and then to check for a reload or shutdown signal, and don't wait at all
+        // while the engine has thinking to do.
+        let timeout = match handler.sessions.waiting().is_empty() {
+            true => Duration::from_millis(500),
+            false => Duration::from_millis(0),
+        };
+        match server.recv_timeout(timeout) {
+            Ok(Some(mut request)) => {
+                let mut body = String::new();
+                let result = match request.as_reader().read_to_string(&mut body) {
+                    Ok(_) => handler.handle(request.method(), request.url(), &body),
+                    Err(e) => Err((400, format!("Couldn't read request body: {}", e))),
+                };
+                match result {
+                    Ok(Reply::AfterEngineMove(id)) => {
+                        if let Some(earlier) = awaiting.insert(id, request) {
+                            respond(earlier, Err((409, format!("A later request is waiting for session {}", id))));
+                        }
+                    },
+                    Ok(Reply::Json(json)) => respond(request, Ok(json)),
+                    Err(error) => respond(request, Err(error)),
+                }
+            },
+            Ok(None) => (),
+            Err(e) => {
+                println!("Failed to receive request: {}", e);
+                break;
+            }
+        }
+
+        for (id, game_move) in handler.sessions.run_slice(SLICE) {
+            if let Some(request) = awaiting.remove(&id) {
+                respond(request, handler.play_response(id, Some(game_move)));
+            }
+        }
+        // Sessions closed while the engine was thinking
+        let closed: Vec<SessionId> = awaiting.keys().cloned().filter(|&id| handler.sessions.get(id).is_none()).collect();
+        for id in closed {
+            respond(awaiting.remove(&id).expect("Request went missing"), Err((404, format!("Session {} was closed", id))));
+        }
+    }
+
+    // Answer anyone still waiting with what the engine has found so far
+    for (id, request) in awaiting {
+        let engine_move = handler.sessions.get_mut(id).and_then(|session| session.engine_move());
+        respond(request, handler.play_response(id, engine_move));
+    }
+
+    match handler.save_sessions(sessions_file) {
+        Ok(count) => println!("Shut down, saving {} open sessions to {}", count, sessions_file.display()),
+        Err(e) => println!("Shut down, dropping {} open sessions: {}", handler.sessions.len(), e),
+    }
+    if let Some(status) = signals::exit_status() {
+        std::process::exit(status);
+    }
+}
+
+/// A softmax rollout policy with `parameters` for `W`'s weights.
+fn softmax_rollout<Game: GameState, W>(parameters: &[f64]) -> Result<Arc<dyn RolloutPolicy<Game> + Send + Sync>, String>
+    where W: TunableWeights<Game> + Default + Send + Sync + 'static
+{
+    let expected = W::default().parameters().len();
+    if parameters.len() != expected {
+        return Err(format!("Expected {} weights, got {}", expected, parameters.len()));
+    }
+    Ok(Arc::new(SoftmaxRollout { weights: W::default().with_parameters(parameters), temperature: 1.0 }))
+}
+
+/// The value following `flag` on the command line, or exit if it isn't a number.
+fn number_arg<T: FromStr>(flag: &str, value: Option<String>) -> T {
+    match value.as_ref().and_then(|value| value.parse().ok()) {
+        Some(number) => number,
+        None => {
+            eprintln!("{} needs a number, got {:?}", flag, value.unwrap_or_default());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut positional = Vec::new();
+    let (mut weights, mut book) = (None, None);
+    let mut sessions_file = PathBuf::from(DEFAULT_SESSIONS_FILE);
+    let mut limits = SessionLimits {
+        max_nodes: Some(DEFAULT_MAX_NODES),
+        max_move_time: Some(DEFAULT_MAX_MOVE_TIME),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--weights" => weights = args.next().map(PathBuf::from),
+            "--book" => book = args.next().map(PathBuf::from),
+            "--sessions" => sessions_file = args.next().map(PathBuf::from).unwrap_or_else(|| {
+                eprintln!("--sessions needs a file");
+                std::process::exit(1);
+            }),
+            "--max-nodes" => limits.max_nodes = Some(number_arg(&arg, args.next())),
+            "--max-move-millis" => limits.max_move_time = Some(Duration::from_millis(number_arg(&arg, args.next()))),
+            _ => positional.push(arg),
+        }
+    }
+    let address = positional.first().cloned().unwrap_or_else(|| "127.0.0.1:8000".to_string());
+    let game = positional.get(1).cloned().unwrap_or_else(|| "tictactoe".to_string());
+    let server = tiny_http::Server::http(&address).expect("Couldn't start server");
+    println!("Listening on {}, serving {}", address, game);
+
+    match game.as_str() {
+        "tictactoe" => serve(server, TicTacToe::new(), limits, ReloadConfig {
+            game,
+            weights,
+            book,
+            rollout_for: softmax_rollout::<TicTacToe, TicTacToeWeights>,
+        }, &sessions_file),
+        "connect4" => serve(server, ConnectFour::new(), limits, ReloadConfig {
+            game,
+            weights,
+            book,
+            rollout_for: softmax_rollout::<ConnectFour, ConnectFourWeights>,
+        }, &sessions_file),
+        _ => {
+            eprintln!("Unknown game '{}', expected one of: connect4, tictactoe", game);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! What the server can reload while it's running, without dropping sessions: the rollout weights
+//! (in `player_of_games::evolution::WeightStore`'s format) and an opening book.
+//!
+//! An opening book is a JSON list of `{"state": ..., "to_move": "One", "move": ...}` entries. When
+//! the engine is to move in a session's position and it's in the book, the book's move is played
+//! without searching.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use game::{GameState, PlayerEnum, RolloutPolicy, UniformRollout};
+use player_of_games::evolution::WeightStore;
+use serde::de::DeserializeOwned;
+
+use api::BookEntry;
+
+/// Makes a rollout policy from a game's weight parameters.
+pub type RolloutFactory<Game> = fn(&[f64]) -> Result<Arc<dyn RolloutPolicy<Game> + Send + Sync>, String>;
+
+/// Where to reload things from.
+#[derive(Debug, Clone)]
+pub struct ReloadConfig<Game: GameState> {
+    /// The game's name in the weights file.
+    pub game: String,
+    /// The weights file. Without one, rollouts are uniformly random.
+    pub weights: Option<PathBuf>,
+    pub book: Option<PathBuf>,
+    pub rollout_for: RolloutFactory<Game>,
+}
+
+/// A rollout policy which can be swapped out from under the searches using it.
+#[derive(Debug)]
+pub struct SharedRollout<Game: GameState> {
+    current: Arc<RwLock<Arc<dyn RolloutPolicy<Game> + Send + Sync>>>,
+}
+
+impl<Game: GameState> Clone for SharedRollout<Game> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<Game: GameState> SharedRollout<Game> {
+    fn new(policy: Arc<dyn RolloutPolicy<Game> + Send + Sync>) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(policy)),
+        }
+    }
+
+    fn replace(&self, policy: Arc<dyn RolloutPolicy<Game> + Send + Sync>) {
+        *self.current.write().expect("Rollout policy poisoned") = policy;
+    }
+}
+
+impl<Game: GameState> RolloutPolicy<Game> for SharedRollout<Game> {
+    fn choose_move(&self, state: &Game, player: PlayerEnum) -> Option<<Game as GameState>::Move> {
+        let policy = self.current.read().expect("Rollout policy poisoned").clone();
+        policy.choose_move(state, player)
+    }
+}
+
+/// The reloadable parts of the server's engine.
+#[derive(Debug)]
+pub struct Reloadable<Game: GameState> {
+    config: ReloadConfig<Game>,
+    rollout: SharedRollout<Game>,
+    weights: Option<Vec<f64>>,
+    book: HashMap<(Game, PlayerEnum), <Game as GameState>::Move>,
+}
+
+impl<Game> Reloadable<Game>
+    where Game: GameState + DeserializeOwned,
+          <Game as GameState>::Move: DeserializeOwned
+{
+    /// Load everything for the first time.
+    pub fn new(config: ReloadConfig<Game>) -> Result<Self, String> {
+        let mut reloadable = Self {
+            config,
+            rollout: SharedRollout::new(Arc::new(UniformRollout)),
+            weights: None,
+            book: HashMap::new(),
+        };
+        reloadable.reload()?;
+        Ok(reloadable)
+    }
+
+    /// Load the weights and the book again. If either can't be loaded, nothing changes.
+    /// Searches already under way pick up the new weights straight away.
+    pub fn reload(&mut self) -> Result<(), String> {
+        let (weights, policy) = match self.config.weights {
+            Some(ref path) => {
+                let weights = WeightStore::new(path.clone()).load(&self.config.game)
+                    .map_err(|e| format!("Couldn't read weights from {:?}: {}", path, e))?
+                    .ok_or_else(|| format!("No weights for {} in {:?}", self.config.game, path))?;
+                let policy = (self.config.rollout_for)(&weights)?;
+                (Some(weights), policy)
+            },
+            None => (None, Arc::new(UniformRollout) as Arc<dyn RolloutPolicy<Game> + Send + Sync>),
+        };
+        let book = match self.config.book {
+            Some(ref path) => {
+                let text = fs::read_to_string(path).map_err(|e| format!("Couldn't read book {:?}: {}", path, e))?;
+                let entries: Vec<BookEntry<Game, <Game as GameState>::Move>> = ::serde_json::from_str(&text)
+                    .map_err(|e| format!("Bad book {:?}: {}", path, e))?;
+                entries.into_iter().map(|entry| ((entry.state, entry.to_move), entry.game_move)).collect()
+            },
+            None => HashMap::new(),
+        };
+
+        self.rollout.replace(policy);
+        self.weights = weights;
+        self.book = book;
+        Ok(())
+    }
+
+    /// The rollout policy to give engines, which follows reloads.
+    pub fn rollout(&self) -> SharedRollout<Game> {
+        self.rollout.clone()
+    }
+
+    /// The weights in use, if any were loaded.
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.weights.as_deref()
+    }
+
+    pub fn book_size(&self) -> usize {
+        self.book.len()
+    }
+
+    /// The book's move for `to_move` in `state`, if it has one.
+    pub fn book_move(&self, state: &Game, to_move: PlayerEnum) -> Option<<Game as GameState>::Move> {
+        self.book.get(&(state.clone(), to_move)).cloned()
+    }
+}
@@ -0,0 +1,269 @@
+//! The Shannon switching game, played on the cells of any `game::board::BoardTopology`: player
+//! one ("Short") claims cells, trying to join the board's two terminals with a chain of claimed
+//! cells, and player two ("Cut") deletes cells, trying to stop them. Whoever succeeds wins, so
+//! there are never draws.
+//!
+//! Hex is this game on a `HexGrid`, joining the top and bottom rows (stopping that is the same as
+//! joining the sides). Bridg-It is the game on the bridges of a grid of dots, which is played here
+//! on the graph whose cells are the bridges, next to each other where they meet at a dot. The
+//! first player can always win both, so they're good tests for solvers.
+
+extern crate game;
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use game::board::{self, BoardTopology, Cell, GraphBoard, HexGrid};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum Side {
+    /// Player one, who claims cells.
+    Short,
+    /// Player two, who deletes cells.
+    Cut,
+}
+
+impl From<game::PlayerEnum> for Side {
+    fn from(player: game::PlayerEnum) -> Self {
+        match player {
+            game::PlayerEnum::One => Side::Short,
+            game::PlayerEnum::Two => Side::Cut,
+        }
+    }
+}
+
+/// The board and the terminals to be joined, which don't change during a game.
+#[derive(Debug)]
+struct Layout<B> {
+    board: B,
+    from: Vec<Cell>,
+    to: Vec<Cell>,
+}
+
+/// A game in progress. States are compared by their cells alone, so only compare states of the
+/// same game.
+pub struct Shannon<B> {
+    layout: Arc<Layout<B>>,
+    cells: Vec<Option<Side>>,
+}
+
+impl<B: BoardTopology> Shannon<B> {
+    /// A game on `board`, where Short wants to join a cell of `from` to a cell of `to`.
+    pub fn new(board: B, from: Vec<Cell>, to: Vec<Cell>) -> Self {
+        let cells = vec![None; board.cell_count()];
+        Self {
+            layout: Arc::new(Layout { board, from, to }),
+            cells,
+        }
+    }
+
+    pub fn board(&self) -> &B {
+        &self.layout.board
+    }
+
+    /// Who has each cell.
+    pub fn cells(&self) -> &[Option<Side>] {
+        &self.cells
+    }
+
+    fn count(&self, side: Side) -> usize {
+        self.cells.iter().filter(|x| **x == Some(side)).count()
+    }
+
+    /// Whether Short has joined the terminals.
+    fn short_wins(&self) -> bool {
+        board::connects(&self.layout.board, &self.cells, &Some(Side::Short), &self.layout.from, &self.layout.to)
+    }
+
+    /// Whether Cut has made joining the terminals impossible.
+    fn cut_wins(&self) -> bool {
+        let open: Vec<bool> = self.cells.iter().map(|x| *x != Some(Side::Cut)).collect();
+        !board::connects(&self.layout.board, &open, &true, &self.layout.from, &self.layout.to)
+    }
+
+    fn is_legal(&self, game_move: Move, player: game::PlayerEnum) -> Result<(), String> {
+        match self.cells.get(game_move.cell) {
+            None => return Err("Cell out of bounds".to_string()),
+            Some(&Some(_)) => return Err("Cell is already taken".to_string()),
+            Some(&None) => (),
+        }
+
+        let short = self.count(Side::Short);
+        let cut = self.count(Side::Cut);
+        match Side::from(player) {
+            Side::Short if short != cut => Err("Short playing out of turn".to_string()),
+            Side::Cut if short != cut + 1 => Err("Cut playing out of turn".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Shannon<HexGrid> {
+    /// Hex on a `size` by `size` board, with Short joining the top and bottom rows.
+    pub fn hex(size: usize) -> Self {
+        let board = HexGrid::new(size);
+        let (from, to) = (board.row(0), board.row(size - 1));
+        Self::new(board, from, to)
+    }
+}
+
+impl Shannon<GraphBoard> {
+    /// Bridg-It on Short's grid of `size` columns by `size + 1` rows of dots, with Short bridging
+    /// the top row to the bottom row. There are no bridges along the top and bottom rows, which
+    /// would be no use.
+    ///
+    /// The cells are the bridges: first the vertical ones, along each row of gaps from the top,
+    /// then the horizontal ones, along each inner row of dots.
+    pub fn bridg_it(size: usize) -> Self {
+        // Each bridge, by the dots it joins, as (column, row).
+        let mut bridges = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                bridges.push(((x, y), (x, y + 1)));
+            }
+        }
+        for y in 1..size {
+            for x in 0..size.saturating_sub(1) {
+                bridges.push(((x, y), (x + 1, y)));
+            }
+        }
+
+        let meets = |a: &((usize, usize), (usize, usize)), b: &((usize, usize), (usize, usize))| {
+            a.0 == b.0 || a.0 == b.1 || a.1 == b.0 || a.1 == b.1
+        };
+        let adjacency = bridges.iter().enumerate().map(|(i, bridge)| {
+            bridges.iter().enumerate().filter(|&(j, other)| i != j && meets(bridge, other)).map(|(j, _)| j).collect()
+        }).collect();
+        // Drawn between the dots they join, with dots on even rows and columns.
+        let positions = bridges.iter().map(|&((x1, y1), (x2, y2))| (y1 + y2, x1 + x2)).collect();
+        let from = (0..bridges.len()).filter(|&i| (bridges[i].0).1 == 0).collect();
+        let to = (0..bridges.len()).filter(|&i| (bridges[i].1).1 == size).collect();
+        let board = GraphBoard {
+            adjacency,
+            lines: Vec::new(),
+            positions,
+        };
+        Self::new(board, from, to)
+    }
+}
+
+impl<B> Clone for Shannon<B> {
+    fn clone(&self) -> Self {
+        Self {
+            layout: self.layout.clone(),
+            cells: self.cells.clone(),
+        }
+    }
+}
+
+impl<B> PartialEq for Shannon<B> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells
+    }
+}
+
+impl<B> Eq for Shannon<B> {}
+
+impl<B> Hash for Shannon<B> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cells.hash(state);
+    }
+}
+
+/// Drawn with `S` for Short's cells, `x` for Cut's and `.` for empty ones.
+impl<B: BoardTopology> fmt::Display for Shannon<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", board::render(&self.layout.board, &self.cells, |cell| match *cell {
+            Some(Side::Short) => 'S',
+            Some(Side::Cut) => 'x',
+            None => '.',
+        }))
+    }
+}
+
+impl<B: BoardTopology> fmt::Debug for Shannon<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Shannon {{")?;
+        writeln!(f, "{}", self)?;
+        write!(f, "}}")
+    }
+}
+
+/// Claim (or delete) a cell.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct Move {
+    cell: Cell,
+}
+
+impl Move {
+    pub fn new(cell: Cell) -> Move {
+        Move { cell }
+    }
+
+    pub fn cell(&self) -> Cell {
+        self.cell
+    }
+}
+
+impl<B: BoardTopology + 'static> game::GameState for Shannon<B> {
+    type Move = Move;
+
+    fn update(&mut self, game_move: Self::Move, player: game::PlayerEnum) {
+        self.is_legal(game_move, player).expect("Move not legal");
+        self.cells[game_move.cell] = Some(Side::from(player));
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: game::PlayerEnum) -> Box<dyn Iterator<Item = Move> + 'a> {
+        Box::new((0..self.cells.len()).map(Move::new).filter(move |game_move| self.is_legal(*game_move, player).is_ok()))
+    }
+
+    fn is_move_legal(&self, game_move: Self::Move, player: game::PlayerEnum) -> Result<(), game::IllegalMove> {
+        self.is_legal(game_move, player).map_err(game::IllegalMove)
+    }
+
+    fn with_move<R, F: FnOnce(&mut Self) -> R>(&mut self, game_move: Self::Move, player: game::PlayerEnum, f: F) -> R {
+        game::UndoableGameState::with_move_in_place(self, game_move, player, f)
+    }
+
+    fn try_conclude(&self, _next_player: game::PlayerEnum) -> Option<game::Conclusion> {
+        // Once every cell is taken one of these is true, so there are no draws.
+        if self.short_wins() {
+            return Some(game::Conclusion::Win(game::PlayerEnum::One))
+        }
+        if self.cut_wins() {
+            return Some(game::Conclusion::Win(game::PlayerEnum::Two))
+        }
+
+        // Otherwise, the game goes on
+        None
+    }
+}
+
+impl<B: BoardTopology + 'static> game::UndoableGameState for Shannon<B> {
+    /// The cell taken.
+    type UndoToken = Cell;
+
+    fn apply(&mut self, game_move: Self::Move, player: game::PlayerEnum) -> Cell {
+        game::GameState::update(self, game_move, player);
+        game_move.cell
+    }
+
+    fn undo(&mut self, cell: Cell) {
+        self.cells[cell] = None;
+    }
+}
+
+/// Cells by number.
+impl<B: BoardTopology + 'static> game::MoveNotation for Shannon<B> {
+    fn parse_move(&self, text: &str, player: game::PlayerEnum) -> Result<Move, String> {
+        let cell = text.trim().parse::<usize>().map_err(|_| format!("Expected a cell number, not '{}'", text))?;
+        let game_move = Move::new(cell);
+        self.is_legal(game_move, player).map_err(|e| format!("Can't play {}: {}", text, e))?;
+        Ok(game_move)
+    }
+
+    fn move_notation(game_move: &Move) -> String {
+        game_move.cell.to_string()
+    }
+}
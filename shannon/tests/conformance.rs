@@ -0,0 +1,18 @@
+extern crate game;
+extern crate shannon;
+
+use game::testutil::check_game_state;
+use game::PlayerEnum;
+use shannon::Shannon;
+
+#[test]
+fn hex_conforms() {
+    game::rng::seed_thread_rng(1);
+    check_game_state(&Shannon::hex(5), PlayerEnum::One, &Default::default()).unwrap();
+}
+
+#[test]
+fn bridg_it_conforms() {
+    game::rng::seed_thread_rng(1);
+    check_game_state(&Shannon::bridg_it(4), PlayerEnum::One, &Default::default()).unwrap();
+}
@@ -0,0 +1,60 @@
+extern crate game;
+extern crate shannon;
+
+use game::{Conclusion, GameState, PlayerEnum};
+use shannon::{Move, Shannon};
+
+/// Play `cells` in turn from Short, checking the game only ends after the last of them.
+fn play<B: game::board::BoardTopology + 'static>(game: &mut Shannon<B>, cells: &[usize]) -> Option<Conclusion> {
+    let mut player = PlayerEnum::One;
+    for (i, &cell) in cells.iter().enumerate() {
+        assert_eq!(game.try_conclude(player), None, "Over before move {}", i);
+        game.update(Move::new(cell), player);
+        player = player.other();
+    }
+    game.try_conclude(player)
+}
+
+#[test]
+fn short_wins_hex_by_joining_top_and_bottom() {
+    // Straight down the middle column of a 3x3 board.
+    assert_eq!(play(&mut Shannon::hex(3), &[1, 0, 4, 3, 7]), Some(Conclusion::Win(PlayerEnum::One)));
+    // Down the diagonal which is joined on a hex grid.
+    assert_eq!(play(&mut Shannon::hex(3), &[2, 0, 4, 1, 6]), Some(Conclusion::Win(PlayerEnum::One)));
+}
+
+#[test]
+fn hex_cells_only_join_along_one_diagonal() {
+    let mut game = Shannon::hex(3);
+    assert_eq!(play(&mut game, &[0, 1, 4, 2, 8]), None);
+}
+
+#[test]
+fn cut_wins_hex_by_joining_the_sides() {
+    assert_eq!(play(&mut Shannon::hex(3), &[0, 3, 1, 4, 2, 5]), Some(Conclusion::Win(PlayerEnum::Two)));
+}
+
+#[test]
+fn short_wins_bridg_it_with_a_chain_of_bridges() {
+    // On the smallest board, bridges 0 and 1 leave the top row, 2 and 3 reach the bottom one,
+    // and 4 joins the two dots in the middle.
+    assert_eq!(Shannon::bridg_it(2).cells().len(), 5);
+    assert_eq!(Shannon::bridg_it(3).cells().len(), 13);
+    assert_eq!(play(&mut Shannon::bridg_it(2), &[0, 1, 2]), Some(Conclusion::Win(PlayerEnum::One)));
+    assert_eq!(play(&mut Shannon::bridg_it(2), &[0, 2, 4, 1, 3]), Some(Conclusion::Win(PlayerEnum::One)));
+}
+
+#[test]
+fn cut_wins_bridg_it_by_deleting_every_way_down() {
+    assert_eq!(play(&mut Shannon::bridg_it(2), &[0, 2, 1, 3]), Some(Conclusion::Win(PlayerEnum::Two)));
+}
+
+#[test]
+fn cells_are_taken_once_and_in_turn() {
+    let mut game = Shannon::hex(3);
+    game.update(Move::new(4), PlayerEnum::One);
+    assert!(game.is_move_legal(Move::new(4), PlayerEnum::Two).is_err());
+    assert!(game.is_move_legal(Move::new(9), PlayerEnum::Two).is_err());
+    assert!(game.is_move_legal(Move::new(0), PlayerEnum::One).is_err());
+    assert!(game.is_move_legal(Move::new(0), PlayerEnum::Two).is_ok());
+}
@@ -0,0 +1,159 @@
+//! Tic-tac-toe with each player's pieces as the bits of a `u16`, and wins found by checking the
+//! eight lines as masks. It plays exactly like `TicTacToe`, with the same moves, but much faster,
+//! for benchmarks and engines which play lots of games.
+
+use std::fmt;
+use std::str::FromStr;
+
+use {Move, Piece, TicTacToe};
+
+/// Each line of three, with square `(x, y)` at bit `3 * x + y`.
+const LINES: [u16; 8] = [
+    0b000_000_111, 0b000_111_000, 0b111_000_000,
+    0b001_001_001, 0b010_010_010, 0b100_100_100,
+    0b100_010_001, 0b001_010_100,
+];
+
+fn bit(x: usize, y: usize) -> u16 {
+    1 << (3 * x + y)
+}
+
+fn wins(pieces: u16) -> bool {
+    LINES.iter().any(|&line| line & !pieces == 0)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct BitTicTacToe {
+    crosses: u16,
+    noughts: u16,
+}
+
+impl BitTicTacToe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Piece> {
+        if x > 2 || y > 2 {
+            panic!("Coordinates were out of bounds.")
+        }
+        if self.crosses & bit(x, y) != 0 {
+            Some(Piece::Cross)
+        } else if self.noughts & bit(x, y) != 0 {
+            Some(Piece::Nought)
+        } else {
+            None
+        }
+    }
+
+    fn pieces(&mut self, piece: Piece) -> &mut u16 {
+        match piece {
+            Piece::Cross => &mut self.crosses,
+            Piece::Nought => &mut self.noughts,
+        }
+    }
+
+    fn is_legal(&self, game_move: Move, player: game::PlayerEnum) -> Result<(), String> {
+        let (x, y) = game_move.coordinates;
+        match (player, game_move.piece) {
+            (game::PlayerEnum::One, Piece::Nought) => return Err("Player 1 tried to place noughts".to_string()),
+            (game::PlayerEnum::Two, Piece::Cross) => return Err("Player 2 tried to place crosses".to_string()),
+            _ => ()
+        }
+        if (self.crosses | self.noughts) & bit(x, y) != 0 {
+            return Err("Trying to override another piece".to_string());
+        }
+        let crosses = self.crosses.count_ones();
+        let noughts = self.noughts.count_ones();
+        match game_move.piece {
+            Piece::Nought if noughts + 1 != crosses => Err("Nought playing out of turn".to_string()),
+            Piece::Cross if noughts != crosses => Err("Crosses playing out of turn".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'a> From<&'a TicTacToe> for BitTicTacToe {
+    fn from(game: &'a TicTacToe) -> Self {
+        let mut bits = Self::new();
+        for ((x, y), piece) in game.occupied() {
+            *bits.pieces(piece) |= bit(x, y);
+        }
+        bits
+    }
+}
+
+impl From<BitTicTacToe> for TicTacToe {
+    fn from(bits: BitTicTacToe) -> Self {
+        let mut game = TicTacToe::new();
+        for x in 0..3 {
+            for y in 0..3 {
+                game.state[[x, y]] = bits.get(x, y).into();
+            }
+        }
+        game
+    }
+}
+
+/// The same as `TicTacToe`'s.
+impl fmt::Display for BitTicTacToe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", TicTacToe::from(*self))
+    }
+}
+
+impl FromStr for BitTicTacToe {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let game: TicTacToe = s.parse()?;
+        Ok(BitTicTacToe::from(&game))
+    }
+}
+
+impl game::MoveNotation for BitTicTacToe {
+    fn parse_move(&self, text: &str, player: game::PlayerEnum) -> Result<Move, String> {
+        game::MoveNotation::parse_move(&TicTacToe::from(*self), text, player)
+    }
+
+    fn move_notation(game_move: &Move) -> String {
+        <TicTacToe as game::MoveNotation>::move_notation(game_move)
+    }
+}
+
+impl game::GameState for BitTicTacToe {
+    type Move = Move;
+
+    fn update(&mut self, game_move: Self::Move, player: game::PlayerEnum) {
+        self.is_legal(game_move, player).expect("Move not legal");
+        let (x, y) = game_move.coordinates;
+        *self.pieces(game_move.piece) |= bit(x, y);
+    }
+
+    fn all_legal_moves<'a>(&'a self, player: game::PlayerEnum) -> Box<dyn Iterator<Item = Move> + 'a> {
+        Box::new((0..9).map(move |square| Move::new(square / 3, square % 3, Piece::from(player)))
+            .filter(move |game_move| self.is_legal(*game_move, player).is_ok()))
+    }
+
+    fn is_move_legal(&self, game_move: Self::Move, player: game::PlayerEnum) -> Result<(), game::IllegalMove> {
+        self.is_legal(game_move, player).map_err(game::IllegalMove)
+    }
+
+    fn try_conclude(&self, next_player: game::PlayerEnum) -> Option<game::Conclusion> {
+        if wins(self.crosses) {
+            return Some(game::Conclusion::Win(game::PlayerEnum::One))
+        }
+        if wins(self.noughts) {
+            return Some(game::Conclusion::Win(game::PlayerEnum::Two))
+        }
+
+        // Otherwise, if there are no moves left for the next player, draw
+        if self.all_legal_moves(next_player).next().is_none() {
+            return Some(game::Conclusion::Draw)
+        }
+
+        // Otherwise, the game goes on
+        None
+    }
+}
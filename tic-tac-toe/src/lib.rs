@@ -1,13 +1,22 @@
 extern crate game;
-#[macro_use]
 extern crate ndarray;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+
+mod bitboard;
+
+pub use bitboard::BitTicTacToe;
 
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
+use std::str::FromStr;
 
 use ndarray::prelude::*;
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Piece {
     Nought,
     Cross,
@@ -23,6 +32,7 @@ impl From<game::PlayerEnum> for Piece {
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct OptionalPiece(Option<Piece>);
 
 impl From<Option<Piece>> for OptionalPiece {
@@ -50,11 +60,18 @@ impl fmt::Display for OptionalPiece {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct TicTacToe {
     state: Array2<OptionalPiece>
 }
 
+impl Default for TicTacToe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TicTacToe {
     pub fn new() -> Self {
         Self {
@@ -62,6 +79,61 @@ impl TicTacToe {
         }
     }
 
+    /// Start setting up a position, from an empty board.
+    pub fn builder() -> TicTacToeBuilder {
+        TicTacToeBuilder {
+            game: Self::new(),
+        }
+    }
+
+    /// The piece on the square at `x`, `y`, if any. Coordinates must be 0, 1 or 2.
+    pub fn get(&self, x: usize, y: usize) -> Option<Piece> {
+        if x > 2 || y > 2 {
+            panic!("Coordinates were out of bounds.")
+        }
+        *self.state[[x, y]]
+    }
+
+    /// The pieces on the board, with their coordinates.
+    pub fn occupied<'a>(&'a self) -> Box<dyn Iterator<Item = ((usize, usize), Piece)> + 'a> {
+        Box::new(self.state.indexed_iter().filter_map(|(coordinates, piece)| piece.map(|piece| (coordinates, piece))))
+    }
+
+    /// The piece which has three in a row, if either does.
+    pub fn winner(&self) -> Option<Piece> {
+        if self.does_piece_win(Piece::Cross) {
+            Some(Piece::Cross)
+        } else if self.does_piece_win(Piece::Nought) {
+            Some(Piece::Nought)
+        } else {
+            None
+        }
+    }
+
+    /// Whether every square is taken.
+    pub fn is_full(&self) -> bool {
+        self.state.iter().all(|x| x.is_some())
+    }
+
+    /// The board as a number, with a base 3 digit for each square (0 for empty, 1 for a cross and
+    /// 2 for a nought). Different boards have different encodings.
+    pub fn encode(&self) -> u16 {
+        self.state.iter().fold(0, |code, square| code * 3 + match **square {
+            None => 0,
+            Some(Piece::Cross) => 1,
+            Some(Piece::Nought) => 2,
+        })
+    }
+
+    /// The player whose turn it is (if the game isn't over). Crosses go first.
+    fn next_player(&self) -> game::PlayerEnum {
+        if self.count(Some(Piece::Cross).into()) == self.count(Some(Piece::Nought).into()) {
+            game::PlayerEnum::One
+        } else {
+            game::PlayerEnum::Two
+        }
+    }
+
     fn count(&self, piece: OptionalPiece) -> u8 {
         self.state.iter().fold(0u8, |n, x| {
             if *x == piece {
@@ -112,6 +184,10 @@ impl TicTacToe {
             _ => ()
         }
 
+        // Moves from elsewhere (e.g. deserialized) may not have been made by `Move::new`
+        if self.state.get([x, y]).is_none() {
+            return Err("Trying to play off the board".to_string());
+        }
         if self.state[[x, y]].is_some() {
             return Err("Trying to override another piece".to_string());
         }
@@ -121,7 +197,7 @@ impl TicTacToe {
         match piece {
             Piece::Nought => {
                 // Check that there's one more Cross
-                if !(count_noughts == count_crosses - 1) {
+                if count_crosses.checked_sub(1) != Some(count_noughts) {
                     return Err("Nought playing out of turn".to_string())
                 }
             }
@@ -138,14 +214,137 @@ impl TicTacToe {
     }
 }
 
+/// Hashes the board's encoding (see `TicTacToe::encode`), rather than the array, which is much
+/// quicker and matters to the tree search.
+impl Hash for TicTacToe {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.encode().hash(state);
+    }
+}
+
 impl fmt::Debug for TicTacToe {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "TicTacToe {{\n{}\n}}", self.state)
     }
 }
 
+/// Written as rows separated by slashes, e.g. `X_O/_X_/__O`, where row `x` holds the squares
+/// `(x, 0)` to `(x, 2)`.
+impl fmt::Display for TicTacToe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (x, row) in self.state.outer_iter().enumerate() {
+            if x > 0 {
+                write!(f, "/")?;
+            }
+            for square in row.iter() {
+                write!(f, "{}", square)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the `Display` form. Empty squares can be `_` or `.`, and pieces can be lower case.
+impl FromStr for TicTacToe {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let rows: Vec<&str> = s.trim().split('/').collect();
+        if rows.len() != 3 || rows.iter().any(|row| row.chars().count() != 3) {
+            return Err(format!("Expected three rows of three squares, separated by '/': {}", s));
+        }
+
+        let mut builder = TicTacToe::builder();
+        for (x, row) in rows.iter().enumerate() {
+            for (y, square) in row.chars().enumerate() {
+                builder = builder.set(x, y, match square {
+                    'X' | 'x' => Some(Piece::Cross),
+                    'O' | 'o' => Some(Piece::Nought),
+                    '_' | '.' => None,
+                    _ => return Err(format!("Unexpected square '{}' in: {}", square, s)),
+                });
+            }
+        }
+        builder.build()
+    }
+}
+
+impl game::Notation for TicTacToe {
+    fn from_notation(text: &str) -> Result<(Self, game::PlayerEnum), String> {
+        let game: TicTacToe = text.parse()?;
+        let to_move = game.next_player();
+        Ok((game, to_move))
+    }
+
+    /// Whose turn it is follows from the position, so `to_move` isn't written down.
+    fn to_notation(&self, _to_move: game::PlayerEnum) -> String {
+        self.to_string()
+    }
+}
+
+/// Squares are a letter for the column (a to c from the left) and a number for the row (1 to 3
+/// from the top, as in the `Display` form), e.g. "b2" for the centre.
+impl game::MoveNotation for TicTacToe {
+    fn parse_move(&self, text: &str, player: game::PlayerEnum) -> Result<Move, String> {
+        let mut chars = text.trim().chars();
+        let (y, x) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(column @ 'a'..='c'), Some(row @ '1'..='3'), None) => (column as usize - 'a' as usize, row as usize - '1' as usize),
+            _ => return Err(format!("Expected a square from a1 to c3, not '{}'", text)),
+        };
+        let game_move = Move::new(x, y, Piece::from(player));
+        game::GameState::is_move_legal(self, game_move, player).map_err(|e| format!("Can't play {}: {}", text, e))?;
+        Ok(game_move)
+    }
+
+    fn move_notation(game_move: &Move) -> String {
+        let (x, y) = game_move.coordinates;
+        format!("{}{}", (b'a' + y as u8) as char, x + 1)
+    }
+}
+
+/// Sets up a position square by square, e.g.
+///
+/// ```ignore
+/// let game = TicTacToe::builder().set(1, 1, Some(Piece::Cross)).build()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct TicTacToeBuilder {
+    game: TicTacToe,
+}
+
+impl TicTacToeBuilder {
+    /// Put `piece` (or nothing) on the square at `x`, `y`. Coordinates must be 0, 1 or 2.
+    pub fn set(mut self, x: usize, y: usize, piece: Option<Piece>) -> Self {
+        if x > 2 || y > 2 {
+            panic!("Coordinates were out of bounds.")
+        }
+        self.game.state[[x, y]] = piece.into();
+        self
+    }
+
+    /// Check that the position could come up in a game, and create it. Crosses go first, so
+    /// there must be as many crosses as noughts or one more, and nobody can have moved after the
+    /// game was won.
+    pub fn build(self) -> Result<TicTacToe, String> {
+        let game = self.game;
+        let crosses = game.count(Some(Piece::Cross).into());
+        let noughts = game.count(Some(Piece::Nought).into());
+        if crosses != noughts && crosses != noughts + 1 {
+            return Err(format!("{} crosses and {} noughts can't come up in a game", crosses, noughts));
+        }
+
+        match (game.does_piece_win(Piece::Cross), game.does_piece_win(Piece::Nought)) {
+            (true, true) => Err("Both players have won".to_string()),
+            (true, false) if crosses == noughts => Err("Noughts moved after crosses won".to_string()),
+            (false, true) if crosses != noughts => Err("Crosses moved after noughts won".to_string()),
+            _ => Ok(game),
+        }
+    }
+}
+
 /// Coordinates are guaranteed to be 0,1,2
 #[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Move {
     coordinates: (usize, usize),
     piece: Piece,
@@ -161,6 +360,99 @@ impl Move {
             piece
         }
     }
+
+    pub fn coordinates(&self) -> (usize, usize) {
+        self.coordinates
+    }
+
+    pub fn piece(&self) -> Piece {
+        self.piece
+    }
+}
+
+/// Move weights for `game::SoftmaxRollout`, scoring moves by whether they win, block the
+/// opponent's win, or take the centre.
+#[derive(Clone, Copy, Debug)]
+pub struct TicTacToeWeights {
+    pub win: f64,
+    pub block: f64,
+    pub centre: f64,
+}
+
+impl Default for TicTacToeWeights {
+    fn default() -> Self {
+        Self {
+            win: 10.0,
+            block: 5.0,
+            centre: 1.0,
+        }
+    }
+}
+
+impl game::MoveWeights<TicTacToe> for TicTacToeWeights {
+    fn score(&self, state: &TicTacToe, game_move: &Move, player: game::PlayerEnum) -> f64 {
+        let (x, y) = game_move.coordinates;
+        let would_win = |piece: Piece| {
+            let mut state = state.clone();
+            state.state[[x, y]] = Some(piece).into();
+            state.does_piece_win(piece)
+        };
+
+        let mut score = 0.0;
+        if would_win(Piece::from(player)) {
+            score += self.win;
+        }
+        if would_win(Piece::from(player.other())) {
+            score += self.block;
+        }
+        if (x, y) == (1, 1) {
+            score += self.centre;
+        }
+        score
+    }
+}
+
+impl game::TunableWeights<TicTacToe> for TicTacToeWeights {
+    fn parameters(&self) -> Vec<f64> {
+        vec![self.win, self.block, self.centre]
+    }
+
+    fn with_parameters(&self, parameters: &[f64]) -> Self {
+        Self {
+            win: parameters[0],
+            block: parameters[1],
+            centre: parameters[2],
+        }
+    }
+}
+
+/// Encodes the board as two 3x3 planes, of the player to move's pieces and then the opponent's,
+/// with rows indexed by y. Each square is a move index, `3 * y + x`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TicTacToeFeatures;
+
+impl game::FeatureEncoder<TicTacToe> for TicTacToeFeatures {
+    fn shape(&self) -> Vec<usize> {
+        vec![2, 3, 3]
+    }
+
+    fn encode(&self, state: &TicTacToe, to_move: game::PlayerEnum) -> game::Features {
+        let mut data = vec![0.0; 18];
+        for ((x, y), piece) in state.occupied() {
+            let plane = if piece == Piece::from(to_move) { 0 } else { 1 };
+            data[9 * plane + 3 * y + x] = 1.0;
+        }
+        game::Features { shape: self.shape(), data }
+    }
+
+    fn move_count(&self) -> usize {
+        9
+    }
+
+    fn move_index(&self, game_move: &Move, _player: game::PlayerEnum) -> usize {
+        let (x, y) = game_move.coordinates;
+        3 * y + x
+    }
 }
 
 impl game::GameState for TicTacToe {
@@ -177,24 +469,32 @@ impl game::GameState for TicTacToe {
         self.state[[x, y]] = Some(piece).into();
     }
 
-    fn all_legal_moves<'a>(&'a self, player: game::PlayerEnum) -> Box<Iterator<Item = Move> + 'a> {
+    fn all_legal_moves<'a>(&'a self, player: game::PlayerEnum) -> Box<dyn Iterator<Item = Move> + 'a> {
         let game_clone = self.clone();
         let closure = move |((x, y), _)| {
             let game_move = Move::new(x, y, Piece::from(player));
             if game_clone.is_legal(game_move, player).is_ok() {
-                return Some(game_move);
+                Some(game_move)
             } else {
-                return None
+                None
             }
         };
         Box::new(self.state.indexed_iter().filter_map(closure))
     }
 
+    fn is_move_legal(&self, game_move: Self::Move, player: game::PlayerEnum) -> Result<(), game::IllegalMove> {
+        self.is_legal(game_move, player).map_err(game::IllegalMove)
+    }
+
+    fn with_move<R, F: FnOnce(&mut Self) -> R>(&mut self, game_move: Self::Move, player: game::PlayerEnum, f: F) -> R {
+        game::UndoableGameState::with_move_in_place(self, game_move, player, f)
+    }
+
     fn try_conclude(&self, next_player: game::PlayerEnum) -> Option<game::Conclusion> {
-        if self.does_piece_win(Piece::Cross.into()) {
+        if self.does_piece_win(Piece::Cross) {
             return Some(game::Conclusion::Win(game::PlayerEnum::One))
         }
-        if self.does_piece_win(Piece::Nought.into()) {
+        if self.does_piece_win(Piece::Nought) {
             return Some(game::Conclusion::Win(game::PlayerEnum::Two))
         }
 
@@ -208,3 +508,30 @@ impl game::GameState for TicTacToe {
     }
 }
 
+impl game::UndoableGameState for TicTacToe {
+    /// The square the piece went on.
+    type UndoToken = (usize, usize);
+
+    fn apply(&mut self, game_move: Self::Move, player: game::PlayerEnum) -> (usize, usize) {
+        game::GameState::update(self, game_move, player);
+        game_move.coordinates
+    }
+
+    fn undo(&mut self, (x, y): (usize, usize)) {
+        self.state[[x, y]] = None.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use game::{GameState, PlayerEnum};
+
+    use super::*;
+
+    #[test]
+    fn noughts_cant_move_first() {
+        let game = TicTacToe::new();
+        assert!(game.is_move_legal(Move::new(0, 0, Piece::Nought), PlayerEnum::Two).is_err());
+        assert!(game.is_move_legal(Move::new(0, 0, Piece::Cross), PlayerEnum::One).is_ok());
+    }
+}
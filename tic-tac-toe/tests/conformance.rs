@@ -0,0 +1,18 @@
+extern crate game;
+extern crate tic_tac_toe;
+
+use game::testutil::check_game_state;
+use game::PlayerEnum;
+use tic_tac_toe::{BitTicTacToe, TicTacToe};
+
+#[test]
+fn conforms() {
+    game::rng::seed_thread_rng(1);
+    check_game_state(&TicTacToe::new(), PlayerEnum::One, &Default::default()).unwrap();
+}
+
+#[test]
+fn bitboard_conforms() {
+    game::rng::seed_thread_rng(1);
+    check_game_state(&BitTicTacToe::new(), PlayerEnum::One, &Default::default()).unwrap();
+}
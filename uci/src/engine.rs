@@ -0,0 +1,179 @@
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use game::{CancellationToken, PlayerEnum};
+use player_of_games::{Analysis, MonteCarloTreeSearchPlayer, ProgressInterval, SearchBudget};
+
+use UciGame;
+
+/// Time to leave on the clock for communication delays.
+const SAFETY_MARGIN: Duration = Duration::from_millis(50);
+/// How many more moves to assume the game lasts, when not told.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// How often to tell the GUI how the search is going.
+const INFO_INTERVAL: Duration = Duration::from_secs(1);
+/// The words which can start an argument of `go`.
+const KEYWORDS: &[&str] = &["searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite"];
+
+/// The arguments of a `go` command which the engine understands.
+#[derive(Debug, Clone, Default)]
+pub struct GoParams {
+    wtime: Option<u64>,
+    btime: Option<u64>,
+    winc: Option<u64>,
+    binc: Option<u64>,
+    movestogo: Option<u32>,
+    movetime: Option<u64>,
+    nodes: Option<u32>,
+    infinite: bool,
+    searchmoves: Vec<String>,
+}
+
+impl GoParams {
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        fn value<T: ::std::str::FromStr>(name: &str, value: Option<&&str>) -> Result<Option<T>, String> {
+            match value.map(|x| x.parse()) {
+                Some(Ok(x)) => Ok(Some(x)),
+                _ => Err(format!("Expected a number after '{}'", name)),
+            }
+        }
+
+        let mut params = GoParams::default();
+        let mut args = args.iter().peekable();
+        while let Some(arg) = args.next() {
+            match *arg {
+                "wtime" => params.wtime = value(arg, args.next())?,
+                "btime" => params.btime = value(arg, args.next())?,
+                "winc" => params.winc = value(arg, args.next())?,
+                "binc" => params.binc = value(arg, args.next())?,
+                "movestogo" => params.movestogo = value(arg, args.next())?,
+                "movetime" => params.movetime = value(arg, args.next())?,
+                "nodes" => params.nodes = value(arg, args.next())?,
+                "infinite" => params.infinite = true,
+                // Everything up to the next keyword is a move.
+                "searchmoves" => while let Some(text) = args.peek() {
+                    if KEYWORDS.contains(*text) {
+                        break;
+                    }
+                    params.searchmoves.push(text.to_string());
+                    args.next();
+                },
+                // Depth, mate, ponder etc. don't mean anything to this engine.
+                _ => (),
+            }
+        }
+        Ok(params)
+    }
+
+    /// How much searching to do, for `player` to move.
+    fn budget(&self, player: PlayerEnum) -> SearchBudget {
+        if self.infinite {
+            return SearchBudget::UntilStopped;
+        }
+        if let Some(nodes) = self.nodes {
+            return SearchBudget::Iterations(nodes);
+        }
+        if let Some(movetime) = self.movetime {
+            return SearchBudget::Time(Duration::from_millis(movetime));
+        }
+
+        let (time, increment) = match player {
+            PlayerEnum::One => (self.wtime, self.winc),
+            PlayerEnum::Two => (self.btime, self.binc),
+        };
+        match time {
+            Some(time) => {
+                let time = Duration::from_millis(time);
+                let share = time / self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1) + Duration::from_millis(increment.unwrap_or(0)) / 2;
+                SearchBudget::Time(share.min(time.checked_sub(SAFETY_MARGIN).unwrap_or_default()))
+            }
+            None => SearchBudget::UntilStopped,
+        }
+    }
+}
+
+/// The engine's state between commands. Searches run on a background thread, so that the engine
+/// can still respond to `stop` and `isready`.
+pub struct Engine<Game: UciGame> {
+    position: Game,
+    search: Option<JoinHandle<()>>,
+    cancellation: CancellationToken,
+}
+
+impl<Game: UciGame + Send + Sync> Engine<Game> where <Game as ::game::GameState>::Move: Send {
+    pub fn new() -> Self {
+        Self {
+            position: Game::start_position(),
+            search: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    pub fn set_position(&mut self, position: Game) {
+        self.stop();
+        self.position = position;
+    }
+
+    /// Start searching the current position. When done, the best move is printed.
+    pub fn go(&mut self, params: GoParams) {
+        self.stop();
+
+        let position = self.position.clone();
+        let player = position.to_move();
+        let mut mcts = MonteCarloTreeSearchPlayer::new(player, 2f64.sqrt());
+        mcts.set_budget(params.budget(player));
+        if !params.searchmoves.is_empty() {
+            match params.searchmoves.iter().map(|text| position.parse_move(text)).collect() {
+                Ok(moves) => mcts.set_search_moves(moves),
+                Err(e) => println!("info string Ignoring searchmoves: {}", e),
+            }
+        }
+        mcts.set_progress_callback(ProgressInterval::Time(INFO_INTERVAL), |progress| {
+            println!("{}", info::<Game>(&progress.analysis, progress.elapsed));
+        });
+        self.cancellation = mcts.cancellation_token();
+
+        self.search = Some(thread::spawn(move || {
+            let start = Instant::now();
+            let analysis = mcts.analyse(&position);
+            println!("{}", info::<Game>(&analysis, start.elapsed()));
+
+            match analysis.best_move {
+                Some(best_move) => match analysis.principal_variation.get(1) {
+                    Some(reply) => println!("bestmove {} ponder {}", Game::format_move(&best_move), Game::format_move(reply)),
+                    None => println!("bestmove {}", Game::format_move(&best_move)),
+                },
+                // No legal moves
+                None => println!("bestmove 0000"),
+            }
+        }));
+    }
+
+    /// Stop the current search (if there is one), waiting for its best move to be printed.
+    pub fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.cancellation.cancel();
+            search.join().expect("Search thread panicked");
+        }
+    }
+}
+
+/// An `info` line describing the search so far.
+fn info<Game: UciGame>(analysis: &Analysis<<Game as ::game::GameState>::Move>, elapsed: Duration) -> String {
+    let mut info = format!("info nodes {} time {}", analysis.simulations, elapsed.as_millis());
+    if let Some(evaluation) = analysis.evaluation {
+        info += &format!(" score cp {}", centipawns(evaluation));
+    }
+    if !analysis.principal_variation.is_empty() {
+        let pv: Vec<String> = analysis.principal_variation.iter().map(Game::format_move).collect();
+        info += &format!(" pv {}", pv.join(" "));
+    }
+    info
+}
+
+/// Convert a chance of winning into the centipawn scale GUIs expect, where +100 is roughly a
+/// pawn's advantage.
+fn centipawns(evaluation: f64) -> i32 {
+    let evaluation = evaluation.clamp(0.001, 0.999);
+    (400.0 * (evaluation / (1.0 - evaluation)).log10()).round() as i32
+}
@@ -0,0 +1,102 @@
+//! A UCI (Universal Chess Interface) front-end for the engine, so that it can be used from chess
+//! GUIs. Any game which can be described by FEN-like strings and text moves can be played this
+//! way, by implementing `UciGame`.
+
+extern crate chess;
+extern crate game;
+extern crate player_of_games;
+
+mod engine;
+
+use std::io::{self, BufRead};
+
+use game::{GameState, PlayerEnum};
+
+use engine::{Engine, GoParams};
+
+/// A game which can be driven over UCI.
+pub trait UciGame: GameState {
+    fn start_position() -> Self;
+    fn from_fen(fen: &str) -> Result<Self, String>;
+    fn parse_move(&self, text: &str) -> Result<Self::Move, String>;
+    fn format_move(game_move: &Self::Move) -> String;
+    fn to_move(&self) -> PlayerEnum;
+}
+
+impl UciGame for chess::Chess {
+    fn start_position() -> Self {
+        chess::Chess::new()
+    }
+    fn from_fen(fen: &str) -> Result<Self, String> {
+        chess::Chess::from_fen(fen)
+    }
+    fn parse_move(&self, text: &str) -> Result<chess::Move, String> {
+        self.parse_uci(text)
+    }
+    fn format_move(game_move: &chess::Move) -> String {
+        game_move.to_uci()
+    }
+    fn to_move(&self) -> PlayerEnum {
+        chess::Chess::to_move(self)
+    }
+}
+
+/// Parse the arguments of a `position` command: `startpos` or `fen <fen>`, then optionally
+/// `moves <move>...`.
+fn parse_position<Game: UciGame>(args: &[&str]) -> Result<Game, String> {
+    let moves_index = args.iter().position(|x| *x == "moves").unwrap_or(args.len());
+    let mut position = match args.first() {
+        Some(&"startpos") => Game::start_position(),
+        Some(&"fen") => Game::from_fen(&args[1..moves_index].join(" "))?,
+        _ => return Err("Expected 'startpos' or 'fen'".to_string()),
+    };
+
+    for text in args.iter().skip(moves_index + 1) {
+        let game_move = position.parse_move(text)?;
+        let player = position.to_move();
+        position.update(game_move, player);
+    }
+    Ok(position)
+}
+
+fn main() {
+    let mut engine: Engine<chess::Chess> = Engine::new();
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line.expect("Failed to read from stdin");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match tokens.split_first() {
+            Some(x) => x,
+            None => continue,
+        };
+
+        match *command {
+            "uci" => {
+                println!("id name player-of-games");
+                println!("id author chriscoomber");
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                engine.stop();
+                engine = Engine::new();
+            }
+            "position" => match parse_position(args) {
+                Ok(position) => engine.set_position(position),
+                Err(e) => println!("info string Bad position: {}", e),
+            },
+            "go" => match GoParams::parse(args) {
+                Ok(params) => engine.go(params),
+                Err(e) => println!("info string Bad go command: {}", e),
+            },
+            "stop" => engine.stop(),
+            "quit" => {
+                engine.stop();
+                return;
+            }
+            // Unknown commands should be ignored
+            _ => (),
+        }
+    }
+}